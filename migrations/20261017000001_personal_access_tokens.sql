@@ -0,0 +1,15 @@
+-- パーソナルアクセストークンテーブル
+CREATE TABLE IF NOT EXISTS personal_access_tokens (
+    token_id VARCHAR(255) PRIMARY KEY,
+    user_id VARCHAR(255) NOT NULL REFERENCES users(user_id) ON DELETE CASCADE,
+    name VARCHAR(100) NOT NULL,
+    -- 一覧表示用のトークン先頭部分（平文のトークンは保存しない）
+    token_prefix VARCHAR(32) NOT NULL,
+    -- トークンのSHA-256ハッシュ（16進）
+    token_hash CHAR(64) UNIQUE NOT NULL,
+    roles TEXT[] NOT NULL,
+    expires_at TIMESTAMP WITH TIME ZONE,
+    last_used_at TIMESTAMP WITH TIME ZONE,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP
+);
+CREATE INDEX IF NOT EXISTS idx_personal_access_tokens_user_id ON personal_access_tokens (user_id);
@@ -0,0 +1,22 @@
+-- リフレッシュトークンのファミリー（ログインごとに1つ、ローテーションしても同じファミリー）
+CREATE TABLE IF NOT EXISTS refresh_token_families (
+    family_id VARCHAR(255) PRIMARY KEY,
+    user_id VARCHAR(255) NOT NULL REFERENCES users(user_id) ON DELETE CASCADE,
+    -- 最新のリフレッシュトークンの有効期限（これを過ぎたファミリーは削除してよい）
+    expires_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP,
+    revoked_at TIMESTAMP WITH TIME ZONE,
+    revoked_reason VARCHAR(50)
+);
+CREATE INDEX IF NOT EXISTS idx_refresh_token_families_user_id ON refresh_token_families (user_id);
+CREATE INDEX IF NOT EXISTS idx_refresh_token_families_expires_at ON refresh_token_families (expires_at);
+
+-- 発行したリフレッシュトークン（rotated_at が設定済みのトークンの再提示は盗用とみなす）
+CREATE TABLE IF NOT EXISTS refresh_tokens (
+    jti VARCHAR(255) PRIMARY KEY,
+    family_id VARCHAR(255) NOT NULL REFERENCES refresh_token_families(family_id) ON DELETE CASCADE,
+    issued_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP,
+    expires_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    rotated_at TIMESTAMP WITH TIME ZONE
+);
+CREATE INDEX IF NOT EXISTS idx_refresh_tokens_family_id ON refresh_tokens (family_id);
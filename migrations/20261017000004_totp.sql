@@ -0,0 +1,24 @@
+-- TOTP二要素認証の設定（ユーザーごとに1つ）
+CREATE TABLE IF NOT EXISTS user_totp (
+    user_id VARCHAR(255) PRIMARY KEY REFERENCES users(user_id) ON DELETE CASCADE,
+    -- AES-256-GCMで暗号化したTOTPシークレット
+    secret_ciphertext BYTEA NOT NULL,
+    secret_nonce BYTEA NOT NULL,
+    -- 確認コードの検証が済むまでは NULL（未有効）
+    enabled_at TIMESTAMP WITH TIME ZONE,
+    -- 最後に使用したタイムステップ（同じコードの再利用を防ぐ）
+    last_used_step BIGINT,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP
+);
+
+-- リカバリーコード（1回のみ使用可能）
+CREATE TABLE IF NOT EXISTS totp_recovery_codes (
+    code_id VARCHAR(255) PRIMARY KEY,
+    user_id VARCHAR(255) NOT NULL REFERENCES users(user_id) ON DELETE CASCADE,
+    -- リカバリーコードのSHA-256ハッシュ（16進）
+    code_hash CHAR(64) NOT NULL,
+    used_at TIMESTAMP WITH TIME ZONE,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE(user_id, code_hash)
+);
+CREATE INDEX IF NOT EXISTS idx_totp_recovery_codes_user_id ON totp_recovery_codes (user_id);
@@ -0,0 +1,16 @@
+-- WebAuthn（パスキー・セキュリティキー）の資格情報
+CREATE TABLE IF NOT EXISTS webauthn_credentials (
+    -- 資格情報ID（base64url）
+    credential_id VARCHAR(1024) PRIMARY KEY,
+    user_id VARCHAR(255) NOT NULL REFERENCES users(user_id) ON DELETE CASCADE,
+    name VARCHAR(100) NOT NULL,
+    -- COSE形式の公開鍵
+    public_key BYTEA NOT NULL,
+    -- COSEアルゴリズム識別子（-7: ES256, -8: EdDSA, -257: RS256）
+    algorithm INTEGER NOT NULL,
+    sign_count BIGINT NOT NULL DEFAULT 0,
+    transports TEXT[] NOT NULL DEFAULT '{}',
+    last_used_at TIMESTAMP WITH TIME ZONE,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP
+);
+CREATE INDEX IF NOT EXISTS idx_webauthn_credentials_user_id ON webauthn_credentials (user_id);
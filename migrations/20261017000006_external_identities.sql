@@ -0,0 +1,16 @@
+-- OpenID Connect のIDプロバイダーで認証された外部ID
+CREATE TABLE IF NOT EXISTS external_identities (
+    -- 設定ファイルのプロバイダー識別子
+    provider VARCHAR(64) NOT NULL,
+    -- IDトークンの sub クレーム
+    subject VARCHAR(255) NOT NULL,
+    user_id VARCHAR(255) NOT NULL REFERENCES users(user_id) ON DELETE CASCADE,
+    -- 連携時・最終ログイン時にIDプロバイダーが返したメールアドレス
+    email VARCHAR(255),
+    last_login_at TIMESTAMP WITH TIME ZONE,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (provider, subject),
+    -- 1つのプロバイダーにつき連携できるIDは1つ
+    UNIQUE (user_id, provider)
+);
+CREATE INDEX IF NOT EXISTS idx_external_identities_user_id ON external_identities (user_id);
@@ -0,0 +1,2 @@
+-- メールのログインリンクによるログインを許可するか（ユーザーごとに無効化できる）
+ALTER TABLE users ADD COLUMN IF NOT EXISTS magic_link_enabled BOOLEAN NOT NULL DEFAULT TRUE;
@@ -0,0 +1,35 @@
+-- 失効したトークン・セッションを各インスタンスの失効キャッシュに通知する（LISTEN auth_revocations）
+-- ペイロード: {"kind": "jti" | "session", "id": ..., "expires_at": UNIX時刻（秒）}
+CREATE OR REPLACE FUNCTION notify_jwt_revocation() RETURNS trigger AS $$
+BEGIN
+    PERFORM pg_notify('auth_revocations', json_build_object(
+        'kind', 'jti',
+        'id', NEW.jti,
+        'expires_at', extract(epoch FROM NEW.expires_at)::bigint
+    )::text);
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE OR REPLACE TRIGGER jwt_revocations_notify
+    AFTER INSERT ON jwt_revocations
+    FOR EACH ROW EXECUTE FUNCTION notify_jwt_revocation();
+
+CREATE OR REPLACE FUNCTION notify_session_revocation() RETURNS trigger AS $$
+BEGIN
+    PERFORM pg_notify('auth_revocations', json_build_object(
+        'kind', 'session',
+        'id', NEW.family_id,
+        'expires_at', extract(epoch FROM NEW.expires_at)::bigint
+    )::text);
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE OR REPLACE TRIGGER refresh_token_families_notify
+    AFTER UPDATE OF revoked_at ON refresh_token_families
+    FOR EACH ROW WHEN (OLD.revoked_at IS NULL AND NEW.revoked_at IS NOT NULL)
+    EXECUTE FUNCTION notify_session_revocation();
+
+-- ログアウト時に失効時刻を有効期限として記録していた行は、トークンの最長の有効期間（7日）だけ残す
+UPDATE jwt_revocations SET expires_at = revoked_at + INTERVAL '7 days' WHERE expires_at <= revoked_at;
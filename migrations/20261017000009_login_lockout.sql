@@ -0,0 +1,7 @@
+-- パスワードによるログインの連続失敗回数とロック（ログインに成功するか、ロックを解除するまで数え続ける）
+ALTER TABLE users ADD COLUMN IF NOT EXISTS failed_login_count INTEGER NOT NULL DEFAULT 0;
+-- この日時までパスワードによるログインを拒否する
+ALTER TABLE users ADD COLUMN IF NOT EXISTS locked_until TIMESTAMP WITH TIME ZONE;
+
+-- 新しい端末からのログインの判定に使う
+CREATE INDEX IF NOT EXISTS idx_sessions_user_ip ON sessions (user_id, ip_address);
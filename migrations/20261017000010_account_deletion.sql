@@ -0,0 +1,24 @@
+-- アカウント削除の予定日時（この日時を過ぎると定期タスクがデータを完全に削除する。それまではログインして取り消せる）
+ALTER TABLE users ADD COLUMN IF NOT EXISTS deletion_scheduled_at TIMESTAMP WITH TIME ZONE;
+
+CREATE INDEX IF NOT EXISTS idx_users_deletion_scheduled_at
+    ON users (deletion_scheduled_at)
+    WHERE deletion_scheduled_at IS NOT NULL;
+
+-- 以前の論理削除で無効化されたままのアカウントも、猶予期間（デフォルト30日）の後に削除する
+UPDATE users
+SET deletion_scheduled_at = CURRENT_TIMESTAMP + INTERVAL '30 days'
+WHERE is_active = false AND deletion_scheduled_at IS NULL;
+
+-- 完全に削除したアカウントの記録（ユーザーを削除した後も残すため外部キーは張らない）
+CREATE TABLE IF NOT EXISTS account_deletion_audit_logs (
+    id BIGSERIAL PRIMARY KEY,
+    user_id VARCHAR(255) NOT NULL,
+    deletion_scheduled_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    purged_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    purged_memos INTEGER NOT NULL,
+    purged_summaries INTEGER NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_account_deletion_audit_logs_user_id
+    ON account_deletion_audit_logs (user_id);
@@ -0,0 +1,22 @@
+-- 個人データのエクスポート（アーカイブはどのインスタンスからでもダウンロードできるようにPostgreSQLに保存する）
+CREATE TABLE IF NOT EXISTS data_exports (
+    export_id VARCHAR(255) PRIMARY KEY,
+    user_id VARCHAR(255) NOT NULL REFERENCES users(user_id) ON DELETE CASCADE,
+    -- processing / ready / failed
+    status VARCHAR(16) NOT NULL,
+    -- 作成に失敗した場合の理由
+    error TEXT,
+    -- 作成したZIPアーカイブ
+    archive BYTEA,
+    size_bytes BIGINT,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    completed_at TIMESTAMP WITH TIME ZONE,
+    -- この日時を過ぎるとアーカイブを削除する
+    expires_at TIMESTAMP WITH TIME ZONE
+);
+CREATE INDEX IF NOT EXISTS idx_data_exports_user_id ON data_exports (user_id, created_at);
+CREATE INDEX IF NOT EXISTS idx_data_exports_expires_at ON data_exports (expires_at);
+-- 作成中のエクスポートはユーザーごとに1つまで
+CREATE UNIQUE INDEX IF NOT EXISTS idx_data_exports_processing
+    ON data_exports (user_id)
+    WHERE status = 'processing';
@@ -0,0 +1,35 @@
+-- 自動ジャーナリング（定期的な自動要約）の設定
+CREATE TABLE IF NOT EXISTS journaling_schedules (
+    user_id VARCHAR(255) PRIMARY KEY REFERENCES users(user_id) ON DELETE CASCADE,
+    -- daily / weekly / monthly
+    frequency VARCHAR(16) NOT NULL,
+    -- 要約を作成する時刻（timezone の現地時刻）
+    local_time TIME NOT NULL,
+    -- IANAタイムゾーン名（例: Asia/Tokyo）
+    timezone VARCHAR(64) NOT NULL,
+    -- weekly の曜日（0 = 日曜日 〜 6 = 土曜日）
+    weekday SMALLINT NOT NULL DEFAULT 0,
+    -- monthly の日（1 〜 28）
+    day_of_month SMALLINT NOT NULL DEFAULT 1,
+    -- 次に要約を作成する日時（この日時までの1期間分のメモを要約する）
+    next_run_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP
+);
+CREATE INDEX IF NOT EXISTS idx_journaling_schedules_next_run_at ON journaling_schedules (next_run_at);
+
+-- 期間ごとの実行記録（同じ期間の要約を重複して作らないため、期間ごとに1行だけ作る）
+CREATE TABLE IF NOT EXISTS journaling_runs (
+    user_id VARCHAR(255) NOT NULL REFERENCES users(user_id) ON DELETE CASCADE,
+    period_start TIMESTAMP WITH TIME ZONE NOT NULL,
+    period_end TIMESTAMP WITH TIME ZONE NOT NULL,
+    -- running / completed / skipped（期間内にメモがない） / failed
+    status VARCHAR(16) NOT NULL,
+    attempts INTEGER NOT NULL DEFAULT 1,
+    -- 作成した要約（MongoDB）のID
+    summary_id VARCHAR(255),
+    error TEXT,
+    started_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    finished_at TIMESTAMP WITH TIME ZONE,
+    PRIMARY KEY (user_id, period_start)
+);
@@ -0,0 +1,23 @@
+-- 要約ジョブ（要約の作成をリクエストから切り離して実行する）
+CREATE TABLE IF NOT EXISTS summary_jobs (
+    job_id VARCHAR(255) PRIMARY KEY,
+    user_id VARCHAR(255) NOT NULL REFERENCES users(user_id) ON DELETE CASCADE,
+    memo_ids TEXT[] NOT NULL,
+    -- queued / running / succeeded / failed
+    status VARCHAR(16) NOT NULL,
+    -- 実行中の段階（loading_memos / generating / saving）
+    stage VARCHAR(32),
+    attempts INTEGER NOT NULL DEFAULT 0,
+    error TEXT,
+    -- 作成した要約（MongoDB）のID
+    summary_id VARCHAR(255),
+    -- この日時を過ぎたら実行する（再試行の待ち時間）
+    run_after TIMESTAMP WITH TIME ZONE NOT NULL,
+    started_at TIMESTAMP WITH TIME ZONE,
+    finished_at TIMESTAMP WITH TIME ZONE,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP
+);
+CREATE INDEX IF NOT EXISTS idx_summary_jobs_queued ON summary_jobs (run_after) WHERE status = 'queued';
+CREATE INDEX IF NOT EXISTS idx_summary_jobs_running ON summary_jobs (user_id) WHERE status = 'running';
+CREATE INDEX IF NOT EXISTS idx_summary_jobs_user_id ON summary_jobs (user_id, created_at DESC);
@@ -1,6 +1,6 @@
 use crate::error::{AppError, Result};
-use chrono::{Duration, Utc};
-use jsonwebtoken::{Algorithm, DecodingKey, EncodingKey, Header, Validation, decode, encode};
+use crate::jwt_keys::JwtKeys;
+use chrono::{DateTime, Duration, Utc};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
@@ -58,18 +58,6 @@ pub fn validate_password_format(password: &str) -> Result<()> {
     Ok(())
 }
 
-//////
-// 鍵関係の実装
-/// EncodingKey を作成（署名用）
-pub fn create_encoding_key(secret: &str) -> EncodingKey {
-    EncodingKey::from_secret(secret.as_bytes())
-}
-
-/// DecodingKey を作成（検証用）
-pub fn create_decoding_key(secret: &str) -> DecodingKey {
-    DecodingKey::from_secret(secret.as_bytes())
-}
-
 //////
 //JWTの実装
 
@@ -80,6 +68,9 @@ enum TokenType {
     Access,
     Registration,  // ユーザー登録用の一時トークン
     PasswordReset, // パスワードリセット用の一時トークン
+    MfaPending,    // パスワード検証後、二要素認証の完了待ちの一時トークン
+    MagicLink,     // メールで送るログインリンク用の一時トークン
+    AccountUnlock, // メールで送るアカウントのロック解除用の一時トークン
 }
 
 // アクセストークンで認可する操作
@@ -94,8 +85,37 @@ pub enum Role {
     ResetPassword,
 }
 
-// JWTヘッダー
-static JWT_ALGORITHM: Algorithm = Algorithm::HS256;
+impl Role {
+    /// DBに保存する際の名前（シリアライズ形式と同じ）
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Role::EditMemo => "EditMemo",
+            Role::ViewMemo => "ViewMemo",
+            Role::SummarizeMemo => "SummarizeMemo",
+            Role::EditTag => "EditTag",
+            Role::EditAccount => "EditAccount",
+            Role::DeleteAccount => "DeleteAccount",
+            Role::ResetPassword => "ResetPassword",
+        }
+    }
+}
+
+impl std::str::FromStr for Role {
+    type Err = AppError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "EditMemo" => Ok(Role::EditMemo),
+            "ViewMemo" => Ok(Role::ViewMemo),
+            "SummarizeMemo" => Ok(Role::SummarizeMemo),
+            "EditTag" => Ok(Role::EditTag),
+            "EditAccount" => Ok(Role::EditAccount),
+            "DeleteAccount" => Ok(Role::DeleteAccount),
+            "ResetPassword" => Ok(Role::ResetPassword),
+            _ => Err(AppError::ValidationError(format!("Unknown role: {}", s))),
+        }
+    }
+}
 
 // JWTペイロード(クレーム)
 #[derive(Debug, Serialize, Deserialize)]
@@ -110,6 +130,43 @@ pub struct JwtClaim {
 
     typ: TokenType,          // トークンの種別
     role: Option<Vec<Role>>, // アクセストークンで認可する操作 (Optional -> Optionに修正)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    sid: Option<String>, // リフレッシュトークンのファミリーID（ログインごとのセッション）
+}
+
+/// 発行したリフレッシュトークン
+pub struct IssuedRefreshToken {
+    pub token: String,
+    pub jti: String,
+    pub expires_at: DateTime<Utc>,
+}
+
+impl JwtClaim {
+    /// User ID
+    pub fn sub(&self) -> &str {
+        &self.sub
+    }
+
+    /// JWT ID
+    pub fn jti(&self) -> &str {
+        &self.jti
+    }
+
+    /// セッション（リフレッシュトークンのファミリー）ID
+    pub fn sid(&self) -> Option<&str> {
+        self.sid.as_deref()
+    }
+
+    /// 有効期限
+    pub fn expires_at(&self) -> DateTime<Utc> {
+        DateTime::from_timestamp(self.exp as i64, 0).unwrap_or_else(Utc::now)
+    }
+
+    /// 指定された権限をすべて持っているか
+    pub fn has_roles(&self, required: &[Role]) -> bool {
+        let granted = self.role.as_deref().unwrap_or_default();
+        required.iter().all(|role| granted.contains(role))
+    }
 }
 
 // ------------------------------------------------------------------
@@ -117,14 +174,15 @@ pub struct JwtClaim {
 // ------------------------------------------------------------------
 
 /// リフレッシュトークンの発行
-/// 引数: &LoginRequest
-/// 戻り値: Result<JWT, 任意のError>
-pub fn issue_refresh_token(user_id: &str, secret: &str) -> Result<String> {
+/// 引数: &UserID, &セッションID, &署名鍵
+/// 戻り値: Result<IssuedRefreshToken, 任意のError>
+pub fn issue_refresh_token(user_id: &str, session_id: &str, keys: &JwtKeys) -> Result<IssuedRefreshToken> {
     let now = Utc::now();
     let expiration = now + Duration::days(7); // 例: 7日間有効
+    let jti = Uuid::new_v4().to_string();
 
     let claims = JwtClaim {
-        jti: Uuid::new_v4().to_string(),
+        jti: jti.clone(),
         iss: "mimo-server".to_string(),
         aud: "mimo-client".to_string(),
         sub: user_id.to_string(),
@@ -133,21 +191,50 @@ pub fn issue_refresh_token(user_id: &str, secret: &str) -> Result<String> {
         exp: expiration.timestamp() as usize,
         typ: TokenType::Refresh,
         role: None, // リフレッシュトークンには権限を付与しない
+        sid: Some(session_id.to_string()),
     };
 
-    let header = Header::new(JWT_ALGORITHM);
-    let key = create_encoding_key(secret);
-    let token =
-        encode(&header, &claims, &key).map_err(|e| AppError::EnvironmentError(e.to_string()))?;
-    Ok(token)
+    let token = keys.sign(&claims)?;
+    Ok(IssuedRefreshToken {
+        token,
+        jti,
+        expires_at: expiration,
+    })
 }
 
 /// アクセストークンの発行
-/// 引数: &UserID, 要求する権限, &リフレッシュトークン, &秘密鍵
+/// 引数: &UserID, &セッションID, 要求する権限, &署名鍵
 /// 戻り値: Result<JWT, 任意のError>
-pub fn issue_access_token(user_id: &str, roles: Vec<Role>, secret: &str) -> Result<String> {
+pub fn issue_access_token(
+    user_id: &str,
+    session_id: Option<&str>,
+    roles: Vec<Role>,
+    keys: &JwtKeys,
+) -> Result<String> {
+    issue_access_token_with_ttl(user_id, session_id, roles, Duration::hours(1), keys) // 例: 1時間有効
+}
+
+/// 再認証後の短時間だけ有効なアクセストークンの発行
+/// 引数: &UserID, &セッションID, 要求する権限, &署名鍵
+/// 戻り値: Result<JWT, 任意のError>
+pub fn issue_step_up_access_token(
+    user_id: &str,
+    session_id: Option<&str>,
+    roles: Vec<Role>,
+    keys: &JwtKeys,
+) -> Result<String> {
+    issue_access_token_with_ttl(user_id, session_id, roles, Duration::minutes(5), keys) // 5分間有効
+}
+
+fn issue_access_token_with_ttl(
+    user_id: &str,
+    session_id: Option<&str>,
+    roles: Vec<Role>,
+    ttl: Duration,
+    keys: &JwtKeys,
+) -> Result<String> {
     let now = Utc::now();
-    let expiration = now + Duration::hours(1); // 例: 1時間有効
+    let expiration = now + ttl;
 
     let claims = JwtClaim {
         jti: Uuid::new_v4().to_string(),
@@ -159,41 +246,73 @@ pub fn issue_access_token(user_id: &str, roles: Vec<Role>, secret: &str) -> Resu
         nbf: now.timestamp() as usize,
         typ: TokenType::Access,
         role: Some(roles), // 要求された権限をセット
+        sid: session_id.map(str::to_string),
     };
 
-    let header = Header::new(JWT_ALGORITHM);
-    let enc_key = create_encoding_key(secret);
-    let token = encode(&header, &claims, &enc_key)
-        .map_err(|e| AppError::EnvironmentError(e.to_string()))?;
-    Ok(token)
+    keys.sign(&claims)
 }
 
 // ------------------------------------------------------------------
 // JWTの検証関数群
 // ------------------------------------------------------------------
 
+/// アクセストークンの検証
+/// 引数: トークン, 検証鍵
+/// 戻り値: Result<JwtClaim, AppError>（失効の確認は呼び出し側で行う）
+pub fn decode_access_token(token: &str, keys: &JwtKeys) -> Result<JwtClaim> {
+    let token_data = keys
+        .verify::<JwtClaim>(token)
+        .map_err(|_| AppError::AuthenticationError("Invalid access token".to_string()))?;
+    let claims = token_data.claims;
+
+    if claims.typ != TokenType::Access {
+        return Err(AppError::AuthenticationError(
+            "Token type is not Access".to_string(),
+        ));
+    }
+
+    Ok(claims)
+}
+
+/// リフレッシュトークンの検証
+/// 引数: トークン, 検証鍵
+/// 戻り値: Result<JwtClaim, AppError>（ローテーション状態の確認は呼び出し側で行う）
+pub fn decode_refresh_token(token: &str, keys: &JwtKeys) -> Result<JwtClaim> {
+    let token_data = keys
+        .verify::<JwtClaim>(token)
+        .map_err(|_| AppError::AuthenticationError("Invalid refresh token".to_string()))?;
+    let claims = token_data.claims;
+
+    if claims.typ != TokenType::Refresh {
+        return Err(AppError::AuthenticationError(
+            "Token type is not Refresh".to_string(),
+        ));
+    }
+
+    Ok(claims)
+}
+
 /// トークンからユーザーIDを抽出（型チェックなし）
-pub fn extract_user_id_from_token(token: &str, key: &DecodingKey) -> Result<String> {
-    let mut validation = Validation::new(JWT_ALGORITHM);
-    validation.set_audience(&["mimo-client"]);
-    let token_data = decode::<JwtClaim>(token, key, &validation)
+pub fn extract_user_id_from_token(token: &str, keys: &JwtKeys) -> Result<String> {
+    let token_data = keys
+        .verify::<JwtClaim>(token)
         .map_err(|e| AppError::ValidationError(e.to_string()))?;
     Ok(token_data.claims.sub)
 }
 
-/// トークンからJTIを抽出
-pub fn extract_jti_from_token(token: &str, key: &DecodingKey) -> Result<String> {
-    let mut validation = Validation::new(JWT_ALGORITHM);
-    validation.set_audience(&["mimo-client"]);
-    let token_data = decode::<JwtClaim>(token, key, &validation)
+/// トークンからJTIと有効期限を抽出
+pub fn extract_jti_from_token(token: &str, keys: &JwtKeys) -> Result<(String, DateTime<Utc>)> {
+    let token_data = keys
+        .verify::<JwtClaim>(token)
         .map_err(|e| AppError::ValidationError(e.to_string()))?;
-    Ok(token_data.claims.jti)
+    let expires_at = token_data.claims.expires_at();
+    Ok((token_data.claims.jti, expires_at))
 }
 
 /// 登録用トークンの発行
-/// 引数: メールアドレス, 秘密鍵
+/// 引数: メールアドレス, 署名鍵
 /// 戻り値: Result<JWT, AppError>
-pub fn issue_registration_token(email: &str, secret: &str) -> Result<String> {
+pub fn issue_registration_token(email: &str, keys: &JwtKeys) -> Result<String> {
     let now = Utc::now();
     let expiration = now + Duration::minutes(15); // 15分間有効
 
@@ -207,22 +326,19 @@ pub fn issue_registration_token(email: &str, secret: &str) -> Result<String> {
         exp: expiration.timestamp() as usize,
         typ: TokenType::Registration,
         role: None,
+        sid: None,
     };
 
-    let header = Header::new(JWT_ALGORITHM);
-    let key = create_encoding_key(secret);
-    let token =
-        encode(&header, &claims, &key).map_err(|e| AppError::EnvironmentError(e.to_string()))?;
+    let token = keys.sign(&claims)?;
     Ok(token)
 }
 
 /// 登録用トークンの検証
-/// 引数: トークン, 期待されるメールアドレス, 秘密鍵
+/// 引数: トークン, 期待されるメールアドレス, 検証鍵
 /// 戻り値: Result<String, AppError> (成功時はJTI)
-pub fn validate_registration_token(token: &str, email: &str, key: &DecodingKey) -> Result<String> {
-    let mut validation = Validation::new(JWT_ALGORITHM);
-    validation.set_audience(&["mimo-client"]);
-    let token_data = decode::<JwtClaim>(token, key, &validation)
+pub fn validate_registration_token(token: &str, email: &str, keys: &JwtKeys) -> Result<String> {
+    let token_data = keys
+        .verify::<JwtClaim>(token)
         .map_err(|e| AppError::ValidationError(e.to_string()))?;
     let claims = token_data.claims;
 
@@ -241,9 +357,9 @@ pub fn validate_registration_token(token: &str, email: &str, key: &DecodingKey)
 }
 
 /// パスワードリセット用トークンの発行
-/// 引数: メールアドレス, 秘密鍵
+/// 引数: メールアドレス, 署名鍵
 /// 戻り値: Result<JWT, AppError>
-pub fn issue_password_reset_token(email: &str, secret: &str) -> Result<String> {
+pub fn issue_password_reset_token(email: &str, keys: &JwtKeys) -> Result<String> {
     let now = Utc::now();
     let expiration = now + Duration::minutes(30); // 30分間有効
 
@@ -257,26 +373,23 @@ pub fn issue_password_reset_token(email: &str, secret: &str) -> Result<String> {
         exp: expiration.timestamp() as usize,
         typ: TokenType::PasswordReset,
         role: None,
+        sid: None,
     };
 
-    let header = Header::new(JWT_ALGORITHM);
-    let key = create_encoding_key(secret);
-    let token =
-        encode(&header, &claims, &key).map_err(|e| AppError::EnvironmentError(e.to_string()))?;
+    let token = keys.sign(&claims)?;
     Ok(token)
 }
 
 /// パスワードリセット用トークンの検証
-/// 引数: トークン, 期待されるメールアドレス, 秘密鍵
+/// 引数: トークン, 期待されるメールアドレス, 検証鍵
 /// 戻り値: Result<String, AppError> (成功時はJTI)
 pub fn validate_password_reset_token(
     token: &str,
     email: &str,
-    key: &DecodingKey,
+    keys: &JwtKeys,
 ) -> Result<String> {
-    let mut validation = Validation::new(JWT_ALGORITHM);
-    validation.set_audience(&["mimo-client"]);
-    let token_data = decode::<JwtClaim>(token, key, &validation)
+    let token_data = keys
+        .verify::<JwtClaim>(token)
         .map_err(|e| AppError::ValidationError(e.to_string()))?;
     let claims = token_data.claims;
 
@@ -293,3 +406,129 @@ pub fn validate_password_reset_token(
 
     Ok(claims.jti)
 }
+
+/// ログインリンク用トークンの発行
+/// 引数: &UserID, 署名鍵
+/// 戻り値: Result<JWT, AppError>
+pub fn issue_magic_link_token(user_id: &str, keys: &JwtKeys) -> Result<String> {
+    let now = Utc::now();
+    let expiration = now + Duration::minutes(15); // 15分間有効
+
+    let claims = JwtClaim {
+        jti: Uuid::new_v4().to_string(),
+        iss: "mimo-server".to_string(),
+        aud: "mimo-client".to_string(),
+        sub: user_id.to_string(),
+        iat: now.timestamp() as usize,
+        nbf: now.timestamp() as usize,
+        exp: expiration.timestamp() as usize,
+        typ: TokenType::MagicLink,
+        role: None,
+        sid: None,
+    };
+
+    let token = keys.sign(&claims)?;
+    Ok(token)
+}
+
+/// ログインリンク用トークンの検証
+/// 引数: トークン, 検証鍵
+/// 戻り値: Result<JwtClaim, AppError>（使用済みの確認は呼び出し側で行う）
+pub fn decode_magic_link_token(token: &str, keys: &JwtKeys) -> Result<JwtClaim> {
+    let token_data = keys
+        .verify::<JwtClaim>(token)
+        .map_err(|_| AppError::AuthenticationError("Invalid or expired login link".to_string()))?;
+    let claims = token_data.claims;
+
+    if claims.typ != TokenType::MagicLink {
+        return Err(AppError::AuthenticationError(
+            "Token type is not MagicLink".to_string(),
+        ));
+    }
+
+    Ok(claims)
+}
+
+/// アカウントのロック解除用トークンの発行
+/// 引数: &UserID, 署名鍵
+/// 戻り値: Result<JWT, AppError>
+pub fn issue_account_unlock_token(user_id: &str, keys: &JwtKeys) -> Result<String> {
+    let now = Utc::now();
+    let expiration = now + Duration::hours(1); // 1時間有効
+
+    let claims = JwtClaim {
+        jti: Uuid::new_v4().to_string(),
+        iss: "mimo-server".to_string(),
+        aud: "mimo-client".to_string(),
+        sub: user_id.to_string(),
+        iat: now.timestamp() as usize,
+        nbf: now.timestamp() as usize,
+        exp: expiration.timestamp() as usize,
+        typ: TokenType::AccountUnlock,
+        role: None,
+        sid: None,
+    };
+
+    let token = keys.sign(&claims)?;
+    Ok(token)
+}
+
+/// アカウントのロック解除用トークンの検証
+/// 引数: トークン, 検証鍵
+/// 戻り値: Result<JwtClaim, AppError>（使用済みの確認は呼び出し側で行う）
+pub fn decode_account_unlock_token(token: &str, keys: &JwtKeys) -> Result<JwtClaim> {
+    let token_data = keys
+        .verify::<JwtClaim>(token)
+        .map_err(|_| AppError::AuthenticationError("Invalid or expired unlock link".to_string()))?;
+    let claims = token_data.claims;
+
+    if claims.typ != TokenType::AccountUnlock {
+        return Err(AppError::AuthenticationError(
+            "Token type is not AccountUnlock".to_string(),
+        ));
+    }
+
+    Ok(claims)
+}
+
+/// 二要素認証待ちトークンの発行
+/// 引数: &UserID, 署名鍵
+/// 戻り値: Result<JWT, AppError>
+pub fn issue_mfa_pending_token(user_id: &str, keys: &JwtKeys) -> Result<String> {
+    let now = Utc::now();
+    let expiration = now + Duration::minutes(5); // 5分間有効
+
+    let claims = JwtClaim {
+        jti: Uuid::new_v4().to_string(),
+        iss: "mimo-server".to_string(),
+        aud: "mimo-client".to_string(),
+        sub: user_id.to_string(),
+        iat: now.timestamp() as usize,
+        nbf: now.timestamp() as usize,
+        exp: expiration.timestamp() as usize,
+        typ: TokenType::MfaPending,
+        role: None, // 二要素認証が完了するまで権限は付与しない
+        sid: None,
+    };
+
+    let token = keys.sign(&claims)?;
+    Ok(token)
+}
+
+/// 二要素認証待ちトークンの検証
+/// 引数: トークン, 検証鍵
+/// 戻り値: Result<JwtClaim, AppError>（使用済みの確認は呼び出し側で行う）
+pub fn decode_mfa_pending_token(token: &str, keys: &JwtKeys) -> Result<JwtClaim> {
+    let token_data = keys
+        .verify::<JwtClaim>(token)
+        .map_err(|_| AppError::AuthenticationError("Invalid MFA token".to_string()))?;
+    let claims = token_data.claims;
+
+    if claims.typ != TokenType::MfaPending {
+        return Err(AppError::AuthenticationError(
+            "Token type is not MfaPending".to_string(),
+        ));
+    }
+
+    Ok(claims)
+}
@@ -61,7 +61,28 @@ pub struct Config {
     pub logging: LoggingConfig,
     pub jwt: JwtConfig,
     pub email: EmailConfig,
+    #[serde(default)]
     pub gemini: GeminiConfig,
+    #[serde(default)]
+    pub llm: LlmConfig,
+    #[serde(default)]
+    pub trash: TrashConfig,
+    #[serde(default)]
+    pub mfa: MfaConfig,
+    #[serde(default)]
+    pub webauthn: WebAuthnConfig,
+    #[serde(default)]
+    pub oidc: OidcConfig,
+    #[serde(default)]
+    pub magic_link: MagicLinkConfig,
+    #[serde(default)]
+    pub account_unlock: AccountUnlockConfig,
+    #[serde(default)]
+    pub account_deletion: AccountDeletionConfig,
+    #[serde(default)]
+    pub data_export: DataExportConfig,
+    #[serde(default)]
+    pub summary_jobs: SummaryJobConfig,
 }
 
 #[derive(Debug, Deserialize, Clone)]
@@ -197,7 +218,68 @@ pub struct LoggingConfig {
 
 #[derive(Debug, Deserialize, Clone)]
 pub struct JwtConfig {
+    /// HS256 の共通鍵（鍵IDのないトークンの署名・検証に使う）
+    #[serde(default)]
     pub secret: String,
+    /// 署名に使う鍵の鍵ID（未設定の場合は共通鍵で署名する）
+    #[serde(default)]
+    pub signing_key_id: String,
+    /// 公開鍵暗号の鍵（署名鍵と、ローテーション後も検証に使う古い鍵）
+    #[serde(default)]
+    pub keys: Vec<JwtKeyConfig>,
+}
+
+/// JWTの公開鍵暗号の鍵
+/// 秘密鍵・公開鍵はPEMを直接指定するか、ファイルのパスを指定する
+#[derive(Debug, Deserialize, Clone)]
+pub struct JwtKeyConfig {
+    /// 鍵ID（JWTヘッダーの kid）
+    pub id: String,
+    /// RS256 または EdDSA
+    pub algorithm: String,
+    /// 秘密鍵（署名鍵のみ必要。公開鍵は秘密鍵から導出する）
+    #[serde(default)]
+    pub private_key: String,
+    #[serde(default)]
+    pub private_key_file: String,
+    /// 公開鍵（検証のみに使う鍵）
+    #[serde(default)]
+    pub public_key: String,
+    #[serde(default)]
+    pub public_key_file: String,
+}
+
+impl JwtConfig {
+    /// 環境変数から公開鍵暗号の鍵を読み込む
+    ///
+    /// JWT_KEYS にカンマ区切りで鍵IDを並べ、鍵IDごとに
+    /// JWT_KEY_<鍵ID>_ALGORITHM などを設定する（JWT_KEYS が未設定・空の場合は None）
+    fn keys_from_env() -> anyhow::Result<Option<Vec<JwtKeyConfig>>> {
+        let ids = env::var("JWT_KEYS").unwrap_or_default();
+        if ids.trim().is_empty() {
+            return Ok(None);
+        }
+
+        let mut keys = Vec::new();
+        for id in ids.split(',').map(str::trim).filter(|id| !id.is_empty()) {
+            let prefix = format!("JWT_KEY_{}", id.to_uppercase().replace(['-', '.'], "_"));
+            let var = |name: &str| env::var(format!("{}_{}", prefix, name)).unwrap_or_default();
+            let algorithm = var("ALGORITHM");
+            if algorithm.is_empty() {
+                anyhow::bail!("{}_ALGORITHM must be set", prefix);
+            }
+            keys.push(JwtKeyConfig {
+                id: id.to_string(),
+                algorithm,
+                private_key: var("PRIVATE_KEY"),
+                private_key_file: var("PRIVATE_KEY_FILE"),
+                public_key: var("PUBLIC_KEY"),
+                public_key_file: var("PUBLIC_KEY_FILE"),
+            });
+        }
+
+        Ok(Some(keys))
+    }
 }
 
 #[derive(Debug, Deserialize, Clone)]
@@ -210,11 +292,427 @@ pub struct EmailConfig {
     pub from_name: String,
 }
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Deserialize, Clone, Default)]
 pub struct GeminiConfig {
+    #[serde(default)]
+    pub api_key: String,
+}
+
+/// LLMの種類
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LlmProviderKind {
+    /// Google Gemini API（GEMINI_API_KEY を使用）
+    Gemini,
+    /// OpenAI互換API（OpenAI・vLLMなど）
+    OpenAi,
+    /// ローカルで動かすOpenAI互換API（llama.cpp・Ollamaなど）
+    Local,
+    /// 外部に接続せず、決まった応答を返す（テスト・オフライン用）
+    Mock,
+}
+
+impl FromStr for LlmProviderKind {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "gemini" => Ok(LlmProviderKind::Gemini),
+            "openai" => Ok(LlmProviderKind::OpenAi),
+            "local" => Ok(LlmProviderKind::Local),
+            "mock" => Ok(LlmProviderKind::Mock),
+            _ => Err(anyhow::anyhow!("Invalid LLM provider: {}", s)),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for LlmProviderKind {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let s = String::deserialize(deserializer)?;
+        LlmProviderKind::from_str(&s).map_err(serde::de::Error::custom)
+    }
+}
+
+/// 機能ごとに使うLLM
+#[derive(Debug, Deserialize, Clone)]
+pub struct LlmFeatureConfig {
+    pub provider: LlmProviderKind,
+    /// モデル名（空の場合はプロバイダーごとの既定のモデル）
+    #[serde(default)]
+    pub model: String,
+    /// 1回のリクエストで送るテキストのトークン数の上限（0の場合はプロバイダーごとの既定値）
+    /// 要約では、これを超えるメモを分割して要約してから統合する
+    #[serde(default)]
+    pub max_input_tokens: usize,
+}
+
+impl LlmFeatureConfig {
+    /// 使用するモデル名
+    pub fn model(&self) -> &str {
+        if !self.model.is_empty() {
+            return &self.model;
+        }
+        match self.provider {
+            LlmProviderKind::Gemini => "gemini-2.5-flash",
+            LlmProviderKind::OpenAi => "gpt-4o-mini",
+            LlmProviderKind::Local => "llama3.2",
+            LlmProviderKind::Mock => "mock",
+        }
+    }
+
+    /// 1回のリクエストで送るテキストのトークン数の上限
+    pub fn max_input_tokens(&self) -> usize {
+        if self.max_input_tokens > 0 {
+            return self.max_input_tokens;
+        }
+        match self.provider {
+            LlmProviderKind::Gemini | LlmProviderKind::OpenAi => 30_000,
+            // ローカルのモデルはコンテキスト長が短いことが多い
+            LlmProviderKind::Local => 4_000,
+            LlmProviderKind::Mock => 1_000,
+        }
+    }
+
+    /// LLM_<機能>_PROVIDER / LLM_<機能>_MODEL / LLM_<機能>_MAX_INPUT_TOKENS で上書き
+    fn with_env(mut self, feature: &str) -> anyhow::Result<Self> {
+        if let Ok(provider) = env::var(format!("LLM_{}_PROVIDER", feature)) {
+            self.provider = provider.parse()?;
+        }
+        if let Ok(model) = env::var(format!("LLM_{}_MODEL", feature)) {
+            self.model = model;
+        }
+        if let Ok(tokens) = env::var(format!("LLM_{}_MAX_INPUT_TOKENS", feature)) {
+            self.max_input_tokens = tokens.parse()?;
+        }
+        Ok(self)
+    }
+}
+
+impl Default for LlmFeatureConfig {
+    fn default() -> Self {
+        Self {
+            provider: LlmProviderKind::Gemini,
+            model: String::new(),
+            max_input_tokens: 0,
+        }
+    }
+}
+
+/// OpenAI互換APIの接続先
+#[derive(Debug, Deserialize, Clone)]
+pub struct OpenAiCompatibleConfig {
+    /// `/chat/completions` の手前までのURL
+    pub base_url: String,
+    /// APIキー（空の場合は Authorization ヘッダーを付けない）
+    #[serde(default)]
     pub api_key: String,
 }
 
+/// LLMの設定
+#[derive(Debug, Deserialize, Clone)]
+pub struct LlmConfig {
+    /// メモの自動タグ付け
+    #[serde(default)]
+    pub tagging: LlmFeatureConfig,
+    /// メモの要約
+    #[serde(default)]
+    pub summary: LlmFeatureConfig,
+    /// provider = "openai" の接続先
+    #[serde(default = "default_openai_config")]
+    pub openai: OpenAiCompatibleConfig,
+    /// provider = "local" の接続先
+    #[serde(default = "default_local_llm_config")]
+    pub local: OpenAiCompatibleConfig,
+}
+
+fn default_openai_config() -> OpenAiCompatibleConfig {
+    OpenAiCompatibleConfig {
+        base_url: "https://api.openai.com/v1".to_string(),
+        api_key: String::new(),
+    }
+}
+
+fn default_local_llm_config() -> OpenAiCompatibleConfig {
+    OpenAiCompatibleConfig {
+        base_url: "http://localhost:11434/v1".to_string(),
+        api_key: String::new(),
+    }
+}
+
+impl Default for LlmConfig {
+    fn default() -> Self {
+        Self {
+            tagging: LlmFeatureConfig::default(),
+            summary: LlmFeatureConfig::default(),
+            openai: default_openai_config(),
+            local: default_local_llm_config(),
+        }
+    }
+}
+
+impl LlmConfig {
+    /// 環境変数で上書き
+    fn with_env(mut self) -> anyhow::Result<Self> {
+        self.tagging = self.tagging.with_env("TAGGING")?;
+        self.summary = self.summary.with_env("SUMMARY")?;
+        if let Ok(base_url) = env::var("OPENAI_BASE_URL") {
+            self.openai.base_url = base_url;
+        }
+        if let Ok(api_key) = env::var("OPENAI_API_KEY") {
+            self.openai.api_key = api_key;
+        }
+        if let Ok(base_url) = env::var("LOCAL_LLM_BASE_URL") {
+            self.local.base_url = base_url;
+        }
+        if let Ok(api_key) = env::var("LOCAL_LLM_API_KEY") {
+            self.local.api_key = api_key;
+        }
+        Ok(self)
+    }
+}
+
+/// ゴミ箱の設定
+#[derive(Debug, Deserialize, Clone)]
+pub struct TrashConfig {
+    /// ゴミ箱に移動してから完全に削除するまでの日数
+    pub retention_days: i64,
+}
+
+impl Default for TrashConfig {
+    fn default() -> Self {
+        Self { retention_days: 30 }
+    }
+}
+
+impl TrashConfig {
+    /// 保持日数として受け付ける範囲（最大で約10年）
+    const RETENTION_DAYS_RANGE: std::ops::RangeInclusive<i64> = 1..=3650;
+
+    /// 保持日数が範囲内かどうかを検証する
+    ///
+    /// # Errors
+    /// 負の値や極端に大きな値が設定されている場合にエラーを返す
+    fn validate(&self) -> anyhow::Result<()> {
+        if !Self::RETENTION_DAYS_RANGE.contains(&self.retention_days) {
+            anyhow::bail!(
+                "TRASH_RETENTION_DAYS must be between {} and {} (got {})",
+                Self::RETENTION_DAYS_RANGE.start(),
+                Self::RETENTION_DAYS_RANGE.end(),
+                self.retention_days
+            );
+        }
+        Ok(())
+    }
+}
+
+/// 二要素認証の設定
+#[derive(Debug, Deserialize, Clone)]
+pub struct MfaConfig {
+    /// TOTPシークレットの暗号化鍵（未設定の場合はJWTの秘密鍵から導出する）
+    #[serde(default)]
+    pub encryption_key: String,
+    /// 認証アプリに表示される発行者名
+    #[serde(default = "default_mfa_issuer")]
+    pub issuer: String,
+}
+
+fn default_mfa_issuer() -> String {
+    "Mimo".to_string()
+}
+
+impl Default for MfaConfig {
+    fn default() -> Self {
+        Self {
+            encryption_key: String::new(),
+            issuer: default_mfa_issuer(),
+        }
+    }
+}
+
+impl MfaConfig {
+    /// 暗号化鍵（未設定の場合はJWTの秘密鍵を使う）
+    pub fn encryption_key_or<'a>(&'a self, jwt_secret: &'a str) -> &'a str {
+        if self.encryption_key.is_empty() {
+            jwt_secret
+        } else {
+            &self.encryption_key
+        }
+    }
+}
+
+/// WebAuthn（パスキー）の設定
+#[derive(Debug, Deserialize, Clone)]
+pub struct WebAuthnConfig {
+    /// RP ID（フロントエンドのドメイン。例: mimo.example.com）
+    pub rp_id: String,
+    /// 認証器に表示されるサービス名
+    pub rp_name: String,
+    /// 許可するオリジン（例: https://mimo.example.com）
+    pub origins: Vec<String>,
+}
+
+impl Default for WebAuthnConfig {
+    fn default() -> Self {
+        Self {
+            rp_id: "localhost".to_string(),
+            rp_name: "Mimo".to_string(),
+            origins: vec!["http://localhost:3000".to_string()],
+        }
+    }
+}
+
+/// メールのログインリンクの設定
+#[derive(Debug, Deserialize, Clone)]
+pub struct MagicLinkConfig {
+    /// メールに記載するリンク先（フロントエンドの画面。?token=... を付けて送る）
+    pub url: String,
+}
+
+impl Default for MagicLinkConfig {
+    fn default() -> Self {
+        Self {
+            url: "http://localhost:3000/auth/magic-link".to_string(),
+        }
+    }
+}
+
+/// ロックされたアカウントの解除リンクの設定
+#[derive(Debug, Deserialize, Clone)]
+pub struct AccountUnlockConfig {
+    /// メールに記載するリンク先（フロントエンドの画面。?token=... を付けて送る）
+    pub url: String,
+}
+
+impl Default for AccountUnlockConfig {
+    fn default() -> Self {
+        Self {
+            url: "http://localhost:3000/auth/unlock".to_string(),
+        }
+    }
+}
+
+/// アカウント削除の設定
+#[derive(Debug, Deserialize, Clone)]
+pub struct AccountDeletionConfig {
+    /// 削除を申請してからデータを完全に削除するまでの日数（この間はログインして取り消せる）
+    pub grace_period_days: i64,
+}
+
+impl Default for AccountDeletionConfig {
+    fn default() -> Self {
+        Self {
+            grace_period_days: 30,
+        }
+    }
+}
+
+/// 個人データのエクスポートの設定
+#[derive(Debug, Deserialize, Clone)]
+pub struct DataExportConfig {
+    /// 完了メールに記載するリンク先（フロントエンドの画面。?export_id=... を付けて送る）
+    pub url: String,
+    /// 作成したアーカイブをダウンロードできる日数
+    pub retention_days: i64,
+}
+
+impl Default for DataExportConfig {
+    fn default() -> Self {
+        Self {
+            url: "http://localhost:3000/settings/export".to_string(),
+            retention_days: 7,
+        }
+    }
+}
+
+/// 要約ジョブの設定
+#[derive(Debug, Deserialize, Clone)]
+pub struct SummaryJobConfig {
+    /// インスタンスごとに同時に実行するジョブの数
+    pub workers: usize,
+    /// ユーザーごとに同時に実行するジョブの数（全インスタンスの合計）
+    pub max_concurrent_per_user: i64,
+    /// 失敗したジョブを含めた最大の試行回数
+    pub max_attempts: i32,
+}
+
+impl Default for SummaryJobConfig {
+    fn default() -> Self {
+        Self {
+            workers: 4,
+            max_concurrent_per_user: 2,
+            max_attempts: 3,
+        }
+    }
+}
+
+/// OpenID Connect ログインの設定
+#[derive(Debug, Deserialize, Clone, Default)]
+pub struct OidcConfig {
+    /// 利用できるIDプロバイダー
+    #[serde(default)]
+    pub providers: Vec<OidcProviderConfig>,
+}
+
+/// OpenID Connect のIDプロバイダーの設定
+#[derive(Debug, Deserialize, Clone)]
+pub struct OidcProviderConfig {
+    /// プロバイダーの識別子（URLに使われる。例: google）
+    pub id: String,
+    /// ログイン画面に表示する名前（未設定の場合は識別子）
+    #[serde(default)]
+    pub name: String,
+    /// 発行者URL（/.well-known/openid-configuration の取得元）
+    pub issuer: String,
+    pub client_id: String,
+    #[serde(default)]
+    pub client_secret: String,
+    /// IDプロバイダーに登録したリダイレクトURI（フロントエンドのコールバック画面）
+    pub redirect_uri: String,
+    #[serde(default = "default_oidc_scopes")]
+    pub scopes: Vec<String>,
+}
+
+fn default_oidc_scopes() -> Vec<String> {
+    vec!["openid".to_string(), "email".to_string(), "profile".to_string()]
+}
+
+impl OidcConfig {
+    /// 環境変数からIDプロバイダーを読み込む
+    ///
+    /// OIDC_PROVIDERS にカンマ区切りで識別子を並べ、識別子ごとに
+    /// OIDC_<識別子>_ISSUER などを設定する（OIDC_PROVIDERS が未設定・空の場合は None）
+    fn from_env() -> anyhow::Result<Option<Self>> {
+        let ids = env::var("OIDC_PROVIDERS").unwrap_or_default();
+        if ids.trim().is_empty() {
+            return Ok(None);
+        }
+
+        let mut providers = Vec::new();
+        for id in ids.split(',').map(str::trim).filter(|id| !id.is_empty()) {
+            let prefix = format!("OIDC_{}", id.to_uppercase().replace('-', "_"));
+            let var = |name: &str| env::var(format!("{}_{}", prefix, name));
+            providers.push(OidcProviderConfig {
+                id: id.to_string(),
+                name: var("NAME").unwrap_or_default(),
+                issuer: var("ISSUER")
+                    .with_context(|| format!("{}_ISSUER must be set", prefix))?,
+                client_id: var("CLIENT_ID")
+                    .with_context(|| format!("{}_CLIENT_ID must be set", prefix))?,
+                client_secret: var("CLIENT_SECRET").unwrap_or_default(),
+                redirect_uri: var("REDIRECT_URI")
+                    .with_context(|| format!("{}_REDIRECT_URI must be set", prefix))?,
+                scopes: var("SCOPES")
+                    .map(|s| s.split_whitespace().map(str::to_string).collect())
+                    .unwrap_or_else(|_| default_oidc_scopes()),
+            });
+        }
+
+        Ok(Some(Self { providers }))
+    }
+}
+
 impl Config {
     pub fn load() -> anyhow::Result<Self> {
         // 環境変数から読み込む場合
@@ -250,9 +748,19 @@ impl Config {
                 logging: LoggingConfig {
                     level: env::var("LOG_LEVEL").unwrap_or_else(|_| "info".to_string()),
                 },
-                jwt: JwtConfig {
-                    secret: env::var("JWT_SECRET")
-                        .context("JWT_SECRET must be set when using env vars")?,
+                jwt: {
+                    let signing_key_id = env::var("JWT_SIGNING_KEY_ID").unwrap_or_default();
+                    JwtConfig {
+                        // 公開鍵暗号の鍵で署名する場合は省略できる
+                        secret: if signing_key_id.is_empty() {
+                            env::var("JWT_SECRET")
+                                .context("JWT_SECRET must be set when using env vars")?
+                        } else {
+                            env::var("JWT_SECRET").unwrap_or_default()
+                        },
+                        signing_key_id,
+                        keys: JwtConfig::keys_from_env()?.unwrap_or_default(),
+                    }
                 },
                 email: EmailConfig {
                     smtp_host: env::var("SMTP_HOST")
@@ -273,6 +781,67 @@ impl Config {
                 gemini: GeminiConfig {
                     api_key: env::var("GEMINI_API_KEY").unwrap_or_else(|_| String::new()),
                 },
+                llm: LlmConfig::default().with_env()?,
+                trash: {
+                    let trash = TrashConfig {
+                        retention_days: env::var("TRASH_RETENTION_DAYS")
+                            .ok()
+                            .and_then(|s| s.parse().ok())
+                            .unwrap_or(TrashConfig::default().retention_days),
+                    };
+                    trash.validate()?;
+                    trash
+                },
+                mfa: MfaConfig {
+                    encryption_key: env::var("MFA_ENCRYPTION_KEY").unwrap_or_default(),
+                    issuer: env::var("MFA_ISSUER").unwrap_or_else(|_| default_mfa_issuer()),
+                },
+                webauthn: WebAuthnConfig {
+                    rp_id: env::var("WEBAUTHN_RP_ID")
+                        .unwrap_or_else(|_| WebAuthnConfig::default().rp_id),
+                    rp_name: env::var("WEBAUTHN_RP_NAME")
+                        .unwrap_or_else(|_| WebAuthnConfig::default().rp_name),
+                    origins: env::var("WEBAUTHN_ORIGINS")
+                        .map(|s| s.split(',').map(|s| s.trim().to_string()).collect())
+                        .unwrap_or_else(|_| WebAuthnConfig::default().origins),
+                },
+                oidc: OidcConfig::from_env()?.unwrap_or_default(),
+                magic_link: MagicLinkConfig {
+                    url: env::var("MAGIC_LINK_URL")
+                        .unwrap_or_else(|_| MagicLinkConfig::default().url),
+                },
+                account_unlock: AccountUnlockConfig {
+                    url: env::var("ACCOUNT_UNLOCK_URL")
+                        .unwrap_or_else(|_| AccountUnlockConfig::default().url),
+                },
+                account_deletion: AccountDeletionConfig {
+                    grace_period_days: env::var("ACCOUNT_DELETION_GRACE_DAYS")
+                        .ok()
+                        .and_then(|s| s.parse().ok())
+                        .unwrap_or(AccountDeletionConfig::default().grace_period_days),
+                },
+                data_export: DataExportConfig {
+                    url: env::var("DATA_EXPORT_URL")
+                        .unwrap_or_else(|_| DataExportConfig::default().url),
+                    retention_days: env::var("DATA_EXPORT_RETENTION_DAYS")
+                        .ok()
+                        .and_then(|s| s.parse().ok())
+                        .unwrap_or(DataExportConfig::default().retention_days),
+                },
+                summary_jobs: SummaryJobConfig {
+                    workers: env::var("SUMMARY_JOB_WORKERS")
+                        .ok()
+                        .and_then(|s| s.parse().ok())
+                        .unwrap_or(SummaryJobConfig::default().workers),
+                    max_concurrent_per_user: env::var("SUMMARY_JOB_MAX_CONCURRENT_PER_USER")
+                        .ok()
+                        .and_then(|s| s.parse().ok())
+                        .unwrap_or(SummaryJobConfig::default().max_concurrent_per_user),
+                    max_attempts: env::var("SUMMARY_JOB_MAX_ATTEMPTS")
+                        .ok()
+                        .and_then(|s| s.parse().ok())
+                        .unwrap_or(SummaryJobConfig::default().max_attempts),
+                },
             });
         }
 
@@ -288,6 +857,12 @@ impl Config {
         if let Ok(secret) = env::var("JWT_SECRET") {
             config.jwt.secret = secret;
         }
+        if let Ok(signing_key_id) = env::var("JWT_SIGNING_KEY_ID") {
+            config.jwt.signing_key_id = signing_key_id;
+        }
+        if let Some(keys) = JwtConfig::keys_from_env()? {
+            config.jwt.keys = keys;
+        }
         if let Ok(host) = env::var("SMTP_HOST") {
             config.email.smtp_host = host;
         }
@@ -311,7 +886,86 @@ impl Config {
         if let Ok(api_key) = env::var("GEMINI_API_KEY") {
             config.gemini.api_key = api_key;
         }
+        config.llm = config.llm.with_env()?;
+        if let Ok(days) = env::var("TRASH_RETENTION_DAYS")
+            && let Ok(days) = days.parse()
+        {
+            config.trash.retention_days = days;
+        }
+        if let Ok(key) = env::var("MFA_ENCRYPTION_KEY") {
+            config.mfa.encryption_key = key;
+        }
+        if let Ok(issuer) = env::var("MFA_ISSUER") {
+            config.mfa.issuer = issuer;
+        }
+        if let Ok(rp_id) = env::var("WEBAUTHN_RP_ID") {
+            config.webauthn.rp_id = rp_id;
+        }
+        if let Ok(rp_name) = env::var("WEBAUTHN_RP_NAME") {
+            config.webauthn.rp_name = rp_name;
+        }
+        if let Ok(origins) = env::var("WEBAUTHN_ORIGINS") {
+            config.webauthn.origins = origins.split(',').map(|s| s.trim().to_string()).collect();
+        }
+        if let Some(oidc) = OidcConfig::from_env()? {
+            config.oidc = oidc;
+        }
+        if let Ok(url) = env::var("MAGIC_LINK_URL") {
+            config.magic_link.url = url;
+        }
+        if let Ok(url) = env::var("ACCOUNT_UNLOCK_URL") {
+            config.account_unlock.url = url;
+        }
+        if let Ok(days) = env::var("ACCOUNT_DELETION_GRACE_DAYS")
+            && let Ok(days) = days.parse()
+        {
+            config.account_deletion.grace_period_days = days;
+        }
+        if let Ok(url) = env::var("DATA_EXPORT_URL") {
+            config.data_export.url = url;
+        }
+        if let Ok(days) = env::var("DATA_EXPORT_RETENTION_DAYS")
+            && let Ok(days) = days.parse()
+        {
+            config.data_export.retention_days = days;
+        }
+        if let Ok(workers) = env::var("SUMMARY_JOB_WORKERS")
+            && let Ok(workers) = workers.parse()
+        {
+            config.summary_jobs.workers = workers;
+        }
+        if let Ok(max) = env::var("SUMMARY_JOB_MAX_CONCURRENT_PER_USER")
+            && let Ok(max) = max.parse()
+        {
+            config.summary_jobs.max_concurrent_per_user = max;
+        }
+        if let Ok(max) = env::var("SUMMARY_JOB_MAX_ATTEMPTS")
+            && let Ok(max) = max.parse()
+        {
+            config.summary_jobs.max_attempts = max;
+        }
+
+        config.trash.validate()?;
 
         Ok(config)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn trash_retention_days_accepts_values_in_range() {
+        for days in [1, 30, 3650] {
+            assert!(TrashConfig { retention_days: days }.validate().is_ok());
+        }
+    }
+
+    #[test]
+    fn trash_retention_days_rejects_values_out_of_range() {
+        for days in [i64::MIN, -1, 0, 3651, i64::MAX] {
+            assert!(TrashConfig { retention_days: days }.validate().is_err());
+        }
+    }
+}
@@ -16,6 +16,7 @@ pub enum AppError {
     ConfigError(String), // APIキー設定エラー
     AuthenticationError(String),
     Forbidden(String),
+    Locked(String), // ログイン失敗の繰り返しによるアカウントのロック
 }
 
 impl std::fmt::Display for AppError {
@@ -30,6 +31,7 @@ impl std::fmt::Display for AppError {
             AppError::ConfigError(msg) => write!(f, "Configuration error: {}", msg), // APIキー設定エラー
             AppError::AuthenticationError(msg) => write!(f, "Authentication error: {}", msg),
             AppError::Forbidden(msg) => write!(f, "Forbidden: {}", msg),
+            AppError::Locked(msg) => write!(f, "Locked: {}", msg),
         }
     }
 }
@@ -53,6 +55,7 @@ impl IntoResponse for AppError {
             AppError::ExternalServiceError(msg) => (StatusCode::BAD_GATEWAY, msg),
             AppError::AuthenticationError(msg) => (StatusCode::UNAUTHORIZED, msg),
             AppError::Forbidden(msg) => (StatusCode::FORBIDDEN, msg),
+            AppError::Locked(msg) => (StatusCode::LOCKED, msg),
         };
 
         (status, Json(ErrorResponse { error: message })).into_response()
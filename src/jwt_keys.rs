@@ -0,0 +1,275 @@
+//! JWTの署名鍵・検証鍵
+//!
+//! 共通鍵（HS256）に加えて、公開鍵暗号（RS256 / EdDSA）の鍵を鍵ID（kid）で複数登録できる。
+//! 署名には1つの鍵だけを使い、登録済みのすべての鍵で検証するため、
+//! 署名鍵を切り替えても古い鍵で署名されたトークンは有効期限まで使える。
+
+use crate::config::{JwtConfig, JwtKeyConfig};
+use crate::error::{AppError, Result};
+use anyhow::{Context, bail};
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use jsonwebtoken::errors::ErrorKind;
+use jsonwebtoken::jwk::{
+    AlgorithmParameters, CommonParameters, EllipticCurve, Jwk, JwkSet, KeyAlgorithm,
+    OctetKeyPairParameters, OctetKeyPairType, PublicKeyUse, RSAKeyParameters, RSAKeyType,
+};
+use jsonwebtoken::{
+    Algorithm, DecodingKey, EncodingKey, Header, TokenData, Validation, decode, decode_header,
+    encode,
+};
+use rsa::pkcs1::{DecodeRsaPrivateKey, DecodeRsaPublicKey, EncodeRsaPrivateKey};
+use rsa::pkcs8::{DecodePrivateKey, DecodePublicKey};
+use rsa::traits::PublicKeyParts;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use std::fs;
+
+/// RSA鍵の最小ビット数
+const RSA_MIN_BITS: usize = 2048;
+
+/// 署名鍵（鍵IDがない場合は共通鍵）
+struct SigningKey {
+    kid: Option<String>,
+    algorithm: Algorithm,
+    key: EncodingKey,
+}
+
+/// 検証鍵（公開鍵暗号の鍵は JWKS として公開する）
+struct VerificationKey {
+    kid: Option<String>,
+    algorithm: Algorithm,
+    key: DecodingKey,
+    jwk: Option<Jwk>,
+}
+
+pub struct JwtKeys {
+    signing: SigningKey,
+    verification: Vec<VerificationKey>,
+}
+
+impl JwtKeys {
+    /// 設定から鍵を読み込む
+    ///
+    /// # Errors
+    /// 鍵の形式が不正な場合や、署名鍵が見つからない場合にエラーを返す
+    pub fn from_config(config: &JwtConfig) -> anyhow::Result<Self> {
+        let mut verification = Vec::new();
+
+        // 共通鍵は鍵IDなしのトークンの検証に使う（公開鍵暗号への移行前に発行されたトークンを含む）
+        if !config.secret.is_empty() {
+            verification.push(VerificationKey {
+                kid: None,
+                algorithm: Algorithm::HS256,
+                key: DecodingKey::from_secret(config.secret.as_bytes()),
+                jwk: None,
+            });
+        }
+
+        let mut signing = None;
+        for key_config in &config.keys {
+            if key_config.id.is_empty() {
+                bail!("JWT key id must not be empty");
+            }
+            if verification
+                .iter()
+                .any(|key| key.kid.as_deref() == Some(key_config.id.as_str()))
+            {
+                bail!("Duplicate JWT key id: {}", key_config.id);
+            }
+
+            let loaded = load_key(key_config)
+                .with_context(|| format!("Failed to load JWT key '{}'", key_config.id))?;
+
+            if key_config.id == config.signing_key_id {
+                let key = loaded.encoding_key.with_context(|| {
+                    format!("JWT signing key '{}' has no private key", key_config.id)
+                })?;
+                signing = Some(SigningKey {
+                    kid: Some(key_config.id.clone()),
+                    algorithm: loaded.algorithm,
+                    key,
+                });
+            }
+
+            verification.push(VerificationKey {
+                kid: Some(key_config.id.clone()),
+                algorithm: loaded.algorithm,
+                key: DecodingKey::from_jwk(&loaded.jwk)
+                    .with_context(|| format!("Invalid public key for '{}'", key_config.id))?,
+                jwk: Some(loaded.jwk),
+            });
+        }
+
+        let signing = if config.signing_key_id.is_empty() {
+            if config.secret.is_empty() {
+                bail!("Either JWT secret or JWT signing key id must be set");
+            }
+            SigningKey {
+                kid: None,
+                algorithm: Algorithm::HS256,
+                key: EncodingKey::from_secret(config.secret.as_bytes()),
+            }
+        } else {
+            signing.with_context(|| {
+                format!("JWT signing key '{}' is not configured", config.signing_key_id)
+            })?
+        };
+
+        Ok(Self {
+            signing,
+            verification,
+        })
+    }
+
+    /// 署名鍵の鍵ID（共通鍵の場合は None）
+    pub fn signing_key_id(&self) -> Option<&str> {
+        self.signing.kid.as_deref()
+    }
+
+    /// クレームに署名してトークンを発行
+    pub fn sign<T: Serialize>(&self, claims: &T) -> Result<String> {
+        let mut header = Header::new(self.signing.algorithm);
+        header.kid = self.signing.kid.clone();
+        encode(&header, claims, &self.signing.key)
+            .map_err(|e| AppError::EnvironmentError(e.to_string()))
+    }
+
+    /// トークンの署名・有効期限・対象者を検証してクレームを取り出す
+    /// ヘッダーの鍵IDで検証鍵を選び、鍵に登録されたアルゴリズム以外は受け付けない
+    pub fn verify<T: DeserializeOwned>(
+        &self,
+        token: &str,
+    ) -> jsonwebtoken::errors::Result<TokenData<T>> {
+        let header = decode_header(token)?;
+        let key = self
+            .verification
+            .iter()
+            .find(|key| key.kid == header.kid)
+            .ok_or(ErrorKind::InvalidSignature)?;
+        if key.algorithm != header.alg {
+            return Err(ErrorKind::InvalidAlgorithm.into());
+        }
+
+        let mut validation = Validation::new(key.algorithm);
+        validation.set_audience(&["mimo-client"]);
+        decode::<T>(token, &key.key, &validation)
+    }
+
+    /// 公開鍵の一覧（/.well-known/jwks.json）
+    pub fn jwks(&self) -> JwkSet {
+        JwkSet {
+            keys: self
+                .verification
+                .iter()
+                .filter_map(|key| key.jwk.clone())
+                .collect(),
+        }
+    }
+}
+
+/// 読み込んだ公開鍵暗号の鍵
+struct LoadedKey {
+    algorithm: Algorithm,
+    jwk: Jwk,
+    /// 秘密鍵がある場合のみ
+    encoding_key: Option<EncodingKey>,
+}
+
+/// 鍵を読み込み、公開鍵をJWKに変換する（秘密鍵がある場合は公開鍵を秘密鍵から導出する）
+fn load_key(config: &JwtKeyConfig) -> anyhow::Result<LoadedKey> {
+    let private_pem = read_pem(&config.private_key, &config.private_key_file)?;
+    let public_pem = read_pem(&config.public_key, &config.public_key_file)?;
+
+    match config.algorithm.as_str() {
+        "RS256" => {
+            let (public_key, encoding_key) = if let Some(pem) = &private_pem {
+                let private_key = rsa::RsaPrivateKey::from_pkcs8_pem(pem)
+                    .or_else(|_| rsa::RsaPrivateKey::from_pkcs1_pem(pem))
+                    .context("Invalid RSA private key (PKCS#8 or PKCS#1 PEM expected)")?;
+                let der = private_key
+                    .to_pkcs1_der()
+                    .context("Failed to encode RSA private key")?;
+                (
+                    private_key.to_public_key(),
+                    Some(EncodingKey::from_rsa_der(der.as_bytes())),
+                )
+            } else if let Some(pem) = &public_pem {
+                let public_key = rsa::RsaPublicKey::from_public_key_pem(pem)
+                    .or_else(|_| rsa::RsaPublicKey::from_pkcs1_pem(pem))
+                    .context("Invalid RSA public key (SPKI or PKCS#1 PEM expected)")?;
+                (public_key, None)
+            } else {
+                bail!("Either a private key or a public key must be set");
+            };
+
+            if public_key.size() * 8 < RSA_MIN_BITS {
+                bail!("RSA key must be at least {} bits", RSA_MIN_BITS);
+            }
+
+            Ok(LoadedKey {
+                algorithm: Algorithm::RS256,
+                jwk: Jwk {
+                    common: common_parameters(&config.id, KeyAlgorithm::RS256),
+                    algorithm: AlgorithmParameters::RSA(RSAKeyParameters {
+                        key_type: RSAKeyType::RSA,
+                        n: URL_SAFE_NO_PAD.encode(public_key.n().to_bytes_be()),
+                        e: URL_SAFE_NO_PAD.encode(public_key.e().to_bytes_be()),
+                    }),
+                },
+                encoding_key,
+            })
+        }
+        "EdDSA" => {
+            let (public_key, encoding_key) = if let Some(pem) = &private_pem {
+                let private_key = ed25519_dalek::SigningKey::from_pkcs8_pem(pem)
+                    .context("Invalid Ed25519 private key (PKCS#8 PEM expected)")?;
+                let encoding_key = EncodingKey::from_ed_pem(pem.as_bytes())
+                    .context("Invalid Ed25519 private key (PKCS#8 PEM expected)")?;
+                (private_key.verifying_key(), Some(encoding_key))
+            } else if let Some(pem) = &public_pem {
+                let public_key = ed25519_dalek::VerifyingKey::from_public_key_pem(pem)
+                    .context("Invalid Ed25519 public key (SPKI PEM expected)")?;
+                (public_key, None)
+            } else {
+                bail!("Either a private key or a public key must be set");
+            };
+
+            Ok(LoadedKey {
+                algorithm: Algorithm::EdDSA,
+                jwk: Jwk {
+                    common: common_parameters(&config.id, KeyAlgorithm::EdDSA),
+                    algorithm: AlgorithmParameters::OctetKeyPair(OctetKeyPairParameters {
+                        key_type: OctetKeyPairType::OctetKeyPair,
+                        curve: EllipticCurve::Ed25519,
+                        x: URL_SAFE_NO_PAD.encode(public_key.as_bytes()),
+                    }),
+                },
+                encoding_key,
+            })
+        }
+        other => bail!("Unsupported JWT algorithm: {} (RS256 or EdDSA)", other),
+    }
+}
+
+fn common_parameters(kid: &str, algorithm: KeyAlgorithm) -> CommonParameters {
+    CommonParameters {
+        public_key_use: Some(PublicKeyUse::Signature),
+        key_algorithm: Some(algorithm),
+        key_id: Some(kid.to_string()),
+        ..Default::default()
+    }
+}
+
+/// PEMを設定値またはファイルから読み込む（設定値を優先）
+fn read_pem(inline: &str, path: &str) -> anyhow::Result<Option<String>> {
+    if !inline.is_empty() {
+        // 環境変数では改行を \n と書けるようにする
+        return Ok(Some(inline.replace("\\n", "\n")));
+    }
+    if path.is_empty() {
+        return Ok(None);
+    }
+    fs::read_to_string(path)
+        .map(Some)
+        .with_context(|| format!("Failed to read key file: {}", path))
+}
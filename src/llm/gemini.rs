@@ -0,0 +1,98 @@
+use super::{LlmProvider, TextStream, sse_data};
+use crate::error::{AppError, Result};
+use futures::StreamExt;
+use reqwest::Client;
+use serde_json::json;
+
+const API_BASE_URL: &str = "https://generativelanguage.googleapis.com/v1beta/models";
+
+/// Google Gemini API
+pub struct GeminiProvider {
+    client: Client,
+    api_key: String,
+    model: String,
+}
+
+impl GeminiProvider {
+    pub fn new(client: Client, api_key: String, model: String) -> Self {
+        Self {
+            client,
+            api_key,
+            model,
+        }
+    }
+
+    /// method: generateContent / streamGenerateContent?alt=sse
+    async fn send(&self, method: &str, prompt: &str) -> Result<reqwest::Response> {
+        // APIキーがなくても起動はできるようにし、呼び出し時にエラーにする
+        if self.api_key.is_empty() {
+            return Err(AppError::ConfigError(
+                "GEMINI_API_KEY is not configured".to_string(),
+            ));
+        }
+
+        let response = self
+            .client
+            .post(format!("{}/{}:{}", API_BASE_URL, self.model, method))
+            .header("x-goog-api-key", &self.api_key)
+            .json(&json!({
+                "contents": [{
+                    "parts": [{ "text": prompt }]
+                }]
+            }))
+            .send()
+            .await
+            .map_err(|e| {
+                AppError::ExternalServiceError(format!("Failed to send request: {}", e))
+            })?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let error_text = response.text().await.unwrap_or_default();
+            return Err(AppError::ExternalServiceError(format!(
+                "Gemini API error: status={}, body={}",
+                status, error_text
+            )));
+        }
+        Ok(response)
+    }
+}
+
+/// 応答の最初の候補のテキスト（ストリーミングでは、前回からの差分）
+fn candidate_text(response_json: &serde_json::Value) -> Option<String> {
+    let parts = response_json["candidates"].get(0)?["content"]["parts"].as_array()?;
+    Some(parts.iter().filter_map(|p| p["text"].as_str()).collect())
+}
+
+#[async_trait::async_trait]
+impl LlmProvider for GeminiProvider {
+    fn model_id(&self) -> String {
+        format!("gemini/{}", self.model)
+    }
+
+    async fn generate(&self, prompt: &str) -> Result<String> {
+        let response = self.send("generateContent", prompt).await?;
+
+        let response_json: serde_json::Value = response.json().await.map_err(|e| {
+            AppError::ExternalServiceError(format!("Failed to parse response: {}", e))
+        })?;
+
+        candidate_text(&response_json).ok_or_else(|| {
+            AppError::ExternalServiceError("Invalid response format from Gemini".to_string())
+        })
+    }
+
+    async fn generate_stream(&self, prompt: &str) -> Result<TextStream> {
+        let response = self.send("streamGenerateContent?alt=sse", prompt).await?;
+
+        Ok(sse_data(response)
+            .map(|data| {
+                let chunk: serde_json::Value = serde_json::from_str(&data?).map_err(|e| {
+                    AppError::ExternalServiceError(format!("Failed to parse response: {}", e))
+                })?;
+                // 最後のチャンクなど、テキストを含まないチャンクもある
+                Ok(candidate_text(&chunk).unwrap_or_default())
+            })
+            .boxed())
+    }
+}
@@ -0,0 +1,52 @@
+use super::{LlmProvider, TextStream};
+use crate::error::Result;
+use futures::{StreamExt, stream};
+use sha2::{Digest, Sha256};
+
+/// 外部に接続しないプロバイダー（テスト・オフライン用）
+///
+/// 同じプロンプトには常に同じ応答を返す。応答はプロンプトのハッシュと末尾部分
+/// （要約ではメモの内容）で、
+/// どの候補にも一致しないため自動タグ付けではタグが付かない。
+pub struct MockProvider {
+    model: String,
+}
+
+impl MockProvider {
+    pub fn new(model: String) -> Self {
+        Self { model }
+    }
+}
+
+/// 応答に含めるプロンプトの末尾の文字数
+const EXCERPT_CHARS: usize = 200;
+/// ストリーミングで1回に返す文字数
+const STREAM_CHUNK_CHARS: usize = 16;
+
+#[async_trait::async_trait]
+impl LlmProvider for MockProvider {
+    fn model_id(&self) -> String {
+        format!("mock/{}", self.model)
+    }
+
+    async fn generate(&self, prompt: &str) -> Result<String> {
+        let digest = hex::encode(Sha256::digest(prompt.as_bytes()));
+        let skip = prompt.chars().count().saturating_sub(EXCERPT_CHARS);
+        let excerpt: String = prompt.chars().skip(skip).collect();
+        Ok(format!(
+            "# Mock response ({}, {})\n\n{}",
+            self.model,
+            &digest[..12],
+            excerpt
+        ))
+    }
+
+    async fn generate_stream(&self, prompt: &str) -> Result<TextStream> {
+        let chars: Vec<char> = self.generate(prompt).await?.chars().collect();
+        let chunks: Vec<Result<String>> = chars
+            .chunks(STREAM_CHUNK_CHARS)
+            .map(|chunk| Ok(chunk.iter().collect()))
+            .collect();
+        Ok(stream::iter(chunks).boxed())
+    }
+}
@@ -0,0 +1,102 @@
+//! LLM（大規模言語モデル）の呼び出し
+//!
+//! 自動タグ付け・要約などの機能は `LlmProvider` を通してLLMを呼び出す。
+//! どのプロバイダー・モデルを使うかは機能ごとに Config の `llm` で選ぶ。
+
+mod gemini;
+mod mock;
+mod openai;
+pub mod tokens;
+
+use crate::config::{Config, LlmFeatureConfig, LlmProviderKind};
+use crate::error::{AppError, Result};
+use futures::stream::{self, BoxStream, StreamExt};
+use std::collections::VecDeque;
+use std::sync::Arc;
+
+pub use gemini::GeminiProvider;
+pub use mock::MockProvider;
+pub use openai::OpenAiCompatibleProvider;
+
+/// 生成されたテキストを少しずつ返すストリーム
+///
+/// ストリームを破棄すると、LLMへのリクエストも中断される。
+pub type TextStream = BoxStream<'static, Result<String>>;
+
+/// テキスト生成を行うLLM
+#[async_trait::async_trait]
+pub trait LlmProvider: Send + Sync {
+    /// プロバイダーとモデルを識別する名前（同じ名前なら同じプロンプトに同じ品質の応答を返す）
+    fn model_id(&self) -> String;
+
+    /// プロンプトに対する応答のテキストを生成
+    async fn generate(&self, prompt: &str) -> Result<String>;
+
+    /// プロンプトに対する応答のテキストを、生成されたところから順に返す
+    /// （ストリーミングに対応していないプロバイダーは、応答全体を1回で返す）
+    async fn generate_stream(&self, prompt: &str) -> Result<TextStream> {
+        let text = self.generate(prompt).await?;
+        Ok(stream::once(async move { Ok(text) }).boxed())
+    }
+}
+
+/// Server-Sent Events 形式のレスポンスから、各イベントの `data:` の内容を取り出す
+fn sse_data(response: reqwest::Response) -> TextStream {
+    let state = (response.bytes_stream(), Vec::new(), VecDeque::new());
+    stream::unfold(state, |(mut bytes, mut buffer, mut pending)| async move {
+        loop {
+            if let Some(data) = pending.pop_front() {
+                return Some((Ok(data), (bytes, buffer, pending)));
+            }
+            match bytes.next().await {
+                Some(Ok(chunk)) => {
+                    buffer.extend_from_slice(&chunk);
+                    // 行の途中で分割されていることがあるため、改行までを取り出す
+                    while let Some(pos) = buffer.iter().position(|&b| b == b'\n') {
+                        let line: Vec<u8> = buffer.drain(..=pos).collect();
+                        let line = String::from_utf8_lossy(&line);
+                        if let Some(data) = line.trim_end().strip_prefix("data:") {
+                            pending.push_back(data.trim_start().to_string());
+                        }
+                    }
+                }
+                Some(Err(e)) => {
+                    let error = AppError::ExternalServiceError(format!(
+                        "Failed to read streaming response: {}",
+                        e
+                    ));
+                    return Some((Err(error), (bytes, Vec::new(), VecDeque::new())));
+                }
+                None => return None,
+            }
+        }
+    })
+    .boxed()
+}
+
+/// 機能の設定に従ってプロバイダーを作成
+pub fn provider_for(
+    feature: &LlmFeatureConfig,
+    config: &Config,
+    client: &reqwest::Client,
+) -> Arc<dyn LlmProvider> {
+    let model = feature.model().to_string();
+    match feature.provider {
+        LlmProviderKind::Gemini => Arc::new(GeminiProvider::new(
+            client.clone(),
+            config.gemini.api_key.clone(),
+            model,
+        )),
+        LlmProviderKind::OpenAi => Arc::new(OpenAiCompatibleProvider::new(
+            client.clone(),
+            config.llm.openai.clone(),
+            model,
+        )),
+        LlmProviderKind::Local => Arc::new(OpenAiCompatibleProvider::new(
+            client.clone(),
+            config.llm.local.clone(),
+            model,
+        )),
+        LlmProviderKind::Mock => Arc::new(MockProvider::new(model)),
+    }
+}
@@ -0,0 +1,97 @@
+use super::{LlmProvider, TextStream, sse_data};
+use crate::config::OpenAiCompatibleConfig;
+use crate::error::{AppError, Result};
+use futures::StreamExt;
+use reqwest::Client;
+use serde_json::json;
+
+/// OpenAI互換の Chat Completions API（OpenAI・llama.cpp・Ollama・vLLMなど）
+pub struct OpenAiCompatibleProvider {
+    client: Client,
+    config: OpenAiCompatibleConfig,
+    model: String,
+}
+
+impl OpenAiCompatibleProvider {
+    pub fn new(client: Client, config: OpenAiCompatibleConfig, model: String) -> Self {
+        Self {
+            client,
+            config,
+            model,
+        }
+    }
+}
+
+impl OpenAiCompatibleProvider {
+    async fn send(&self, prompt: &str, stream: bool) -> Result<reqwest::Response> {
+        let url = format!(
+            "{}/chat/completions",
+            self.config.base_url.trim_end_matches('/')
+        );
+        let mut request = self.client.post(&url).json(&json!({
+            "model": self.model,
+            "messages": [{ "role": "user", "content": prompt }],
+            "stream": stream,
+        }));
+        // ローカルのサーバーはAPIキーを要求しないことが多い
+        if !self.config.api_key.is_empty() {
+            request = request.bearer_auth(&self.config.api_key);
+        }
+
+        let response = request.send().await.map_err(|e| {
+            AppError::ExternalServiceError(format!("Failed to send request to {}: {}", url, e))
+        })?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let error_text = response.text().await.unwrap_or_default();
+            return Err(AppError::ExternalServiceError(format!(
+                "LLM API error: status={}, body={}",
+                status, error_text
+            )));
+        }
+        Ok(response)
+    }
+}
+
+#[async_trait::async_trait]
+impl LlmProvider for OpenAiCompatibleProvider {
+    fn model_id(&self) -> String {
+        format!("{}/{}", self.config.base_url.trim_end_matches('/'), self.model)
+    }
+
+    async fn generate(&self, prompt: &str) -> Result<String> {
+        let response = self.send(prompt, false).await?;
+
+        let response_json: serde_json::Value = response.json().await.map_err(|e| {
+            AppError::ExternalServiceError(format!("Failed to parse response: {}", e))
+        })?;
+
+        response_json["choices"]
+            .get(0)
+            .and_then(|c| c["message"]["content"].as_str())
+            .map(str::to_string)
+            .ok_or_else(|| {
+                AppError::ExternalServiceError("Invalid response format from LLM API".to_string())
+            })
+    }
+
+    async fn generate_stream(&self, prompt: &str) -> Result<TextStream> {
+        let response = self.send(prompt, true).await?;
+
+        Ok(sse_data(response)
+            // 最後に [DONE] が送られる
+            .take_while(|data| futures::future::ready(!matches!(data, Ok(d) if d == "[DONE]")))
+            .map(|data| {
+                let chunk: serde_json::Value = serde_json::from_str(&data?).map_err(|e| {
+                    AppError::ExternalServiceError(format!("Failed to parse response: {}", e))
+                })?;
+                Ok(chunk["choices"]
+                    .get(0)
+                    .and_then(|c| c["delta"]["content"].as_str())
+                    .unwrap_or_default()
+                    .to_string())
+            })
+            .boxed())
+    }
+}
@@ -0,0 +1,96 @@
+//! トークン数の見積もり
+//!
+//! プロバイダーごとのトークナイザーは使わず、文字の種類ごとの目安で多めに見積もる。
+//! 英数字は4文字で約1トークン、かな・記号は1文字で約1トークン、漢字は1文字で1〜2トークンになることが多い。
+
+/// 1トークンを10として数えた、文字ごとのコスト
+fn char_cost(c: char) -> usize {
+    match c {
+        c if c.is_ascii() => 3,
+        // ひらがな・カタカナ・全角記号・半角カナ
+        '\u{3000}'..='\u{30FF}' | '\u{FF00}'..='\u{FFEF}' => 10,
+        // 漢字
+        '\u{3400}'..='\u{4DBF}' | '\u{4E00}'..='\u{9FFF}' | '\u{F900}'..='\u{FAFF}' => 15,
+        // 絵文字など（UTF-8で4バイトの文字）
+        c if c.len_utf8() >= 4 => 20,
+        _ => 10,
+    }
+}
+
+/// テキストのトークン数の見積もり
+pub fn estimate_tokens(text: &str) -> usize {
+    text.chars().map(char_cost).sum::<usize>().div_ceil(10)
+}
+
+/// 切り詰めたことを示す末尾の記号
+const ELLIPSIS: char = '…';
+
+/// 見積もりが max_tokens 以下になるよう末尾を切り詰める（末尾の記号も含めて max_tokens 以下）
+pub fn truncate_to_tokens(text: &str, max_tokens: usize) -> String {
+    if estimate_tokens(text) <= max_tokens {
+        return text.to_string();
+    }
+    if max_tokens == 0 {
+        return String::new();
+    }
+
+    let budget = (max_tokens * 10).saturating_sub(char_cost(ELLIPSIS));
+    let mut cost = 0;
+    let mut end = 0;
+    for (i, c) in text.char_indices() {
+        cost += char_cost(c);
+        if cost > budget {
+            break;
+        }
+        end = i + c.len_utf8();
+    }
+    format!("{}{}", &text[..end], ELLIPSIS)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn estimates_ascii_as_about_four_chars_per_token() {
+        assert_eq!(estimate_tokens(""), 0);
+        assert_eq!(estimate_tokens("abc"), 1);
+        assert_eq!(estimate_tokens("hello world!"), 4);
+    }
+
+    #[test]
+    fn estimates_japanese_by_character_kind() {
+        // ひらがな・カタカナは1文字1トークン
+        assert_eq!(estimate_tokens("あいう"), 3);
+        assert_eq!(estimate_tokens("カタカナ"), 4);
+        // 漢字は1文字1.5トークン
+        assert_eq!(estimate_tokens("日本語"), 5);
+        assert_eq!(estimate_tokens("今日は晴れ"), 7);
+    }
+
+    #[test]
+    fn estimates_emoji_as_two_tokens() {
+        assert_eq!(estimate_tokens("😀"), 2);
+        assert_eq!(estimate_tokens("😀😀a"), 5);
+    }
+
+    #[test]
+    fn truncate_keeps_text_within_budget() {
+        assert_eq!(truncate_to_tokens("あいう", 3), "あいう");
+        assert_eq!(truncate_to_tokens("あいうえお", 3), "あい…");
+        assert_eq!(truncate_to_tokens("", 0), "");
+        assert_eq!(truncate_to_tokens("あ", 0), "");
+    }
+
+    #[test]
+    fn truncate_cuts_on_char_boundary() {
+        // 途中で切れると不正なUTF-8になるマルチバイト文字だけのテキスト
+        let text = "漢字😀漢字😀漢字";
+        for max in 0..=estimate_tokens(text) {
+            let truncated = truncate_to_tokens(text, max);
+            let body = truncated.strip_suffix('…').unwrap_or(&truncated);
+            assert!(text.starts_with(body));
+            assert!(estimate_tokens(&truncated) <= max);
+        }
+    }
+}
@@ -7,15 +7,25 @@ use std::sync::Arc;
 mod auth;
 mod config;
 mod error;
+mod jwt_keys;
+mod llm;
 mod repositories;
 mod routes;
 mod server;
 mod services;
+mod webauthn;
 
 use config::Config;
-use repositories::{MemoRepository, SummaryRepository, TagRepository};
+use repositories::{
+    DataExportRepository, JournalingRepository, MemoRepository, MfaRepository, MemoRevisionRepository, PersonalAccessTokenRepository, SearchIndexRepository,
+    ShareRepository, SummaryChunkRepository, SummaryJobRepository, SummaryRepository, TagRepository, WebAuthnRepository,
+};
 use server::AppState;
-use services::{AuthService, MemoService, SummaryService, TagService};
+use services::{
+    AccountDeletionService, AuthService, ExportService, JournalingService, MemoService, MfaService, OidcService,
+    PersonalAccessTokenService, RevocationCache, SearchService, ShareService, SummaryJobService, SummaryService,
+    TagService, WebAuthnService,
+};
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
@@ -43,8 +53,9 @@ async fn main() -> anyhow::Result<()> {
     }
 
     println!("Connecting to PostgreSQL at {}", &postgres_url);
+    // 失効の通知の受信に1接続を占有する
     let pg_pool = PgPoolOptions::new()
-        .max_connections(5)
+        .max_connections(6)
         .connect(&postgres_url)
         .await
         .context("Failed to connect to PostgreSQL")?;
@@ -63,23 +74,90 @@ async fn main() -> anyhow::Result<()> {
         .context("Failed to connect to MongoDB")?;
     let mongo_db = mongo_client.database(&config.database.mongodb.db_name);
 
-    // JWT秘密鍵の読み込み（Configから）
-    println!("Loading JWT secret key...");
+    // JWTの署名鍵・検証鍵の読み込み（Configから）
+    println!("Loading JWT keys...");
+    let jwt_keys = Arc::new(
+        jwt_keys::JwtKeys::from_config(&config.jwt).context("Failed to load JWT keys")?,
+    );
+    match jwt_keys.signing_key_id() {
+        Some(kid) => println!("Signing JWTs with key '{}'", kid),
+        None => println!("Signing JWTs with the shared secret (HS256)"),
+    }
     let jwt_secret = config.jwt.secret.clone();
 
+    // MongoDBインデックスの作成
+    let memo_repo = Arc::new(MemoRepository::new(mongo_db.clone()));
+    memo_repo
+        .ensure_indexes()
+        .await
+        .context("Failed to create MongoDB indexes")?;
+    let revision_repo = Arc::new(MemoRevisionRepository::new(mongo_db.clone()));
+    revision_repo
+        .ensure_indexes()
+        .await
+        .context("Failed to create MongoDB indexes")?;
+    let search_index_repo = Arc::new(SearchIndexRepository::new(mongo_db.clone()));
+    search_index_repo
+        .ensure_indexes()
+        .await
+        .context("Failed to create MongoDB indexes")?;
+    let share_repo = Arc::new(ShareRepository::new(mongo_db.clone()));
+    share_repo
+        .ensure_indexes()
+        .await
+        .context("Failed to create MongoDB indexes")?;
+    let summary_repo = Arc::new(SummaryRepository::new(mongo_db.clone()));
+    let summary_chunk_repo = Arc::new(SummaryChunkRepository::new(mongo_db.clone()));
+    summary_chunk_repo
+        .ensure_indexes()
+        .await
+        .context("Failed to create MongoDB indexes")?;
+
+    // LLMプロバイダーの構築（機能ごとに選ぶ）
+    let http_client = reqwest::Client::new();
+    let tagging_llm = llm::provider_for(&config.llm.tagging, &config, &http_client);
+    let summary_llm = llm::provider_for(&config.llm.summary, &config, &http_client);
+    println!(
+        "Using LLM {:?} ({}) for tagging and {:?} ({}) for summaries",
+        config.llm.tagging.provider,
+        config.llm.tagging.model(),
+        config.llm.summary.provider,
+        config.llm.summary.model()
+    );
+
     // サービスの構築
     println!("Constructing services...");
-    let tag_service = Arc::new(TagService::new(
-        Arc::new(TagRepository::new(pg_pool.clone())),
-        config.gemini.api_key.clone(),
+    let tag_repo = Arc::new(TagRepository::new(pg_pool.clone()));
+    let tag_service = Arc::new(TagService::new(tag_repo.clone(), tagging_llm));
+    let search_service = Arc::new(SearchService::new(
+        search_index_repo,
+        memo_repo.clone(),
+        summary_repo.clone(),
     ));
     let memo_service = Arc::new(MemoService::new(
-        Arc::new(MemoRepository::new(mongo_db.clone())),
+        memo_repo.clone(),
+        revision_repo,
+        share_repo.clone(),
+        summary_chunk_repo.clone(),
         tag_service.clone(),
+        search_service.clone(),
+    ));
+    let share_rate_limiter = Arc::new(services::rate_limiter::ShareRateLimiter::new());
+    let share_service = Arc::new(ShareService::new(
+        share_repo,
+        memo_repo.clone(),
+        share_rate_limiter.clone(),
     ));
+    let personal_access_token_service = Arc::new(PersonalAccessTokenService::new(Arc::new(
+        PersonalAccessTokenRepository::new(pg_pool.clone()),
+    )));
     let summary_service = Arc::new(SummaryService::new(
-        Arc::new(SummaryRepository::new(mongo_db.clone())),
-        Arc::new(MemoRepository::new(mongo_db.clone())),
+        summary_repo.clone(),
+        memo_repo.clone(),
+        search_service.clone(),
+        summary_chunk_repo,
+        summary_llm,
+        config.llm.summary.max_input_tokens(),
     ));
     let email_service = Arc::new(services::email_service::EmailService::from_config(
         &config.email.smtp_host,
@@ -92,38 +170,110 @@ async fn main() -> anyhow::Result<()> {
     let verification_store = Arc::new(services::verification_store::VerificationStore::new());
     let email_rate_limiter = Arc::new(services::rate_limiter::EmailRateLimiter::new());
     let auth_rate_limiter = Arc::new(services::rate_limiter::AuthRateLimiter::new());
+    if config.mfa.encryption_key.is_empty() {
+        anyhow::ensure!(
+            !jwt_secret.is_empty(),
+            "MFA_ENCRYPTION_KEY must be set when JWT_SECRET is not set"
+        );
+        println!("Warning: MFA_ENCRYPTION_KEY is not set; deriving the TOTP encryption key from JWT_SECRET");
+    }
+    let mfa_service = Arc::new(MfaService::new(
+        Arc::new(MfaRepository::new(pg_pool.clone())),
+        config.mfa.encryption_key_or(&jwt_secret),
+        config.mfa.issuer.clone(),
+    ));
+    let webauthn_service = Arc::new(WebAuthnService::new(
+        Arc::new(WebAuthnRepository::new(pg_pool.clone())),
+        webauthn::RelyingParty {
+            id: config.webauthn.rp_id.clone(),
+            name: config.webauthn.rp_name.clone(),
+            origins: config.webauthn.origins.clone(),
+        },
+    ));
+    let oidc_service = Arc::new(OidcService::new(config.oidc.providers.clone()));
+    let auth_repo = Arc::new(repositories::AuthRepository::new(pg_pool.clone()));
+    let revocation_cache = Arc::new(RevocationCache::new(auth_repo.clone()));
     let auth_service = Arc::new(AuthService::new(
-        Arc::new(repositories::AuthRepository::new(pg_pool.clone())),
+        auth_repo.clone(),
         tag_service.clone(),
-        jwt_secret.clone(),
-        email_service,
+        mfa_service.clone(),
+        webauthn_service.clone(),
+        jwt_keys.clone(),
+        email_service.clone(),
         verification_store,
         email_rate_limiter,
+        revocation_cache.clone(),
+        config.magic_link.url.clone(),
+        config.account_unlock.url.clone(),
+        config.account_deletion.grace_period_days,
+    ));
+    let export_service = Arc::new(ExportService::new(
+        Arc::new(DataExportRepository::new(pg_pool.clone())),
+        auth_repo.clone(),
+        tag_repo,
+        memo_repo.clone(),
+        summary_repo.clone(),
+        email_service,
+        config.data_export.url.clone(),
+        config.data_export.retention_days,
+    ));
+    let summary_job_service = Arc::new(SummaryJobService::new(
+        Arc::new(SummaryJobRepository::new(pg_pool.clone())),
+        summary_service.clone(),
+        config.summary_jobs.workers,
+        config.summary_jobs.max_concurrent_per_user,
+        config.summary_jobs.max_attempts,
+    ));
+    let journaling_service = Arc::new(JournalingService::new(
+        Arc::new(JournalingRepository::new(pg_pool.clone())),
+        memo_repo.clone(),
+        summary_service.clone(),
+    ));
+    let account_deletion_service = Arc::new(AccountDeletionService::new(
+        auth_repo.clone(),
+        memo_service.clone(),
+        summary_service.clone(),
+        search_service.clone(),
+        revocation_cache.clone(),
     ));
 
     // AppState の構築
     let state = AppState {
-        jwt_decoding_key: auth::create_decoding_key(&jwt_secret),
+        jwt_keys,
         auth_service: auth_service.clone(),
+        export_service: export_service.clone(),
+        journaling_service: journaling_service.clone(),
         memo_service,
+        mfa_service,
+        oidc_service,
+        personal_access_token_service,
+        search_service,
+        share_service,
+        summary_job_service: summary_job_service.clone(),
         summary_service,
         tag_service,
+        webauthn_service,
         auth_rate_limiter,
+        share_rate_limiter: share_rate_limiter.clone(),
         config: Arc::new(config.clone()),
     };
     println!("Constructed AppState");
 
     // JWT Revocationのクリーンアップ（起動時）
     println!("Cleaning up expired JWT revocations...");
-    let auth_repo = repositories::AuthRepository::new(pg_pool.clone());
     if let Err(e) = auth_repo.cleanup_expired_tokens().await {
         eprintln!("Warning: Failed to cleanup expired tokens on startup: {}", e);
     } else {
         println!("Initial JWT revocation cleanup completed");
     }
 
+    // 失効キャッシュの読み込みと、他のインスタンスでの失効の反映を開始
+    // （読み込みが完了するまではPostgreSQLに問い合わせる）
+    tokio::spawn(async move { revocation_cache.run_sync().await });
+    println!("Revocation cache sync task started");
+
     // 定期的なクリーンアップタスクを起動（3日に1回）
-    let auth_repo_for_cleanup = repositories::AuthRepository::new(pg_pool.clone());
+    let auth_repo_for_cleanup = auth_repo.clone();
     tokio::spawn(async move {
         let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(3 * 24 * 60 * 60));
         loop {
@@ -138,6 +288,106 @@ async fn main() -> anyhow::Result<()> {
     });
     println!("Scheduled JWT revocation cleanup task started (every 3 days)");
 
+    // ゴミ箱の定期削除タスクを起動（1日に1回）
+    let memo_service_for_purge = state.memo_service.clone();
+    let summary_service_for_purge = state.summary_service.clone();
+    let retention_days = config.trash.retention_days;
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(24 * 60 * 60));
+        loop {
+            interval.tick().await;
+            let cutoff = chrono::Utc::now() - chrono::Duration::days(retention_days);
+            println!("Running scheduled trash purge (before {})...", cutoff);
+            match memo_service_for_purge.purge_trash_before(cutoff).await {
+                Ok(count) => println!("Purged {} memos from trash", count),
+                Err(e) => eprintln!("Error during scheduled memo trash purge: {}", e),
+            }
+            match summary_service_for_purge.purge_trash_before(cutoff).await {
+                Ok(count) => println!("Purged {} summaries from trash", count),
+                Err(e) => eprintln!("Error during scheduled summary trash purge: {}", e),
+            }
+        }
+    });
+    println!(
+        "Scheduled trash purge task started (every day, retention {} days)",
+        retention_days
+    );
+
+    // 共有リンクのレート制限の状態を整理するタスクを起動（10分に1回）
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(10 * 60));
+        loop {
+            interval.tick().await;
+            share_rate_limiter.retain_recent();
+        }
+    });
+    println!("Share rate limiter cleanup task started (every 10 minutes)");
+
+    // 期限切れのエクスポートの削除タスクを起動（1時間に1回）
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(60 * 60));
+        loop {
+            interval.tick().await;
+            match export_service.cleanup().await {
+                Ok(count) => println!("Deleted {} expired data exports", count),
+                Err(e) => eprintln!("Error during data export cleanup: {}", e),
+            }
+        }
+    });
+    println!(
+        "Scheduled data export cleanup task started (every hour, retention {} days)",
+        config.data_export.retention_days
+    );
+
+    // 削除予定日時を過ぎたアカウントの削除タスクを起動（1時間に1回）
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(60 * 60));
+        loop {
+            interval.tick().await;
+            println!("Running scheduled account deletion...");
+            match account_deletion_service.purge_due_accounts().await {
+                Ok(count) => println!("Purged {} accounts", count),
+                Err(e) => eprintln!("Error during scheduled account deletion: {}", e),
+            }
+        }
+    });
+    println!(
+        "Scheduled account deletion task started (every hour, grace period {} days)",
+        config.account_deletion.grace_period_days
+    );
+
+    // 要約ジョブのワーカーと、中断されたジョブの再実行・古いジョブの削除タスクを起動（10分に1回）
+    summary_job_service.clone().start_workers();
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(10 * 60));
+        loop {
+            interval.tick().await;
+            match summary_job_service.cleanup().await {
+                Ok(count) => println!("Deleted {} finished summary jobs", count),
+                Err(e) => eprintln!("Error during summary job cleanup: {}", e),
+            }
+        }
+    });
+    println!(
+        "Summary job workers started ({} workers, {} concurrent jobs per user)",
+        config.summary_jobs.workers, config.summary_jobs.max_concurrent_per_user
+    );
+
+    // 自動ジャーナリングタスクを起動（1分に1回）
+    // 作成日時はPostgreSQLに保存しているため、停止中に過ぎた期間は再起動後に作成される
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(60));
+        loop {
+            interval.tick().await;
+            match journaling_service.run_due(chrono::Utc::now()).await {
+                Ok(0) => {}
+                Ok(count) => println!("Created {} journaling summaries", count),
+                Err(e) => eprintln!("Error during scheduled journaling: {}", e),
+            }
+        }
+    });
+    println!("Scheduled journaling task started (every minute)");
+
     // サーバー起動
     let addr: SocketAddr = format!("{}:{}", config.server.host, config.server.port)
         .parse()
@@ -1,9 +1,11 @@
+use crate::auth::IssuedRefreshToken;
 use crate::error::{AppError, Result};
 use argon2::{
     Argon2, PasswordHash, password_hash::{PasswordHasher, PasswordVerifier, SaltString, rand_core}
 };
 use serde::{Deserialize, Serialize};
 use sqlx::Row;
+use sqlx::postgres::PgListener;
 
 pub struct AuthRepository {
     pub pool: sqlx::PgPool,
@@ -15,7 +17,7 @@ impl AuthRepository {
     }
 
     // ヘルパーメソッド: パスワードをハッシュ化
-    fn hash_password(password: &str) -> Result<String> {
+    pub(crate) fn hash_password(password: &str) -> Result<String> {
         let argon2 = Argon2::default();
         let salt = SaltString::generate(&mut rand_core::OsRng);
         let hash = PasswordHasher::hash_password(&argon2, password.as_bytes(), &salt)
@@ -24,7 +26,7 @@ impl AuthRepository {
     }
 
     // ヘルパーメソッド: パスワードを検証
-    fn verify_password(password: &str, hash: &str) -> Result<()> {
+    pub(crate) fn verify_password(password: &str, hash: &str) -> Result<()> {
         let argon2 = Argon2::default();
         let parsed_hash = PasswordHash::new(hash)
             .map_err(|e| AppError::HashingError(e.to_string()))?;
@@ -57,9 +59,10 @@ impl AuthRepository {
     }
     
     /// ログアウト処理（JWTを無効化）
-    pub async fn logout(&self, jtis: Vec<String>) -> Result<()> {
-        for jti in jtis {
-            self.revoke_jwt(&jti, chrono::Utc::now()).await?;
+    /// 引数: (JTI, トークンの有効期限) のリスト
+    pub async fn logout(&self, tokens: Vec<(String, chrono::DateTime<chrono::Utc>)>) -> Result<()> {
+        for (jti, exp) in tokens {
+            self.revoke_jwt(&jti, exp).await?;
         }
         Ok(())
     }
@@ -105,9 +108,9 @@ pub struct UserCreateRequest {
     pub password: String,
 }
 
+/// メールアドレスは確認コードによる変更（update_email）でのみ変更する
 #[derive(Deserialize, Debug)]
 pub struct UserUpdateRequest {
-    pub email: Option<String>,
     pub display_name: Option<String>,
     pub password: Option<String>,
 }
@@ -173,10 +176,6 @@ impl AuthRepository {
         let mut query_parts = Vec::new();
         let mut bind_count = 1;
 
-        if req.email.is_some() {
-            query_parts.push(format!("email = ${}", bind_count));
-            bind_count += 1;
-        }
         if req.display_name.is_some() {
             query_parts.push(format!("display_name = ${}", bind_count));
             bind_count += 1;
@@ -200,9 +199,6 @@ impl AuthRepository {
 
         let mut query = sqlx::query(&query_str);
 
-        if let Some(email) = req.email {
-            query = query.bind(email);
-        }
         if let Some(display_name) = req.display_name {
             query = query.bind(display_name);
         }
@@ -228,18 +224,52 @@ impl AuthRepository {
         })
     }
 
-    /// ユーザーを削除（論理削除）
-    pub async fn delete_user(&self, user_id: &str) -> Result<()> {
-        sqlx::query("UPDATE users SET is_active = false, updated_at = $1 WHERE user_id = $2")
-            .bind(chrono::Utc::now())
-            .bind(user_id)
-            .execute(&self.pool)
-            .await
-            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
-        Ok(())
+    /// メールアドレスを変更（変更後のアドレスの確認が済んでから呼ぶ）
+    pub async fn update_email(&self, user_id: &str, email: &str) -> Result<UserResponse> {
+        sqlx::query_as::<_, UserResponse>(
+            "UPDATE users SET email = $1, updated_at = $2 WHERE user_id = $3 \
+             RETURNING user_id, email, display_name, created_at, updated_at, is_active",
+        )
+        .bind(email)
+        .bind(chrono::Utc::now())
+        .bind(user_id)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?
+        .ok_or_else(|| AppError::NotFound("User not found".to_string()))
     }
 }
 
+////////
+/// 失効関連の構造体
+////////
+/// 失効させたトークン（JTI）またはセッション（ファミリーID）
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct RevokedToken {
+    pub id: String,
+    /// これを過ぎると失効の記録は不要になる
+    pub expires_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// 失効の通知（migrations/20261017000008_revocation_notify.sql のトリガーが送信）
+#[derive(Debug, Deserialize)]
+pub struct RevocationNotification {
+    pub kind: RevocationKind,
+    pub id: String,
+    /// UNIX時刻（秒）
+    pub expires_at: i64,
+}
+
+#[derive(Debug, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum RevocationKind {
+    Jti,
+    Session,
+}
+
+/// 失効を通知するチャンネル
+pub const REVOCATION_CHANNEL: &str = "auth_revocations";
+
 ////////
 /// JWT管理メソッド
 ////////
@@ -257,6 +287,21 @@ impl AuthRepository {
         Ok(())
     }
 
+    /// 一度だけ使用できるJWTを使用済みにする（既に使用済みの場合は false）
+    pub async fn consume_jwt(&self, jti: &str, exp: chrono::DateTime<chrono::Utc>) -> Result<bool> {
+        let result = sqlx::query(
+            "INSERT INTO jwt_revocations (jti, expires_at, revoked_at) VALUES ($1, $2, now()) \
+             ON CONFLICT (jti) DO NOTHING",
+        )
+        .bind(jti)
+        .bind(exp)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
     /// JWTが無効化されているか確認
     pub async fn is_jwt_revoked(&self, jti: &str) -> Result<bool> {
         let row =
@@ -269,12 +314,747 @@ impl AuthRepository {
         Ok(row.0 > 0)
     }
 
+    /// アクセストークンが無効化されているか確認（JTI単位・セッション単位）
+    pub async fn is_access_token_revoked(&self, jti: &str, session_id: Option<&str>) -> Result<bool> {
+        let row = sqlx::query_as::<_, (bool,)>(
+            "SELECT EXISTS (SELECT 1 FROM jwt_revocations WHERE jti = $1) \
+             OR EXISTS (SELECT 1 FROM refresh_token_families WHERE family_id = $2 AND revoked_at IS NOT NULL)",
+        )
+        .bind(jti)
+        .bind(session_id)
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Ok(row.0)
+    }
+
+    /// 有効期限内の失効済みJTIの一覧（失効キャッシュの読み込み用）
+    pub async fn find_revoked_jtis(&self) -> Result<Vec<RevokedToken>> {
+        sqlx::query_as::<_, RevokedToken>(
+            "SELECT jti AS id, expires_at FROM jwt_revocations WHERE expires_at > now()",
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))
+    }
+
+    /// 有効期限内の失効済みセッションの一覧（失効キャッシュの読み込み用）
+    pub async fn find_revoked_sessions(&self) -> Result<Vec<RevokedToken>> {
+        sqlx::query_as::<_, RevokedToken>(
+            "SELECT family_id AS id, expires_at FROM refresh_token_families \
+             WHERE revoked_at IS NOT NULL AND expires_at > now()",
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))
+    }
+
+    /// 失効の通知を受け取る接続を開く
+    pub async fn listen_revocations(&self) -> Result<PgListener> {
+        let mut listener = PgListener::connect_with(&self.pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        listener
+            .listen(REVOCATION_CHANNEL)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        Ok(listener)
+    }
+
     /// 期限切れトークンをクリーンアップ
     pub async fn cleanup_expired_tokens(&self) -> Result<()> {
         sqlx::query("DELETE FROM jwt_revocations WHERE expires_at < now()")
             .execute(&self.pool)
             .await
             .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        // ファミリーを削除すると所属するリフレッシュトークンも削除される
+        sqlx::query("DELETE FROM refresh_token_families WHERE expires_at < now()")
+            .execute(&self.pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        Ok(())
+    }
+}
+
+/// リフレッシュトークンのローテーション結果
+pub enum RefreshRotation {
+    /// 新しいトークンに置き換えた
+    Rotated,
+    /// ローテーション済みのトークンが再提示された（ファミリーは失効させた）
+    ReuseDetected(RevokedToken),
+    /// 未登録のトークン、または失効済みのファミリー
+    Rejected,
+}
+
+////////
+/// リフレッシュトークンのファミリー管理メソッド
+////////
+impl AuthRepository {
+    /// ログイン時に新しいファミリーと最初のリフレッシュトークンを登録
+    pub async fn create_refresh_token_family(
+        &self,
+        family_id: &str,
+        user_id: &str,
+        token: &IssuedRefreshToken,
+    ) -> Result<()> {
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        sqlx::query(
+            "INSERT INTO refresh_token_families (family_id, user_id, expires_at) VALUES ($1, $2, $3)",
+        )
+        .bind(family_id)
+        .bind(user_id)
+        .bind(token.expires_at)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        sqlx::query("INSERT INTO refresh_tokens (jti, family_id, expires_at) VALUES ($1, $2, $3)")
+            .bind(&token.jti)
+            .bind(family_id)
+            .bind(token.expires_at)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        tx.commit()
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))
+    }
+
+    /// 提示されたリフレッシュトークンを新しいトークンに置き換える
+    /// ローテーション済みのトークンが再提示された場合はファミリー全体を失効させる
+    pub async fn rotate_refresh_token(
+        &self,
+        old_jti: &str,
+        family_id: &str,
+        user_id: &str,
+        new_token: &IssuedRefreshToken,
+    ) -> Result<RefreshRotation> {
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        // 同時リクエストで二重にローテーションしないよう行ロックを取る
+        let row = sqlx::query_as::<_, (bool, bool)>(
+            "SELECT t.rotated_at IS NOT NULL, f.revoked_at IS NOT NULL \
+             FROM refresh_tokens t JOIN refresh_token_families f ON f.family_id = t.family_id \
+             WHERE t.jti = $1 AND t.family_id = $2 AND f.user_id = $3 \
+             FOR UPDATE",
+        )
+        .bind(old_jti)
+        .bind(family_id)
+        .bind(user_id)
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        let outcome = match row {
+            None | Some((_, true)) => RefreshRotation::Rejected,
+            Some((true, false)) => {
+                let revoked = sqlx::query_as::<_, RevokedToken>(
+                    "UPDATE refresh_token_families SET revoked_at = now(), revoked_reason = 'reuse_detected' \
+                     WHERE family_id = $1 RETURNING family_id AS id, expires_at",
+                )
+                .bind(family_id)
+                .fetch_one(&mut *tx)
+                .await
+                .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+                RefreshRotation::ReuseDetected(revoked)
+            }
+            Some((false, false)) => {
+                sqlx::query("UPDATE refresh_tokens SET rotated_at = now() WHERE jti = $1")
+                    .bind(old_jti)
+                    .execute(&mut *tx)
+                    .await
+                    .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+                sqlx::query(
+                    "INSERT INTO refresh_tokens (jti, family_id, expires_at) VALUES ($1, $2, $3)",
+                )
+                .bind(&new_token.jti)
+                .bind(family_id)
+                .bind(new_token.expires_at)
+                .execute(&mut *tx)
+                .await
+                .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+                sqlx::query("UPDATE refresh_token_families SET expires_at = $1 WHERE family_id = $2")
+                    .bind(new_token.expires_at)
+                    .bind(family_id)
+                    .execute(&mut *tx)
+                    .await
+                    .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+                RefreshRotation::Rotated
+            }
+        };
+
+        tx.commit()
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        Ok(outcome)
+    }
+
+    /// リフレッシュトークンのJTIから、そのファミリーを失効させる
+    /// 戻り値: 失効させたファミリー（存在しない・失効済みの場合は None）
+    pub async fn revoke_refresh_token_family_by_jti(
+        &self,
+        jti: &str,
+        reason: &str,
+    ) -> Result<Option<RevokedToken>> {
+        sqlx::query_as::<_, RevokedToken>(
+            "UPDATE refresh_token_families SET revoked_at = now(), revoked_reason = $1 \
+             WHERE revoked_at IS NULL AND family_id = (SELECT family_id FROM refresh_tokens WHERE jti = $2) \
+             RETURNING family_id AS id, expires_at",
+        )
+        .bind(reason)
+        .bind(jti)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))
+    }
+
+    /// ユーザーのすべてのファミリーを失効させる
+    /// 戻り値: 失効させたファミリー
+    pub async fn revoke_all_refresh_token_families(
+        &self,
+        user_id: &str,
+        reason: &str,
+    ) -> Result<Vec<RevokedToken>> {
+        sqlx::query_as::<_, RevokedToken>(
+            "UPDATE refresh_token_families SET revoked_at = now(), revoked_reason = $1 \
+             WHERE user_id = $2 AND revoked_at IS NULL \
+             RETURNING family_id AS id, expires_at",
+        )
+        .bind(reason)
+        .bind(user_id)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))
+    }
+}
+
+////////
+/// セッション関連の構造体
+////////
+/// ログイン元の端末情報
+pub struct SessionClient {
+    pub user_agent: Option<String>,
+    pub ip_address: Option<String>,
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct Session {
+    pub session_id: String,
+    pub user_agent: Option<String>,
+    pub ip_address: Option<String>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub last_seen_at: chrono::DateTime<chrono::Utc>,
+    /// リクエストに使用したトークンのセッションか
+    #[sqlx(skip)]
+    pub current: bool,
+}
+
+#[derive(Serialize)]
+pub struct SessionList {
+    pub sessions: Vec<Session>,
+}
+
+/// 過去のセッションとの端末情報の比較結果
+#[derive(Debug, sqlx::FromRow)]
+pub struct KnownClient {
+    /// 過去のセッションがあるか（初回のログインでは比較できない）
+    pub has_sessions: bool,
+    /// 同じIPアドレスのセッションがあるか
+    pub known_ip_address: bool,
+    /// 同じUser-Agentのセッションがあるか
+    pub known_user_agent: bool,
+}
+
+////////
+/// セッション管理メソッド
+////////
+impl AuthRepository {
+    /// セッションを記録
+    pub async fn create_session(
+        &self,
+        session_id: &str,
+        user_id: &str,
+        refresh_jti: &str,
+        client: &SessionClient,
+    ) -> Result<()> {
+        sqlx::query(
+            "INSERT INTO sessions (session_id, user_id, refresh_jti, user_agent, ip_address, created_at, last_seen_at) \
+             VALUES ($1, $2, $3, $4, $5, now(), now())",
+        )
+        .bind(session_id)
+        .bind(user_id)
+        .bind(refresh_jti)
+        .bind(&client.user_agent)
+        .bind(&client.ip_address)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
         Ok(())
     }
+
+    /// リフレッシュ時に最新のJTI・端末情報・最終アクセス日時を更新
+    pub async fn touch_session(
+        &self,
+        session_id: &str,
+        refresh_jti: &str,
+        client: &SessionClient,
+    ) -> Result<()> {
+        sqlx::query(
+            "UPDATE sessions SET refresh_jti = $1, user_agent = COALESCE($2, user_agent), \
+             ip_address = COALESCE($3, ip_address), last_seen_at = now() WHERE session_id = $4",
+        )
+        .bind(refresh_jti)
+        .bind(&client.user_agent)
+        .bind(&client.ip_address)
+        .bind(session_id)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        Ok(())
+    }
+
+    /// 有効なセッションの一覧（最近使用した順）
+    pub async fn find_active_sessions(&self, user_id: &str) -> Result<Vec<Session>> {
+        sqlx::query_as::<_, Session>(
+            "SELECT s.session_id, s.user_agent, s.ip_address, s.created_at, s.last_seen_at \
+             FROM sessions s JOIN refresh_token_families f ON f.family_id = s.session_id \
+             WHERE s.user_id = $1 AND f.revoked_at IS NULL AND f.expires_at > now() \
+             ORDER BY s.last_seen_at DESC",
+        )
+        .bind(user_id)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))
+    }
+
+    /// 過去のセッションの端末情報と比較
+    pub async fn find_known_client(&self, user_id: &str, client: &SessionClient) -> Result<KnownClient> {
+        sqlx::query_as::<_, KnownClient>(
+            "SELECT COUNT(*) > 0 AS has_sessions, \
+             COALESCE(bool_or(ip_address = $2), false) AS known_ip_address, \
+             COALESCE(bool_or(user_agent = $3), false) AS known_user_agent \
+             FROM sessions WHERE user_id = $1",
+        )
+        .bind(user_id)
+        .bind(&client.ip_address)
+        .bind(&client.user_agent)
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))
+    }
+
+    /// 指定したセッションを失効させる
+    /// 戻り値: 失効させたセッション（存在しない・失効済みの場合は None）
+    pub async fn revoke_session(&self, user_id: &str, session_id: &str) -> Result<Option<RevokedToken>> {
+        sqlx::query_as::<_, RevokedToken>(
+            "UPDATE refresh_token_families SET revoked_at = now(), revoked_reason = 'revoked_by_user' \
+             WHERE family_id = $1 AND user_id = $2 AND revoked_at IS NULL \
+             RETURNING family_id AS id, expires_at",
+        )
+        .bind(session_id)
+        .bind(user_id)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))
+    }
+
+    /// 指定したセッション以外をすべて失効させる
+    /// 戻り値: 失効させたセッション
+    pub async fn revoke_other_sessions(
+        &self,
+        user_id: &str,
+        current_session_id: &str,
+    ) -> Result<Vec<RevokedToken>> {
+        sqlx::query_as::<_, RevokedToken>(
+            "UPDATE refresh_token_families SET revoked_at = now(), revoked_reason = 'revoked_by_user' \
+             WHERE user_id = $1 AND family_id <> $2 AND revoked_at IS NULL \
+             RETURNING family_id AS id, expires_at",
+        )
+        .bind(user_id)
+        .bind(current_session_id)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))
+    }
+}
+
+////////
+/// ログインリンク関連の構造体
+////////
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MagicLinkSettings {
+    pub enabled: bool,
+}
+
+////////
+/// ログインリンク設定メソッド
+////////
+impl AuthRepository {
+    /// ログインリンクによるログインが有効か
+    pub async fn is_magic_link_enabled(&self, user_id: &str) -> Result<bool> {
+        sqlx::query_scalar::<_, bool>("SELECT magic_link_enabled FROM users WHERE user_id = $1")
+            .bind(user_id)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?
+            .ok_or_else(|| AppError::NotFound("User not found".to_string()))
+    }
+
+    /// ログインリンクによるログインの有効・無効を設定
+    pub async fn set_magic_link_enabled(&self, user_id: &str, enabled: bool) -> Result<()> {
+        sqlx::query("UPDATE users SET magic_link_enabled = $1, updated_at = $2 WHERE user_id = $3")
+            .bind(enabled)
+            .bind(chrono::Utc::now())
+            .bind(user_id)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        Ok(())
+    }
+}
+
+////////
+/// ログイン試行関連の構造体
+////////
+/// パスワードによるログインの失敗状況
+#[derive(Debug, sqlx::FromRow)]
+pub struct LoginFailureState {
+    pub user_id: String,
+    pub email: String,
+    /// ログインに成功するか、ロックを解除するまでの連続失敗回数
+    pub failed_login_count: i32,
+    pub locked_until: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+////////
+/// ログイン試行の記録メソッド
+////////
+impl AuthRepository {
+    /// メールアドレスからログインの失敗状況を取得
+    pub async fn find_login_failure_state(&self, email: &str) -> Result<Option<LoginFailureState>> {
+        sqlx::query_as::<_, LoginFailureState>(
+            "SELECT user_id, email, failed_login_count, locked_until FROM users WHERE email = $1",
+        )
+        .bind(email)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))
+    }
+
+    /// ユーザーIDからログインの失敗状況を取得
+    pub async fn find_login_failure_state_by_user_id(
+        &self,
+        user_id: &str,
+    ) -> Result<Option<LoginFailureState>> {
+        sqlx::query_as::<_, LoginFailureState>(
+            "SELECT user_id, email, failed_login_count, locked_until FROM users WHERE user_id = $1",
+        )
+        .bind(user_id)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))
+    }
+
+    /// ログインの失敗を記録
+    /// 戻り値: 記録後の連続失敗回数
+    pub async fn record_failed_login(&self, user_id: &str) -> Result<i32> {
+        sqlx::query_scalar::<_, i32>(
+            "UPDATE users SET failed_login_count = failed_login_count + 1 WHERE user_id = $1 \
+             RETURNING failed_login_count",
+        )
+        .bind(user_id)
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))
+    }
+
+    /// 指定した日時までパスワードによるログインを拒否する
+    pub async fn lock_account(
+        &self,
+        user_id: &str,
+        locked_until: chrono::DateTime<chrono::Utc>,
+    ) -> Result<()> {
+        sqlx::query("UPDATE users SET locked_until = $1 WHERE user_id = $2")
+            .bind(locked_until)
+            .bind(user_id)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        Ok(())
+    }
+
+    /// 連続失敗回数とロックをリセット
+    pub async fn clear_failed_logins(&self, user_id: &str) -> Result<()> {
+        sqlx::query(
+            "UPDATE users SET failed_login_count = 0, locked_until = NULL \
+             WHERE user_id = $1 AND (failed_login_count > 0 OR locked_until IS NOT NULL)",
+        )
+        .bind(user_id)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        Ok(())
+    }
+}
+
+////////
+/// 外部ID関連の構造体
+////////
+/// IDプロバイダーの外部ID（OpenID Connect）
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct ExternalIdentity {
+    pub provider: String,
+    pub subject: String,
+    pub email: Option<String>,
+    pub last_login_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Serialize)]
+pub struct ExternalIdentityList {
+    pub identities: Vec<ExternalIdentity>,
+}
+
+////////
+/// 外部ID管理メソッド
+////////
+impl AuthRepository {
+    /// 外部IDに連携されたユーザーIDを検索
+    pub async fn find_user_id_by_external_identity(
+        &self,
+        provider: &str,
+        subject: &str,
+    ) -> Result<Option<String>> {
+        sqlx::query_scalar::<_, String>(
+            "SELECT user_id FROM external_identities WHERE provider = $1 AND subject = $2",
+        )
+        .bind(provider)
+        .bind(subject)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))
+    }
+
+    /// 外部IDをユーザーに連携
+    /// 戻り値: 連携したか（同じプロバイダーの別のIDが連携済みの場合は false）
+    pub async fn link_external_identity(
+        &self,
+        provider: &str,
+        subject: &str,
+        user_id: &str,
+        email: Option<&str>,
+    ) -> Result<bool> {
+        let result = sqlx::query(
+            "INSERT INTO external_identities (provider, subject, user_id, email, last_login_at) \
+             VALUES ($1, $2, $3, $4, now()) ON CONFLICT DO NOTHING",
+        )
+        .bind(provider)
+        .bind(subject)
+        .bind(user_id)
+        .bind(email)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// 外部IDでのログイン日時とメールアドレスを更新
+    pub async fn touch_external_identity(
+        &self,
+        provider: &str,
+        subject: &str,
+        email: Option<&str>,
+    ) -> Result<()> {
+        sqlx::query(
+            "UPDATE external_identities SET last_login_at = now(), email = COALESCE($1, email) \
+             WHERE provider = $2 AND subject = $3",
+        )
+        .bind(email)
+        .bind(provider)
+        .bind(subject)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        Ok(())
+    }
+
+    /// ユーザーに連携された外部IDの一覧
+    pub async fn find_external_identities(&self, user_id: &str) -> Result<Vec<ExternalIdentity>> {
+        sqlx::query_as::<_, ExternalIdentity>(
+            "SELECT provider, subject, email, last_login_at, created_at \
+             FROM external_identities WHERE user_id = $1 ORDER BY created_at",
+        )
+        .bind(user_id)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))
+    }
+
+    /// 外部IDの連携を解除
+    /// 戻り値: 解除したか（連携されていない場合は false）
+    pub async fn unlink_external_identity(&self, user_id: &str, provider: &str) -> Result<bool> {
+        let result = sqlx::query(
+            "DELETE FROM external_identities WHERE user_id = $1 AND provider = $2",
+        )
+        .bind(user_id)
+        .bind(provider)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        Ok(result.rows_affected() > 0)
+    }
+}
+
+////////
+/// アカウント削除関連の構造体
+////////
+/// 削除予定日時を過ぎたアカウント
+#[derive(Debug, sqlx::FromRow)]
+pub struct ScheduledAccountDeletion {
+    pub user_id: String,
+    pub deletion_scheduled_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// 完全に削除したデータの件数（監査ログに記録する）
+#[derive(Debug, Default)]
+pub struct PurgedAccountData {
+    pub memos: usize,
+    pub summaries: usize,
+}
+
+////////
+/// アカウント削除メソッド
+////////
+impl AuthRepository {
+    /// アカウントの削除を予約（予定日時まではログインして取り消せる）
+    pub async fn schedule_deletion(
+        &self,
+        user_id: &str,
+        scheduled_at: chrono::DateTime<chrono::Utc>,
+    ) -> Result<()> {
+        sqlx::query(
+            "UPDATE users SET deletion_scheduled_at = $1, updated_at = $2 WHERE user_id = $3",
+        )
+        .bind(scheduled_at)
+        .bind(chrono::Utc::now())
+        .bind(user_id)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        Ok(())
+    }
+
+    /// アカウントの削除予定日時（予約されていない場合は None）
+    pub async fn find_deletion_scheduled_at(
+        &self,
+        user_id: &str,
+    ) -> Result<Option<chrono::DateTime<chrono::Utc>>> {
+        sqlx::query_scalar::<_, Option<chrono::DateTime<chrono::Utc>>>(
+            "SELECT deletion_scheduled_at FROM users WHERE user_id = $1",
+        )
+        .bind(user_id)
+        .fetch_optional(&self.pool)
+        .await
+        .map(Option::flatten)
+        .map_err(|e| AppError::DatabaseError(e.to_string()))
+    }
+
+    /// アカウントの削除を取り消す
+    /// 戻り値: 取り消したか（予約されていない場合や、削除が始まっている場合は false）
+    pub async fn cancel_deletion(&self, user_id: &str) -> Result<bool> {
+        let result = sqlx::query(
+            "UPDATE users SET deletion_scheduled_at = NULL, updated_at = $1 \
+             WHERE user_id = $2 AND deletion_scheduled_at IS NOT NULL AND is_active = true",
+        )
+        .bind(chrono::Utc::now())
+        .bind(user_id)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// 削除予定日時を過ぎたアカウントの一覧
+    pub async fn find_due_deletions(
+        &self,
+        now: chrono::DateTime<chrono::Utc>,
+    ) -> Result<Vec<ScheduledAccountDeletion>> {
+        sqlx::query_as::<_, ScheduledAccountDeletion>(
+            "SELECT user_id, deletion_scheduled_at FROM users \
+             WHERE deletion_scheduled_at <= $1 ORDER BY deletion_scheduled_at",
+        )
+        .bind(now)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))
+    }
+
+    /// 削除を始める前にアカウントを無効化する（以降はログインも取り消しもできない）
+    /// 戻り値: 無効化したか（直前に削除が取り消された場合は false）
+    pub async fn deactivate_for_deletion(
+        &self,
+        user_id: &str,
+        now: chrono::DateTime<chrono::Utc>,
+    ) -> Result<bool> {
+        let result = sqlx::query(
+            "UPDATE users SET is_active = false, updated_at = $1 \
+             WHERE user_id = $2 AND deletion_scheduled_at <= $1",
+        )
+        .bind(now)
+        .bind(user_id)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// ユーザーを物理削除し、監査ログに記録する
+    /// タグ・セッション・トークン・二要素認証などは外部キーの ON DELETE CASCADE で削除される
+    pub async fn purge_user(
+        &self,
+        deletion: &ScheduledAccountDeletion,
+        purged: &PurgedAccountData,
+    ) -> Result<()> {
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        sqlx::query(
+            "INSERT INTO account_deletion_audit_logs \
+             (user_id, deletion_scheduled_at, purged_memos, purged_summaries) \
+             VALUES ($1, $2, $3, $4)",
+        )
+        .bind(&deletion.user_id)
+        .bind(deletion.deletion_scheduled_at)
+        .bind(purged.memos as i32)
+        .bind(purged.summaries as i32)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        sqlx::query("DELETE FROM users WHERE user_id = $1")
+            .bind(&deletion.user_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        tx.commit()
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))
+    }
 }
@@ -0,0 +1,204 @@
+use crate::error::{AppError, Result};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::{Row, postgres::PgRow};
+
+/// エクスポートの状態
+#[derive(Serialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum DataExportStatus {
+    /// アーカイブを作成中
+    Processing,
+    /// ダウンロードできる
+    Ready,
+    /// 作成に失敗した
+    Failed,
+}
+
+impl DataExportStatus {
+    /// DBに保存する際の名前（シリアライズ形式と同じ）
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DataExportStatus::Processing => "processing",
+            DataExportStatus::Ready => "ready",
+            DataExportStatus::Failed => "failed",
+        }
+    }
+}
+
+impl std::str::FromStr for DataExportStatus {
+    type Err = AppError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "processing" => Ok(DataExportStatus::Processing),
+            "ready" => Ok(DataExportStatus::Ready),
+            "failed" => Ok(DataExportStatus::Failed),
+            _ => Err(AppError::DatabaseError(format!(
+                "Unknown export status: {}",
+                s
+            ))),
+        }
+    }
+}
+
+/// 個人データのエクスポート（アーカイブ本体は含めない）
+#[derive(Serialize, Debug, Clone)]
+pub struct DataExport {
+    pub export_id: String,
+    pub status: DataExportStatus,
+    pub error: Option<String>,
+    pub size_bytes: Option<i64>,
+    pub created_at: DateTime<Utc>,
+    pub completed_at: Option<DateTime<Utc>>,
+    /// この日時を過ぎるとダウンロードできなくなる
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+#[async_trait::async_trait]
+pub trait DataExportHandler: Send + Sync {
+    async fn find_by_id(&self, user_id: &str, export_id: &str) -> Result<Option<DataExport>>;
+    async fn create(&self, user_id: &str) -> Result<Option<DataExport>>;
+    async fn complete(&self, export_id: &str, archive: Vec<u8>, expires_at: DateTime<Utc>) -> Result<()>;
+    async fn fail(&self, export_id: &str, error: &str) -> Result<()>;
+    async fn find_archive(&self, user_id: &str, export_id: &str, now: DateTime<Utc>) -> Result<Option<Vec<u8>>>;
+    async fn fail_stale(&self, started_before: DateTime<Utc>) -> Result<u64>;
+    async fn delete_expired(&self, now: DateTime<Utc>) -> Result<u64>;
+}
+
+pub struct DataExportRepository {
+    pub pool: sqlx::PgPool,
+}
+
+impl DataExportRepository {
+    pub fn new(pool: sqlx::PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+const EXPORT_COLUMNS: &str =
+    "export_id, status, error, size_bytes, created_at, completed_at, expires_at";
+
+fn from_row(row: PgRow) -> Result<DataExport> {
+    Ok(DataExport {
+        export_id: row.get("export_id"),
+        status: row.get::<String, _>("status").parse()?,
+        error: row.get("error"),
+        size_bytes: row.get("size_bytes"),
+        created_at: row.get("created_at"),
+        completed_at: row.get("completed_at"),
+        expires_at: row.get("expires_at"),
+    })
+}
+
+#[async_trait::async_trait]
+impl DataExportHandler for DataExportRepository {
+    async fn find_by_id(&self, user_id: &str, export_id: &str) -> Result<Option<DataExport>> {
+        let row = sqlx::query(&format!(
+            "SELECT {EXPORT_COLUMNS} FROM data_exports WHERE export_id = $1 AND user_id = $2"
+        ))
+        .bind(export_id)
+        .bind(user_id)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        row.map(from_row).transpose()
+    }
+
+    /// 作成中のエクスポートとして登録
+    /// 戻り値: 登録したエクスポート（同じユーザーのエクスポートが作成中の場合は None）
+    async fn create(&self, user_id: &str) -> Result<Option<DataExport>> {
+        let row = sqlx::query(&format!(
+            "INSERT INTO data_exports (export_id, user_id, status, created_at) \
+             VALUES ($1, $2, $3, $4) \
+             ON CONFLICT (user_id) WHERE status = 'processing' DO NOTHING \
+             RETURNING {EXPORT_COLUMNS}"
+        ))
+        .bind(uuid::Uuid::new_v4().to_string())
+        .bind(user_id)
+        .bind(DataExportStatus::Processing.as_str())
+        .bind(Utc::now())
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        row.map(from_row).transpose()
+    }
+
+    async fn complete(&self, export_id: &str, archive: Vec<u8>, expires_at: DateTime<Utc>) -> Result<()> {
+        sqlx::query(
+            "UPDATE data_exports SET status = $1, archive = $2, size_bytes = $3, \
+             completed_at = $4, expires_at = $5 WHERE export_id = $6",
+        )
+        .bind(DataExportStatus::Ready.as_str())
+        .bind(&archive)
+        .bind(archive.len() as i64)
+        .bind(Utc::now())
+        .bind(expires_at)
+        .bind(export_id)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        Ok(())
+    }
+
+    async fn fail(&self, export_id: &str, error: &str) -> Result<()> {
+        sqlx::query(
+            "UPDATE data_exports SET status = $1, error = $2, completed_at = $3 WHERE export_id = $4",
+        )
+        .bind(DataExportStatus::Failed.as_str())
+        .bind(error)
+        .bind(Utc::now())
+        .bind(export_id)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        Ok(())
+    }
+
+    /// ダウンロードできるアーカイブ（作成中・失敗・期限切れの場合は None）
+    async fn find_archive(&self, user_id: &str, export_id: &str, now: DateTime<Utc>) -> Result<Option<Vec<u8>>> {
+        sqlx::query_scalar::<_, Vec<u8>>(
+            "SELECT archive FROM data_exports \
+             WHERE export_id = $1 AND user_id = $2 AND status = $3 AND expires_at > $4",
+        )
+        .bind(export_id)
+        .bind(user_id)
+        .bind(DataExportStatus::Ready.as_str())
+        .bind(now)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))
+    }
+
+    /// 作成中のままのエクスポートを失敗にする（作成中にインスタンスが停止した場合）
+    async fn fail_stale(&self, started_before: DateTime<Utc>) -> Result<u64> {
+        let result = sqlx::query(
+            "UPDATE data_exports SET status = $1, error = $2, completed_at = $3 \
+             WHERE status = $4 AND created_at < $5",
+        )
+        .bind(DataExportStatus::Failed.as_str())
+        .bind("Export was interrupted")
+        .bind(Utc::now())
+        .bind(DataExportStatus::Processing.as_str())
+        .bind(started_before)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        Ok(result.rows_affected())
+    }
+
+    /// 期限切れのアーカイブと、古い失敗の記録を削除
+    async fn delete_expired(&self, now: DateTime<Utc>) -> Result<u64> {
+        let result = sqlx::query(
+            "DELETE FROM data_exports WHERE expires_at <= $1 OR (status = $2 AND completed_at <= $1 - INTERVAL '7 days')",
+        )
+        .bind(now)
+        .bind(DataExportStatus::Failed.as_str())
+        .execute(&self.pool)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        Ok(result.rows_affected())
+    }
+}
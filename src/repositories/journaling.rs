@@ -0,0 +1,549 @@
+use crate::error::{AppError, Result};
+use chrono::{DateTime, Datelike, Duration, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Utc};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+use sqlx::{Row, postgres::PgRow};
+
+/// 自動ジャーナリングの頻度
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum JournalingFrequency {
+    /// 自動ジャーナリングを行わない（設定は保存しない）
+    Off,
+    /// 毎日
+    Daily,
+    /// 毎週（weekday の曜日）
+    Weekly,
+    /// 毎月（day_of_month の日）
+    Monthly,
+}
+
+impl JournalingFrequency {
+    /// DBに保存する際の名前（シリアライズ形式と同じ）
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            JournalingFrequency::Off => "off",
+            JournalingFrequency::Daily => "daily",
+            JournalingFrequency::Weekly => "weekly",
+            JournalingFrequency::Monthly => "monthly",
+        }
+    }
+}
+
+impl std::str::FromStr for JournalingFrequency {
+    type Err = AppError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "off" => Ok(JournalingFrequency::Off),
+            "daily" => Ok(JournalingFrequency::Daily),
+            "weekly" => Ok(JournalingFrequency::Weekly),
+            "monthly" => Ok(JournalingFrequency::Monthly),
+            _ => Err(AppError::DatabaseError(format!(
+                "Unknown journaling frequency: {}",
+                s
+            ))),
+        }
+    }
+}
+
+/// 自動ジャーナリングの設定
+///
+/// 要約を作成する日時（local_time の現地時刻）ごとに、前回の作成日時からのメモをまとめて要約する。
+#[derive(Serialize, Debug, Clone)]
+pub struct JournalingSchedule {
+    #[serde(skip)]
+    pub user_id: String,
+    pub frequency: JournalingFrequency,
+    pub local_time: NaiveTime,
+    /// IANAタイムゾーン名
+    pub timezone: String,
+    /// weekly の曜日（0 = 日曜日 〜 6 = 土曜日）
+    pub weekday: i16,
+    /// monthly の日（1 〜 28）
+    pub day_of_month: i16,
+    /// 次に要約を作成する日時（off の場合は None）
+    pub next_run_at: Option<DateTime<Utc>>,
+}
+
+impl JournalingSchedule {
+    /// 設定がないユーザーの既定値
+    pub fn off(user_id: &str) -> Self {
+        Self {
+            user_id: user_id.to_string(),
+            frequency: JournalingFrequency::Off,
+            local_time: NaiveTime::from_hms_opt(21, 0, 0).unwrap(),
+            timezone: "Asia/Tokyo".to_string(),
+            weekday: 0,
+            day_of_month: 1,
+            next_run_at: None,
+        }
+    }
+
+    fn tz(&self) -> Result<Tz> {
+        self.timezone
+            .parse()
+            .map_err(|_| AppError::ValidationError(format!("Unknown timezone: {}", self.timezone)))
+    }
+
+    /// 要約を作成する日かどうか
+    fn runs_on(&self, date: NaiveDate) -> bool {
+        match self.frequency {
+            JournalingFrequency::Off => false,
+            JournalingFrequency::Daily => true,
+            JournalingFrequency::Weekly => {
+                date.weekday().num_days_from_sunday() as i16 == self.weekday
+            }
+            JournalingFrequency::Monthly => date.day() as i16 == self.day_of_month,
+        }
+    }
+
+    /// その日の要約を作成する日時
+    fn run_at_on(&self, tz: Tz, date: NaiveDate) -> DateTime<Utc> {
+        to_utc(tz, date.and_time(self.local_time))
+    }
+
+    /// after より後の最初の作成日時
+    pub fn next_run_after(&self, after: DateTime<Utc>) -> Result<DateTime<Utc>> {
+        let tz = self.tz()?;
+        let mut date = after.with_timezone(&tz).date_naive() - Duration::days(1);
+        // 月1回でも32日以内に必ず見つかる
+        for _ in 0..64 {
+            if self.runs_on(date) {
+                let run_at = self.run_at_on(tz, date);
+                if run_at > after {
+                    return Ok(run_at);
+                }
+            }
+            date += Duration::days(1);
+        }
+        Err(AppError::ValidationError(
+            "Journaling schedule never runs".to_string(),
+        ))
+    }
+
+    /// before より前の最後の作成日時（run_at に作成する要約の期間の開始）
+    pub fn previous_run_before(&self, before: DateTime<Utc>) -> Result<DateTime<Utc>> {
+        let tz = self.tz()?;
+        let mut date = before.with_timezone(&tz).date_naive() + Duration::days(1);
+        for _ in 0..64 {
+            if self.runs_on(date) {
+                let run_at = self.run_at_on(tz, date);
+                if run_at < before {
+                    return Ok(run_at);
+                }
+            }
+            date -= Duration::days(1);
+        }
+        Err(AppError::ValidationError(
+            "Journaling schedule never runs".to_string(),
+        ))
+    }
+}
+
+/// 自動ジャーナリングの設定の変更（指定しなかった項目は変更しない）
+#[derive(Deserialize, Debug)]
+pub struct UpdateJournalingScheduleRequest {
+    pub frequency: Option<JournalingFrequency>,
+    /// 現地時刻（HH:MM）
+    pub local_time: Option<String>,
+    /// IANAタイムゾーン名（例: Asia/Tokyo）
+    pub timezone: Option<String>,
+    pub weekday: Option<i16>,
+    pub day_of_month: Option<i16>,
+}
+
+/// 現地時刻をUTCに変換
+/// 夏時間の切り替えで存在しない時刻は1時間後に、重複する時刻は早い方にする
+fn to_utc(tz: Tz, local: NaiveDateTime) -> DateTime<Utc> {
+    tz.from_local_datetime(&local)
+        .earliest()
+        .or_else(|| tz.from_local_datetime(&(local + Duration::hours(1))).earliest())
+        .map(|dt| dt.with_timezone(&Utc))
+        .unwrap_or_else(|| local.and_utc())
+}
+
+/// 期間ごとの実行の状態
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum JournalingRunStatus {
+    /// 要約を作成中
+    Running,
+    /// 要約を作成した
+    Completed,
+    /// 期間内にメモがなかった
+    Skipped,
+    /// 失敗した（再試行回数が残っていれば再試行する）
+    Failed,
+}
+
+impl JournalingRunStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            JournalingRunStatus::Running => "running",
+            JournalingRunStatus::Completed => "completed",
+            JournalingRunStatus::Skipped => "skipped",
+            JournalingRunStatus::Failed => "failed",
+        }
+    }
+}
+
+impl std::str::FromStr for JournalingRunStatus {
+    type Err = AppError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "running" => Ok(JournalingRunStatus::Running),
+            "completed" => Ok(JournalingRunStatus::Completed),
+            "skipped" => Ok(JournalingRunStatus::Skipped),
+            "failed" => Ok(JournalingRunStatus::Failed),
+            _ => Err(AppError::DatabaseError(format!(
+                "Unknown journaling run status: {}",
+                s
+            ))),
+        }
+    }
+}
+
+/// 期間ごとの実行記録
+#[derive(Debug, Clone)]
+pub struct JournalingRun {
+    pub status: JournalingRunStatus,
+    pub attempts: i32,
+    pub started_at: DateTime<Utc>,
+}
+
+#[async_trait::async_trait]
+pub trait JournalingHandler: Send + Sync {
+    async fn find_schedule(&self, user_id: &str) -> Result<Option<JournalingSchedule>>;
+    async fn upsert_schedule(&self, schedule: &JournalingSchedule) -> Result<JournalingSchedule>;
+    async fn delete_schedule(&self, user_id: &str) -> Result<()>;
+    async fn find_due_schedules(&self, now: DateTime<Utc>, limit: i64) -> Result<Vec<JournalingSchedule>>;
+    async fn advance_schedule(&self, user_id: &str, run_at: DateTime<Utc>, next_run_at: DateTime<Utc>) -> Result<bool>;
+    async fn claim_run(&self, user_id: &str, period_start: DateTime<Utc>, period_end: DateTime<Utc>, max_attempts: i32, retry_before: DateTime<Utc>) -> Result<Option<i32>>;
+    async fn find_run(&self, user_id: &str, period_start: DateTime<Utc>) -> Result<Option<JournalingRun>>;
+    async fn finish_run(&self, user_id: &str, period_start: DateTime<Utc>, status: JournalingRunStatus, summary_id: Option<&str>, error: Option<&str>) -> Result<()>;
+}
+
+pub struct JournalingRepository {
+    pub pool: sqlx::PgPool,
+}
+
+impl JournalingRepository {
+    pub fn new(pool: sqlx::PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+const SCHEDULE_COLUMNS: &str =
+    "user_id, frequency, local_time, timezone, weekday, day_of_month, next_run_at";
+
+fn from_row(row: PgRow) -> Result<JournalingSchedule> {
+    Ok(JournalingSchedule {
+        user_id: row.get("user_id"),
+        frequency: row.get::<String, _>("frequency").parse()?,
+        local_time: row.get("local_time"),
+        timezone: row.get("timezone"),
+        weekday: row.get("weekday"),
+        day_of_month: row.get("day_of_month"),
+        next_run_at: Some(row.get("next_run_at")),
+    })
+}
+
+#[async_trait::async_trait]
+impl JournalingHandler for JournalingRepository {
+    async fn find_schedule(&self, user_id: &str) -> Result<Option<JournalingSchedule>> {
+        let row = sqlx::query(&format!(
+            "SELECT {SCHEDULE_COLUMNS} FROM journaling_schedules WHERE user_id = $1"
+        ))
+        .bind(user_id)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        row.map(from_row).transpose()
+    }
+
+    async fn upsert_schedule(&self, schedule: &JournalingSchedule) -> Result<JournalingSchedule> {
+        let now = Utc::now();
+        let row = sqlx::query(&format!(
+            "INSERT INTO journaling_schedules \
+             (user_id, frequency, local_time, timezone, weekday, day_of_month, next_run_at, created_at, updated_at) \
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $8) \
+             ON CONFLICT (user_id) DO UPDATE SET \
+             frequency = EXCLUDED.frequency, local_time = EXCLUDED.local_time, timezone = EXCLUDED.timezone, \
+             weekday = EXCLUDED.weekday, day_of_month = EXCLUDED.day_of_month, \
+             next_run_at = EXCLUDED.next_run_at, updated_at = EXCLUDED.updated_at \
+             RETURNING {SCHEDULE_COLUMNS}"
+        ))
+        .bind(&schedule.user_id)
+        .bind(schedule.frequency.as_str())
+        .bind(schedule.local_time)
+        .bind(&schedule.timezone)
+        .bind(schedule.weekday)
+        .bind(schedule.day_of_month)
+        .bind(schedule.next_run_at)
+        .bind(now)
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        from_row(row)
+    }
+
+    async fn delete_schedule(&self, user_id: &str) -> Result<()> {
+        sqlx::query("DELETE FROM journaling_schedules WHERE user_id = $1")
+            .bind(user_id)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        Ok(())
+    }
+
+    /// 作成日時を過ぎた設定（古いものから）
+    async fn find_due_schedules(&self, now: DateTime<Utc>, limit: i64) -> Result<Vec<JournalingSchedule>> {
+        let rows = sqlx::query(&format!(
+            "SELECT {SCHEDULE_COLUMNS} FROM journaling_schedules \
+             WHERE next_run_at <= $1 ORDER BY next_run_at LIMIT $2"
+        ))
+        .bind(now)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        rows.into_iter().map(from_row).collect()
+    }
+
+    /// 次の作成日時に進める
+    /// 戻り値: 進めたかどうか（他のインスタンスが進めた場合や、設定が変更された場合は false）
+    async fn advance_schedule(&self, user_id: &str, run_at: DateTime<Utc>, next_run_at: DateTime<Utc>) -> Result<bool> {
+        let result = sqlx::query(
+            "UPDATE journaling_schedules SET next_run_at = $1 WHERE user_id = $2 AND next_run_at = $3",
+        )
+        .bind(next_run_at)
+        .bind(user_id)
+        .bind(run_at)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// 期間の実行を開始として記録
+    ///
+    /// 初回か、失敗していて再試行回数が残っている（かつ retry_before より前に失敗した）場合のみ記録する。
+    /// 戻り値: 何回目の実行か（他のインスタンスが実行中・実行済みの場合は None）
+    async fn claim_run(&self, user_id: &str, period_start: DateTime<Utc>, period_end: DateTime<Utc>, max_attempts: i32, retry_before: DateTime<Utc>) -> Result<Option<i32>> {
+        sqlx::query_scalar::<_, i32>(
+            "INSERT INTO journaling_runs (user_id, period_start, period_end, status, attempts, started_at) \
+             VALUES ($1, $2, $3, $4, 1, $5) \
+             ON CONFLICT (user_id, period_start) DO UPDATE SET \
+             status = EXCLUDED.status, attempts = journaling_runs.attempts + 1, \
+             error = NULL, started_at = EXCLUDED.started_at, finished_at = NULL \
+             WHERE journaling_runs.status = $6 AND journaling_runs.attempts < $7 \
+             AND journaling_runs.finished_at < $8 \
+             RETURNING attempts",
+        )
+        .bind(user_id)
+        .bind(period_start)
+        .bind(period_end)
+        .bind(JournalingRunStatus::Running.as_str())
+        .bind(Utc::now())
+        .bind(JournalingRunStatus::Failed.as_str())
+        .bind(max_attempts)
+        .bind(retry_before)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))
+    }
+
+    async fn find_run(&self, user_id: &str, period_start: DateTime<Utc>) -> Result<Option<JournalingRun>> {
+        let row = sqlx::query(
+            "SELECT status, attempts, started_at FROM journaling_runs \
+             WHERE user_id = $1 AND period_start = $2",
+        )
+        .bind(user_id)
+        .bind(period_start)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        row.map(|row| {
+            Ok(JournalingRun {
+                status: row.get::<String, _>("status").parse()?,
+                attempts: row.get("attempts"),
+                started_at: row.get("started_at"),
+            })
+        })
+        .transpose()
+    }
+
+    async fn finish_run(&self, user_id: &str, period_start: DateTime<Utc>, status: JournalingRunStatus, summary_id: Option<&str>, error: Option<&str>) -> Result<()> {
+        sqlx::query(
+            "UPDATE journaling_runs SET status = $1, summary_id = $2, error = $3, finished_at = $4 \
+             WHERE user_id = $5 AND period_start = $6",
+        )
+        .bind(status.as_str())
+        .bind(summary_id)
+        .bind(error)
+        .bind(Utc::now())
+        .bind(user_id)
+        .bind(period_start)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// America/New_York は 2026-03-08 02:00 に夏時間（UTC-4）が始まり、2026-11-01 02:00 に終わる（UTC-5）
+    const NEW_YORK: &str = "America/New_York";
+
+    fn schedule(
+        frequency: JournalingFrequency,
+        hour: u32,
+        minute: u32,
+        timezone: &str,
+    ) -> JournalingSchedule {
+        JournalingSchedule {
+            frequency,
+            local_time: NaiveTime::from_hms_opt(hour, minute, 0).unwrap(),
+            timezone: timezone.to_string(),
+            ..JournalingSchedule::off("user")
+        }
+    }
+
+    fn utc(year: i32, month: u32, day: u32, hour: u32, minute: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(year, month, day, hour, minute, 0).unwrap()
+    }
+
+    /// next_run_after と previous_run_before が互いに逆の関係になっているか確認
+    fn assert_round_trip(schedule: &JournalingSchedule, run_at: DateTime<Utc>) {
+        let next = schedule.next_run_after(run_at).unwrap();
+        assert_eq!(schedule.previous_run_before(next).unwrap(), run_at);
+        let previous = schedule.previous_run_before(run_at).unwrap();
+        assert_eq!(schedule.next_run_after(previous).unwrap(), run_at);
+    }
+
+    #[test]
+    fn daily_keeps_local_time_across_spring_forward() {
+        let schedule = schedule(JournalingFrequency::Daily, 21, 0, NEW_YORK);
+
+        // 3/7 21:00 EST の次は 3/8 21:00 EDT（UTCでは23時間後）
+        let before_dst = utc(2026, 3, 8, 2, 0);
+        let after_dst = schedule.next_run_after(before_dst).unwrap();
+        assert_eq!(after_dst, utc(2026, 3, 9, 1, 0));
+        assert_eq!(schedule.previous_run_before(after_dst).unwrap(), before_dst);
+        assert_round_trip(&schedule, after_dst);
+    }
+
+    #[test]
+    fn daily_moves_nonexistent_local_time_one_hour_later() {
+        let schedule = schedule(JournalingFrequency::Daily, 2, 30, NEW_YORK);
+
+        // 3/8 02:30 は存在しないため 03:30 EDT に作成する
+        let skipped = schedule.next_run_after(utc(2026, 3, 7, 7, 30)).unwrap();
+        assert_eq!(skipped, utc(2026, 3, 8, 7, 30));
+        assert_eq!(schedule.next_run_after(skipped).unwrap(), utc(2026, 3, 9, 6, 30));
+        assert_round_trip(&schedule, skipped);
+    }
+
+    #[test]
+    fn daily_runs_once_on_repeated_local_time() {
+        let schedule = schedule(JournalingFrequency::Daily, 1, 30, NEW_YORK);
+
+        // 11/1 01:30 は2回あるが、早い方（EDT）の1回だけ作成する
+        let repeated = schedule.next_run_after(utc(2026, 10, 31, 5, 30)).unwrap();
+        assert_eq!(repeated, utc(2026, 11, 1, 5, 30));
+        assert_eq!(schedule.next_run_after(repeated).unwrap(), utc(2026, 11, 2, 6, 30));
+        assert_round_trip(&schedule, repeated);
+    }
+
+    #[test]
+    fn weekly_spans_spring_forward() {
+        let schedule = JournalingSchedule {
+            weekday: 0,
+            ..schedule(JournalingFrequency::Weekly, 9, 0, NEW_YORK)
+        };
+
+        // 3/1（日）09:00 EST の次は 3/8（日）09:00 EDT
+        let before_dst = utc(2026, 3, 1, 14, 0);
+        let after_dst = schedule.next_run_after(before_dst).unwrap();
+        assert_eq!(after_dst, utc(2026, 3, 8, 13, 0));
+        assert_eq!(schedule.previous_run_before(after_dst).unwrap(), before_dst);
+        assert_round_trip(&schedule, after_dst);
+
+        // 曜日の途中から探しても同じ日時になる
+        assert_eq!(schedule.next_run_after(utc(2026, 3, 4, 0, 0)).unwrap(), after_dst);
+    }
+
+    #[test]
+    fn weekly_spans_fall_back() {
+        let schedule = JournalingSchedule {
+            weekday: 0,
+            ..schedule(JournalingFrequency::Weekly, 9, 0, NEW_YORK)
+        };
+
+        // 10/25（日）09:00 EDT の次は 11/1（日）09:00 EST
+        let before = utc(2026, 10, 25, 13, 0);
+        let after = schedule.next_run_after(before).unwrap();
+        assert_eq!(after, utc(2026, 11, 1, 14, 0));
+        assert_round_trip(&schedule, after);
+    }
+
+    #[test]
+    fn monthly_crosses_month_boundary_in_local_time() {
+        let schedule = JournalingSchedule {
+            day_of_month: 1,
+            ..schedule(JournalingFrequency::Monthly, 0, 30, "Asia/Tokyo")
+        };
+
+        // UTCではまだ1/31だが、現地時刻では2/1 00:30
+        let run_at = schedule.next_run_after(utc(2026, 1, 31, 12, 0)).unwrap();
+        assert_eq!(run_at, utc(2026, 1, 31, 15, 30));
+        // 2月は28日まで
+        assert_eq!(schedule.next_run_after(run_at).unwrap(), utc(2026, 2, 28, 15, 30));
+        assert_round_trip(&schedule, run_at);
+    }
+
+    #[test]
+    fn monthly_spans_month_boundary_and_dst() {
+        let schedule = JournalingSchedule {
+            day_of_month: 28,
+            ..schedule(JournalingFrequency::Monthly, 21, 0, NEW_YORK)
+        };
+
+        // 2/28 21:00 EST（UTCでは3/1）の次は 3/28 21:00 EDT
+        let february = utc(2026, 3, 1, 2, 0);
+        assert_eq!(schedule.previous_run_before(utc(2026, 3, 1, 2, 1)).unwrap(), february);
+        let march = schedule.next_run_after(february).unwrap();
+        assert_eq!(march, utc(2026, 3, 29, 1, 0));
+        assert_round_trip(&schedule, march);
+    }
+
+    #[test]
+    fn off_never_runs() {
+        let schedule = JournalingSchedule::off("user");
+        assert!(schedule.next_run_after(utc(2026, 1, 1, 0, 0)).is_err());
+        assert!(schedule.previous_run_before(utc(2026, 1, 1, 0, 0)).is_err());
+    }
+
+    #[test]
+    fn to_utc_handles_gap_and_overlap() {
+        let tz: Tz = NEW_YORK.parse().unwrap();
+        let local = |month: u32, day: u32, hour: u32, minute: u32| {
+            NaiveDate::from_ymd_opt(2026, month, day)
+                .unwrap()
+                .and_hms_opt(hour, minute, 0)
+                .unwrap()
+        };
+
+        assert_eq!(to_utc(tz, local(3, 8, 2, 30)), utc(2026, 3, 8, 7, 30));
+        assert_eq!(to_utc(tz, local(11, 1, 1, 30)), utc(2026, 11, 1, 5, 30));
+        assert_eq!(to_utc(tz, local(11, 2, 1, 30)), utc(2026, 11, 2, 6, 30));
+    }
+}
@@ -1,6 +1,11 @@
 use crate::error::{AppError, Result};
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
 use chrono::{DateTime, Utc};
-use mongodb::bson::doc;
+use mongodb::{
+    IndexModel,
+    bson::{Bson, Document, doc},
+    options::IndexOptions,
+};
 use serde::{Deserialize, Serialize};
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -13,11 +18,91 @@ pub struct Memo {
     pub share_url_token: Option<String>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
+    /// ゴミ箱に移動した日時（Noneなら通常のメモ）
+    #[serde(default)]
+    pub deleted_at: Option<DateTime<Utc>>,
+    /// 現在の内容の版番号（版の記録導入前に作成され、まだ更新されていないメモは0）
+    #[serde(default)]
+    pub revision: u32,
 }
 
 #[derive(Serialize, Deserialize)]
 pub struct MemoList {
     pub memos: Vec<Memo>,
+    /// 次ページ取得用のカーソル（最終ページの場合はNone）
+    #[serde(default)]
+    pub next_cursor: Option<String>,
+}
+
+/// メモ一覧のソート対象
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum MemoSortField {
+    #[default]
+    CreatedAt,
+    UpdatedAt,
+}
+
+impl MemoSortField {
+    fn as_field(&self) -> &'static str {
+        match self {
+            MemoSortField::CreatedAt => "created_at",
+            MemoSortField::UpdatedAt => "updated_at",
+        }
+    }
+}
+
+/// ソート順
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum SortOrder {
+    Asc,
+    #[default]
+    Desc,
+}
+
+/// メモ一覧取得のクエリパラメータ
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct MemoListQuery {
+    /// 1ページあたりの件数
+    pub limit: Option<i64>,
+    /// 前回レスポンスの next_cursor
+    pub cursor: Option<String>,
+    #[serde(default)]
+    pub sort_by: MemoSortField,
+    #[serde(default)]
+    pub order: SortOrder,
+    /// auto_tag_id または manual_tag_id に含まれるタグ
+    pub tag_id: Option<String>,
+    /// created_at の範囲（from <= created_at < to）
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+    /// true: 共有中のメモのみ, false: 共有していないメモのみ
+    pub shared: Option<bool>,
+}
+
+/// ページングカーソルの中身（クライアントには不透明な文字列として渡す）
+#[derive(Serialize, Deserialize)]
+struct MemoCursor {
+    sort_by: String,
+    value: String,
+    memo_id: String,
+}
+
+impl MemoCursor {
+    fn encode(&self) -> Result<String> {
+        let json = serde_json::to_vec(self)
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        Ok(URL_SAFE_NO_PAD.encode(json))
+    }
+
+    fn decode(cursor: &str) -> Result<Self> {
+        let bytes = URL_SAFE_NO_PAD
+            .decode(cursor)
+            .map_err(|_| AppError::ValidationError("Invalid cursor".to_string()))?;
+        serde_json::from_slice(&bytes)
+            .map_err(|_| AppError::ValidationError("Invalid cursor".to_string()))
+    }
 }
 
 #[derive(Deserialize)]
@@ -38,9 +123,17 @@ pub trait MemoHandler: Send + Sync {
     async fn find_by_id(&self, memo_id: &str) -> Result<Option<Memo>>;
     async fn find_by_ids(&self, memo_ids: &[String]) -> Result<Vec<Memo>>;
     async fn find_by_user_id(&self, user_id: &str) -> Result<Vec<Memo>>;
+    async fn find_trashed_by_user_id(&self, user_id: &str) -> Result<Vec<Memo>>;
+    async fn find_trashed_before(&self, cutoff: DateTime<Utc>) -> Result<Vec<Memo>>;
+    async fn find_created_between(&self, user_id: &str, from: DateTime<Utc>, to: DateTime<Utc>) -> Result<Vec<Memo>>;
+    async fn find_page_by_user_id(&self, user_id: &str, query: &MemoListQuery) -> Result<MemoList>;
     async fn create(&self, memo: Memo) -> Result<Memo>;
-    async fn update(&self, memo: Memo) -> Result<Memo>;
+    /// ゴミ箱にないメモの版番号が expected_revision の場合のみ保存する
+    /// 戻り値: 保存したかどうか（他の更新と競合した場合は false）
+    async fn update_if_revision(&self, memo: &Memo, expected_revision: u32) -> Result<bool>;
     async fn delete(&self, memo_id: &str) -> Result<()>;
+    async fn set_deleted_at(&self, memo_id: &str, deleted_at: Option<DateTime<Utc>>) -> Result<()>;
+    async fn set_share_url_token(&self, memo_id: &str, token: Option<String>) -> Result<()>;
 }
 
 // MemoRepo
@@ -48,12 +141,110 @@ pub struct MemoRepository {
     collection: mongodb::Collection<Memo>,
 }
 
+pub const DEFAULT_PAGE_LIMIT: i64 = 50;
+pub const MAX_PAGE_LIMIT: i64 = 100;
+
 impl MemoRepository {
     pub fn new(db: mongodb::Database) -> Self {
         Self {
             collection: db.collection("memos"),
         }
     }
+
+    /// 一覧取得・検索で使用するインデックスを作成（起動時に呼び出す）
+    pub async fn ensure_indexes(&self) -> Result<()> {
+        let indexes = vec![
+            IndexModel::builder()
+                .keys(doc! { "memo_id": 1 })
+                .options(IndexOptions::builder().unique(true).build())
+                .build(),
+            IndexModel::builder()
+                .keys(doc! { "user_id": 1, "created_at": -1, "memo_id": -1 })
+                .build(),
+            IndexModel::builder()
+                .keys(doc! { "user_id": 1, "updated_at": -1, "memo_id": -1 })
+                .build(),
+            IndexModel::builder()
+                .keys(doc! { "user_id": 1, "auto_tag_id": 1, "created_at": -1 })
+                .build(),
+            IndexModel::builder()
+                .keys(doc! { "user_id": 1, "manual_tag_id": 1, "created_at": -1 })
+                .build(),
+            IndexModel::builder()
+                .keys(doc! { "user_id": 1, "deleted_at": -1 })
+                .build(),
+            IndexModel::builder()
+                .keys(doc! { "share_url_token": 1 })
+                .options(IndexOptions::builder().sparse(true).build())
+                .build(),
+        ];
+
+        self.collection
+            .create_indexes(indexes)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        Ok(())
+    }
+
+    /// クエリパラメータからフィルタ条件を構築
+    fn build_page_filter(user_id: &str, query: &MemoListQuery) -> Result<Document> {
+        let mut conditions = vec![doc! { "user_id": user_id, "deleted_at": Bson::Null }];
+
+        if let Some(tag_id) = &query.tag_id {
+            conditions.push(doc! {
+                "$or": [
+                    { "auto_tag_id": tag_id },
+                    { "manual_tag_id": tag_id },
+                ]
+            });
+        }
+
+        // 日付はシリアライズ済みの文字列として保存されているため、同じ形式で比較する
+        let mut created_at = Document::new();
+        if let Some(from) = &query.from {
+            created_at.insert("$gte", to_stored_value(from)?);
+        }
+        if let Some(to) = &query.to {
+            created_at.insert("$lt", to_stored_value(to)?);
+        }
+        if !created_at.is_empty() {
+            conditions.push(doc! { "created_at": created_at });
+        }
+
+        match query.shared {
+            Some(true) => conditions.push(doc! { "share_url_token": { "$ne": Bson::Null } }),
+            Some(false) => conditions.push(doc! { "share_url_token": Bson::Null }),
+            None => {}
+        }
+
+        // カーソル以降の要素に限定（sort_by, memo_id の複合キーでシーク）
+        if let Some(cursor) = &query.cursor {
+            let cursor = MemoCursor::decode(cursor)?;
+            let field = query.sort_by.as_field();
+            if cursor.sort_by != field {
+                return Err(AppError::ValidationError(
+                    "Cursor does not match sort_by".to_string(),
+                ));
+            }
+            let op = match query.order {
+                SortOrder::Asc => "$gt",
+                SortOrder::Desc => "$lt",
+            };
+            conditions.push(doc! {
+                "$or": [
+                    { field: { op: &cursor.value } },
+                    { field: &cursor.value, "memo_id": { op: &cursor.memo_id } },
+                ]
+            });
+        }
+
+        Ok(doc! { "$and": conditions })
+    }
+}
+
+/// DateTime を MongoDB 上の保存形式（文字列）に変換
+fn to_stored_value(value: &DateTime<Utc>) -> Result<Bson> {
+    mongodb::bson::to_bson(value).map_err(|e| AppError::DatabaseError(e.to_string()))
 }
 
 #[async_trait::async_trait]
@@ -62,7 +253,20 @@ impl MemoHandler for MemoRepository {
         use futures::stream::TryStreamExt;
 
         self.collection
-            .find(doc! { "user_id": user_id })
+            .find(doc! { "user_id": user_id, "deleted_at": Bson::Null })
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?
+            .try_collect()
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))
+    }
+
+    async fn find_trashed_by_user_id(&self, user_id: &str) -> Result<Vec<Memo>> {
+        use futures::stream::TryStreamExt;
+
+        self.collection
+            .find(doc! { "user_id": user_id, "deleted_at": { "$ne": Bson::Null } })
+            .sort(doc! { "deleted_at": -1 })
             .await
             .map_err(|e| AppError::DatabaseError(e.to_string()))?
             .try_collect()
@@ -70,6 +274,82 @@ impl MemoHandler for MemoRepository {
             .map_err(|e| AppError::DatabaseError(e.to_string()))
     }
 
+    async fn find_trashed_before(&self, cutoff: DateTime<Utc>) -> Result<Vec<Memo>> {
+        use futures::stream::TryStreamExt;
+
+        let cutoff = to_stored_value(&cutoff)?;
+        self.collection
+            .find(doc! { "deleted_at": { "$ne": Bson::Null, "$lt": cutoff } })
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?
+            .try_collect()
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))
+    }
+
+    /// 期間内（from 以上 to 未満）に作成されたメモ（ゴミ箱内のものは除く）
+    async fn find_created_between(&self, user_id: &str, from: DateTime<Utc>, to: DateTime<Utc>) -> Result<Vec<Memo>> {
+        use futures::stream::TryStreamExt;
+
+        // 日付はシリアライズ済みの文字列として保存されているため、同じ形式で比較する
+        self.collection
+            .find(doc! {
+                "user_id": user_id,
+                "deleted_at": Bson::Null,
+                "created_at": { "$gte": to_stored_value(&from)?, "$lt": to_stored_value(&to)? },
+            })
+            .sort(doc! { "created_at": 1 })
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?
+            .try_collect()
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))
+    }
+
+    async fn find_page_by_user_id(&self, user_id: &str, query: &MemoListQuery) -> Result<MemoList> {
+        use futures::stream::TryStreamExt;
+
+        let limit = query.limit.unwrap_or(DEFAULT_PAGE_LIMIT);
+        let field = query.sort_by.as_field();
+        let direction = match query.order {
+            SortOrder::Asc => 1,
+            SortOrder::Desc => -1,
+        };
+
+        let filter = Self::build_page_filter(user_id, query)?;
+
+        // 次ページの有無を判定するため1件多く取得する
+        let mut memos: Vec<Memo> = self
+            .collection
+            .find(filter)
+            .sort(doc! { field: direction, "memo_id": direction })
+            .limit(limit + 1)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?
+            .try_collect()
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        let next_cursor = if memos.len() as i64 > limit {
+            memos.truncate(limit as usize);
+            let last = memos.last().expect("limit is at least 1");
+            let value = match query.sort_by {
+                MemoSortField::CreatedAt => to_stored_value(&last.created_at)?,
+                MemoSortField::UpdatedAt => to_stored_value(&last.updated_at)?,
+            };
+            let cursor = MemoCursor {
+                sort_by: field.to_string(),
+                value: value.as_str().unwrap_or_default().to_string(),
+                memo_id: last.memo_id.clone(),
+            };
+            Some(cursor.encode()?)
+        } else {
+            None
+        };
+
+        Ok(MemoList { memos, next_cursor })
+    }
+
     async fn find_by_id(&self, memo_id: &str) -> Result<Option<Memo>> {
         let memo = self
             .collection
@@ -98,12 +378,24 @@ impl MemoHandler for MemoRepository {
         Ok(memo)
     }
 
-    async fn update(&self, memo: Memo) -> Result<Memo> {
-        self.collection
-            .replace_one(doc! { "memo_id": &memo.memo_id }, memo.clone())
+    async fn update_if_revision(&self, memo: &Memo, expected_revision: u32) -> Result<bool> {
+        let mut filter = doc! { "memo_id": &memo.memo_id, "deleted_at": Bson::Null };
+        if expected_revision == 0 {
+            // 版番号を持たない古いメモ
+            filter.insert(
+                "$or",
+                vec![doc! { "revision": 0 }, doc! { "revision": { "$exists": false } }],
+            );
+        } else {
+            filter.insert("revision", expected_revision);
+        }
+
+        let result = self
+            .collection
+            .replace_one(filter, memo)
             .await
             .map_err(|e| AppError::DatabaseError(e.to_string()))?;
-        Ok(memo)
+        Ok(result.matched_count == 1)
     }
 
     async fn delete(&self, memo_id: &str) -> Result<()> {
@@ -113,4 +405,27 @@ impl MemoHandler for MemoRepository {
             .map_err(|e| AppError::DatabaseError(e.to_string()))?;
         Ok(())
     }
+
+    async fn set_deleted_at(&self, memo_id: &str, deleted_at: Option<DateTime<Utc>>) -> Result<()> {
+        let value = match deleted_at {
+            Some(at) => to_stored_value(&at)?,
+            None => Bson::Null,
+        };
+        self.collection
+            .update_one(doc! { "memo_id": memo_id }, doc! { "$set": { "deleted_at": value } })
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        Ok(())
+    }
+
+    async fn set_share_url_token(&self, memo_id: &str, token: Option<String>) -> Result<()> {
+        self.collection
+            .update_one(
+                doc! { "memo_id": memo_id },
+                doc! { "$set": { "share_url_token": token } },
+            )
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        Ok(())
+    }
 }
@@ -0,0 +1,223 @@
+use crate::error::{AppError, Result};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+/// 保存されているTOTPの設定（シークレットは暗号化されたまま）
+#[derive(Debug, sqlx::FromRow)]
+pub struct TotpCredential {
+    pub user_id: String,
+    pub secret_ciphertext: Vec<u8>,
+    pub secret_nonce: Vec<u8>,
+    pub enabled_at: Option<DateTime<Utc>>,
+}
+
+/// 二要素認証の状態
+#[derive(Debug, Serialize)]
+pub struct MfaStatus {
+    pub totp_enabled: bool,
+    pub enabled_at: Option<DateTime<Utc>>,
+    pub recovery_codes_remaining: i64,
+}
+
+pub struct MfaRepository {
+    pub pool: sqlx::PgPool,
+}
+
+impl MfaRepository {
+    pub fn new(pool: sqlx::PgPool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn find_totp(&self, user_id: &str) -> Result<Option<TotpCredential>> {
+        sqlx::query_as::<_, TotpCredential>(
+            "SELECT user_id, secret_ciphertext, secret_nonce, enabled_at \
+             FROM user_totp WHERE user_id = $1",
+        )
+        .bind(user_id)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))
+    }
+
+    /// 未有効のシークレットを保存（登録をやり直した場合は置き換える）
+    /// 既に有効な場合は変更せず false を返す
+    pub async fn save_pending_totp(&self, user_id: &str, ciphertext: &[u8], nonce: &[u8]) -> Result<bool> {
+        let result = sqlx::query(
+            "INSERT INTO user_totp (user_id, secret_ciphertext, secret_nonce, created_at) \
+             VALUES ($1, $2, $3, $4) \
+             ON CONFLICT (user_id) DO UPDATE \
+             SET secret_ciphertext = EXCLUDED.secret_ciphertext, secret_nonce = EXCLUDED.secret_nonce, \
+                 last_used_step = NULL, created_at = EXCLUDED.created_at \
+             WHERE user_totp.enabled_at IS NULL",
+        )
+        .bind(user_id)
+        .bind(ciphertext)
+        .bind(nonce)
+        .bind(Utc::now())
+        .execute(&self.pool)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// TOTPを有効化し、リカバリーコードを置き換える
+    pub async fn enable_totp(&self, user_id: &str, step: i64, recovery_code_hashes: &[String]) -> Result<bool> {
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        let result = sqlx::query(
+            "UPDATE user_totp SET enabled_at = $1, last_used_step = $2 \
+             WHERE user_id = $3 AND enabled_at IS NULL",
+        )
+        .bind(Utc::now())
+        .bind(step)
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        if result.rows_affected() == 0 {
+            return Ok(false);
+        }
+
+        Self::replace_recovery_codes(&mut tx, user_id, recovery_code_hashes).await?;
+
+        tx.commit()
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        Ok(true)
+    }
+
+    /// 有効なTOTPのリカバリーコードを再発行
+    pub async fn regenerate_recovery_codes(&self, user_id: &str, recovery_code_hashes: &[String]) -> Result<()> {
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Self::replace_recovery_codes(&mut tx, user_id, recovery_code_hashes).await?;
+
+        tx.commit()
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        Ok(())
+    }
+
+    async fn replace_recovery_codes(
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        user_id: &str,
+        recovery_code_hashes: &[String],
+    ) -> Result<()> {
+        sqlx::query("DELETE FROM totp_recovery_codes WHERE user_id = $1")
+            .bind(user_id)
+            .execute(&mut **tx)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        for code_hash in recovery_code_hashes {
+            sqlx::query(
+                "INSERT INTO totp_recovery_codes (code_id, user_id, code_hash, created_at) \
+                 VALUES ($1, $2, $3, $4)",
+            )
+            .bind(uuid::Uuid::new_v4().to_string())
+            .bind(user_id)
+            .bind(code_hash)
+            .bind(Utc::now())
+            .execute(&mut **tx)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        }
+
+        Ok(())
+    }
+
+    /// 使用したタイムステップを記録（以前のステップ以下の場合は false）
+    pub async fn consume_totp_step(&self, user_id: &str, step: i64) -> Result<bool> {
+        let result = sqlx::query(
+            "UPDATE user_totp SET last_used_step = $1 \
+             WHERE user_id = $2 AND enabled_at IS NOT NULL \
+               AND (last_used_step IS NULL OR last_used_step < $1)",
+        )
+        .bind(step)
+        .bind(user_id)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// 未使用のリカバリーコードを使用済みにする
+    pub async fn consume_recovery_code(&self, user_id: &str, code_hash: &str) -> Result<bool> {
+        let result = sqlx::query(
+            "UPDATE totp_recovery_codes SET used_at = $1 \
+             WHERE user_id = $2 AND code_hash = $3 AND used_at IS NULL",
+        )
+        .bind(Utc::now())
+        .bind(user_id)
+        .bind(code_hash)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    pub async fn count_unused_recovery_codes(&self, user_id: &str) -> Result<i64> {
+        let row = sqlx::query_as::<_, (i64,)>(
+            "SELECT COUNT(*) FROM totp_recovery_codes WHERE user_id = $1 AND used_at IS NULL",
+        )
+        .bind(user_id)
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Ok(row.0)
+    }
+
+    /// TOTPの設定とリカバリーコードを削除
+    pub async fn delete_totp(&self, user_id: &str) -> Result<bool> {
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        sqlx::query("DELETE FROM totp_recovery_codes WHERE user_id = $1")
+            .bind(user_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        let result = sqlx::query("DELETE FROM user_totp WHERE user_id = $1")
+            .bind(user_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        tx.commit()
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        Ok(result.rows_affected() > 0)
+    }
+}
+
+/// TOTP登録開始時に返す情報
+#[derive(Debug, Serialize)]
+pub struct TotpEnrollment {
+    /// Base32でエンコードしたシークレット（手入力用）
+    pub secret: String,
+    /// 認証アプリに登録する otpauth:// URI（QRコード用）
+    pub provisioning_uri: String,
+}
+
+/// 発行したリカバリーコード（平文はこのレスポンスでのみ返す）
+#[derive(Debug, Serialize)]
+pub struct RecoveryCodes {
+    pub recovery_codes: Vec<String>,
+}
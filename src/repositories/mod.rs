@@ -1,10 +1,41 @@
 pub mod auth;
+pub mod export;
+pub mod journaling;
 pub mod memo;
+pub mod mfa;
+pub mod personal_access_token;
+pub mod revision;
+pub mod search;
+pub mod share;
 pub mod summary;
+pub mod summary_chunk;
+pub mod summary_job;
 pub mod tag;
+pub mod webauthn;
 
-pub use memo::{Memo, MemoCreateRequest, MemoHandler, MemoList, MemoRepository, MemoUpdateRequest};
-pub use summary::{AISummary, SummarizeRequest, SummaryList, SummaryRepository};
+pub use export::{DataExport, DataExportRepository};
+pub use journaling::{
+    JournalingRepository, JournalingSchedule, UpdateJournalingScheduleRequest,
+};
+pub use memo::{
+    Memo, MemoCreateRequest, MemoHandler, MemoList, MemoListQuery, MemoRepository,
+    MemoUpdateRequest,
+};
+pub use mfa::{MfaRepository, MfaStatus, RecoveryCodes, TotpEnrollment};
+pub use personal_access_token::{
+    CreatePersonalAccessTokenRequest, CreatedPersonalAccessToken, PersonalAccessToken,
+    PersonalAccessTokenList, PersonalAccessTokenRepository, UpdatePersonalAccessTokenRequest,
+};
+pub use revision::{MemoDiff, MemoDiffQuery, MemoRevisionList, MemoRevisionRepository};
+pub use search::{SearchIndexRepository, SearchRequest, SearchResults};
+pub use share::{CreateShareRequest, ShareInfo, ShareRepository, SharedMemo};
+pub use summary::{AISummary, SummarizeRequest, SummaryList, SummaryRepository, SummaryView};
+pub use summary_chunk::SummaryChunkRepository;
+pub use summary_job::{SummaryJob, SummaryJobRepository};
 pub use tag::{CreateTagRequest, Tag, TagList, TagRepository, UpdateTagRequest};
+pub use webauthn::{
+    UpdateWebAuthnCredentialRequest, WebAuthnAuthenticationChallenge, WebAuthnCredential,
+    WebAuthnCredentialList, WebAuthnRegistrationChallenge, WebAuthnRepository,
+};
 
 pub use auth::AuthRepository;
@@ -0,0 +1,196 @@
+use crate::auth::Role;
+use crate::error::{AppError, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{Row, postgres::PgRow};
+
+/// パーソナルアクセストークン（平文のトークン・ハッシュは含めない）
+#[derive(Serialize, Debug, Clone)]
+pub struct PersonalAccessToken {
+    pub token_id: String,
+    pub user_id: String,
+    pub name: String,
+    pub token_prefix: String,
+    pub roles: Vec<Role>,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub last_used_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Serialize)]
+pub struct PersonalAccessTokenList {
+    pub tokens: Vec<PersonalAccessToken>,
+}
+
+#[derive(Deserialize)]
+pub struct CreatePersonalAccessTokenRequest {
+    pub name: String,
+    pub roles: Vec<Role>,
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Deserialize)]
+pub struct UpdatePersonalAccessTokenRequest {
+    pub name: String,
+}
+
+/// 作成時のみ平文のトークンを返す
+#[derive(Serialize)]
+pub struct CreatedPersonalAccessToken {
+    pub token: String,
+    #[serde(flatten)]
+    pub info: PersonalAccessToken,
+}
+
+/// 新規作成するトークンの保存内容
+pub struct NewPersonalAccessToken {
+    pub user_id: String,
+    pub name: String,
+    pub token_prefix: String,
+    pub token_hash: String,
+    pub roles: Vec<Role>,
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+#[async_trait::async_trait]
+pub trait PersonalAccessTokenHandler: Send + Sync {
+    async fn find_by_user_id(&self, user_id: &str) -> Result<Vec<PersonalAccessToken>>;
+    async fn count_by_user_id(&self, user_id: &str) -> Result<i64>;
+    async fn find_active_by_hash(&self, token_hash: &str) -> Result<Option<PersonalAccessToken>>;
+    async fn create(&self, token: NewPersonalAccessToken) -> Result<PersonalAccessToken>;
+    async fn rename(&self, user_id: &str, token_id: &str, name: &str) -> Result<Option<PersonalAccessToken>>;
+    async fn delete(&self, user_id: &str, token_id: &str) -> Result<bool>;
+    async fn touch(&self, token_id: &str, used_at: DateTime<Utc>) -> Result<()>;
+}
+
+pub struct PersonalAccessTokenRepository {
+    pub pool: sqlx::PgPool,
+}
+
+impl PersonalAccessTokenRepository {
+    pub fn new(pool: sqlx::PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+const TOKEN_COLUMNS: &str =
+    "token_id, user_id, name, token_prefix, roles, expires_at, last_used_at, created_at";
+
+fn from_row(row: PgRow) -> Result<PersonalAccessToken> {
+    let roles = row
+        .get::<Vec<String>, _>("roles")
+        .iter()
+        .map(|role| role.parse())
+        .collect::<Result<Vec<Role>>>()?;
+
+    Ok(PersonalAccessToken {
+        token_id: row.get("token_id"),
+        user_id: row.get("user_id"),
+        name: row.get("name"),
+        token_prefix: row.get("token_prefix"),
+        roles,
+        expires_at: row.get("expires_at"),
+        last_used_at: row.get("last_used_at"),
+        created_at: row.get("created_at"),
+    })
+}
+
+#[async_trait::async_trait]
+impl PersonalAccessTokenHandler for PersonalAccessTokenRepository {
+    async fn find_by_user_id(&self, user_id: &str) -> Result<Vec<PersonalAccessToken>> {
+        let rows = sqlx::query(&format!(
+            "SELECT {TOKEN_COLUMNS} FROM personal_access_tokens WHERE user_id = $1 ORDER BY created_at DESC"
+        ))
+        .bind(user_id)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        rows.into_iter().map(from_row).collect()
+    }
+
+    async fn count_by_user_id(&self, user_id: &str) -> Result<i64> {
+        let row = sqlx::query_as::<_, (i64,)>(
+            "SELECT COUNT(*) FROM personal_access_tokens WHERE user_id = $1",
+        )
+        .bind(user_id)
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Ok(row.0)
+    }
+
+    /// 有効なユーザーのトークンのみを返す（期限は呼び出し側で確認する）
+    async fn find_active_by_hash(&self, token_hash: &str) -> Result<Option<PersonalAccessToken>> {
+        let row = sqlx::query(
+            "SELECT t.token_id, t.user_id, t.name, t.token_prefix, t.roles, t.expires_at, t.last_used_at, t.created_at \
+             FROM personal_access_tokens t JOIN users u ON u.user_id = t.user_id \
+             WHERE t.token_hash = $1 AND u.is_active = true",
+        )
+        .bind(token_hash)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        row.map(from_row).transpose()
+    }
+
+    async fn create(&self, token: NewPersonalAccessToken) -> Result<PersonalAccessToken> {
+        let roles: Vec<&str> = token.roles.iter().map(Role::as_str).collect();
+        let row = sqlx::query(&format!(
+            "INSERT INTO personal_access_tokens (token_id, user_id, name, token_prefix, token_hash, roles, expires_at, created_at) \
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8) RETURNING {TOKEN_COLUMNS}"
+        ))
+        .bind(uuid::Uuid::new_v4().to_string())
+        .bind(&token.user_id)
+        .bind(&token.name)
+        .bind(&token.token_prefix)
+        .bind(&token.token_hash)
+        .bind(&roles)
+        .bind(token.expires_at)
+        .bind(Utc::now())
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        from_row(row)
+    }
+
+    async fn rename(&self, user_id: &str, token_id: &str, name: &str) -> Result<Option<PersonalAccessToken>> {
+        let row = sqlx::query(&format!(
+            "UPDATE personal_access_tokens SET name = $1 WHERE token_id = $2 AND user_id = $3 RETURNING {TOKEN_COLUMNS}"
+        ))
+        .bind(name)
+        .bind(token_id)
+        .bind(user_id)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        row.map(from_row).transpose()
+    }
+
+    async fn delete(&self, user_id: &str, token_id: &str) -> Result<bool> {
+        let result = sqlx::query(
+            "DELETE FROM personal_access_tokens WHERE token_id = $1 AND user_id = $2",
+        )
+        .bind(token_id)
+        .bind(user_id)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn touch(&self, token_id: &str, used_at: DateTime<Utc>) -> Result<()> {
+        sqlx::query("UPDATE personal_access_tokens SET last_used_at = $1 WHERE token_id = $2")
+            .bind(used_at)
+            .bind(token_id)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        Ok(())
+    }
+}
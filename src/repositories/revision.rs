@@ -0,0 +1,152 @@
+use crate::error::{AppError, Result};
+use chrono::{DateTime, Utc};
+use mongodb::{
+    IndexModel,
+    bson::doc,
+    error::{ErrorKind, WriteFailure},
+    options::IndexOptions,
+};
+use serde::{Deserialize, Serialize};
+
+/// メモの版（保存時点の内容のスナップショット）
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct MemoRevision {
+    pub memo_id: String,
+    pub user_id: String,
+    /// メモごとに1から始まる連番
+    pub revision: u32,
+    pub content: String,
+    pub manual_tag_id: Option<Vec<String>>,
+    /// 復元によって作成された場合の復元元の版
+    pub restored_from: Option<u32>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct MemoRevisionList {
+    pub revisions: Vec<MemoRevision>,
+}
+
+#[derive(Deserialize)]
+pub struct MemoDiffQuery {
+    pub from: u32,
+    pub to: u32,
+}
+
+/// 差分の種類
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum DiffOp {
+    Equal,
+    Insert,
+    Delete,
+}
+
+#[derive(Serialize, Debug)]
+pub struct DiffSegment {
+    pub op: DiffOp,
+    pub text: String,
+}
+
+/// 2つの版の差分
+#[derive(Serialize, Debug)]
+pub struct MemoDiff {
+    pub memo_id: String,
+    pub from: u32,
+    pub to: u32,
+    pub segments: Vec<DiffSegment>,
+    pub added_tag_ids: Vec<String>,
+    pub removed_tag_ids: Vec<String>,
+}
+
+#[async_trait::async_trait]
+pub trait MemoRevisionHandler: Send + Sync {
+    async fn find_by_memo_id(&self, memo_id: &str) -> Result<Vec<MemoRevision>>;
+    async fn find_by_revision(&self, memo_id: &str, revision: u32) -> Result<Option<MemoRevision>>;
+    async fn find_latest(&self, memo_id: &str) -> Result<Option<MemoRevision>>;
+    /// 版を記録する（同じ版番号が記録済みの場合は記録せず false を返す）
+    async fn try_create(&self, revision: &MemoRevision) -> Result<bool>;
+    async fn delete_by_memo_id(&self, memo_id: &str) -> Result<()>;
+}
+
+pub struct MemoRevisionRepository {
+    collection: mongodb::Collection<MemoRevision>,
+}
+
+impl MemoRevisionRepository {
+    pub fn new(db: mongodb::Database) -> Self {
+        Self {
+            collection: db.collection("memo_revisions"),
+        }
+    }
+
+    /// 版番号の重複を防ぐインデックスを作成（起動時に呼び出す）
+    pub async fn ensure_indexes(&self) -> Result<()> {
+        self.collection
+            .create_index(
+                IndexModel::builder()
+                    .keys(doc! { "memo_id": 1, "revision": -1 })
+                    .options(IndexOptions::builder().unique(true).build())
+                    .build(),
+            )
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        Ok(())
+    }
+}
+
+#[async_trait::async_trait]
+impl MemoRevisionHandler for MemoRevisionRepository {
+    async fn find_by_memo_id(&self, memo_id: &str) -> Result<Vec<MemoRevision>> {
+        use futures::stream::TryStreamExt;
+
+        self.collection
+            .find(doc! { "memo_id": memo_id })
+            .sort(doc! { "revision": -1 })
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?
+            .try_collect()
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))
+    }
+
+    async fn find_by_revision(&self, memo_id: &str, revision: u32) -> Result<Option<MemoRevision>> {
+        self.collection
+            .find_one(doc! { "memo_id": memo_id, "revision": revision })
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))
+    }
+
+    async fn find_latest(&self, memo_id: &str) -> Result<Option<MemoRevision>> {
+        self.collection
+            .find_one(doc! { "memo_id": memo_id })
+            .sort(doc! { "revision": -1 })
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))
+    }
+
+    async fn try_create(&self, revision: &MemoRevision) -> Result<bool> {
+        match self.collection.insert_one(revision).await {
+            Ok(_) => Ok(true),
+            Err(e) if is_duplicate_key(&e) => Ok(false),
+            Err(e) => Err(AppError::DatabaseError(e.to_string())),
+        }
+    }
+
+    async fn delete_by_memo_id(&self, memo_id: &str) -> Result<()> {
+        self.collection
+            .delete_many(doc! { "memo_id": memo_id })
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        Ok(())
+    }
+}
+
+/// 一意制約違反（版番号の重複）かどうか
+fn is_duplicate_key(error: &mongodb::error::Error) -> bool {
+    const DUPLICATE_KEY: i32 = 11000;
+    matches!(
+        error.kind.as_ref(),
+        ErrorKind::Write(WriteFailure::WriteError(e)) if e.code == DUPLICATE_KEY
+    )
+}
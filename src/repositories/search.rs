@@ -0,0 +1,208 @@
+use crate::error::{AppError, Result};
+use chrono::{DateTime, Utc};
+use mongodb::{
+    IndexModel,
+    bson::{Document, doc},
+    options::IndexOptions,
+};
+use serde::{Deserialize, Serialize};
+
+/// 検索対象ドキュメントの種別
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SearchDocType {
+    Memo,
+    Summary,
+}
+
+impl SearchDocType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SearchDocType::Memo => "memo",
+            SearchDocType::Summary => "summary",
+        }
+    }
+}
+
+/// 検索インデックスのエントリ（メモ・要約1件につき1ドキュメント）
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SearchIndexEntry {
+    pub doc_type: SearchDocType,
+    pub doc_id: String,
+    pub user_id: String,
+    /// 正規化済み本文から生成したN-gramトークン（重複なし）
+    pub tokens: Vec<String>,
+    /// auto_tag_id と manual_tag_id を合わせたもの（要約は空）
+    pub tag_ids: Vec<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// 検索対象の絞り込み
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum SearchTarget {
+    #[default]
+    All,
+    Memo,
+    Summary,
+}
+
+/// GET /memos/search のクエリパラメータ
+#[derive(Deserialize, Debug, Clone)]
+pub struct SearchRequest {
+    /// 検索語（空白区切りでAND検索）
+    pub q: String,
+    #[serde(default, rename = "type")]
+    pub target: SearchTarget,
+    pub tag_id: Option<String>,
+    /// created_at の範囲（from <= created_at < to）
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+    pub limit: Option<usize>,
+    pub offset: Option<usize>,
+}
+
+/// 検索結果1件
+#[derive(Serialize, Debug)]
+pub struct SearchHit {
+    pub doc_type: SearchDocType,
+    pub id: String,
+    pub score: f64,
+    /// 一致箇所周辺の抜粋
+    pub snippet: String,
+    /// snippet 内の一致箇所 [開始, 終了)（文字単位）
+    pub highlights: Vec<[usize; 2]>,
+    pub tag_ids: Vec<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct SearchResults {
+    pub results: Vec<SearchHit>,
+    /// ページング前の一致件数
+    pub total: usize,
+}
+
+/// インデックス検索の条件
+pub struct SearchIndexQuery<'a> {
+    pub user_id: &'a str,
+    pub tokens: &'a [String],
+    pub doc_types: &'a [SearchDocType],
+    pub tag_id: Option<&'a str>,
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+    /// 取得する候補の上限
+    pub limit: i64,
+}
+
+#[async_trait::async_trait]
+pub trait SearchIndexHandler: Send + Sync {
+    async fn upsert(&self, entry: SearchIndexEntry) -> Result<()>;
+    async fn delete(&self, doc_type: SearchDocType, doc_id: &str) -> Result<()>;
+    async fn delete_by_user_id(&self, user_id: &str) -> Result<()>;
+    async fn find_candidates(&self, query: SearchIndexQuery<'_>) -> Result<Vec<SearchIndexEntry>>;
+}
+
+pub struct SearchIndexRepository {
+    collection: mongodb::Collection<SearchIndexEntry>,
+}
+
+impl SearchIndexRepository {
+    pub fn new(db: mongodb::Database) -> Self {
+        Self {
+            collection: db.collection("search_index"),
+        }
+    }
+
+    /// 検索用インデックスを作成（起動時に呼び出す）
+    pub async fn ensure_indexes(&self) -> Result<()> {
+        let indexes = vec![
+            IndexModel::builder()
+                .keys(doc! { "doc_type": 1, "doc_id": 1 })
+                .options(IndexOptions::builder().unique(true).build())
+                .build(),
+            IndexModel::builder()
+                .keys(doc! { "user_id": 1, "tokens": 1 })
+                .build(),
+        ];
+
+        self.collection
+            .create_indexes(indexes)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        Ok(())
+    }
+}
+
+#[async_trait::async_trait]
+impl SearchIndexHandler for SearchIndexRepository {
+    async fn upsert(&self, entry: SearchIndexEntry) -> Result<()> {
+        self.collection
+            .replace_one(
+                doc! { "doc_type": entry.doc_type.as_str(), "doc_id": &entry.doc_id },
+                &entry,
+            )
+            .upsert(true)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        Ok(())
+    }
+
+    async fn delete(&self, doc_type: SearchDocType, doc_id: &str) -> Result<()> {
+        self.collection
+            .delete_one(doc! { "doc_type": doc_type.as_str(), "doc_id": doc_id })
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        Ok(())
+    }
+
+    async fn delete_by_user_id(&self, user_id: &str) -> Result<()> {
+        self.collection
+            .delete_many(doc! { "user_id": user_id })
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        Ok(())
+    }
+
+    async fn find_candidates(&self, query: SearchIndexQuery<'_>) -> Result<Vec<SearchIndexEntry>> {
+        use futures::stream::TryStreamExt;
+
+        let doc_types: Vec<&str> = query.doc_types.iter().map(|t| t.as_str()).collect();
+        let mut filter = doc! {
+            "user_id": query.user_id,
+            "tokens": { "$all": query.tokens },
+            "doc_type": { "$in": doc_types },
+        };
+        if let Some(tag_id) = query.tag_id {
+            filter.insert("tag_ids", tag_id);
+        }
+
+        // 日付はシリアライズ済みの文字列として保存されているため、同じ形式で比較する
+        let mut created_at = Document::new();
+        if let Some(from) = &query.from {
+            created_at.insert(
+                "$gte",
+                mongodb::bson::to_bson(from).map_err(|e| AppError::DatabaseError(e.to_string()))?,
+            );
+        }
+        if let Some(to) = &query.to {
+            created_at.insert(
+                "$lt",
+                mongodb::bson::to_bson(to).map_err(|e| AppError::DatabaseError(e.to_string()))?,
+            );
+        }
+        if !created_at.is_empty() {
+            filter.insert("created_at", created_at);
+        }
+
+        self.collection
+            .find(filter)
+            .sort(doc! { "created_at": -1 })
+            .limit(query.limit)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?
+            .try_collect()
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))
+    }
+}
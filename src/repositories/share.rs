@@ -0,0 +1,150 @@
+use crate::error::{AppError, Result};
+use chrono::{DateTime, Utc};
+use mongodb::{IndexModel, bson::doc, options::IndexOptions};
+use serde::{Deserialize, Serialize};
+
+/// メモの共有リンク
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct MemoShare {
+    /// URLに含める共有トークン（Memo.share_url_token と同じ値）
+    pub token: String,
+    pub memo_id: String,
+    pub user_id: String,
+    /// 閲覧パスワードのハッシュ（Argon2）
+    pub password_hash: Option<String>,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub view_count: i64,
+    pub last_viewed_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Deserialize)]
+pub struct CreateShareRequest {
+    pub expires_at: Option<DateTime<Utc>>,
+    pub password: Option<String>,
+}
+
+/// 所有者向けの共有リンク情報
+#[derive(Serialize)]
+pub struct ShareInfo {
+    pub token: String,
+    pub memo_id: String,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub has_password: bool,
+    pub view_count: i64,
+    pub last_viewed_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl From<MemoShare> for ShareInfo {
+    fn from(share: MemoShare) -> Self {
+        Self {
+            token: share.token,
+            memo_id: share.memo_id,
+            expires_at: share.expires_at,
+            has_password: share.password_hash.is_some(),
+            view_count: share.view_count,
+            last_viewed_at: share.last_viewed_at,
+            created_at: share.created_at,
+        }
+    }
+}
+
+/// 共有リンクの閲覧者に返す読み取り専用のメモ（ユーザーIDやタグなどは含めない）
+#[derive(Serialize)]
+pub struct SharedMemo {
+    pub content: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub view_count: i64,
+}
+
+#[async_trait::async_trait]
+pub trait ShareHandler: Send + Sync {
+    async fn find_by_token(&self, token: &str) -> Result<Option<MemoShare>>;
+    async fn find_by_memo_id(&self, memo_id: &str) -> Result<Option<MemoShare>>;
+    async fn create(&self, share: MemoShare) -> Result<MemoShare>;
+    async fn delete_by_memo_id(&self, memo_id: &str) -> Result<()>;
+    async fn record_view(&self, token: &str, viewed_at: DateTime<Utc>) -> Result<()>;
+}
+
+pub struct ShareRepository {
+    collection: mongodb::Collection<MemoShare>,
+}
+
+impl ShareRepository {
+    pub fn new(db: mongodb::Database) -> Self {
+        Self {
+            collection: db.collection("memo_shares"),
+        }
+    }
+
+    /// 共有トークン・メモIDの一意制約を作成（起動時に呼び出す）
+    pub async fn ensure_indexes(&self) -> Result<()> {
+        let indexes = vec![
+            IndexModel::builder()
+                .keys(doc! { "token": 1 })
+                .options(IndexOptions::builder().unique(true).build())
+                .build(),
+            IndexModel::builder()
+                .keys(doc! { "memo_id": 1 })
+                .options(IndexOptions::builder().unique(true).build())
+                .build(),
+        ];
+
+        self.collection
+            .create_indexes(indexes)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        Ok(())
+    }
+}
+
+#[async_trait::async_trait]
+impl ShareHandler for ShareRepository {
+    async fn find_by_token(&self, token: &str) -> Result<Option<MemoShare>> {
+        self.collection
+            .find_one(doc! { "token": token })
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))
+    }
+
+    async fn find_by_memo_id(&self, memo_id: &str) -> Result<Option<MemoShare>> {
+        self.collection
+            .find_one(doc! { "memo_id": memo_id })
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))
+    }
+
+    async fn create(&self, share: MemoShare) -> Result<MemoShare> {
+        self.collection
+            .insert_one(&share)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        Ok(share)
+    }
+
+    async fn delete_by_memo_id(&self, memo_id: &str) -> Result<()> {
+        self.collection
+            .delete_many(doc! { "memo_id": memo_id })
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        Ok(())
+    }
+
+    async fn record_view(&self, token: &str, viewed_at: DateTime<Utc>) -> Result<()> {
+        let viewed_at =
+            mongodb::bson::to_bson(&viewed_at).map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        self.collection
+            .update_one(
+                doc! { "token": token },
+                doc! {
+                    "$inc": { "view_count": 1 },
+                    "$set": { "last_viewed_at": viewed_at },
+                },
+            )
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        Ok(())
+    }
+}
@@ -1,6 +1,7 @@
 use crate::error::Result;
 use async_trait::async_trait;
 use chrono::{DateTime, Utc};
+use mongodb::bson::Bson;
 use serde::{Deserialize, Serialize};
 
 // AI-generated summary structure
@@ -13,6 +14,18 @@ pub struct AISummary {
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
     pub is_auto_generated: bool,
+    /// ゴミ箱に移動した日時（Noneなら通常の要約）
+    #[serde(default)]
+    pub deleted_at: Option<DateTime<Utc>>,
+}
+
+/// 要約のレスポンス（要約元メモの状態を付加したもの）
+#[derive(Serialize, Deserialize)]
+pub struct SummaryView {
+    #[serde(flatten)]
+    pub summary: AISummary,
+    /// memo_ids のうちゴミ箱に入っているメモ
+    pub trashed_memo_ids: Vec<String>,
 }
 
 #[derive(Deserialize)]
@@ -23,16 +36,20 @@ pub struct SummarizeRequest {
 // Wrapper for a list of AI summaries
 #[derive(Serialize, Deserialize)]
 pub struct SummaryList {
-    pub summaries: Vec<AISummary>,
+    pub summaries: Vec<SummaryView>,
 }
 
 #[async_trait]
 // Summary repository trait
 pub trait SummaryHandler: Send + Sync {
     async fn find_by_user_id(&self, user_id: &str) -> Result<Vec<AISummary>>;
+    async fn find_trashed_by_user_id(&self, user_id: &str) -> Result<Vec<AISummary>>;
+    async fn find_trashed_before(&self, cutoff: DateTime<Utc>) -> Result<Vec<AISummary>>;
     async fn find_by_id(&self, summary_id: &str) -> Result<Option<AISummary>>;
+    async fn find_by_ids(&self, summary_ids: &[String]) -> Result<Vec<AISummary>>;
     async fn create(&self, summary: AISummary) -> Result<AISummary>;
     async fn delete(&self, summary_id: &str) -> Result<()>;
+    async fn set_deleted_at(&self, summary_id: &str, deleted_at: Option<DateTime<Utc>>) -> Result<()>;
 }
 
 pub struct SummaryRepository {
@@ -52,7 +69,33 @@ impl SummaryHandler for SummaryRepository {
     async fn find_by_user_id(&self, user_id: &str) -> Result<Vec<AISummary>> {
         use futures::stream::TryStreamExt;
         self.collection
-            .find(mongodb::bson::doc! { "user_id": user_id })
+            .find(mongodb::bson::doc! { "user_id": user_id, "deleted_at": Bson::Null })
+            .await
+            .map_err(|e| crate::error::AppError::DatabaseError(e.to_string()))?
+            .try_collect()
+            .await
+            .map_err(|e| crate::error::AppError::DatabaseError(e.to_string()))
+    }
+
+    async fn find_trashed_by_user_id(&self, user_id: &str) -> Result<Vec<AISummary>> {
+        use futures::stream::TryStreamExt;
+        self.collection
+            .find(mongodb::bson::doc! { "user_id": user_id, "deleted_at": { "$ne": Bson::Null } })
+            .sort(mongodb::bson::doc! { "deleted_at": -1 })
+            .await
+            .map_err(|e| crate::error::AppError::DatabaseError(e.to_string()))?
+            .try_collect()
+            .await
+            .map_err(|e| crate::error::AppError::DatabaseError(e.to_string()))
+    }
+
+    async fn find_trashed_before(&self, cutoff: DateTime<Utc>) -> Result<Vec<AISummary>> {
+        use futures::stream::TryStreamExt;
+        // 日付はシリアライズ済みの文字列として保存されているため、同じ形式で比較する
+        let cutoff = mongodb::bson::to_bson(&cutoff)
+            .map_err(|e| crate::error::AppError::DatabaseError(e.to_string()))?;
+        self.collection
+            .find(mongodb::bson::doc! { "deleted_at": { "$ne": Bson::Null, "$lt": cutoff } })
             .await
             .map_err(|e| crate::error::AppError::DatabaseError(e.to_string()))?
             .try_collect()
@@ -67,6 +110,17 @@ impl SummaryHandler for SummaryRepository {
             .map_err(|e| crate::error::AppError::DatabaseError(e.to_string()))
     }
 
+    async fn find_by_ids(&self, summary_ids: &[String]) -> Result<Vec<AISummary>> {
+        use futures::stream::TryStreamExt;
+        self.collection
+            .find(mongodb::bson::doc! { "summary_id": { "$in": summary_ids } })
+            .await
+            .map_err(|e| crate::error::AppError::DatabaseError(e.to_string()))?
+            .try_collect()
+            .await
+            .map_err(|e| crate::error::AppError::DatabaseError(e.to_string()))
+    }
+
     async fn create(&self, summary: AISummary) -> Result<AISummary> {
         self.collection
             .insert_one(&summary)
@@ -82,4 +136,20 @@ impl SummaryHandler for SummaryRepository {
             .map_err(|e| crate::error::AppError::DatabaseError(e.to_string()))?;
         Ok(())
     }
+
+    async fn set_deleted_at(&self, summary_id: &str, deleted_at: Option<DateTime<Utc>>) -> Result<()> {
+        let value = match deleted_at {
+            Some(at) => mongodb::bson::to_bson(&at)
+                .map_err(|e| crate::error::AppError::DatabaseError(e.to_string()))?,
+            None => Bson::Null,
+        };
+        self.collection
+            .update_one(
+                mongodb::bson::doc! { "summary_id": summary_id },
+                mongodb::bson::doc! { "$set": { "deleted_at": value } },
+            )
+            .await
+            .map_err(|e| crate::error::AppError::DatabaseError(e.to_string()))?;
+        Ok(())
+    }
 }
@@ -0,0 +1,100 @@
+use crate::error::{AppError, Result};
+use mongodb::{IndexModel, bson::doc, options::IndexOptions};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// キャッシュを残しておく日数
+const CACHE_RETENTION_DAYS: u64 = 30;
+
+/// 分割して要約したときの、途中の要約のキャッシュ
+///
+/// 同じメモの組み合わせを要約し直すときに、要約済みの部分のLLM呼び出しを省く。
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SummaryChunk {
+    pub user_id: String,
+    /// モデルとプロンプトのハッシュ
+    pub cache_key: String,
+    pub content: String,
+    /// 期限切れの削除（TTLインデックス）に使うため、BSONの日時として保存する
+    pub created_at: mongodb::bson::DateTime,
+}
+
+#[async_trait::async_trait]
+pub trait SummaryChunkHandler: Send + Sync {
+    async fn find(&self, user_id: &str, cache_key: &str) -> Result<Option<String>>;
+    async fn save(&self, user_id: &str, cache_key: &str, content: &str) -> Result<()>;
+    async fn delete_by_user_id(&self, user_id: &str) -> Result<u64>;
+}
+
+pub struct SummaryChunkRepository {
+    collection: mongodb::Collection<SummaryChunk>,
+}
+
+impl SummaryChunkRepository {
+    pub fn new(db: mongodb::Database) -> Self {
+        Self {
+            collection: db.collection("summary_chunks"),
+        }
+    }
+
+    /// キャッシュキーの一意制約と、期限切れのキャッシュを削除するTTLインデックスを作成（起動時に呼び出す）
+    pub async fn ensure_indexes(&self) -> Result<()> {
+        let indexes = vec![
+            IndexModel::builder()
+                .keys(doc! { "user_id": 1, "cache_key": 1 })
+                .options(IndexOptions::builder().unique(true).build())
+                .build(),
+            IndexModel::builder()
+                .keys(doc! { "created_at": 1 })
+                .options(
+                    IndexOptions::builder()
+                        .expire_after(Duration::from_secs(CACHE_RETENTION_DAYS * 24 * 60 * 60))
+                        .build(),
+                )
+                .build(),
+        ];
+
+        self.collection
+            .create_indexes(indexes)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        Ok(())
+    }
+}
+
+#[async_trait::async_trait]
+impl SummaryChunkHandler for SummaryChunkRepository {
+    async fn find(&self, user_id: &str, cache_key: &str) -> Result<Option<String>> {
+        let chunk = self
+            .collection
+            .find_one(doc! { "user_id": user_id, "cache_key": cache_key })
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        Ok(chunk.map(|chunk| chunk.content))
+    }
+
+    async fn save(&self, user_id: &str, cache_key: &str, content: &str) -> Result<()> {
+        let chunk = SummaryChunk {
+            user_id: user_id.to_string(),
+            cache_key: cache_key.to_string(),
+            content: content.to_string(),
+            created_at: mongodb::bson::DateTime::now(),
+        };
+        // 同時に同じ部分を要約した場合は後から保存した方で上書きする
+        self.collection
+            .replace_one(doc! { "user_id": user_id, "cache_key": cache_key }, chunk)
+            .upsert(true)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        Ok(())
+    }
+
+    async fn delete_by_user_id(&self, user_id: &str) -> Result<u64> {
+        let result = self
+            .collection
+            .delete_many(doc! { "user_id": user_id })
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        Ok(result.deleted_count)
+    }
+}
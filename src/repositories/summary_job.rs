@@ -0,0 +1,365 @@
+use crate::error::{AppError, Result};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::{Row, postgres::PgRow};
+
+/// 要約ジョブの状態
+#[derive(Serialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum SummaryJobStatus {
+    /// 実行待ち（再試行待ちを含む）
+    Queued,
+    /// 実行中
+    Running,
+    /// 要約を作成した
+    Succeeded,
+    /// 失敗した（再試行しない）
+    Failed,
+}
+
+impl SummaryJobStatus {
+    /// DBに保存する際の名前（シリアライズ形式と同じ）
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SummaryJobStatus::Queued => "queued",
+            SummaryJobStatus::Running => "running",
+            SummaryJobStatus::Succeeded => "succeeded",
+            SummaryJobStatus::Failed => "failed",
+        }
+    }
+
+    /// これ以上状態が変わらないかどうか
+    pub fn is_finished(&self) -> bool {
+        matches!(self, SummaryJobStatus::Succeeded | SummaryJobStatus::Failed)
+    }
+}
+
+impl std::str::FromStr for SummaryJobStatus {
+    type Err = AppError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "queued" => Ok(SummaryJobStatus::Queued),
+            "running" => Ok(SummaryJobStatus::Running),
+            "succeeded" => Ok(SummaryJobStatus::Succeeded),
+            "failed" => Ok(SummaryJobStatus::Failed),
+            _ => Err(AppError::DatabaseError(format!(
+                "Unknown summary job status: {}",
+                s
+            ))),
+        }
+    }
+}
+
+/// 実行中のジョブの段階
+#[derive(Serialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum SummaryJobStage {
+    /// 要約するメモを読み込み中
+    LoadingMemos,
+    /// LLMで要約を生成中
+    Generating,
+    /// 要約を保存中
+    Saving,
+}
+
+impl SummaryJobStage {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SummaryJobStage::LoadingMemos => "loading_memos",
+            SummaryJobStage::Generating => "generating",
+            SummaryJobStage::Saving => "saving",
+        }
+    }
+}
+
+impl std::str::FromStr for SummaryJobStage {
+    type Err = AppError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "loading_memos" => Ok(SummaryJobStage::LoadingMemos),
+            "generating" => Ok(SummaryJobStage::Generating),
+            "saving" => Ok(SummaryJobStage::Saving),
+            _ => Err(AppError::DatabaseError(format!(
+                "Unknown summary job stage: {}",
+                s
+            ))),
+        }
+    }
+}
+
+/// 要約ジョブ
+#[derive(Serialize, Debug, Clone)]
+pub struct SummaryJob {
+    pub job_id: String,
+    #[serde(skip)]
+    pub user_id: String,
+    pub memo_ids: Vec<String>,
+    pub status: SummaryJobStatus,
+    /// 実行中の段階（実行中以外は None）
+    pub stage: Option<SummaryJobStage>,
+    /// 実行した回数
+    pub attempts: i32,
+    /// 直近の失敗の理由
+    pub error: Option<String>,
+    /// 作成した要約のID（成功した場合）
+    pub summary_id: Option<String>,
+    /// 実行待ちの場合、この日時を過ぎたら実行する
+    pub run_after: DateTime<Utc>,
+    pub created_at: DateTime<Utc>,
+    pub started_at: Option<DateTime<Utc>>,
+    pub finished_at: Option<DateTime<Utc>>,
+}
+
+#[async_trait::async_trait]
+pub trait SummaryJobHandler: Send + Sync {
+    async fn create(&self, user_id: &str, memo_ids: &[String]) -> Result<SummaryJob>;
+    async fn find_by_id(&self, user_id: &str, job_id: &str) -> Result<Option<SummaryJob>>;
+    async fn claim_next(&self, now: DateTime<Utc>, max_running_per_user: i64) -> Result<Option<SummaryJob>>;
+    async fn heartbeat(&self, job_id: &str, attempt: i32) -> Result<bool>;
+    async fn set_stage(&self, job_id: &str, attempt: i32, stage: SummaryJobStage) -> Result<bool>;
+    async fn succeed(&self, job_id: &str, attempt: i32, summary_id: &str) -> Result<bool>;
+    async fn retry(&self, job_id: &str, attempt: i32, error: &str, run_after: DateTime<Utc>) -> Result<bool>;
+    async fn fail(&self, job_id: &str, attempt: i32, error: &str) -> Result<bool>;
+    async fn requeue_stale(&self, updated_before: DateTime<Utc>, max_attempts: i32) -> Result<u64>;
+    async fn delete_finished_before(&self, cutoff: DateTime<Utc>) -> Result<u64>;
+}
+
+pub struct SummaryJobRepository {
+    pub pool: sqlx::PgPool,
+}
+
+impl SummaryJobRepository {
+    pub fn new(pool: sqlx::PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+const JOB_COLUMNS: &str = "job_id, user_id, memo_ids, status, stage, attempts, error, summary_id, \
+     run_after, created_at, started_at, finished_at";
+
+fn from_row(row: PgRow) -> Result<SummaryJob> {
+    Ok(SummaryJob {
+        job_id: row.get("job_id"),
+        user_id: row.get("user_id"),
+        memo_ids: row.get("memo_ids"),
+        status: row.get::<String, _>("status").parse()?,
+        stage: row
+            .get::<Option<String>, _>("stage")
+            .map(|stage| stage.parse())
+            .transpose()?,
+        attempts: row.get("attempts"),
+        error: row.get("error"),
+        summary_id: row.get("summary_id"),
+        run_after: row.get("run_after"),
+        created_at: row.get("created_at"),
+        started_at: row.get("started_at"),
+        finished_at: row.get("finished_at"),
+    })
+}
+
+#[async_trait::async_trait]
+impl SummaryJobHandler for SummaryJobRepository {
+    async fn create(&self, user_id: &str, memo_ids: &[String]) -> Result<SummaryJob> {
+        let now = Utc::now();
+        let row = sqlx::query(&format!(
+            "INSERT INTO summary_jobs (job_id, user_id, memo_ids, status, run_after, created_at, updated_at) \
+             VALUES ($1, $2, $3, $4, $5, $5, $5) \
+             RETURNING {JOB_COLUMNS}"
+        ))
+        .bind(uuid::Uuid::new_v4().to_string())
+        .bind(user_id)
+        .bind(memo_ids)
+        .bind(SummaryJobStatus::Queued.as_str())
+        .bind(now)
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        from_row(row)
+    }
+
+    async fn find_by_id(&self, user_id: &str, job_id: &str) -> Result<Option<SummaryJob>> {
+        let row = sqlx::query(&format!(
+            "SELECT {JOB_COLUMNS} FROM summary_jobs WHERE job_id = $1 AND user_id = $2"
+        ))
+        .bind(job_id)
+        .bind(user_id)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        row.map(from_row).transpose()
+    }
+
+    /// 実行できるジョブを1つ取り出して実行中にする
+    ///
+    /// 実行中のジョブが max_running_per_user 件あるユーザーのジョブは取り出さない。
+    /// 複数のインスタンスが同時に取り出しても上限を超えないよう、取り出しはロックを取って1つずつ行う。
+    async fn claim_next(&self, now: DateTime<Utc>, max_running_per_user: i64) -> Result<Option<SummaryJob>> {
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        sqlx::query("SELECT pg_advisory_xact_lock(hashtext('summary_jobs_claim'))")
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        let row = sqlx::query(&format!(
+            "UPDATE summary_jobs SET status = $1, stage = NULL, attempts = attempts + 1, \
+             started_at = $2, updated_at = $2 \
+             WHERE job_id = ( \
+                 SELECT j.job_id FROM summary_jobs j \
+                 WHERE j.status = $3 AND j.run_after <= $2 \
+                 AND (SELECT COUNT(*) FROM summary_jobs r WHERE r.user_id = j.user_id AND r.status = $1) < $4 \
+                 ORDER BY j.run_after \
+                 LIMIT 1 \
+                 FOR UPDATE SKIP LOCKED \
+             ) \
+             RETURNING {JOB_COLUMNS}"
+        ))
+        .bind(SummaryJobStatus::Running.as_str())
+        .bind(now)
+        .bind(SummaryJobStatus::Queued.as_str())
+        .bind(max_running_per_user)
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        tx.commit()
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        row.map(from_row).transpose()
+    }
+
+    /// 実行中であることを記録（requeue_stale で中断されたとみなされないようにする）
+    /// 戻り値: まだこのワーカーが実行中かどうか
+    async fn heartbeat(&self, job_id: &str, attempt: i32) -> Result<bool> {
+        let result = sqlx::query(
+            "UPDATE summary_jobs SET updated_at = $1 \
+             WHERE job_id = $2 AND status = $3 AND attempts = $4",
+        )
+        .bind(Utc::now())
+        .bind(job_id)
+        .bind(SummaryJobStatus::Running.as_str())
+        .bind(attempt)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// 以下の状態の変更は、取り出したときの試行回数（attempt）のまま実行中の場合だけ行う
+    /// （中断されたとみなされて別のワーカーが実行し直している場合は false を返す）
+    async fn set_stage(&self, job_id: &str, attempt: i32, stage: SummaryJobStage) -> Result<bool> {
+        let result = sqlx::query(
+            "UPDATE summary_jobs SET stage = $1, updated_at = $2 \
+             WHERE job_id = $3 AND status = $4 AND attempts = $5",
+        )
+        .bind(stage.as_str())
+        .bind(Utc::now())
+        .bind(job_id)
+        .bind(SummaryJobStatus::Running.as_str())
+        .bind(attempt)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn succeed(&self, job_id: &str, attempt: i32, summary_id: &str) -> Result<bool> {
+        let result = sqlx::query(
+            "UPDATE summary_jobs SET status = $1, stage = NULL, error = NULL, summary_id = $2, \
+             finished_at = $3, updated_at = $3 WHERE job_id = $4 AND status = $5 AND attempts = $6",
+        )
+        .bind(SummaryJobStatus::Succeeded.as_str())
+        .bind(summary_id)
+        .bind(Utc::now())
+        .bind(job_id)
+        .bind(SummaryJobStatus::Running.as_str())
+        .bind(attempt)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// 失敗したジョブを実行待ちに戻す（run_after を過ぎたら再試行する）
+    async fn retry(&self, job_id: &str, attempt: i32, error: &str, run_after: DateTime<Utc>) -> Result<bool> {
+        let result = sqlx::query(
+            "UPDATE summary_jobs SET status = $1, stage = NULL, error = $2, run_after = $3, \
+             updated_at = $4 WHERE job_id = $5 AND status = $6 AND attempts = $7",
+        )
+        .bind(SummaryJobStatus::Queued.as_str())
+        .bind(error)
+        .bind(run_after)
+        .bind(Utc::now())
+        .bind(job_id)
+        .bind(SummaryJobStatus::Running.as_str())
+        .bind(attempt)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn fail(&self, job_id: &str, attempt: i32, error: &str) -> Result<bool> {
+        let result = sqlx::query(
+            "UPDATE summary_jobs SET status = $1, stage = NULL, error = $2, \
+             finished_at = $3, updated_at = $3 WHERE job_id = $4 AND status = $5 AND attempts = $6",
+        )
+        .bind(SummaryJobStatus::Failed.as_str())
+        .bind(error)
+        .bind(Utc::now())
+        .bind(job_id)
+        .bind(SummaryJobStatus::Running.as_str())
+        .bind(attempt)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// 実行中のまま updated_before 以降に更新（heartbeat）されていないジョブを実行待ちに戻す
+    /// （実行中にインスタンスが停止した場合）。試行回数を使い切ったジョブは失敗にする
+    async fn requeue_stale(&self, updated_before: DateTime<Utc>, max_attempts: i32) -> Result<u64> {
+        let now = Utc::now();
+        let result = sqlx::query(
+            "UPDATE summary_jobs SET \
+             status = CASE WHEN attempts < $1 THEN $2 ELSE $3 END, \
+             finished_at = CASE WHEN attempts < $1 THEN NULL ELSE $4 END, \
+             stage = NULL, error = $5, run_after = $4, updated_at = $4 \
+             WHERE status = $6 AND updated_at < $7",
+        )
+        .bind(max_attempts)
+        .bind(SummaryJobStatus::Queued.as_str())
+        .bind(SummaryJobStatus::Failed.as_str())
+        .bind(now)
+        .bind("Summary job was interrupted")
+        .bind(SummaryJobStatus::Running.as_str())
+        .bind(updated_before)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        Ok(result.rows_affected())
+    }
+
+    /// 終了してから cutoff を過ぎたジョブを削除
+    async fn delete_finished_before(&self, cutoff: DateTime<Utc>) -> Result<u64> {
+        let result = sqlx::query(
+            "DELETE FROM summary_jobs WHERE status IN ($1, $2) AND finished_at < $3",
+        )
+        .bind(SummaryJobStatus::Succeeded.as_str())
+        .bind(SummaryJobStatus::Failed.as_str())
+        .bind(cutoff)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        Ok(result.rows_affected())
+    }
+}
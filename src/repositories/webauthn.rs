@@ -0,0 +1,169 @@
+use crate::error::{AppError, Result};
+use chrono::{DateTime, Utc};
+use crate::webauthn::{CreationOptions, RequestOptions};
+use serde::{Deserialize, Serialize};
+
+/// WebAuthnの資格情報（公開鍵は一覧に含めない）
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct WebAuthnCredential {
+    pub credential_id: String,
+    #[serde(skip)]
+    pub user_id: String,
+    pub name: String,
+    #[serde(skip)]
+    pub public_key: Vec<u8>,
+    pub algorithm: i32,
+    #[serde(skip)]
+    pub sign_count: i64,
+    pub transports: Vec<String>,
+    pub last_used_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Serialize)]
+pub struct WebAuthnCredentialList {
+    pub credentials: Vec<WebAuthnCredential>,
+}
+
+#[derive(Deserialize)]
+pub struct UpdateWebAuthnCredentialRequest {
+    pub name: String,
+}
+
+/// 新規作成する資格情報の保存内容
+pub struct NewWebAuthnCredential {
+    pub credential_id: String,
+    pub user_id: String,
+    pub name: String,
+    pub public_key: Vec<u8>,
+    pub algorithm: i32,
+    pub sign_count: i64,
+    pub transports: Vec<String>,
+}
+
+pub struct WebAuthnRepository {
+    pub pool: sqlx::PgPool,
+}
+
+const CREDENTIAL_COLUMNS: &str = "credential_id, user_id, name, public_key, algorithm, sign_count, transports, last_used_at, created_at";
+
+impl WebAuthnRepository {
+    pub fn new(pool: sqlx::PgPool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn find_by_user_id(&self, user_id: &str) -> Result<Vec<WebAuthnCredential>> {
+        sqlx::query_as::<_, WebAuthnCredential>(&format!(
+            "SELECT {CREDENTIAL_COLUMNS} FROM webauthn_credentials WHERE user_id = $1 ORDER BY created_at DESC"
+        ))
+        .bind(user_id)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))
+    }
+
+    /// 有効なユーザーの資格情報のみを返す
+    pub async fn find_active_by_id(&self, credential_id: &str) -> Result<Option<WebAuthnCredential>> {
+        sqlx::query_as::<_, WebAuthnCredential>(
+            "SELECT c.credential_id, c.user_id, c.name, c.public_key, c.algorithm, c.sign_count, c.transports, c.last_used_at, c.created_at \
+             FROM webauthn_credentials c JOIN users u ON u.user_id = c.user_id \
+             WHERE c.credential_id = $1 AND u.is_active = true",
+        )
+        .bind(credential_id)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))
+    }
+
+    pub async fn count_by_user_id(&self, user_id: &str) -> Result<i64> {
+        let row = sqlx::query_as::<_, (i64,)>(
+            "SELECT COUNT(*) FROM webauthn_credentials WHERE user_id = $1",
+        )
+        .bind(user_id)
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Ok(row.0)
+    }
+
+    /// 資格情報を保存（同じ資格情報IDが登録済みの場合は None）
+    pub async fn create(&self, credential: NewWebAuthnCredential) -> Result<Option<WebAuthnCredential>> {
+        sqlx::query_as::<_, WebAuthnCredential>(&format!(
+            "INSERT INTO webauthn_credentials (credential_id, user_id, name, public_key, algorithm, sign_count, transports, created_at) \
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8) ON CONFLICT (credential_id) DO NOTHING \
+             RETURNING {CREDENTIAL_COLUMNS}"
+        ))
+        .bind(&credential.credential_id)
+        .bind(&credential.user_id)
+        .bind(&credential.name)
+        .bind(&credential.public_key)
+        .bind(credential.algorithm)
+        .bind(credential.sign_count)
+        .bind(&credential.transports)
+        .bind(Utc::now())
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))
+    }
+
+    /// 署名カウンタと最終使用日時を更新
+    /// 同時に同じカウンタで認証された場合に備え、読み取った時点のカウンタと一致する場合のみ更新する
+    pub async fn record_use(&self, credential_id: &str, old_sign_count: i64, new_sign_count: i64) -> Result<bool> {
+        let result = sqlx::query(
+            "UPDATE webauthn_credentials SET sign_count = $1, last_used_at = $2 \
+             WHERE credential_id = $3 AND sign_count = $4",
+        )
+        .bind(new_sign_count)
+        .bind(Utc::now())
+        .bind(credential_id)
+        .bind(old_sign_count)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    pub async fn rename(&self, user_id: &str, credential_id: &str, name: &str) -> Result<Option<WebAuthnCredential>> {
+        sqlx::query_as::<_, WebAuthnCredential>(&format!(
+            "UPDATE webauthn_credentials SET name = $1 WHERE credential_id = $2 AND user_id = $3 \
+             RETURNING {CREDENTIAL_COLUMNS}"
+        ))
+        .bind(name)
+        .bind(credential_id)
+        .bind(user_id)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))
+    }
+
+    pub async fn delete(&self, user_id: &str, credential_id: &str) -> Result<bool> {
+        let result = sqlx::query(
+            "DELETE FROM webauthn_credentials WHERE credential_id = $1 AND user_id = $2",
+        )
+        .bind(credential_id)
+        .bind(user_id)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Ok(result.rows_affected() > 0)
+    }
+}
+
+/// 登録セレモニーの開始時に返す情報
+#[derive(Serialize)]
+pub struct WebAuthnRegistrationChallenge {
+    pub ceremony_id: String,
+    /// navigator.credentials.create() の publicKey に渡す
+    pub public_key: CreationOptions,
+}
+
+/// 認証セレモニーの開始時に返す情報
+#[derive(Serialize)]
+pub struct WebAuthnAuthenticationChallenge {
+    pub ceremony_id: String,
+    /// navigator.credentials.get() の publicKey に渡す
+    pub public_key: RequestOptions,
+}
@@ -1,7 +1,8 @@
 use axum::{
     Router,
-    extract::{State, ConnectInfo},
-    http::{StatusCode, HeaderMap},
+    body::Bytes,
+    extract::{Path, State, ConnectInfo},
+    http::{StatusCode, HeaderMap, header},
     response::{IntoResponse, Json, Response},
     routing::{get, post, patch, delete},
 };
@@ -13,12 +14,19 @@ use time;
 
 use crate::auth::{extract_jti_from_token, extract_user_id_from_token};
 use crate::error::{AppError, map_error};
-use crate::repositories::auth::UserCreateRequest;
+use crate::repositories::WebAuthnAuthenticationChallenge;
+use crate::repositories::auth::{
+    ExternalIdentityList, MagicLinkSettings, SessionClient, UserCreateRequest, UserResponse,
+};
+use crate::routes::authorization::{Authorized, bearer_token, require};
 use crate::server::AppState;
+use crate::services::LoginOutcome;
+use crate::services::oidc_service::OidcProviderList;
+use crate::webauthn::{AuthenticationCredential, UserVerification};
 
 /// クライアントの実際のIPアドレスを取得
 /// Cloudflare Tunnel経由の場合はCF-Connecting-IPヘッダーから取得
-fn get_client_ip(headers: &HeaderMap, addr: &SocketAddr) -> String {
+pub(crate) fn get_client_ip(headers: &HeaderMap, addr: &SocketAddr) -> String {
     // 1. CF-Connecting-IP (Cloudflare推奨)
     if let Some(ip) = headers.get("cf-connecting-ip") {
         if let Ok(ip_str) = ip.to_str() {
@@ -46,13 +54,53 @@ fn get_client_ip(headers: &HeaderMap, addr: &SocketAddr) -> String {
     addr.ip().to_string()
 }
 
+/// セッションに記録する端末情報を取得
+fn session_client(headers: &HeaderMap, addr: &SocketAddr) -> SessionClient {
+    let user_agent = headers
+        .get(header::USER_AGENT)
+        .and_then(|value| value.to_str().ok())
+        .map(|ua| ua.chars().take(512).collect());
+
+    SessionClient {
+        user_agent,
+        ip_address: Some(get_client_ip(headers, addr)),
+    }
+}
+
 pub fn create_auth_routes() -> Router<AppState> {
     Router::new()
         .route("/auth/login", post(handle_login))
+        .route("/auth/login/mfa", post(handle_login_mfa))
+        .route(
+            "/auth/login/mfa/webauthn/start",
+            post(handle_login_mfa_webauthn_start),
+        )
+        .route(
+            "/auth/login/mfa/webauthn/finish",
+            post(handle_login_mfa_webauthn_finish),
+        )
+        .route("/auth/login/webauthn/start", post(handle_login_webauthn_start))
+        .route("/auth/login/webauthn/finish", post(handle_login_webauthn_finish))
+        .route("/auth/login/magic-link", post(handle_send_magic_link))
+        .route("/auth/login/magic-link/verify", post(handle_login_magic_link))
+        .route("/auth/unlock/request", post(handle_request_account_unlock))
+        .route("/auth/unlock", post(handle_unlock_account))
+        .route("/auth/magic-link", get(handle_get_magic_link_settings))
+        .route("/auth/magic-link", patch(handle_update_magic_link_settings))
+        .route("/auth/oidc/providers", get(handle_list_oidc_providers))
+        .route("/auth/oidc/{capture}/authorize", post(handle_oidc_authorize))
+        .route("/auth/oidc/{capture}/callback", post(handle_oidc_callback))
+        .route("/auth/identities", get(handle_list_external_identities))
+        .route("/auth/identities/{capture}", delete(handle_unlink_external_identity))
         .route("/auth/logout", post(handle_logout))
         .route("/auth/me", get(handle_get_current_user))
         .route("/auth/user", patch(handle_update_user))
         .route("/auth/user", delete(handle_delete_user))
+        .route("/auth/user/deletion", get(handle_get_account_deletion))
+        .route("/auth/user/deletion", delete(handle_cancel_account_deletion))
+        .route("/auth/email/change", post(handle_request_email_change))
+        .route("/auth/email/verify", post(handle_confirm_email_change))
+        .route("/auth/reauthenticate", post(handle_reauthenticate))
         .route("/auth/register/start", post(handle_start_registration))
         .route("/auth/register/verify", post(handle_verify_email))
         .route(
@@ -66,11 +114,134 @@ pub fn create_auth_routes() -> Router<AppState> {
         .route("/auth/password/reset", post(handle_complete_password_reset))
 }
 
+/// アクセストークンの有効期間（秒）
+const ACCESS_TOKEN_MAX_AGE: i64 = 60 * 60;
+/// 二要素認証待ちトークンの有効期間（秒）
+const MFA_TOKEN_MAX_AGE: i64 = 5 * 60;
+/// OpenID Connect の認可リクエストの state を保持するCookieの有効期間（秒）
+const OIDC_STATE_MAX_AGE: i64 = 10 * 60;
+
 // リクエスト/レスポンス構造体
 #[derive(Deserialize)]
 struct LoginRequest {
     email: String,
     password: String,
+    #[serde(default)]
+    token_delivery: TokenDelivery,
+}
+
+/// 二要素認証によるログインのリクエスト
+#[derive(Deserialize)]
+struct MfaLoginRequest {
+    mfa_token: String,
+    /// TOTPコードまたはリカバリーコード
+    code: String,
+    #[serde(default)]
+    token_delivery: TokenDelivery,
+}
+
+/// 二要素認証としてパスキーを使う場合のセレモニー開始リクエスト
+#[derive(Deserialize)]
+struct MfaWebAuthnStartRequest {
+    mfa_token: String,
+}
+
+/// 二要素認証としてパスキーを使う場合のログインリクエスト
+#[derive(Deserialize)]
+struct MfaWebAuthnFinishRequest {
+    mfa_token: String,
+    ceremony_id: String,
+    credential: AuthenticationCredential,
+    #[serde(default)]
+    token_delivery: TokenDelivery,
+}
+
+/// パスキーによるログインのリクエスト
+#[derive(Deserialize)]
+struct WebAuthnLoginRequest {
+    ceremony_id: String,
+    credential: AuthenticationCredential,
+    #[serde(default)]
+    token_delivery: TokenDelivery,
+}
+
+/// ログインリンクの送信リクエスト
+#[derive(Deserialize)]
+struct SendMagicLinkRequest {
+    email: String,
+}
+
+/// ログインリンクによるログインのリクエスト
+#[derive(Deserialize)]
+struct MagicLinkLoginRequest {
+    token: String,
+    #[serde(default)]
+    token_delivery: TokenDelivery,
+}
+
+/// ロック解除リンクの再送信リクエスト
+#[derive(Deserialize)]
+struct RequestAccountUnlockRequest {
+    email: String,
+}
+
+/// ロック解除のリクエスト
+#[derive(Deserialize)]
+struct UnlockAccountRequest {
+    token: String,
+}
+
+/// OpenID Connect のコールバック（フロントエンドがリダイレクト先で受け取った値を送る）
+#[derive(Deserialize)]
+struct OidcCallbackRequest {
+    code: String,
+    state: String,
+    #[serde(default)]
+    token_delivery: TokenDelivery,
+}
+
+/// トークンの受け渡し方法
+/// Cookie: Set-Cookie で設定（ブラウザ向け）
+/// Body: レスポンスのJSONで返す（CLI・ネイティブアプリ向け）
+#[derive(Deserialize, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+enum TokenDelivery {
+    #[default]
+    Cookie,
+    Body,
+}
+
+/// リフレッシュトークンをJSONで送る場合のリクエスト
+#[derive(Deserialize)]
+struct RefreshTokenRequest {
+    #[serde(default)]
+    refresh_token: Option<String>,
+}
+
+/// リクエストボディのリフレッシュトークン
+///
+/// ボディが空・`{}` の場合や、JSON以外のボディの場合は None（Cookieを使う）。
+/// JSONとして送られたボディが不正な場合はエラー（400）。
+fn refresh_token_from_body(
+    headers: &HeaderMap,
+    body: &Bytes,
+) -> crate::error::Result<Option<String>> {
+    if body.iter().all(u8::is_ascii_whitespace) {
+        return Ok(None);
+    }
+
+    let is_json = headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.starts_with("application/json"));
+    match serde_json::from_slice::<RefreshTokenRequest>(body) {
+        Ok(req) => Ok(req.refresh_token.filter(|token| !token.is_empty())),
+        Err(e) if is_json => Err(AppError::ValidationError(format!(
+            "Invalid request body: {}",
+            e
+        ))),
+        Err(_) => Ok(None),
+    }
 }
 
 #[derive(Deserialize)]
@@ -94,17 +265,35 @@ struct CompleteRegistrationRequest {
 
 #[derive(Deserialize)]
 struct UpdateUserRequest {
+    /// 変更できない（/auth/email/change で確認コードによって変更する）
     email: Option<String>,
     display_name: Option<String>,
     password: Option<String>,
 }
 
+#[derive(Deserialize)]
+struct RequestEmailChangeRequest {
+    new_email: String,
+}
+
+#[derive(Deserialize)]
+struct ConfirmEmailChangeRequest {
+    code: String,
+}
+
 #[derive(Deserialize)]
 struct ResetPasswordRequest {
     old_password: String,
     new_password: String,
 }
 
+#[derive(Deserialize)]
+struct ReauthenticateRequest {
+    password: String,
+    #[serde(default)]
+    token_delivery: TokenDelivery,
+}
+
 #[derive(Deserialize)]
 struct ForgotPasswordRequest {
     email: String,
@@ -129,7 +318,7 @@ async fn handle_login(
     headers: HeaderMap,
     jar: CookieJar,
     Json(req): Json<LoginRequest>,
-) -> Result<impl IntoResponse, Response> {
+) -> Result<Response, Response> {
     // レート制限チェック（IPベース）
     let ip = get_client_ip(&headers, &addr);
     state
@@ -156,11 +345,495 @@ async fn handle_login(
         })?;
 
     // ログイン処理
+    let outcome = state
+        .auth_service
+        .login(req.email, req.password, session_client(&headers, &addr))
+        .await
+        .map_err(map_error)?;
+
+    Ok(login_outcome_response(&state, jar, outcome, req.token_delivery))
+}
+
+/// 二要素認証によるログインの2段階目
+async fn handle_login_mfa(
+    State(state): State<AppState>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    jar: CookieJar,
+    Json(req): Json<MfaLoginRequest>,
+) -> Result<Response, Response> {
+    // レート制限チェック（IPベース）
+    let ip = get_client_ip(&headers, &addr);
+    state
+        .auth_rate_limiter
+        .check_ip_limit(&ip)
+        .map_err(|e| {
+            (
+                StatusCode::TOO_MANY_REQUESTS,
+                Json(json!({"error": e})),
+            )
+                .into_response()
+        })?;
+
+    // ユーザーIDベースのレート制限（コードの総当たりを防ぐ）
+    let key = &state.jwt_keys;
+    let user_id = extract_user_id_from_token(&req.mfa_token, key).map_err(map_error)?;
+    state
+        .auth_rate_limiter
+        .check_user_limit(&user_id)
+        .map_err(|e| {
+            (
+                StatusCode::TOO_MANY_REQUESTS,
+                Json(json!({"error": e})),
+            )
+                .into_response()
+        })?;
+
     let (access_token, refresh_token, user) = state
         .auth_service
-        .login(req.email, req.password)
+        .complete_mfa_login(&req.mfa_token, &req.code, session_client(&headers, &addr))
+        .await
+        .map_err(map_error)?;
+
+    Ok(login_response(
+        &state,
+        jar,
+        access_token,
+        refresh_token,
+        &user,
+        req.token_delivery,
+    ))
+}
+
+/// 二要素認証としてパスキーの認証セレモニーを開始
+async fn handle_login_mfa_webauthn_start(
+    State(state): State<AppState>,
+    Json(req): Json<MfaWebAuthnStartRequest>,
+) -> Result<Json<WebAuthnAuthenticationChallenge>, Response> {
+    let challenge = state
+        .auth_service
+        .start_mfa_webauthn(&req.mfa_token)
         .await
         .map_err(map_error)?;
+    Ok(Json(challenge))
+}
+
+/// 二要素認証によるログインの2段階目（パスキー）
+async fn handle_login_mfa_webauthn_finish(
+    State(state): State<AppState>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    jar: CookieJar,
+    Json(req): Json<MfaWebAuthnFinishRequest>,
+) -> Result<Response, Response> {
+    // レート制限チェック（IPベース）
+    let ip = get_client_ip(&headers, &addr);
+    state
+        .auth_rate_limiter
+        .check_ip_limit(&ip)
+        .map_err(|e| {
+            (
+                StatusCode::TOO_MANY_REQUESTS,
+                Json(json!({"error": e})),
+            )
+                .into_response()
+        })?;
+
+    let (access_token, refresh_token, user) = state
+        .auth_service
+        .complete_mfa_login_with_webauthn(
+            &req.mfa_token,
+            &req.ceremony_id,
+            &req.credential,
+            session_client(&headers, &addr),
+        )
+        .await
+        .map_err(map_error)?;
+
+    Ok(login_response(
+        &state,
+        jar,
+        access_token,
+        refresh_token,
+        &user,
+        req.token_delivery,
+    ))
+}
+
+/// パスキーによるログインの認証セレモニーを開始
+async fn handle_login_webauthn_start(
+    State(state): State<AppState>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+) -> Result<Json<WebAuthnAuthenticationChallenge>, Response> {
+    // レート制限チェック（IPベース）
+    let ip = get_client_ip(&headers, &addr);
+    state
+        .auth_rate_limiter
+        .check_ip_limit(&ip)
+        .map_err(|e| {
+            (
+                StatusCode::TOO_MANY_REQUESTS,
+                Json(json!({"error": e})),
+            )
+                .into_response()
+        })?;
+
+    let challenge = state
+        .webauthn_service
+        .start_authentication(None, UserVerification::Required)
+        .await
+        .map_err(map_error)?;
+    Ok(Json(challenge))
+}
+
+/// パスキーによるログイン（パスワードレス）
+async fn handle_login_webauthn_finish(
+    State(state): State<AppState>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    jar: CookieJar,
+    Json(req): Json<WebAuthnLoginRequest>,
+) -> Result<Response, Response> {
+    // レート制限チェック（IPベース）
+    let ip = get_client_ip(&headers, &addr);
+    state
+        .auth_rate_limiter
+        .check_ip_limit(&ip)
+        .map_err(|e| {
+            (
+                StatusCode::TOO_MANY_REQUESTS,
+                Json(json!({"error": e})),
+            )
+                .into_response()
+        })?;
+
+    let (access_token, refresh_token, user) = state
+        .auth_service
+        .login_with_passkey(&req.ceremony_id, &req.credential, session_client(&headers, &addr))
+        .await
+        .map_err(map_error)?;
+
+    Ok(login_response(
+        &state,
+        jar,
+        access_token,
+        refresh_token,
+        &user,
+        req.token_delivery,
+    ))
+}
+
+/// ログインリンクをメールで送信
+async fn handle_send_magic_link(
+    State(state): State<AppState>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    Json(req): Json<SendMagicLinkRequest>,
+) -> Result<Json<serde_json::Value>, Response> {
+    let ip = get_client_ip(&headers, &addr);
+    state
+        .auth_service
+        .send_magic_link(&req.email, Some(&ip))
+        .await
+        .map_err(map_error)?;
+
+    Ok(Json(json!({
+        "message": "If the account exists, a login link has been sent to the email address"
+    })))
+}
+
+/// ログインリンクのトークンでログイン
+async fn handle_login_magic_link(
+    State(state): State<AppState>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    jar: CookieJar,
+    Json(req): Json<MagicLinkLoginRequest>,
+) -> Result<Response, Response> {
+    // レート制限チェック（IPベース）
+    let ip = get_client_ip(&headers, &addr);
+    state
+        .auth_rate_limiter
+        .check_ip_limit(&ip)
+        .map_err(|e| {
+            (
+                StatusCode::TOO_MANY_REQUESTS,
+                Json(json!({"error": e})),
+            )
+                .into_response()
+        })?;
+
+    let outcome = state
+        .auth_service
+        .login_with_magic_link(&req.token, session_client(&headers, &addr))
+        .await
+        .map_err(map_error)?;
+
+    Ok(login_outcome_response(&state, jar, outcome, req.token_delivery))
+}
+
+/// ロック解除のリンクをメールで再送信
+async fn handle_request_account_unlock(
+    State(state): State<AppState>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    Json(req): Json<RequestAccountUnlockRequest>,
+) -> Result<Json<serde_json::Value>, Response> {
+    let ip = get_client_ip(&headers, &addr);
+    state
+        .auth_service
+        .request_account_unlock(&req.email, Some(&ip))
+        .await
+        .map_err(map_error)?;
+
+    Ok(Json(json!({
+        "message": "If the account is locked, an unlock link has been sent to the email address"
+    })))
+}
+
+/// ロック解除のリンクのトークンでアカウントのロックを解除
+async fn handle_unlock_account(
+    State(state): State<AppState>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    Json(req): Json<UnlockAccountRequest>,
+) -> Result<Json<serde_json::Value>, Response> {
+    // レート制限チェック（IPベース）
+    let ip = get_client_ip(&headers, &addr);
+    state
+        .auth_rate_limiter
+        .check_ip_limit(&ip)
+        .map_err(|e| {
+            (
+                StatusCode::TOO_MANY_REQUESTS,
+                Json(json!({"error": e})),
+            )
+                .into_response()
+        })?;
+
+    state
+        .auth_service
+        .unlock_account(&req.token)
+        .await
+        .map_err(map_error)?;
+
+    Ok(Json(json!({"message": "Account unlocked successfully"})))
+}
+
+/// ログインリンクの設定を取得
+async fn handle_get_magic_link_settings(
+    State(state): State<AppState>,
+    auth: Authorized<require::EditAccount>,
+) -> Result<Json<MagicLinkSettings>, Response> {
+    let settings = state
+        .auth_service
+        .magic_link_settings(&auth.user_id)
+        .await
+        .map_err(map_error)?;
+    Ok(Json(settings))
+}
+
+/// ログインリンクによるログインの有効・無効を設定
+async fn handle_update_magic_link_settings(
+    State(state): State<AppState>,
+    auth: Authorized<require::EditAccount>,
+    Json(req): Json<MagicLinkSettings>,
+) -> Result<Json<MagicLinkSettings>, Response> {
+    let settings = state
+        .auth_service
+        .update_magic_link_settings(&auth.user_id, req)
+        .await
+        .map_err(map_error)?;
+    Ok(Json(settings))
+}
+
+/// ログインに使えるIDプロバイダーの一覧
+async fn handle_list_oidc_providers(State(state): State<AppState>) -> Json<OidcProviderList> {
+    Json(OidcProviderList {
+        providers: state.oidc_service.providers(),
+    })
+}
+
+/// IDプロバイダーでのログインを開始（リダイレクト先のURLを返す）
+async fn handle_oidc_authorize(
+    State(state): State<AppState>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    jar: CookieJar,
+    Path(provider): Path<String>,
+) -> Result<Response, Response> {
+    // レート制限チェック（IPベース）
+    let ip = get_client_ip(&headers, &addr);
+    state
+        .auth_rate_limiter
+        .check_ip_limit(&ip)
+        .map_err(|e| {
+            (
+                StatusCode::TOO_MANY_REQUESTS,
+                Json(json!({"error": e})),
+            )
+                .into_response()
+        })?;
+
+    let authorization = state
+        .oidc_service
+        .start_authorization(&provider)
+        .await
+        .map_err(map_error)?;
+
+    // コールバックを開始したブラウザと結び付けるため、state をCookieにも保存する
+    let cookie_config = state.config.server.get_cookie_config();
+    let state_cookie = Cookie::build(("oidc_state", authorization.state.clone()))
+        .path("/")
+        .max_age(time::Duration::seconds(OIDC_STATE_MAX_AGE))
+        .same_site(cookie_config.same_site)
+        .secure(cookie_config.secure)
+        .http_only(cookie_config.http_only)
+        .build();
+
+    Ok((
+        jar.add(state_cookie),
+        Json(json!({
+            "authorization_url": authorization.authorization_url,
+            "state": authorization.state,
+            "expires_in": OIDC_STATE_MAX_AGE,
+        })),
+    )
+        .into_response())
+}
+
+/// IDプロバイダーからのコールバック（認可コードでログイン）
+async fn handle_oidc_callback(
+    State(state): State<AppState>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    jar: CookieJar,
+    Path(provider): Path<String>,
+    Json(req): Json<OidcCallbackRequest>,
+) -> Result<Response, Response> {
+    // レート制限チェック（IPベース）
+    let ip = get_client_ip(&headers, &addr);
+    state
+        .auth_rate_limiter
+        .check_ip_limit(&ip)
+        .map_err(|e| {
+            (
+                StatusCode::TOO_MANY_REQUESTS,
+                Json(json!({"error": e})),
+            )
+                .into_response()
+        })?;
+
+    // トークンの受け渡し方法に関係なく、ログインを開始したクライアントか確認する（ログインCSRF対策）
+    // 盗んだ認可コードと state を別のクライアントから送ってもログインできないようにする
+    if jar.get("oidc_state").map(|cookie| cookie.value()) != Some(req.state.as_str()) {
+        return Err(map_error(AppError::AuthenticationError(
+            "Authorization request is invalid or expired".to_string(),
+        )));
+    }
+
+    let identity = state
+        .oidc_service
+        .complete_authorization(&provider, &req.code, &req.state)
+        .await
+        .map_err(map_error)?;
+
+    let outcome = state
+        .auth_service
+        .login_with_external_identity(identity, session_client(&headers, &addr))
+        .await
+        .map_err(map_error)?;
+
+    let jar = jar.remove(Cookie::from("oidc_state"));
+    Ok(login_outcome_response(&state, jar, outcome, req.token_delivery))
+}
+
+/// 連携済みの外部ID一覧
+async fn handle_list_external_identities(
+    State(state): State<AppState>,
+    auth: Authorized<require::EditAccount>,
+) -> Result<Json<ExternalIdentityList>, Response> {
+    let identities = state
+        .auth_service
+        .list_external_identities(&auth.user_id)
+        .await
+        .map_err(map_error)?;
+    Ok(Json(ExternalIdentityList { identities }))
+}
+
+/// 外部IDの連携を解除
+async fn handle_unlink_external_identity(
+    State(state): State<AppState>,
+    auth: Authorized<require::EditAccount>,
+    Path(provider): Path<String>,
+) -> Result<Json<serde_json::Value>, Response> {
+    state
+        .auth_service
+        .unlink_external_identity(&auth.user_id, &provider)
+        .await
+        .map_err(map_error)?;
+    Ok(Json(json!({
+        "status": "success",
+        "message": format!("External identity unlinked: {provider}")
+    })))
+}
+
+/// パスワードなどの1段階目の認証結果をレスポンスに変換
+fn login_outcome_response(
+    state: &AppState,
+    jar: CookieJar,
+    outcome: LoginOutcome,
+    token_delivery: TokenDelivery,
+) -> Response {
+    match outcome {
+        LoginOutcome::Authenticated {
+            access_token,
+            refresh_token,
+            user,
+        } => login_response(state, jar, access_token, refresh_token, &user, token_delivery),
+        // 二要素認証が必要な場合はトークンを発行せず、二要素認証待ちトークンを返す
+        LoginOutcome::MfaRequired {
+            mfa_token,
+            mfa_methods,
+        } => Json(json!({
+            "message": "Two-factor authentication required",
+            "mfa_required": true,
+            "mfa_token": mfa_token,
+            "mfa_methods": mfa_methods,
+            "expires_in": MFA_TOKEN_MAX_AGE,
+        }))
+        .into_response(),
+    }
+}
+
+/// ログイン成功時のレスポンス（トークンをCookieまたはJSONで返す）
+fn login_response(
+    state: &AppState,
+    jar: CookieJar,
+    access_token: String,
+    refresh_token: String,
+    user: &UserResponse,
+    token_delivery: TokenDelivery,
+) -> Response {
+    let user_json = json!({
+        "user_id": user.user_id,
+        "email": user.email,
+        "display_name": user.display_name,
+    });
+
+    // JSONでトークンを返す（Cookieは設定しない）
+    if token_delivery == TokenDelivery::Body {
+        return Json(json!({
+            "message": "Login successful",
+            "access_token": access_token,
+            "refresh_token": refresh_token,
+            "token_type": "Bearer",
+            "expires_in": ACCESS_TOKEN_MAX_AGE,
+            "user": user_json,
+        }))
+        .into_response();
+    }
 
     // Cookieを設定（環境に応じて自動的に設定される）
     let cookie_config = state.config.server.get_cookie_config();
@@ -175,7 +848,7 @@ async fn handle_login(
 
     let access_cookie = Cookie::build(("access_token", access_token))
         .path("/")
-        .max_age(time::Duration::seconds(60 * 60))
+        .max_age(time::Duration::seconds(ACCESS_TOKEN_MAX_AGE))
         .same_site(cookie_config.same_site)
         .secure(cookie_config.secure)
         .http_only(cookie_config.http_only)
@@ -183,43 +856,48 @@ async fn handle_login(
 
     let jar = jar.add(refresh_cookie).add(access_cookie);
 
-    Ok((
+    (
         jar,
         Json(json!({
             "message": "Login successful",
-            "user": {
-                "user_id": user.user_id,
-                "email": user.email,
-                "display_name": user.display_name,
-            }
+            "user": user_json,
         })),
-    ))
+    )
+        .into_response()
 }
 
 /// ログアウト処理
 async fn handle_logout(
     State(state): State<AppState>,
+    headers: HeaderMap,
     jar: CookieJar,
+    body: Bytes,
 ) -> Result<impl IntoResponse, Response> {
-    let mut jtis = Vec::new();
+    let mut tokens = Vec::new();
 
-    // Cookieからトークンを取得してJTIを抽出
-    let key = &state.jwt_decoding_key;
+    // ヘッダー・Cookie・リクエストボディからトークンを取得してJTIと有効期限を抽出
+    let key = &state.jwt_keys;
 
-    if let Some(access_token) = jar.get("access_token") {
-        if let Ok(jti) = extract_jti_from_token(access_token.value(), &key) {
-            jtis.push(jti);
-        }
+    let access_token = bearer_token(&headers)
+        .or_else(|| jar.get("access_token").map(|cookie| cookie.value()));
+    if let Some(access_token) = access_token
+        && let Ok(token) = extract_jti_from_token(access_token, key)
+    {
+        tokens.push(token);
     }
 
-    if let Some(refresh_token) = jar.get("refresh_token") {
-        if let Ok(jti) = extract_jti_from_token(refresh_token.value(), &key) {
-            jtis.push(jti);
-        }
+    let body_token = refresh_token_from_body(&headers, &body).map_err(map_error)?;
+    let refresh_token = body_token
+        .as_deref()
+        .or_else(|| jar.get("refresh_token").map(|cookie| cookie.value()));
+    if let Some(refresh_token) = refresh_token
+        && let Ok(token) = extract_jti_from_token(refresh_token, key)
+    {
+        tokens.push(token);
     }
 
     // トークンを無効化
-    state.auth_service.logout(jtis).await.map_err(map_error)?;
+    state.auth_service.logout(tokens).await.map_err(map_error)?;
 
     // Cookieを削除
     let jar = jar
@@ -232,13 +910,9 @@ async fn handle_logout(
 /// 現在のユーザー情報取得
 async fn handle_get_current_user(
     State(state): State<AppState>,
-    jar: CookieJar,
+    auth: Authorized<require::Authenticated>,
 ) -> Result<impl IntoResponse, Response> {
-    // アクセストークンからユーザーIDを取得・検証
-    let user_id = state
-        .auth_service
-        .extract_and_verify_user_from_access_token(&jar)
-        .await?;
+    let user_id = auth.user_id;
 
     // ユーザー情報を取得
     let user = state
@@ -304,6 +978,8 @@ async fn handle_verify_email(
 /// ステップ3: 登録完了
 async fn handle_complete_registration(
     State(state): State<AppState>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
     jar: CookieJar,
     Json(req): Json<CompleteRegistrationRequest>,
 ) -> Result<impl IntoResponse, Response> {
@@ -328,7 +1004,7 @@ async fn handle_complete_registration(
     // ユーザー登録
     let (access_token, refresh_token, user) = state
         .auth_service
-        .complete_registration(registration_token, user_req)
+        .complete_registration(registration_token, user_req, session_client(&headers, &addr))
         .await
         .map_err(map_error)?;
 
@@ -376,7 +1052,8 @@ async fn handle_refresh(
     ConnectInfo(addr): ConnectInfo<SocketAddr>,
     headers: HeaderMap,
     jar: CookieJar,
-) -> Result<impl IntoResponse, Response> {
+    body: Bytes,
+) -> Result<Response, Response> {
     // レート制限チェック（IPベース）
     let ip = get_client_ip(&headers, &addr);
     state
@@ -390,24 +1067,24 @@ async fn handle_refresh(
                 .into_response()
         })?;
 
-    // リフレッシュトークンを取得
-    let refresh_token = jar.get("refresh_token").ok_or_else(|| {
-        map_error(AppError::AuthenticationError(
-            "Refresh token required".to_string(),
-        ))
-    })?;
-
-    // トークンからユーザーIDとJTIを取得
-    let key = &state.jwt_decoding_key;
-    let user_id = extract_user_id_from_token(refresh_token.value(), key).map_err(map_error)?;
-    let jti = extract_jti_from_token(refresh_token.value(), key).map_err(map_error)?;
+    // リフレッシュトークンを取得（JSONで送られた場合はJSONで返す）
+    let body_token = refresh_token_from_body(&headers, &body).map_err(map_error)?;
+    let body_mode = body_token.is_some();
+    let refresh_token = match &body_token {
+        Some(token) => token.as_str(),
+        None => jar
+            .get("refresh_token")
+            .map(|cookie| cookie.value())
+            .ok_or_else(|| {
+                map_error(AppError::AuthenticationError(
+                    "Refresh token required".to_string(),
+                ))
+            })?,
+    };
 
-    // トークンが失効されていないか確認
-    if state.auth_service.is_token_revoked(&jti).await.map_err(map_error)? {
-        return Err(map_error(AppError::AuthenticationError(
-            "Token has been revoked".to_string(),
-        )));
-    }
+    // トークンからユーザーIDを取得
+    let key = &state.jwt_keys;
+    let user_id = extract_user_id_from_token(refresh_token, key).map_err(map_error)?;
 
     // ユーザーIDベースのレート制限
     state
@@ -421,41 +1098,57 @@ async fn handle_refresh(
                 .into_response()
         })?;
 
-    // 新しいアクセストークンを発行
-    let access_token = state
+    // リフレッシュトークンをローテーションし、新しいトークンを発行
+    let (access_token, refresh_token) = state
         .auth_service
-        .refresh_access_token(&user_id)
+        .refresh_tokens(refresh_token, session_client(&headers, &addr))
         .await
         .map_err(map_error)?;
 
-    // 新しいアクセストークンをCookieに設定
+    if body_mode {
+        return Ok(Json(json!({
+            "message": "Token refresh successful",
+            "access_token": access_token,
+            "refresh_token": refresh_token,
+            "token_type": "Bearer",
+            "expires_in": ACCESS_TOKEN_MAX_AGE,
+        }))
+        .into_response());
+    }
+
+    // 新しいトークンをCookieに設定
     let cookie_config = state.config.server.get_cookie_config();
 
+    let refresh_cookie = Cookie::build(("refresh_token", refresh_token))
+        .path("/")
+        .max_age(time::Duration::seconds(7 * 24 * 60 * 60))
+        .same_site(cookie_config.same_site)
+        .secure(cookie_config.secure)
+        .http_only(cookie_config.http_only)
+        .build();
+
     let access_cookie = Cookie::build(("access_token", access_token))
         .path("/")
-        .max_age(time::Duration::seconds(60 * 60))
+        .max_age(time::Duration::seconds(ACCESS_TOKEN_MAX_AGE))
         .same_site(cookie_config.same_site)
         .secure(cookie_config.secure)
         .http_only(cookie_config.http_only)
         .build();
 
     Ok((
-        jar.add(access_cookie),
+        jar.add(refresh_cookie).add(access_cookie),
         Json(json!({"message": "Token refresh successful"})),
-    ))
+    )
+        .into_response())
 }
 
 /// パスワードリセット
 async fn handle_reset_password(
     State(state): State<AppState>,
-    jar: CookieJar,
+    auth: Authorized<require::EditAccount>,
     Json(req): Json<ResetPasswordRequest>,
 ) -> Result<impl IntoResponse, Response> {
-    // アクセストークンからユーザーIDを取得・検証
-    let user_id = state
-        .auth_service
-        .extract_and_verify_user_from_access_token(&jar)
-        .await?;
+    let user_id = auth.user_id;
 
     state
         .auth_service
@@ -469,17 +1162,18 @@ async fn handle_reset_password(
 /// ユーザー情報更新
 async fn handle_update_user(
     State(state): State<AppState>,
-    jar: CookieJar,
+    auth: Authorized<require::EditAccount>,
     Json(req): Json<UpdateUserRequest>,
 ) -> Result<impl IntoResponse, Response> {
-    // アクセストークンからユーザーIDを取得・検証
-    let user_id = state
-        .auth_service
-        .extract_and_verify_user_from_access_token(&jar)
-        .await?;
+    let user_id = auth.user_id;
+
+    if req.email.is_some() {
+        return Err(map_error(AppError::ValidationError(
+            "Email address cannot be changed here. Use /auth/email/change instead".to_string(),
+        )));
+    }
 
     let update_req = crate::repositories::auth::UserUpdateRequest {
-        email: req.email,
         display_name: req.display_name,
         password: req.password,
     };
@@ -500,38 +1194,96 @@ async fn handle_update_user(
     })))
 }
 
-/// ユーザー削除（論理削除）
-async fn handle_delete_user(
+/// メールアドレスの変更を開始（変更後のアドレスに確認コードを送信）
+async fn handle_request_email_change(
     State(state): State<AppState>,
-    jar: CookieJar,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    auth: Authorized<require::EditAccount>,
+    Json(req): Json<RequestEmailChangeRequest>,
 ) -> Result<impl IntoResponse, Response> {
-    // アクセストークンからユーザーIDを取得・検証
-    let user_id = state
+    let ip = get_client_ip(&headers, &addr);
+    state
         .auth_service
-        .extract_and_verify_user_from_access_token(&jar)
-        .await?;
+        .request_email_change(&auth.user_id, &req.new_email, Some(&ip))
+        .await
+        .map_err(map_error)?;
 
+    Ok(Json(json!({"message": "Verification code sent to the new email address"})))
+}
+
+/// 確認コードを検証してメールアドレスを変更（すべてのセッションが失効する）
+async fn handle_confirm_email_change(
+    State(state): State<AppState>,
+    auth: Authorized<require::EditAccount>,
+    jar: CookieJar,
+    Json(req): Json<ConfirmEmailChangeRequest>,
+) -> Result<impl IntoResponse, Response> {
+    // コードの総当たりを防ぐ
     state
+        .auth_rate_limiter
+        .check_user_limit(&auth.user_id)
+        .map_err(|e| {
+            (
+                StatusCode::TOO_MANY_REQUESTS,
+                Json(json!({"error": e})),
+            )
+                .into_response()
+        })?;
+
+    let user = state
+        .auth_service
+        .confirm_email_change(&auth.user_id, &req.code)
+        .await
+        .map_err(map_error)?;
+
+    // セッションは失効しているため、Cookieを削除して再ログインを求める
+    let jar = jar
+        .remove(Cookie::from("refresh_token"))
+        .remove(Cookie::from("access_token"));
+
+    Ok((
+        jar,
+        Json(json!({
+            "message": "Email address changed successfully. Please log in again",
+            "user": {
+                "user_id": user.user_id,
+                "email": user.email,
+                "display_name": user.display_name,
+            }
+        })),
+    ))
+}
+
+/// ユーザー削除（猶予期間の後に完全に削除される）
+async fn handle_delete_user(
+    State(state): State<AppState>,
+    auth: Authorized<require::DeleteAccount>,
+    jar: CookieJar,
+) -> Result<impl IntoResponse, Response> {
+    let user_id = auth.user_id;
+
+    let deletion_scheduled_at = state
         .auth_service
         .delete_user(&user_id)
         .await
         .map_err(map_error)?;
 
     // ユーザー削除後、すべてのトークンを無効化
-    let key = &state.jwt_decoding_key;
-    let mut jtis = Vec::new();
+    let key = &state.jwt_keys;
+    let mut tokens = Vec::new();
 
     if let Some(access_token) = jar.get("access_token") {
-        if let Ok(jti) = extract_jti_from_token(access_token.value(), &key) {
-            jtis.push(jti);
+        if let Ok(token) = extract_jti_from_token(access_token.value(), &key) {
+            tokens.push(token);
         }
     }
     if let Some(refresh_token) = jar.get("refresh_token") {
-        if let Ok(jti) = extract_jti_from_token(refresh_token.value(), &key) {
-            jtis.push(jti);
+        if let Ok(token) = extract_jti_from_token(refresh_token.value(), &key) {
+            tokens.push(token);
         }
     }
-    state.auth_service.logout(jtis).await.map_err(map_error)?;
+    state.auth_service.logout(tokens).await.map_err(map_error)?;
 
     // Cookieを削除
     let jar = jar
@@ -540,10 +1292,80 @@ async fn handle_delete_user(
 
     Ok((
         jar,
-        Json(json!({"message": "User deleted successfully"})),
+        Json(json!({
+            "message": "User deleted successfully",
+            "deletion_scheduled_at": deletion_scheduled_at,
+        })),
     ))
 }
 
+/// アカウントの削除予定日時を取得（削除を申請していない場合は null）
+async fn handle_get_account_deletion(
+    State(state): State<AppState>,
+    auth: Authorized<require::EditAccount>,
+) -> Result<impl IntoResponse, Response> {
+    let deletion_scheduled_at = state
+        .auth_service
+        .get_account_deletion(&auth.user_id)
+        .await
+        .map_err(map_error)?;
+    Ok(Json(json!({"deletion_scheduled_at": deletion_scheduled_at})))
+}
+
+/// アカウントの削除を取り消す（猶予期間中のみ）
+async fn handle_cancel_account_deletion(
+    State(state): State<AppState>,
+    auth: Authorized<require::EditAccount>,
+) -> Result<impl IntoResponse, Response> {
+    state
+        .auth_service
+        .cancel_account_deletion(&auth.user_id)
+        .await
+        .map_err(map_error)?;
+    Ok(Json(json!({"message": "Account deletion cancelled"})))
+}
+
+/// 再認証（アカウント削除など権限の強い操作の前に行う）
+async fn handle_reauthenticate(
+    State(state): State<AppState>,
+    auth: Authorized<require::Authenticated>,
+    jar: CookieJar,
+    Json(req): Json<ReauthenticateRequest>,
+) -> Result<Response, Response> {
+    let access_token = state
+        .auth_service
+        .reauthenticate(&auth.user_id, auth.session_id.as_deref(), &req.password)
+        .await
+        .map_err(map_error)?;
+
+    if req.token_delivery == TokenDelivery::Body {
+        return Ok(Json(json!({
+            "message": "Reauthentication successful",
+            "access_token": access_token,
+            "token_type": "Bearer",
+            "expires_in": 5 * 60,
+        }))
+        .into_response());
+    }
+
+    // 再認証済みのアクセストークンは5分間のみ有効
+    let cookie_config = state.config.server.get_cookie_config();
+
+    let access_cookie = Cookie::build(("access_token", access_token))
+        .path("/")
+        .max_age(time::Duration::seconds(5 * 60))
+        .same_site(cookie_config.same_site)
+        .secure(cookie_config.secure)
+        .http_only(cookie_config.http_only)
+        .build();
+
+    Ok((
+        jar.add(access_cookie),
+        Json(json!({"message": "Reauthentication successful"})),
+    )
+        .into_response())
+}
+
 /// ステップ1: パスワード忘れ（確認コード送信）
 async fn handle_forgot_password(
     State(state): State<AppState>,
@@ -619,3 +1441,54 @@ async fn handle_complete_password_reset(
 
     Ok((jar, Json(json!({"message": "Password reset successful"}))))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn headers(content_type: Option<&str>) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        if let Some(content_type) = content_type {
+            headers.insert(header::CONTENT_TYPE, content_type.parse().unwrap());
+        }
+        headers
+    }
+
+    #[test]
+    fn empty_body_uses_cookie() {
+        for body in ["", "  \n", "{}", r#"{"refresh_token": ""}"#] {
+            let token =
+                refresh_token_from_body(&headers(Some("application/json")), &Bytes::from(body));
+            assert_eq!(token.unwrap(), None, "body: {:?}", body);
+        }
+        let token = refresh_token_from_body(&headers(None), &Bytes::new());
+        assert_eq!(token.unwrap(), None);
+    }
+
+    #[test]
+    fn non_json_body_is_ignored() {
+        let token = refresh_token_from_body(
+            &headers(Some("application/x-www-form-urlencoded")),
+            &Bytes::from("refresh_token=abc"),
+        );
+        assert_eq!(token.unwrap(), None);
+    }
+
+    #[test]
+    fn json_body_token_is_used() {
+        let token = refresh_token_from_body(
+            &headers(Some("application/json; charset=utf-8")),
+            &Bytes::from(r#"{"refresh_token": "abc"}"#),
+        );
+        assert_eq!(token.unwrap(), Some("abc".to_string()));
+    }
+
+    #[test]
+    fn malformed_json_body_is_rejected() {
+        let token = refresh_token_from_body(
+            &headers(Some("application/json")),
+            &Bytes::from("{"),
+        );
+        assert!(matches!(token, Err(AppError::ValidationError(_))));
+    }
+}
@@ -0,0 +1,82 @@
+use axum::{
+    Router,
+    extract::{Path, State},
+    http::{StatusCode, header},
+    response::{IntoResponse, Json, Response},
+    routing::{get, post},
+};
+use serde_json::json;
+
+use crate::{
+    error::map_error,
+    repositories::DataExport,
+    routes::authorization::{Authorized, require},
+    server::AppState,
+};
+
+pub fn create_export_routes() -> Router<AppState> {
+    Router::new()
+        .route("/auth/export", post(start_export))
+        .route("/auth/export/{capture}", get(get_export))
+        .route("/auth/export/{capture}/download", get(download_export))
+}
+
+/// エクスポートを開始（作成はバックグラウンドで行い、完了したらメールで通知する）
+async fn start_export(
+    State(state): State<AppState>,
+    auth: Authorized<require::EditAccount>,
+) -> std::result::Result<(StatusCode, Json<DataExport>), Response> {
+    state
+        .auth_rate_limiter
+        .check_user_limit(&auth.user_id)
+        .map_err(|e| (StatusCode::TOO_MANY_REQUESTS, Json(json!({"error": e}))).into_response())?;
+
+    let export = state
+        .export_service
+        .clone()
+        .start_export(&auth.user_id)
+        .await
+        .map_err(map_error)?;
+    Ok((StatusCode::ACCEPTED, Json(export)))
+}
+
+async fn get_export(
+    State(state): State<AppState>,
+    auth: Authorized<require::EditAccount>,
+    Path(export_id): Path<String>,
+) -> std::result::Result<Json<DataExport>, Response> {
+    let export = state
+        .export_service
+        .get_export(&auth.user_id, &export_id)
+        .await
+        .map_err(map_error)?;
+    Ok(Json(export))
+}
+
+async fn download_export(
+    State(state): State<AppState>,
+    auth: Authorized<require::EditAccount>,
+    Path(export_id): Path<String>,
+) -> std::result::Result<Response, Response> {
+    let archive = state
+        .export_service
+        .download_archive(&auth.user_id, &export_id)
+        .await
+        .map_err(map_error)?;
+
+    let filename = format!(
+        "mimo-export-{}.zip",
+        chrono::Utc::now().format("%Y%m%d")
+    );
+    Ok((
+        [
+            (header::CONTENT_TYPE, "application/zip".to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"{}\"", filename),
+            ),
+        ],
+        archive,
+    )
+        .into_response())
+}
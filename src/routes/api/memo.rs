@@ -1,49 +1,101 @@
 use axum::{
     Router,
-    extract::{Path, State},
+    extract::{Path, Query, State},
     response::{Json, Response},
     routing::{delete, get, patch, post},
 };
-use axum_extra::extract::CookieJar;
 use serde_json::json;
 
 use crate::{
     error::{AppError, map_error},
-    repositories::{Memo, MemoCreateRequest, MemoList, MemoUpdateRequest},
+    repositories::{
+        Memo, MemoCreateRequest, MemoDiff, MemoDiffQuery, MemoList, MemoListQuery,
+        MemoRevisionList, MemoUpdateRequest, SearchRequest, SearchResults, CreateShareRequest,
+        ShareInfo,
+    },
+    routes::authorization::{Authorized, require},
     server::AppState,
 };
 
 pub fn create_memo_routes() -> Router<AppState> {
     Router::new()
         .route("/memos/list/{capture}", get(list_memos))
+        .route("/memos/search", get(search_memos))
+        .route("/memos/search/reindex", post(reindex_memos))
         .route("/memos", post(create_memo))
         .route("/memos/{capture}", patch(update_memo))
         .route("/memos/{capture}", get(get_memo))
         .route("/memos/{capture}", delete(delete_memo))
+        .route("/memos/{capture}/revisions", get(list_revisions))
+        .route("/memos/{capture}/revisions/diff", get(diff_revisions))
+        .route(
+            "/memos/{capture}/revisions/{revision}/restore",
+            post(restore_revision),
+        )
+        .route("/memos/{capture}/share", post(create_share))
+        .route("/memos/{capture}/share", get(get_share))
+        .route("/memos/{capture}/share", delete(revoke_share))
 }
 
 async fn list_memos(
     State(state): State<AppState>,
-    jar: CookieJar,
+    auth: Authorized<require::ViewMemo>,
     Path(user_id): Path<String>,
+    Query(query): Query<MemoListQuery>,
 ) -> std::result::Result<Json<MemoList>, Response> {
-    let authenticated_user_id = state.auth_service.extract_and_verify_user_from_access_token(&jar).await?;
+    let authenticated_user_id = auth.user_id;
 
     // パスからのuser_idと認証されたユーザーIDが一致するか確認
     if authenticated_user_id != user_id {
         return Err(map_error(AppError::Forbidden("Access denied".to_string())));
     }
 
-    let memos = state.memo_service.find_by_user(&user_id).await.map_err(map_error)?;
-    Ok(Json(MemoList { memos }))
+    let memos = state
+        .memo_service
+        .find_page_by_user(&user_id, query)
+        .await
+        .map_err(map_error)?;
+    Ok(Json(memos))
+}
+
+async fn search_memos(
+    State(state): State<AppState>,
+    auth: Authorized<require::ViewMemo>,
+    Query(req): Query<SearchRequest>,
+) -> std::result::Result<Json<SearchResults>, Response> {
+    let authenticated_user_id = auth.user_id;
+
+    let results = state
+        .search_service
+        .search(&authenticated_user_id, req)
+        .await
+        .map_err(map_error)?;
+    Ok(Json(results))
+}
+
+async fn reindex_memos(
+    State(state): State<AppState>,
+    auth: Authorized<require::EditMemo>,
+) -> std::result::Result<Json<serde_json::Value>, Response> {
+    let authenticated_user_id = auth.user_id;
+
+    let indexed = state
+        .search_service
+        .reindex_user(&authenticated_user_id)
+        .await
+        .map_err(map_error)?;
+    Ok(Json(json!({
+        "status": "success",
+        "indexed": indexed
+    })))
 }
 
 async fn create_memo(
     State(state): State<AppState>,
-    jar: CookieJar,
+    auth: Authorized<require::EditMemo>,
     Json(req): Json<MemoCreateRequest>,
 ) -> std::result::Result<Json<Memo>, Response> {
-    let authenticated_user_id = state.auth_service.extract_and_verify_user_from_access_token(&jar).await?;
+    let authenticated_user_id = auth.user_id;
 
     // リクエストのuser_idと認証されたユーザーIDが一致するか確認
     if authenticated_user_id != req.user_id {
@@ -56,10 +108,10 @@ async fn create_memo(
 
 async fn get_memo(
     State(state): State<AppState>,
-    jar: CookieJar,
+    auth: Authorized<require::ViewMemo>,
     Path(id): Path<String>,
 ) -> std::result::Result<Json<Memo>, Response> {
-    let authenticated_user_id = state.auth_service.extract_and_verify_user_from_access_token(&jar).await?;
+    let authenticated_user_id = auth.user_id;
 
     let memo = state.memo_service.find_by_id(&id).await.map_err(map_error)?;
 
@@ -73,11 +125,11 @@ async fn get_memo(
 
 async fn update_memo(
     State(state): State<AppState>,
-    jar: CookieJar,
+    auth: Authorized<require::EditMemo>,
     Path(id): Path<String>,
     Json(req): Json<MemoUpdateRequest>,
 ) -> std::result::Result<Json<Memo>, Response> {
-    let authenticated_user_id = state.auth_service.extract_and_verify_user_from_access_token(&jar).await?;
+    let authenticated_user_id = auth.user_id;
 
     // 更新前にメモの所有者を確認
     let existing_memo = state.memo_service.find_by_id(&id).await.map_err(map_error)?;
@@ -91,10 +143,10 @@ async fn update_memo(
 
 async fn delete_memo(
     State(state): State<AppState>,
-    jar: CookieJar,
+    auth: Authorized<require::EditMemo>,
     Path(id): Path<String>,
 ) -> std::result::Result<Json<serde_json::Value>, Response> {
-    let authenticated_user_id = state.auth_service.extract_and_verify_user_from_access_token(&jar).await?;
+    let authenticated_user_id = auth.user_id;
 
     // 削除前にメモの所有者を確認
     let existing_memo = state.memo_service.find_by_id(&id).await.map_err(map_error)?;
@@ -108,3 +160,130 @@ async fn delete_memo(
         "message": format!("Memo deletion completed: {id}")
     })))
 }
+
+async fn list_revisions(
+    State(state): State<AppState>,
+    auth: Authorized<require::ViewMemo>,
+    Path(id): Path<String>,
+) -> std::result::Result<Json<MemoRevisionList>, Response> {
+    let authenticated_user_id = auth.user_id;
+
+    // メモの所有者を確認
+    let existing_memo = state.memo_service.find_by_id(&id).await.map_err(map_error)?;
+    if existing_memo.user_id != authenticated_user_id {
+        return Err(map_error(AppError::Forbidden("Access denied".to_string())));
+    }
+
+    let revisions = state.memo_service.list_revisions(&id).await.map_err(map_error)?;
+    Ok(Json(revisions))
+}
+
+async fn diff_revisions(
+    State(state): State<AppState>,
+    auth: Authorized<require::ViewMemo>,
+    Path(id): Path<String>,
+    Query(query): Query<MemoDiffQuery>,
+) -> std::result::Result<Json<MemoDiff>, Response> {
+    let authenticated_user_id = auth.user_id;
+
+    // メモの所有者を確認
+    let existing_memo = state.memo_service.find_by_id(&id).await.map_err(map_error)?;
+    if existing_memo.user_id != authenticated_user_id {
+        return Err(map_error(AppError::Forbidden("Access denied".to_string())));
+    }
+
+    let diff = state
+        .memo_service
+        .diff_revisions(&id, query.from, query.to)
+        .await
+        .map_err(map_error)?;
+    Ok(Json(diff))
+}
+
+async fn restore_revision(
+    State(state): State<AppState>,
+    auth: Authorized<require::EditMemo>,
+    Path((id, revision)): Path<(String, u32)>,
+) -> std::result::Result<Json<Memo>, Response> {
+    let authenticated_user_id = auth.user_id;
+
+    // 復元前にメモの所有者を確認
+    let existing_memo = state.memo_service.find_by_id(&id).await.map_err(map_error)?;
+    if existing_memo.user_id != authenticated_user_id {
+        return Err(map_error(AppError::Forbidden("Access denied".to_string())));
+    }
+
+    let memo = state
+        .memo_service
+        .restore_revision(&id, revision)
+        .await
+        .map_err(map_error)?;
+    Ok(Json(memo))
+}
+
+async fn create_share(
+    State(state): State<AppState>,
+    auth: Authorized<require::EditMemo>,
+    Path(id): Path<String>,
+    Json(req): Json<CreateShareRequest>,
+) -> std::result::Result<Json<ShareInfo>, Response> {
+    let authenticated_user_id = auth.user_id;
+
+    // 共有前にメモの所有者を確認
+    let existing_memo = state.memo_service.find_by_id(&id).await.map_err(map_error)?;
+    if existing_memo.user_id != authenticated_user_id {
+        return Err(map_error(AppError::Forbidden("Access denied".to_string())));
+    }
+
+    let share = state
+        .share_service
+        .create_share(&existing_memo, req)
+        .await
+        .map_err(map_error)?;
+    Ok(Json(share))
+}
+
+async fn get_share(
+    State(state): State<AppState>,
+    auth: Authorized<require::ViewMemo>,
+    Path(id): Path<String>,
+) -> std::result::Result<Json<ShareInfo>, Response> {
+    let authenticated_user_id = auth.user_id;
+
+    // メモの所有者を確認
+    let existing_memo = state.memo_service.find_by_id(&id).await.map_err(map_error)?;
+    if existing_memo.user_id != authenticated_user_id {
+        return Err(map_error(AppError::Forbidden("Access denied".to_string())));
+    }
+
+    let share = state
+        .share_service
+        .get_share(&existing_memo)
+        .await
+        .map_err(map_error)?;
+    Ok(Json(share))
+}
+
+async fn revoke_share(
+    State(state): State<AppState>,
+    auth: Authorized<require::EditMemo>,
+    Path(id): Path<String>,
+) -> std::result::Result<Json<serde_json::Value>, Response> {
+    let authenticated_user_id = auth.user_id;
+
+    // 無効化前にメモの所有者を確認
+    let existing_memo = state.memo_service.find_by_id(&id).await.map_err(map_error)?;
+    if existing_memo.user_id != authenticated_user_id {
+        return Err(map_error(AppError::Forbidden("Access denied".to_string())));
+    }
+
+    state
+        .share_service
+        .revoke_share(&existing_memo)
+        .await
+        .map_err(map_error)?;
+    Ok(Json(json!({
+        "status": "success",
+        "message": format!("Share link revoked: {id}")
+    })))
+}
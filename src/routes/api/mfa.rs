@@ -0,0 +1,120 @@
+use axum::{
+    Router,
+    extract::State,
+    http::StatusCode,
+    response::{IntoResponse, Json, Response},
+    routing::{get, post},
+};
+use serde::Deserialize;
+use serde_json::json;
+
+use crate::{
+    error::map_error,
+    repositories::{MfaStatus, RecoveryCodes, TotpEnrollment},
+    routes::authorization::{Authorized, require},
+    server::AppState,
+};
+
+pub fn create_mfa_routes() -> Router<AppState> {
+    Router::new()
+        .route("/auth/mfa", get(get_status))
+        .route("/auth/mfa/totp", post(start_totp_enrollment).delete(disable_totp))
+        .route("/auth/mfa/totp/confirm", post(confirm_totp_enrollment))
+        .route("/auth/mfa/recovery-codes", post(regenerate_recovery_codes))
+}
+
+/// TOTPコード（無効化・再発行ではリカバリーコードも可）
+#[derive(Deserialize)]
+struct MfaCodeRequest {
+    code: String,
+}
+
+async fn get_status(
+    State(state): State<AppState>,
+    auth: Authorized<require::EditAccount>,
+) -> std::result::Result<Json<MfaStatus>, Response> {
+    let status = state
+        .mfa_service
+        .status(&auth.user_id)
+        .await
+        .map_err(map_error)?;
+    Ok(Json(status))
+}
+
+async fn start_totp_enrollment(
+    State(state): State<AppState>,
+    auth: Authorized<require::EditAccount>,
+) -> std::result::Result<Json<TotpEnrollment>, Response> {
+    let user = state
+        .auth_service
+        .get_current_user(&auth.user_id)
+        .await
+        .map_err(map_error)?;
+
+    let enrollment = state
+        .mfa_service
+        .start_totp_enrollment(&auth.user_id, &user.email)
+        .await
+        .map_err(map_error)?;
+    Ok(Json(enrollment))
+}
+
+async fn confirm_totp_enrollment(
+    State(state): State<AppState>,
+    auth: Authorized<require::EditAccount>,
+    Json(req): Json<MfaCodeRequest>,
+) -> std::result::Result<Json<RecoveryCodes>, Response> {
+    // コードの総当たりを防ぐためのユーザー単位のレート制限
+    state
+        .auth_rate_limiter
+        .check_user_limit(&auth.user_id)
+        .map_err(|e| (StatusCode::TOO_MANY_REQUESTS, Json(json!({"error": e}))).into_response())?;
+
+    let recovery_codes = state
+        .mfa_service
+        .confirm_totp_enrollment(&auth.user_id, &req.code)
+        .await
+        .map_err(map_error)?;
+    Ok(Json(RecoveryCodes { recovery_codes }))
+}
+
+async fn regenerate_recovery_codes(
+    State(state): State<AppState>,
+    auth: Authorized<require::EditAccount>,
+    Json(req): Json<MfaCodeRequest>,
+) -> std::result::Result<Json<RecoveryCodes>, Response> {
+    // コードの総当たりを防ぐためのユーザー単位のレート制限
+    state
+        .auth_rate_limiter
+        .check_user_limit(&auth.user_id)
+        .map_err(|e| (StatusCode::TOO_MANY_REQUESTS, Json(json!({"error": e}))).into_response())?;
+
+    let recovery_codes = state
+        .mfa_service
+        .regenerate_recovery_codes(&auth.user_id, &req.code)
+        .await
+        .map_err(map_error)?;
+    Ok(Json(RecoveryCodes { recovery_codes }))
+}
+
+async fn disable_totp(
+    State(state): State<AppState>,
+    auth: Authorized<require::EditAccount>,
+    Json(req): Json<MfaCodeRequest>,
+) -> std::result::Result<Json<serde_json::Value>, Response> {
+    // コードの総当たりを防ぐためのユーザー単位のレート制限
+    state
+        .auth_rate_limiter
+        .check_user_limit(&auth.user_id)
+        .map_err(|e| (StatusCode::TOO_MANY_REQUESTS, Json(json!({"error": e}))).into_response())?;
+
+    state
+        .mfa_service
+        .disable(&auth.user_id, &req.code)
+        .await
+        .map_err(map_error)?;
+    Ok(Json(json!({
+        "status": "success",
+        "message": "Two-factor authentication disabled"
+    })))
+}
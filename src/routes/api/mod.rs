@@ -1,24 +1,43 @@
 use axum::Router;
 
 mod auth;
+mod export;
 mod memo;
+mod mfa;
+mod sessions;
 // mod settings;
 mod sum;
 mod tags;
+mod tokens;
+mod trash;
+mod webauthn;
 
 use auth::create_auth_routes;
+pub(crate) use auth::get_client_ip;
+use export::create_export_routes;
 use memo::create_memo_routes;
+use mfa::create_mfa_routes;
+use sessions::create_sessions_routes;
 // use settings::create_settings_routes;
 use sum::create_sum_routes;
 use tags::create_tags_routes;
+use tokens::create_tokens_routes;
+use trash::create_trash_routes;
+use webauthn::create_webauthn_routes;
 
 use crate::server::AppState;
 
 pub fn create_api_routes() -> Router<AppState> {
     Router::new()
         .merge(create_auth_routes())
+        .merge(create_export_routes())
         .merge(create_sum_routes())
         .merge(create_memo_routes())
+        .merge(create_mfa_routes())
+        .merge(create_sessions_routes())
         .merge(create_tags_routes())
+        .merge(create_tokens_routes())
+        .merge(create_trash_routes())
+        .merge(create_webauthn_routes())
     // .merge(create_settings_routes())
 }
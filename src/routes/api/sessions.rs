@@ -0,0 +1,64 @@
+use axum::{
+    Router,
+    extract::{Path, State},
+    response::{Json, Response},
+    routing::{delete, get, post},
+};
+use serde_json::json;
+
+use crate::{
+    error::map_error,
+    repositories::auth::SessionList,
+    routes::authorization::{Authorized, require},
+    server::AppState,
+};
+
+pub fn create_sessions_routes() -> Router<AppState> {
+    Router::new()
+        .route("/auth/sessions", get(list_sessions))
+        .route("/auth/sessions/revoke-others", post(revoke_other_sessions))
+        .route("/auth/sessions/{capture}", delete(revoke_session))
+}
+
+async fn list_sessions(
+    State(state): State<AppState>,
+    auth: Authorized<require::EditAccount>,
+) -> std::result::Result<Json<SessionList>, Response> {
+    let sessions = state
+        .auth_service
+        .list_sessions(&auth.user_id, auth.session_id.as_deref())
+        .await
+        .map_err(map_error)?;
+    Ok(Json(SessionList { sessions }))
+}
+
+async fn revoke_session(
+    State(state): State<AppState>,
+    auth: Authorized<require::EditAccount>,
+    Path(session_id): Path<String>,
+) -> std::result::Result<Json<serde_json::Value>, Response> {
+    state
+        .auth_service
+        .revoke_session(&auth.user_id, &session_id)
+        .await
+        .map_err(map_error)?;
+    Ok(Json(json!({
+        "status": "success",
+        "message": format!("Session revoked: {session_id}")
+    })))
+}
+
+async fn revoke_other_sessions(
+    State(state): State<AppState>,
+    auth: Authorized<require::EditAccount>,
+) -> std::result::Result<Json<serde_json::Value>, Response> {
+    let revoked = state
+        .auth_service
+        .revoke_other_sessions(&auth.user_id, auth.session_id.as_deref())
+        .await
+        .map_err(map_error)?;
+    Ok(Json(json!({
+        "status": "success",
+        "revoked_sessions": revoked
+    })))
+}
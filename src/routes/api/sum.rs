@@ -1,20 +1,35 @@
 use crate::{
-    error::{AppError, Result, map_error},
-    repositories::{AISummary, SummarizeRequest, SummaryList},
+    error::{AppError, map_error},
+    repositories::{
+        JournalingSchedule, SummarizeRequest, SummaryJob, SummaryList, SummaryView,
+        UpdateJournalingScheduleRequest,
+        summary_job::{SummaryJobStage, SummaryJobStatus},
+    },
+    routes::authorization::{Authorized, require},
     server::AppState,
 };
 use axum::{
     Router,
     extract::{Path, State},
-    response::{Json, Response},
+    http::StatusCode,
+    response::{
+        Json, Response,
+        sse::{Event, KeepAlive, Sse},
+    },
     routing::{delete, get, patch, post},
 };
-use axum_extra::extract::CookieJar;
+use futures::{Stream, StreamExt};
 use serde_json::json;
 
+/// 進捗のイベントで、ジョブの状態を確認する間隔（秒）
+const JOB_EVENTS_POLL_SECONDS: u64 = 1;
+
 pub fn create_sum_routes() -> Router<AppState> {
     Router::new()
         .route("/sum/summarize", post(summarize_memo))
+        .route("/sum/summarize/stream", post(summarize_memo_stream))
+        .route("/sum/jobs/{capture}", get(get_summary_job))
+        .route("/sum/jobs/{capture}/events", get(summary_job_events))
         .route("/sum/{capture}", get(get_summary))
         .route("/sum/list/{capture}", get(get_summaries))
         .route("/sum/{capture}", delete(delete_summary))
@@ -22,33 +37,173 @@ pub fn create_sum_routes() -> Router<AppState> {
         .route("/sum/journaling-freq", patch(update_frequency))
 }
 
+/// 要約ジョブを登録（要約はバックグラウンドで作成し、ジョブの状態で結果を確認する）
 async fn summarize_memo(
     State(state): State<AppState>,
-    jar: CookieJar,
+    auth: Authorized<require::SummarizeMemo>,
     Json(req): Json<SummarizeRequest>,
-) -> std::result::Result<Json<AISummary>, Response> {
-    let authenticated_user_id = state.auth_service.extract_and_verify_user_from_access_token(&jar).await?;
+) -> std::result::Result<(StatusCode, Json<SummaryJob>), Response> {
+    let job = state
+        .summary_job_service
+        .enqueue(&auth.user_id, req.memo_ids)
+        .await
+        .map_err(map_error)?;
 
-    let is_auto_generated = false;
-    let summary = state
+    Ok((StatusCode::ACCEPTED, Json(job)))
+}
+
+/// 要約を作成し、生成されたテキストを Server-Sent Events で順に送る
+///
+/// 生成中は `delta` イベント（`{"text": ...}`）を送り、完了したら要約を保存して `summary` イベントで送る。
+/// 失敗した場合は `error` イベントを送る。クライアントが切断するとLLMへのリクエストも中断し、要約は保存しない。
+async fn summarize_memo_stream(
+    State(state): State<AppState>,
+    auth: Authorized<require::SummarizeMemo>,
+    Json(req): Json<SummarizeRequest>,
+) -> std::result::Result<Sse<impl Stream<Item = std::result::Result<Event, axum::Error>>>, Response> {
+    // メモがない場合やLLMへのリクエストが失敗した場合は、ストリームを始める前にエラーを返す
+    let memos = state
+        .summary_service
+        .load_memos(&auth.user_id, &req.memo_ids)
+        .await
+        .map_err(map_error)?;
+    let upstream = state
         .summary_service
-        .summarize_and_save(authenticated_user_id, req.memo_ids, is_auto_generated)
+        .generate_summary_stream(&memos)
+        .await
+        .map_err(map_error)?;
 
-        .await.map_err(map_error)?;
+    let service = state.summary_service.clone();
+    let user_id = auth.user_id;
+    let memo_ids = req.memo_ids;
+    // ストリームが破棄される（クライアントが切断する）と upstream も破棄され、LLMへのリクエストが中断される
+    let stream = futures::stream::unfold(
+        Some((upstream, String::new())),
+        move |state| {
+            let service = service.clone();
+            let user_id = user_id.clone();
+            let memo_ids = memo_ids.clone();
+            async move {
+                let (mut upstream, mut content) = state?;
+                loop {
+                    match upstream.next().await {
+                        Some(Ok(text)) if text.is_empty() => continue,
+                        Some(Ok(text)) => {
+                            content.push_str(&text);
+                            let event = Event::default().event("delta").json_data(json!({ "text": text }));
+                            return Some((event, Some((upstream, content))));
+                        }
+                        Some(Err(e)) => {
+                            let event = Event::default().event("error").json_data(json!({ "error": e.to_string() }));
+                            return Some((event, None));
+                        }
+                        None if content.trim().is_empty() => {
+                            // 空の要約は保存しない
+                            let event = Event::default().event("error").json_data(json!({ "error": "LLM returned an empty summary" }));
+                            return Some((event, None));
+                        }
+                        None => {
+                            let event = match service.save_summary(user_id, memo_ids, content, false).await {
+                                Ok(summary) => Event::default().event("summary").json_data(&summary),
+                                Err(e) => Event::default().event("error").json_data(json!({ "error": e.to_string() })),
+                            };
+                            return Some((event, None));
+                        }
+                    }
+                }
+            }
+        },
+    );
 
-    Ok(Json(summary))
+    Ok(Sse::new(stream).keep_alive(KeepAlive::default()))
+}
+
+async fn get_summary_job(
+    State(state): State<AppState>,
+    auth: Authorized<require::SummarizeMemo>,
+    Path(job_id): Path<String>,
+) -> std::result::Result<Json<SummaryJob>, Response> {
+    let job = state
+        .summary_job_service
+        .get_job(&auth.user_id, &job_id)
+        .await
+        .map_err(map_error)?;
+
+    Ok(Json(job))
+}
+
+/// ジョブの進捗（Server-Sent Events）
+///
+/// 状態・段階が変わるたびに `progress` イベントでジョブを送り、終了したら `done` イベントを送って閉じる。
+async fn summary_job_events(
+    State(state): State<AppState>,
+    auth: Authorized<require::SummarizeMemo>,
+    Path(job_id): Path<String>,
+) -> std::result::Result<Sse<impl Stream<Item = std::result::Result<Event, axum::Error>>>, Response> {
+    // 存在しないジョブは最初に404を返す
+    let job = state
+        .summary_job_service
+        .get_job(&auth.user_id, &job_id)
+        .await
+        .map_err(map_error)?;
+
+    let service = state.summary_job_service.clone();
+    let user_id = auth.user_id;
+    let stream = futures::stream::unfold(
+        (Some(job), None::<(SummaryJobStatus, Option<SummaryJobStage>, i32)>, false),
+        move |(next, last, done)| {
+            let service = service.clone();
+            let user_id = user_id.clone();
+            let job_id = job_id.clone();
+            async move {
+                if done {
+                    return None;
+                }
+                let mut next = next;
+                loop {
+                    let job = match next.take() {
+                        Some(job) => job,
+                        None => {
+                            tokio::time::sleep(std::time::Duration::from_secs(JOB_EVENTS_POLL_SECONDS)).await;
+                            match service.get_job(&user_id, &job_id).await {
+                                Ok(job) => job,
+                                Err(e) => {
+                                    let event = Event::default()
+                                        .event("error")
+                                        .json_data(json!({ "error": e.to_string() }));
+                                    return Some((event, (None, last, true)));
+                                }
+                            }
+                        }
+                    };
+
+                    let finished = job.status.is_finished();
+                    let snapshot = (job.status, job.stage, job.attempts);
+                    if last == Some(snapshot) && !finished {
+                        continue;
+                    }
+                    let event = Event::default()
+                        .event(if finished { "done" } else { "progress" })
+                        .json_data(&job);
+                    return Some((event, (None, Some(snapshot), finished)));
+                }
+            }
+        },
+    );
+
+    Ok(Sse::new(stream).keep_alive(KeepAlive::default()))
 }
 
 async fn get_summary(
     State(state): State<AppState>,
-    jar: CookieJar,
+    auth: Authorized<require::ViewMemo>,
     Path(summary_id): Path<String>,
-) -> std::result::Result<Json<AISummary>, Response> {
-    let authenticated_user_id = state.auth_service.extract_and_verify_user_from_access_token(&jar).await?;
+) -> std::result::Result<Json<SummaryView>, Response> {
+    let authenticated_user_id = auth.user_id;
 
     let summary = state
         .summary_service
-        .get_summary_by_id(&authenticated_user_id, &summary_id)
+        .get_summary(&authenticated_user_id, &summary_id)
         .await.map_err(map_error)?;
 
     Ok(Json(summary))
@@ -56,10 +211,10 @@ async fn get_summary(
 
 async fn get_summaries(
     State(state): State<AppState>,
-    jar: CookieJar,
+    auth: Authorized<require::ViewMemo>,
     Path(user_id): Path<String>,
 ) -> std::result::Result<Json<SummaryList>, Response> {
-    let authenticated_user_id = state.auth_service.extract_and_verify_user_from_access_token(&jar).await?;
+    let authenticated_user_id = auth.user_id;
 
     // パスからのuser_idと認証されたユーザーIDが一致するか確認
     if authenticated_user_id != user_id {
@@ -72,10 +227,10 @@ async fn get_summaries(
 
 async fn delete_summary(
     State(state): State<AppState>,
-    jar: CookieJar,
+    auth: Authorized<require::EditMemo>,
     Path(summary_id): Path<String>,
 ) -> std::result::Result<Json<serde_json::Value>, Response> {
-    let authenticated_user_id = state.auth_service.extract_and_verify_user_from_access_token(&jar).await?;
+    let authenticated_user_id = auth.user_id;
 
     state
         .summary_service
@@ -88,18 +243,29 @@ async fn delete_summary(
     })))
 }
 
-async fn set_frequency(_jar: CookieJar) -> Result<Json<serde_json::Value>> {
-    // TODO: Implementation here
-    Ok(Json(json!({
-        "status": "success",
-        "message": "Frequency retrieved successfully"
-    })))
+/// 自動ジャーナリングの設定
+async fn set_frequency(
+    State(state): State<AppState>,
+    auth: Authorized<require::SummarizeMemo>,
+) -> std::result::Result<Json<JournalingSchedule>, Response> {
+    let schedule = state
+        .journaling_service
+        .get_schedule(&auth.user_id)
+        .await
+        .map_err(map_error)?;
+    Ok(Json(schedule))
 }
 
-async fn update_frequency(_jar: CookieJar) -> Result<Json<serde_json::Value>> {
-    // TODO: Implementation here
-    Ok(Json(json!({
-        "status": "success",
-        "message": "Frequency updated successfully"
-    })))
+/// 自動ジャーナリングの設定を変更（frequency を off にすると停止する）
+async fn update_frequency(
+    State(state): State<AppState>,
+    auth: Authorized<require::SummarizeMemo>,
+    Json(req): Json<UpdateJournalingScheduleRequest>,
+) -> std::result::Result<Json<JournalingSchedule>, Response> {
+    let schedule = state
+        .journaling_service
+        .update_schedule(&auth.user_id, req)
+        .await
+        .map_err(map_error)?;
+    Ok(Json(schedule))
 }
@@ -4,11 +4,10 @@ use axum::{
     response::{Json, Response},
     routing::{delete, get, patch, post},
 };
-
-use axum_extra::extract::CookieJar;
 use serde_json::{Value, json};
 
 use crate::server::AppState;
+use crate::routes::authorization::{Authorized, require};
 use crate::repositories::{Tag, TagList, CreateTagRequest, UpdateTagRequest};
 use crate::error::{AppError, map_error};
 
@@ -22,11 +21,11 @@ pub fn create_tags_routes() -> Router<AppState> {
 
 async fn handle_create_tag(
     State(state): State<AppState>, 
-    jar: CookieJar,
+    auth: Authorized<require::EditTag>,
     Path(user_id): Path<String>,
     Json(req): Json<CreateTagRequest>,
 ) -> std::result::Result<Json<Tag>, Response> {
-    let authenticated_user_id = state.auth_service.extract_and_verify_user_from_access_token(&jar).await?;
+    let authenticated_user_id = auth.user_id;
 
     // パスからのuser_idと認証されたユーザーIDが一致するか確認
     if authenticated_user_id != user_id {
@@ -38,11 +37,11 @@ async fn handle_create_tag(
 }
 
 async fn handle_get_tag_list(
-    jar: CookieJar,
+    auth: Authorized<require::ViewMemo>,
     Path(user_id): Path<String>,
     State(state): State<AppState>,
 ) -> std::result::Result<Json<TagList>, Response> {
-    let authenticated_user_id = state.auth_service.extract_and_verify_user_from_access_token(&jar).await?;
+    let authenticated_user_id = auth.user_id;
 
     // パスからのuser_idと認証されたユーザーIDが一致するか確認
     if authenticated_user_id != user_id {
@@ -54,23 +53,23 @@ async fn handle_get_tag_list(
 }
 
 async fn handle_update_tag(
-    jar: CookieJar,
+    auth: Authorized<require::EditTag>,
     Path(tag_id): Path<String>,
     State(state): State<AppState>,
     Json(req): Json<UpdateTagRequest>,
 ) -> std::result::Result<Json<Tag>, Response> {
-    let authenticated_user_id = state.auth_service.extract_and_verify_user_from_access_token(&jar).await?;
+    let authenticated_user_id = auth.user_id;
 
     let updated_tag = state.tag_service.update_tag(&authenticated_user_id, &tag_id, req).await.map_err(map_error)?;
     Ok(Json(updated_tag))
 }
 
 async fn handle_delete_tag(
-    jar: CookieJar,
+    auth: Authorized<require::EditTag>,
     Path(tag_id): Path<String>,
     State(state): State<AppState>,
 ) -> std::result::Result<Json<Value>, Response> {
-    let authenticated_user_id = state.auth_service.extract_and_verify_user_from_access_token(&jar).await?;
+    let authenticated_user_id = auth.user_id;
 
     state.tag_service.delete_tag(&authenticated_user_id, &tag_id).await.map_err(map_error)?;
     Ok(Json(json!({"status": format!("tag_id: {} deleted", tag_id)})))
@@ -0,0 +1,80 @@
+use axum::{
+    Router,
+    extract::{Path, State},
+    response::{Json, Response},
+    routing::{delete, get, patch, post},
+};
+use serde_json::json;
+
+use crate::{
+    error::map_error,
+    repositories::{
+        CreatePersonalAccessTokenRequest, CreatedPersonalAccessToken, PersonalAccessToken,
+        PersonalAccessTokenList, UpdatePersonalAccessTokenRequest,
+    },
+    routes::authorization::{Authorized, require},
+    server::AppState,
+};
+
+pub fn create_tokens_routes() -> Router<AppState> {
+    Router::new()
+        .route("/auth/tokens", get(list_tokens))
+        .route("/auth/tokens", post(create_token))
+        .route("/auth/tokens/{capture}", patch(update_token))
+        .route("/auth/tokens/{capture}", delete(revoke_token))
+}
+
+async fn list_tokens(
+    State(state): State<AppState>,
+    auth: Authorized<require::EditAccount>,
+) -> std::result::Result<Json<PersonalAccessTokenList>, Response> {
+    let tokens = state
+        .personal_access_token_service
+        .list(&auth.user_id)
+        .await
+        .map_err(map_error)?;
+    Ok(Json(PersonalAccessTokenList { tokens }))
+}
+
+async fn create_token(
+    State(state): State<AppState>,
+    auth: Authorized<require::EditAccount>,
+    Json(req): Json<CreatePersonalAccessTokenRequest>,
+) -> std::result::Result<Json<CreatedPersonalAccessToken>, Response> {
+    let token = state
+        .personal_access_token_service
+        .create(&auth.user_id, req)
+        .await
+        .map_err(map_error)?;
+    Ok(Json(token))
+}
+
+async fn update_token(
+    State(state): State<AppState>,
+    auth: Authorized<require::EditAccount>,
+    Path(token_id): Path<String>,
+    Json(req): Json<UpdatePersonalAccessTokenRequest>,
+) -> std::result::Result<Json<PersonalAccessToken>, Response> {
+    let token = state
+        .personal_access_token_service
+        .rename(&auth.user_id, &token_id, &req.name)
+        .await
+        .map_err(map_error)?;
+    Ok(Json(token))
+}
+
+async fn revoke_token(
+    State(state): State<AppState>,
+    auth: Authorized<require::EditAccount>,
+    Path(token_id): Path<String>,
+) -> std::result::Result<Json<serde_json::Value>, Response> {
+    state
+        .personal_access_token_service
+        .revoke(&auth.user_id, &token_id)
+        .await
+        .map_err(map_error)?;
+    Ok(Json(json!({
+        "status": "success",
+        "message": format!("Personal access token revoked: {token_id}")
+    })))
+}
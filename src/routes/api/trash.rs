@@ -0,0 +1,147 @@
+use axum::{
+    Router,
+    extract::{Path, State},
+    response::{Json, Response},
+    routing::{delete, get, post},
+};
+use serde::Serialize;
+use serde_json::json;
+
+use crate::{
+    error::map_error,
+    repositories::{AISummary, Memo, SummaryView},
+    routes::authorization::{Authorized, require},
+    server::AppState,
+};
+
+pub fn create_trash_routes() -> Router<AppState> {
+    Router::new()
+        .route("/trash", get(list_trash))
+        .route("/trash", delete(empty_trash))
+        .route("/trash/memos/{capture}/restore", post(restore_memo))
+        .route("/trash/memos/{capture}", delete(purge_memo))
+        .route("/trash/summaries/{capture}/restore", post(restore_summary))
+        .route("/trash/summaries/{capture}", delete(purge_summary))
+}
+
+#[derive(Serialize)]
+struct TrashList {
+    memos: Vec<Memo>,
+    summaries: Vec<AISummary>,
+    /// ゴミ箱内の項目が完全に削除されるまでの日数
+    retention_days: i64,
+}
+
+async fn list_trash(
+    State(state): State<AppState>,
+    auth: Authorized<require::ViewMemo>,
+) -> std::result::Result<Json<TrashList>, Response> {
+    let authenticated_user_id = auth.user_id;
+
+    let memos = state
+        .memo_service
+        .list_trash(&authenticated_user_id)
+        .await
+        .map_err(map_error)?;
+    let summaries = state
+        .summary_service
+        .list_trash(&authenticated_user_id)
+        .await
+        .map_err(map_error)?;
+
+    Ok(Json(TrashList {
+        memos,
+        summaries,
+        retention_days: state.config.trash.retention_days,
+    }))
+}
+
+async fn empty_trash(
+    State(state): State<AppState>,
+    auth: Authorized<require::EditMemo>,
+) -> std::result::Result<Json<serde_json::Value>, Response> {
+    let authenticated_user_id = auth.user_id;
+
+    let memos = state
+        .memo_service
+        .empty_trash(&authenticated_user_id)
+        .await
+        .map_err(map_error)?;
+    let summaries = state
+        .summary_service
+        .empty_trash(&authenticated_user_id)
+        .await
+        .map_err(map_error)?;
+
+    Ok(Json(json!({
+        "status": "success",
+        "deleted_memos": memos,
+        "deleted_summaries": summaries
+    })))
+}
+
+async fn restore_memo(
+    State(state): State<AppState>,
+    auth: Authorized<require::EditMemo>,
+    Path(id): Path<String>,
+) -> std::result::Result<Json<Memo>, Response> {
+    let authenticated_user_id = auth.user_id;
+
+    let memo = state
+        .memo_service
+        .restore_from_trash(&authenticated_user_id, &id)
+        .await
+        .map_err(map_error)?;
+    Ok(Json(memo))
+}
+
+async fn purge_memo(
+    State(state): State<AppState>,
+    auth: Authorized<require::EditMemo>,
+    Path(id): Path<String>,
+) -> std::result::Result<Json<serde_json::Value>, Response> {
+    let authenticated_user_id = auth.user_id;
+
+    state
+        .memo_service
+        .purge_from_trash(&authenticated_user_id, &id)
+        .await
+        .map_err(map_error)?;
+    Ok(Json(json!({
+        "status": "success",
+        "message": format!("Memo permanently deleted: {id}")
+    })))
+}
+
+async fn restore_summary(
+    State(state): State<AppState>,
+    auth: Authorized<require::EditMemo>,
+    Path(id): Path<String>,
+) -> std::result::Result<Json<SummaryView>, Response> {
+    let authenticated_user_id = auth.user_id;
+
+    let summary = state
+        .summary_service
+        .restore_from_trash(&authenticated_user_id, &id)
+        .await
+        .map_err(map_error)?;
+    Ok(Json(summary))
+}
+
+async fn purge_summary(
+    State(state): State<AppState>,
+    auth: Authorized<require::EditMemo>,
+    Path(id): Path<String>,
+) -> std::result::Result<Json<serde_json::Value>, Response> {
+    let authenticated_user_id = auth.user_id;
+
+    state
+        .summary_service
+        .purge_from_trash(&authenticated_user_id, &id)
+        .await
+        .map_err(map_error)?;
+    Ok(Json(json!({
+        "status": "success",
+        "message": format!("Summary permanently deleted: {id}")
+    })))
+}
@@ -0,0 +1,114 @@
+use axum::{
+    Router,
+    extract::{Path, State},
+    response::{Json, Response},
+    routing::{delete, get, patch, post},
+};
+use serde::Deserialize;
+use serde_json::json;
+
+use crate::{
+    error::map_error,
+    repositories::{
+        UpdateWebAuthnCredentialRequest, WebAuthnCredential, WebAuthnCredentialList,
+        WebAuthnRegistrationChallenge,
+    },
+    routes::authorization::{Authorized, require},
+    server::AppState,
+    webauthn::RegistrationCredential,
+};
+
+pub fn create_webauthn_routes() -> Router<AppState> {
+    Router::new()
+        .route("/auth/webauthn/register/start", post(start_registration))
+        .route("/auth/webauthn/register/finish", post(finish_registration))
+        .route("/auth/webauthn/credentials", get(list_credentials))
+        .route("/auth/webauthn/credentials/{capture}", patch(rename_credential))
+        .route("/auth/webauthn/credentials/{capture}", delete(delete_credential))
+}
+
+#[derive(Deserialize)]
+struct FinishRegistrationRequest {
+    ceremony_id: String,
+    /// 一覧に表示する名前（省略時は "Passkey"）
+    name: Option<String>,
+    credential: RegistrationCredential,
+}
+
+async fn start_registration(
+    State(state): State<AppState>,
+    auth: Authorized<require::EditAccount>,
+) -> std::result::Result<Json<WebAuthnRegistrationChallenge>, Response> {
+    let user = state
+        .auth_service
+        .get_current_user(&auth.user_id)
+        .await
+        .map_err(map_error)?;
+
+    let challenge = state
+        .webauthn_service
+        .start_registration(&user.user_id, &user.email, &user.display_name)
+        .await
+        .map_err(map_error)?;
+    Ok(Json(challenge))
+}
+
+async fn finish_registration(
+    State(state): State<AppState>,
+    auth: Authorized<require::EditAccount>,
+    Json(req): Json<FinishRegistrationRequest>,
+) -> std::result::Result<Json<WebAuthnCredential>, Response> {
+    let credential = state
+        .webauthn_service
+        .finish_registration(
+            &auth.user_id,
+            &req.ceremony_id,
+            req.name.as_deref(),
+            &req.credential,
+        )
+        .await
+        .map_err(map_error)?;
+    Ok(Json(credential))
+}
+
+async fn list_credentials(
+    State(state): State<AppState>,
+    auth: Authorized<require::EditAccount>,
+) -> std::result::Result<Json<WebAuthnCredentialList>, Response> {
+    let credentials = state
+        .webauthn_service
+        .list(&auth.user_id)
+        .await
+        .map_err(map_error)?;
+    Ok(Json(WebAuthnCredentialList { credentials }))
+}
+
+async fn rename_credential(
+    State(state): State<AppState>,
+    auth: Authorized<require::EditAccount>,
+    Path(credential_id): Path<String>,
+    Json(req): Json<UpdateWebAuthnCredentialRequest>,
+) -> std::result::Result<Json<WebAuthnCredential>, Response> {
+    let credential = state
+        .webauthn_service
+        .rename(&auth.user_id, &credential_id, &req.name)
+        .await
+        .map_err(map_error)?;
+    Ok(Json(credential))
+}
+
+async fn delete_credential(
+    State(state): State<AppState>,
+    auth: Authorized<require::EditAccount>,
+    Path(credential_id): Path<String>,
+) -> std::result::Result<Json<serde_json::Value>, Response> {
+    state
+        .webauthn_service
+        .delete(&auth.user_id, &credential_id)
+        .await
+        .map_err(map_error)?;
+    Ok(Json(json!({
+        "status": "success",
+        "message": format!("Passkey deleted: {credential_id}")
+    })))
+}
@@ -0,0 +1,131 @@
+use axum::{
+    extract::FromRequestParts,
+    http::{HeaderMap, header, request::Parts},
+    response::Response,
+};
+use axum_extra::extract::CookieJar;
+use std::marker::PhantomData;
+
+use crate::auth::{Role, decode_access_token};
+use crate::error::{AppError, map_error};
+use crate::server::AppState;
+use crate::services::is_personal_access_token;
+
+/// ルートが要求する権限の組
+pub trait RequiredRoles: Send + Sync + 'static {
+    const ROLES: &'static [Role];
+}
+
+macro_rules! required_roles {
+    ($($(#[$meta:meta])* $name:ident => [$($role:ident),*];)*) => {
+        $(
+            $(#[$meta])*
+            pub struct $name;
+
+            impl RequiredRoles for $name {
+                const ROLES: &'static [Role] = &[$(Role::$role),*];
+            }
+        )*
+    };
+}
+
+/// `Authorized<R>` の型引数として使う権限の指定
+pub mod require {
+    use super::{RequiredRoles, Role};
+
+    required_roles! {
+        /// 有効なアクセストークンのみを要求（権限は問わない）
+        Authenticated => [];
+        ViewMemo => [ViewMemo];
+        EditMemo => [EditMemo];
+        SummarizeMemo => [SummarizeMemo];
+        EditTag => [EditTag];
+        EditAccount => [EditAccount];
+        DeleteAccount => [DeleteAccount];
+    }
+}
+
+/// `Authorization: Bearer <jwt>` ヘッダーからトークンを取得
+pub fn bearer_token(headers: &HeaderMap) -> Option<&str> {
+    let value = headers.get(header::AUTHORIZATION)?.to_str().ok()?;
+    let (scheme, token) = value.split_once(' ')?;
+    let token = token.trim();
+    (scheme.eq_ignore_ascii_case("bearer") && !token.is_empty()).then_some(token)
+}
+
+/// アクセストークンを検証し、ルートが要求する権限を持つユーザー
+///
+/// `Authorization: Bearer` ヘッダー（なければ Cookie の `access_token`）をデコードし、
+/// 種別・失効・権限を確認する。Bearer にはパーソナルアクセストークンも指定できる。
+/// 検証に失敗した場合は 401、権限が不足している場合は 403 を返す。
+pub struct Authorized<R: RequiredRoles> {
+    pub user_id: String,
+    /// ログインセッションのID（パーソナルアクセストークンの場合は None）
+    pub session_id: Option<String>,
+    _roles: PhantomData<R>,
+}
+
+impl<R: RequiredRoles> FromRequestParts<AppState> for Authorized<R> {
+    type Rejection = Response;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &AppState,
+    ) -> std::result::Result<Self, Self::Rejection> {
+        let jar = CookieJar::from_headers(&parts.headers);
+        let token = bearer_token(&parts.headers)
+            .or_else(|| jar.get("access_token").map(|cookie| cookie.value()))
+            .ok_or_else(|| {
+                map_error(AppError::AuthenticationError(
+                    "Authentication required".to_string(),
+                ))
+            })?;
+
+        // パーソナルアクセストークン
+        if is_personal_access_token(token) {
+            let pat = state
+                .personal_access_token_service
+                .authenticate(token)
+                .await
+                .map_err(map_error)?;
+
+            if !R::ROLES.iter().all(|role| pat.roles.contains(role)) {
+                return Err(insufficient_permissions());
+            }
+
+            return Ok(Self {
+                user_id: pat.user_id,
+                session_id: None,
+                _roles: PhantomData,
+            });
+        }
+
+        let claims = decode_access_token(token, &state.jwt_keys).map_err(map_error)?;
+
+        // トークン・セッションが失効されていないか確認
+        if state
+            .auth_service
+            .is_access_token_revoked(&claims)
+            .await
+            .map_err(map_error)?
+        {
+            return Err(map_error(AppError::AuthenticationError(
+                "Token has been revoked".to_string(),
+            )));
+        }
+
+        if !claims.has_roles(R::ROLES) {
+            return Err(insufficient_permissions());
+        }
+
+        Ok(Self {
+            user_id: claims.sub().to_string(),
+            session_id: claims.sid().map(str::to_string),
+            _roles: PhantomData,
+        })
+    }
+}
+
+fn insufficient_permissions() -> Response {
+    map_error(AppError::Forbidden("Insufficient permissions".to_string()))
+}
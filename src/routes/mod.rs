@@ -1,5 +1,8 @@
 pub mod api;
+mod authorization;
 mod share;
+mod well_known;
 
 pub use api::create_api_routes;
-// pub use share::create_share_routes;
+pub use share::create_share_routes;
+pub use well_known::create_well_known_routes;
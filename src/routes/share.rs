@@ -1,29 +1,54 @@
-use axum::{Json, Router, extract::Path, response::IntoResponse, routing::get};
-use axum_extra::extract::CookieJar;
+use axum::{
+    Json, Router,
+    extract::{ConnectInfo, Path, State},
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    routing::get,
+};
 use serde_json::json;
+use std::net::SocketAddr;
 
-use crate::repositories::Memo;
+use crate::error::map_error;
+use crate::repositories::SharedMemo;
+use crate::routes::api::get_client_ip;
 use crate::server::AppState;
 
+/// 共有リンクの閲覧パスワードを受け取るヘッダー
+const SHARE_PASSWORD_HEADER: &str = "x-share-password";
+
 pub fn create_share_routes() -> Router<AppState> {
     Router::new()
         .route("/{capture}", get(handle_get_share))
-        .route("/test", get(handle_test))
 }
 
-async fn handle_get_share(_jar: CookieJar, _req: Path<String>) -> impl IntoResponse {
-    Json(Memo {
-        memo_id: "hoge".to_string(),
-        user_id: "user_123".to_string(),
-        content: "This is a shared memo.".to_string(),
-        auto_tag_id: Some(vec!["auto_tag_123".to_string()]),
-        manual_tag_id: Some(vec!["manual_tag_456".to_string()]),
-        share_url_token: None,
-        created_at: chrono::Utc::now(),
-        updated_at: chrono::Utc::now(),
-    })
-}
+/// 共有リンクからメモを閲覧（認証不要）
+async fn handle_get_share(
+    State(state): State<AppState>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    Path(token): Path<String>,
+) -> std::result::Result<Json<SharedMemo>, Response> {
+    // IPアドレスごと・IPアドレスとトークンの組ごとのレート制限（パスワードの失敗はサービス側でトークンごとに制限）
+    let ip = get_client_ip(&headers, &addr);
+    state
+        .share_rate_limiter
+        .check_limit(&ip, &token)
+        .map_err(|e| {
+            (
+                StatusCode::TOO_MANY_REQUESTS,
+                Json(json!({"error": e})),
+            )
+                .into_response()
+        })?;
+
+    let password = headers
+        .get(SHARE_PASSWORD_HEADER)
+        .and_then(|value| value.to_str().ok());
 
-async fn handle_test() -> impl IntoResponse {
-    Json(json!({"status": "share service is running"}))
+    let memo = state
+        .share_service
+        .view_shared_memo(&token, password)
+        .await
+        .map_err(map_error)?;
+    Ok(Json(memo))
 }
@@ -0,0 +1,29 @@
+use axum::{
+    Json, Router,
+    extract::State,
+    http::header,
+    response::IntoResponse,
+    routing::get,
+};
+
+use crate::server::AppState;
+
+/// JWKSをキャッシュしてよい期間（秒）
+/// 鍵のローテーションでは、新しい鍵を検証鍵として公開してからこの期間以上あけて署名鍵を切り替える
+const JWKS_MAX_AGE: u32 = 5 * 60;
+
+pub fn create_well_known_routes() -> Router<AppState> {
+    Router::new().route("/jwks.json", get(handle_jwks))
+}
+
+/// JWTの検証に使う公開鍵の一覧（認証不要）
+/// 共通鍵（HS256）は公開しない
+async fn handle_jwks(State(state): State<AppState>) -> impl IntoResponse {
+    (
+        [(
+            header::CACHE_CONTROL,
+            format!("public, max-age={}", JWKS_MAX_AGE),
+        )],
+        Json(state.jwt_keys.jwks()),
+    )
+}
@@ -2,28 +2,41 @@ use axum::{
     Router,
     http::{Method, header},
 };
-use jsonwebtoken::DecodingKey;
 use std::sync::Arc;
 use std::{net::SocketAddr, time::Duration};
 use tokio::net::TcpListener;
 use tower_http::cors::CorsLayer;
 
 use crate::config::Config;
-use crate::routes::create_api_routes;
-use crate::services::{AuthService, MemoService, SummaryService, TagService};
+use crate::jwt_keys::JwtKeys;
+use crate::routes::{create_api_routes, create_share_routes, create_well_known_routes};
+use crate::services::{
+    AuthService, ExportService, JournalingService, MemoService, MfaService, OidcService, PersonalAccessTokenService,
+    SearchService, ShareService, SummaryJobService, SummaryService, TagService, WebAuthnService,
+};
 
 /// アプリケーション全体で共有される状態
 #[derive(Clone)]
 pub struct AppState {
-    /// JWT検証用のデコード鍵（一度だけ生成して再利用）
-    pub jwt_decoding_key: DecodingKey,
+    /// JWTの署名鍵・検証鍵（一度だけ読み込んで再利用）
+    pub jwt_keys: Arc<JwtKeys>,
     /// サービス層
     pub auth_service: Arc<AuthService>,
+    pub export_service: Arc<ExportService>,
+    pub journaling_service: Arc<JournalingService>,
     pub memo_service: Arc<MemoService>,
+    pub mfa_service: Arc<MfaService>,
+    pub oidc_service: Arc<OidcService>,
+    pub personal_access_token_service: Arc<PersonalAccessTokenService>,
+    pub search_service: Arc<SearchService>,
+    pub share_service: Arc<ShareService>,
+    pub summary_job_service: Arc<SummaryJobService>,
     pub summary_service: Arc<SummaryService>,
     pub tag_service: Arc<TagService>,
+    pub webauthn_service: Arc<WebAuthnService>,
     /// レート制限
     pub auth_rate_limiter: Arc<crate::services::AuthRateLimiter>,
+    pub share_rate_limiter: Arc<crate::services::ShareRateLimiter>,
     /// アプリケーション設定
     pub config: Arc<Config>,
 }
@@ -52,14 +65,20 @@ pub async fn start_server(
             Method::PATCH,
             Method::DELETE,
         ])
-        .allow_headers(vec![header::CONTENT_TYPE, header::ACCEPT])
+        .allow_headers(vec![
+            header::CONTENT_TYPE,
+            header::ACCEPT,
+            header::AUTHORIZATION,
+            header::HeaderName::from_static("x-share-password"),
+        ])
         .expose_headers(vec![header::CONTENT_TYPE, header::SET_COOKIE])
         .max_age(Duration::from_secs(180));
 
     println!("Creating routes...");
     let app = Router::new()
         .merge(create_api_routes())
-        //.nest("/share", create_share_routes())
+        .nest("/share", create_share_routes())
+        .nest("/.well-known", create_well_known_routes())
         .with_state(state)
         .layer(cors)
         .into_make_service_with_connect_info::<SocketAddr>();
@@ -0,0 +1,85 @@
+use crate::error::Result;
+use crate::repositories::auth::{AuthRepository, PurgedAccountData, ScheduledAccountDeletion};
+use crate::services::{MemoService, RevocationCache, SearchService, SummaryService};
+use chrono::Utc;
+use std::sync::Arc;
+
+/// 削除予定日時を過ぎたアカウントのデータの完全削除
+///
+/// MongoDBのメモ・要約・検索インデックスを先に削除し、最後にPostgreSQLのユーザーを削除する。
+/// 途中で失敗した場合はユーザーが残るため、次回の実行で続きから削除される。
+pub struct AccountDeletionService {
+    auth_repo: Arc<AuthRepository>,
+    memo_service: Arc<MemoService>,
+    summary_service: Arc<SummaryService>,
+    search_service: Arc<SearchService>,
+    revocation_cache: Arc<RevocationCache>,
+}
+
+impl AccountDeletionService {
+    pub fn new(
+        auth_repo: Arc<AuthRepository>,
+        memo_service: Arc<MemoService>,
+        summary_service: Arc<SummaryService>,
+        search_service: Arc<SearchService>,
+        revocation_cache: Arc<RevocationCache>,
+    ) -> Self {
+        Self {
+            auth_repo,
+            memo_service,
+            summary_service,
+            search_service,
+            revocation_cache,
+        }
+    }
+
+    /// 削除予定日時を過ぎたアカウントをすべて削除
+    /// 戻り値: 削除したアカウント数（失敗したアカウントは次回の実行で再試行する）
+    pub async fn purge_due_accounts(&self) -> Result<usize> {
+        let now = Utc::now();
+        let deletions = self.auth_repo.find_due_deletions(now).await?;
+
+        let mut purged = 0;
+        for deletion in &deletions {
+            // 直前に削除が取り消された場合は何もしない
+            if !self
+                .auth_repo
+                .deactivate_for_deletion(&deletion.user_id, now)
+                .await?
+            {
+                continue;
+            }
+
+            match self.purge_account(deletion).await {
+                Ok(()) => purged += 1,
+                Err(e) => eprintln!("Failed to purge account {}: {}", deletion.user_id, e),
+            }
+        }
+        Ok(purged)
+    }
+
+    async fn purge_account(&self, deletion: &ScheduledAccountDeletion) -> Result<()> {
+        let user_id = deletion.user_id.as_str();
+
+        // 猶予期間中にログインして作られたセッションを失効させる
+        // （ファミリーはユーザーと一緒に削除されるため、発行済みのアクセストークンはキャッシュで拒否する）
+        let revoked = self
+            .auth_repo
+            .revoke_all_refresh_token_families(user_id, "account_deleted")
+            .await?;
+        self.revocation_cache.revoke_sessions(&revoked);
+
+        let purged = PurgedAccountData {
+            memos: self.memo_service.purge_all_by_user(user_id).await?,
+            summaries: self.summary_service.purge_all_by_user(user_id).await?,
+        };
+        self.search_service.remove_user(user_id).await?;
+
+        self.auth_repo.purge_user(deletion, &purged).await?;
+        println!(
+            "Purged account {} ({} memos, {} summaries)",
+            user_id, purged.memos, purged.summaries
+        );
+        Ok(())
+    }
+}
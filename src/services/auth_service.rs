@@ -1,63 +1,166 @@
 use crate::auth::{
-    Role, create_decoding_key, extract_jti_from_token, extract_user_id_from_token,
-    issue_access_token, issue_password_reset_token, issue_refresh_token,
-    issue_registration_token, validate_display_name_format, validate_email_format,
-    validate_password_format, validate_password_reset_token, validate_registration_token,
-    validate_user_id_format,
+    JwtClaim, Role, decode_account_unlock_token, decode_magic_link_token, decode_mfa_pending_token, decode_refresh_token,
+    issue_access_token, issue_account_unlock_token, issue_magic_link_token, issue_mfa_pending_token, issue_password_reset_token,
+    issue_refresh_token, issue_registration_token, issue_step_up_access_token,
+    validate_display_name_format, validate_email_format, validate_password_format,
+    validate_password_reset_token, validate_registration_token, validate_user_id_format,
 };
-use crate::error::{AppError, Result, map_error};
-use axum::response::Response;
-use axum_extra::extract::CookieJar;
+use crate::error::{AppError, Result};
+use crate::jwt_keys::JwtKeys;
+use crate::webauthn::{AuthenticationCredential, UserVerification};
 use crate::repositories::auth::{
-    AuthRepository, UserCreateRequest, UserLoginRequest, UserResponse, UserUpdateRequest,
+    AuthRepository, ExternalIdentity, LoginFailureState, MagicLinkSettings, RefreshRotation, Session, SessionClient, UserCreateRequest, UserLoginRequest,
+    UserResponse, UserUpdateRequest,
 };
 use crate::repositories::tag::CreateTagRequest;
-use crate::services::TagService;
+use crate::repositories::webauthn::WebAuthnAuthenticationChallenge;
+use crate::services::oidc_service::VerifiedIdentity;
+use crate::services::{MfaService, RevocationCache, TagService, WebAuthnService};
 use crate::services::verification_store::VerificationPurpose;
 use crate::services::{EmailRateLimiter, EmailService, VerificationStore};
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use chrono::{DateTime, Utc};
 use std::sync::Arc;
+use uuid::Uuid;
+
+/// ログイン・リフレッシュで発行するアクセストークンの権限
+const SESSION_ROLES: [Role; 5] = [
+    Role::EditMemo,
+    Role::ViewMemo,
+    Role::SummarizeMemo,
+    Role::EditTag,
+    Role::EditAccount,
+];
+
+/// アカウントをロックする連続失敗回数（以降もこの回数ごとにロックする）
+const LOCKOUT_THRESHOLD: i32 = 5;
+/// 最初のロックの期間（分）。ロックのたびに倍にする
+const LOCKOUT_BASE_MINUTES: i64 = 1;
+/// ロックの最長期間（分）
+const LOCKOUT_MAX_MINUTES: i64 = 24 * 60;
+
+/// 既存のユーザーへの連携・ユーザーの作成に使うメールアドレス
+/// IDプロバイダーが確認済みとしたメールアドレスのみ使う（未確認の場合は None）
+fn verified_email(identity: &VerifiedIdentity) -> Option<&str> {
+    identity.email.as_deref().filter(|_| identity.email_verified)
+}
+
+/// 外部IDから作成するユーザーの表示名の最大バイト数（validate_display_name_format に合わせる）
+const DISPLAY_NAME_MAX_BYTES: usize = 50;
+
+/// ログインの結果
+pub enum LoginOutcome {
+    /// ログイン完了（アクセストークン, リフレッシュトークン, ユーザー）
+    Authenticated {
+        access_token: String,
+        refresh_token: String,
+        user: UserResponse,
+    },
+    /// 二要素認証が必要（二要素認証待ちトークン, 利用できる方式）
+    MfaRequired {
+        mfa_token: String,
+        mfa_methods: Vec<&'static str>,
+    },
+}
+
+/// send_verification_code_internal で確認コードを送る目的
+/// メールアドレスの変更はユーザーIDに結び付けて保存するため request_email_change で送る
+#[derive(Clone, Copy)]
+enum EmailCodePurpose {
+    Registration,
+    PasswordReset,
+}
+
+impl From<EmailCodePurpose> for VerificationPurpose {
+    fn from(purpose: EmailCodePurpose) -> Self {
+        match purpose {
+            EmailCodePurpose::Registration => VerificationPurpose::Registration,
+            EmailCodePurpose::PasswordReset => VerificationPurpose::PasswordReset,
+        }
+    }
+}
 
 pub struct AuthService {
     auth_repo: Arc<AuthRepository>,
     tag_service: Arc<TagService>,
-    jwt_secret: String,
+    mfa_service: Arc<MfaService>,
+    webauthn_service: Arc<WebAuthnService>,
+    jwt_keys: Arc<JwtKeys>,
     email_service: Arc<EmailService>,
     verification_store: Arc<VerificationStore>,
     rate_limiter: Arc<EmailRateLimiter>,
+    revocation_cache: Arc<RevocationCache>,
+    /// ログインリンクのリンク先（フロントエンドの画面）
+    magic_link_url: String,
+    /// ロック解除リンクのリンク先（フロントエンドの画面）
+    account_unlock_url: String,
+    /// 削除を申請してからデータを完全に削除するまでの日数
+    account_deletion_grace_days: i64,
 }
 
 impl AuthService {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         auth_repo: Arc<AuthRepository>,
         tag_service: Arc<TagService>,
-        jwt_secret: String,
+        mfa_service: Arc<MfaService>,
+        webauthn_service: Arc<WebAuthnService>,
+        jwt_keys: Arc<JwtKeys>,
         email_service: Arc<EmailService>,
         verification_store: Arc<VerificationStore>,
         rate_limiter: Arc<EmailRateLimiter>,
+        revocation_cache: Arc<RevocationCache>,
+        magic_link_url: String,
+        account_unlock_url: String,
+        account_deletion_grace_days: i64,
     ) -> Self {
         Self {
             auth_repo,
             tag_service,
-            jwt_secret,
+            mfa_service,
+            webauthn_service,
+            jwt_keys,
             email_service,
             verification_store,
             rate_limiter,
+            revocation_cache,
+            magic_link_url,
+            account_unlock_url,
+            account_deletion_grace_days,
         }
     }
 
     /// ログイン処理
+    /// 二要素認証が有効な場合はトークンを発行せず、二要素認証待ちトークンを返す
+    /// パスワードを続けて間違えるとアカウントをロックする
     pub async fn login(
         &self,
         email: String,
         password: String,
-    ) -> Result<(String, String, UserResponse)> {
+        client: SessionClient,
+    ) -> Result<LoginOutcome> {
+        // ロック中はパスワードを検証しない（ロック中に正しいパスワードを探り当てられないようにする）
+        let failure_state = self.auth_repo.find_login_failure_state(&email).await?;
+        if let Some(locked_until) = failure_state.as_ref().and_then(|state| state.locked_until)
+            && locked_until > Utc::now()
+        {
+            return Err(account_locked_error(locked_until));
+        }
+
         let req = UserLoginRequest {
             email: email.clone(),
             password,
         };
 
         // パスワード検証
-        self.auth_repo.validate_password(req).await?;
+        if let Err(e) = self.auth_repo.validate_password(req).await {
+            if let (Some(state), AppError::AuthenticationError(_)) = (&failure_state, &e)
+                && let Some(locked_until) = self.record_failed_login(state).await?
+            {
+                return Err(account_locked_error(locked_until));
+            }
+            return Err(e);
+        }
 
         // ユーザー情報取得
         let user = self
@@ -73,25 +176,481 @@ impl AuthService {
             ));
         }
 
+        let outcome = self.complete_primary_login(user, &client).await?;
+
+        // 二要素認証が必要な場合は、コードの総当たりでロックを回避されないよう二要素認証の完了まで残す
+        if let LoginOutcome::Authenticated { .. } = &outcome
+            && let Some(state) = &failure_state
+            && (state.failed_login_count > 0 || state.locked_until.is_some())
+        {
+            self.auth_repo.clear_failed_logins(&state.user_id).await?;
+        }
+
+        Ok(outcome)
+    }
+
+    /// パスワードによるログインの失敗を記録し、一定回数ごとにアカウントをロックする
+    /// 戻り値: ロックした場合はロックの期限
+    async fn record_failed_login(&self, state: &LoginFailureState) -> Result<Option<DateTime<Utc>>> {
+        let failed_login_count = self.auth_repo.record_failed_login(&state.user_id).await?;
+        let Some(duration) = lockout_duration(failed_login_count) else {
+            return Ok(None);
+        };
+
+        let locked_until = Utc::now() + duration;
+        self.auth_repo
+            .lock_account(&state.user_id, locked_until)
+            .await?;
+        eprintln!(
+            "Account locked after {} failed login attempts: user={}, until={}",
+            failed_login_count, state.user_id, locked_until
+        );
+
+        // ロック解除のリンクを送る（送信に失敗してもロックは維持する）
+        if let Err(e) = self
+            .send_account_unlock_link(&state.user_id, &state.email, locked_until)
+            .await
+        {
+            eprintln!(
+                "Failed to send account unlock link to user {}: {}",
+                state.user_id, e
+            );
+        }
+
+        Ok(Some(locked_until))
+    }
+
+    /// ロック解除のリンクをメールで送信
+    async fn send_account_unlock_link(
+        &self,
+        user_id: &str,
+        email: &str,
+        locked_until: DateTime<Utc>,
+    ) -> Result<()> {
+        let token = issue_account_unlock_token(user_id, &self.jwt_keys)?;
+        let link = reqwest::Url::parse_with_params(&self.account_unlock_url, &[("token", token)])
+            .map_err(|e| AppError::ConfigError(format!("Invalid ACCOUNT_UNLOCK_URL: {}", e)))?;
+
+        self.email_service
+            .send_account_locked(
+                email,
+                &locked_until.format("%Y-%m-%d %H:%M UTC").to_string(),
+                link.as_str(),
+            )
+            .await
+    }
+
+    /// ロック解除のリンクを再送信
+    /// アカウントの有無を推測されないよう、ユーザーが存在しない・ロックされていない場合も成功として扱う
+    pub async fn request_account_unlock(&self, email: &str, client_ip: Option<&str>) -> Result<()> {
+        validate_email_format(email)?;
+
+        // レート制限チェック（確認コードの送信と同じ制限）
+        self.rate_limiter
+            .check_email_limit(email)
+            .map_err(AppError::ValidationError)?;
+        if let Some(ip) = client_ip {
+            self.rate_limiter
+                .check_ip_limit(ip)
+                .map_err(AppError::ValidationError)?;
+        }
+
+        let Some(state) = self.auth_repo.find_login_failure_state(email).await? else {
+            return Ok(());
+        };
+        match state.locked_until {
+            Some(locked_until) if locked_until > Utc::now() => {
+                self.send_account_unlock_link(&state.user_id, &state.email, locked_until)
+                    .await
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// ロック解除のリンクでアカウントのロックを解除（リンクは一度だけ使用できる）
+    pub async fn unlock_account(&self, token: &str) -> Result<()> {
+        let claims = decode_account_unlock_token(token, &self.jwt_keys)?;
+
+        if !self
+            .auth_repo
+            .consume_jwt(claims.jti(), claims.expires_at())
+            .await?
+        {
+            return Err(AppError::AuthenticationError(
+                "Unlock link has already been used".to_string(),
+            ));
+        }
+
+        self.auth_repo.clear_failed_logins(claims.sub()).await
+    }
+
+    /// 外部IDプロバイダー（OpenID Connect）によるログイン
+    /// 連携済みの外部IDがなければ、確認済みのメールアドレスで既存のユーザーに連携する。
+    /// 該当するユーザーもいなければ新しくユーザーを作成する
+    pub async fn login_with_external_identity(
+        &self,
+        identity: VerifiedIdentity,
+        client: SessionClient,
+    ) -> Result<LoginOutcome> {
+        let email = verified_email(&identity);
+
+        // 連携済みの外部ID
+        if let Some(user_id) = self
+            .auth_repo
+            .find_user_id_by_external_identity(&identity.provider, &identity.subject)
+            .await?
+        {
+            let user = self.get_current_user(&user_id).await?;
+            self.auth_repo
+                .touch_external_identity(&identity.provider, &identity.subject, email)
+                .await?;
+            return self.complete_primary_login(user, &client).await;
+        }
+
+        // 未確認のメールアドレスでは既存のアカウントに連携しない（アカウントの乗っ取りを防ぐ）
+        let email = email.ok_or_else(|| {
+            AppError::AuthenticationError(
+                "The identity provider did not return a verified email address".to_string(),
+            )
+        })?;
+        validate_email_format(email)?;
+
+        if let Some(user) = self.auth_repo.find_user_by_email(email).await? {
+            if !user.is_active {
+                return Err(AppError::AuthenticationError(
+                    "Account is deactivated".to_string(),
+                ));
+            }
+            self.link_external_identity(&identity, &user.user_id, email)
+                .await?;
+            return self.complete_primary_login(user, &client).await;
+        }
+
+        // 新規ユーザーを作成（パスワードはランダムに設定し、パスワードリセットで設定できる）
+        let user = UserCreateRequest {
+            user_id: self.generate_user_id(email).await?,
+            email: email.to_string(),
+            display_name: Some(external_display_name(identity.name.as_deref(), email)),
+            password: URL_SAFE_NO_PAD.encode(rand::random::<[u8; 32]>()),
+        };
+        let user = self.auth_repo.register(user).await?;
+        self.create_default_tags(&user.user_id).await?;
+        self.link_external_identity(&identity, &user.user_id, email)
+            .await?;
+
+        let (access_token, refresh_token) = self.start_session(&user.user_id, &client).await?;
+        Ok(LoginOutcome::Authenticated {
+            access_token,
+            refresh_token,
+            user,
+        })
+    }
+
+    /// ユーザーに連携された外部IDの一覧
+    pub async fn list_external_identities(&self, user_id: &str) -> Result<Vec<ExternalIdentity>> {
+        self.auth_repo.find_external_identities(user_id).await
+    }
+
+    /// 外部IDの連携を解除
+    pub async fn unlink_external_identity(&self, user_id: &str, provider: &str) -> Result<()> {
+        if !self
+            .auth_repo
+            .unlink_external_identity(user_id, provider)
+            .await?
+        {
+            return Err(AppError::NotFound("External identity not found".to_string()));
+        }
+        Ok(())
+    }
+
+    async fn link_external_identity(
+        &self,
+        identity: &VerifiedIdentity,
+        user_id: &str,
+        email: &str,
+    ) -> Result<()> {
+        if !self
+            .auth_repo
+            .link_external_identity(&identity.provider, &identity.subject, user_id, Some(email))
+            .await?
+        {
+            return Err(AppError::ValidationError(
+                "Another account from this identity provider is already linked".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// メールアドレスのローカル部から重複しないユーザーIDを生成
+    async fn generate_user_id(&self, email: &str) -> Result<String> {
+        let local_part = email.split('@').next().unwrap_or_default();
+        let mut base: String = local_part
+            .chars()
+            .filter(|c| c.is_ascii_alphanumeric() || *c == '_' || *c == '-')
+            .take(24)
+            .collect();
+        if base.len() < 3 {
+            base = "user".to_string();
+        }
+
+        for _ in 0..5 {
+            let user_id = format!("{}-{}", base, &Uuid::new_v4().simple().to_string()[..6]);
+            if self.auth_repo.find_user_by_id(&user_id).await?.is_none() {
+                return Ok(user_id);
+            }
+        }
+        Err(AppError::ValidationError(
+            "Failed to generate a unique user ID".to_string(),
+        ))
+    }
+
+    /// パスワードなどの1段階目の認証が完了したユーザーのログイン
+    /// 二要素認証（TOTP・パスキー）が設定されている場合は2段階目へ
+    async fn complete_primary_login(
+        &self,
+        user: UserResponse,
+        client: &SessionClient,
+    ) -> Result<LoginOutcome> {
+        let mut mfa_methods = Vec::new();
+        if self.mfa_service.is_enabled(&user.user_id).await? {
+            mfa_methods.push("totp");
+        }
+        if self.webauthn_service.has_credentials(&user.user_id).await? {
+            mfa_methods.push("webauthn");
+        }
+        if !mfa_methods.is_empty() {
+            let mfa_token = issue_mfa_pending_token(&user.user_id, &self.jwt_keys)?;
+            return Ok(LoginOutcome::MfaRequired {
+                mfa_token,
+                mfa_methods,
+            });
+        }
+
         // トークン発行
-        let roles = vec![
-            Role::EditMemo,
-            Role::ViewMemo,
-            Role::SummarizeMemo,
-            Role::EditTag,
-            Role::EditAccount,
-        ];
-        let access_token = issue_access_token(&user.user_id, roles, &self.jwt_secret)?;
-        let refresh_token = issue_refresh_token(&user.user_id, &self.jwt_secret)?;
+        let (access_token, refresh_token) = self.start_session(&user.user_id, client).await?;
+
+        Ok(LoginOutcome::Authenticated {
+            access_token,
+            refresh_token,
+            user,
+        })
+    }
+
+    /// ログインリンクをメールで送信
+    /// アカウントの有無を推測されないよう、ユーザーが存在しない・ログインリンクが無効な場合も成功として扱う
+    pub async fn send_magic_link(&self, email: &str, client_ip: Option<&str>) -> Result<()> {
+        validate_email_format(email)?;
+
+        // レート制限チェック（確認コードの送信と同じ制限）
+        self.rate_limiter
+            .check_email_limit(email)
+            .map_err(AppError::ValidationError)?;
+        if let Some(ip) = client_ip {
+            self.rate_limiter
+                .check_ip_limit(ip)
+                .map_err(AppError::ValidationError)?;
+        }
+
+        let Some(user) = self.auth_repo.find_user_by_email(email).await? else {
+            return Ok(());
+        };
+        if !user.is_active || !self.auth_repo.is_magic_link_enabled(&user.user_id).await? {
+            return Ok(());
+        }
+
+        let token = issue_magic_link_token(&user.user_id, &self.jwt_keys)?;
+        let link = reqwest::Url::parse_with_params(&self.magic_link_url, &[("token", token)])
+            .map_err(|e| AppError::ConfigError(format!("Invalid MAGIC_LINK_URL: {}", e)))?;
+
+        self.email_service
+            .send_magic_link(&user.email, link.as_str())
+            .await
+    }
+
+    /// ログインリンクによるログイン
+    /// ログインリンクは一度だけ使用でき、二要素認証が有効な場合は2段階目へ進む
+    pub async fn login_with_magic_link(
+        &self,
+        token: &str,
+        client: SessionClient,
+    ) -> Result<LoginOutcome> {
+        let claims = decode_magic_link_token(token, &self.jwt_keys)?;
+
+        if !self
+            .auth_repo
+            .consume_jwt(claims.jti(), claims.expires_at())
+            .await?
+        {
+            return Err(AppError::AuthenticationError(
+                "Login link has already been used".to_string(),
+            ));
+        }
+
+        let user = self.get_current_user(claims.sub()).await?;
+        // リンクの送信後に無効化された場合も使用できない
+        if !self.auth_repo.is_magic_link_enabled(&user.user_id).await? {
+            return Err(AppError::AuthenticationError(
+                "Login links are disabled for this account".to_string(),
+            ));
+        }
+
+        self.complete_primary_login(user, &client).await
+    }
+
+    /// ログインリンクの設定を取得
+    pub async fn magic_link_settings(&self, user_id: &str) -> Result<MagicLinkSettings> {
+        Ok(MagicLinkSettings {
+            enabled: self.auth_repo.is_magic_link_enabled(user_id).await?,
+        })
+    }
+
+    /// ログインリンクによるログインの有効・無効を設定
+    pub async fn update_magic_link_settings(
+        &self,
+        user_id: &str,
+        settings: MagicLinkSettings,
+    ) -> Result<MagicLinkSettings> {
+        self.auth_repo
+            .set_magic_link_enabled(user_id, settings.enabled)
+            .await?;
+        Ok(settings)
+    }
+
+    /// 二要素認証によるログインの2段階目
+    /// 二要素認証待ちトークンとTOTPコード（またはリカバリーコード）を検証してトークンを発行
+    pub async fn complete_mfa_login(
+        &self,
+        mfa_token: &str,
+        code: &str,
+        client: SessionClient,
+    ) -> Result<(String, String, UserResponse)> {
+        let claims = self.verify_mfa_pending_token(mfa_token).await?;
+
+        // コードの失敗もパスワードの失敗と合わせて数え、ロック中はコードを検証しない
+        let failure_state = self
+            .auth_repo
+            .find_login_failure_state_by_user_id(claims.sub())
+            .await?;
+        if let Some(locked_until) = failure_state.as_ref().and_then(|state| state.locked_until)
+            && locked_until > Utc::now()
+        {
+            return Err(account_locked_error(locked_until));
+        }
+
+        if let Err(e) = self.mfa_service.verify(claims.sub(), code).await {
+            if let (Some(state), AppError::AuthenticationError(_)) = (&failure_state, &e)
+                && let Some(locked_until) = self.record_failed_login(state).await?
+            {
+                return Err(account_locked_error(locked_until));
+            }
+            return Err(e);
+        }
+
+        if let Some(state) = &failure_state
+            && (state.failed_login_count > 0 || state.locked_until.is_some())
+        {
+            self.auth_repo.clear_failed_logins(&state.user_id).await?;
+        }
+
+        self.finish_mfa_login(&claims, &client).await
+    }
+
+    /// 二要素認証としてパスキーの認証セレモニーを開始
+    pub async fn start_mfa_webauthn(&self, mfa_token: &str) -> Result<WebAuthnAuthenticationChallenge> {
+        let claims = self.verify_mfa_pending_token(mfa_token).await?;
+        self.webauthn_service
+            .start_authentication(Some(claims.sub()), UserVerification::Discouraged)
+            .await
+    }
+
+    /// 二要素認証によるログインの2段階目（パスキー）
+    pub async fn complete_mfa_login_with_webauthn(
+        &self,
+        mfa_token: &str,
+        ceremony_id: &str,
+        credential: &AuthenticationCredential,
+        client: SessionClient,
+    ) -> Result<(String, String, UserResponse)> {
+        let claims = self.verify_mfa_pending_token(mfa_token).await?;
+        self.webauthn_service
+            .finish_authentication(ceremony_id, credential, Some(claims.sub()))
+            .await?;
+        self.finish_mfa_login(&claims, &client).await
+    }
+
+    /// パスキーによるパスワードレスログイン
+    /// 認証器でのユーザー検証（PIN・生体認証）を必須とし、二要素認証は要求しない
+    pub async fn login_with_passkey(
+        &self,
+        ceremony_id: &str,
+        credential: &AuthenticationCredential,
+        client: SessionClient,
+    ) -> Result<(String, String, UserResponse)> {
+        let user_id = self
+            .webauthn_service
+            .finish_authentication(ceremony_id, credential, None)
+            .await?;
+
+        let user = self.get_current_user(&user_id).await?;
+        let (access_token, refresh_token) = self.start_session(&user.user_id, &client).await?;
+
+        Ok((access_token, refresh_token, user))
+    }
+
+    /// 二要素認証待ちトークンを検証（使用済みのトークンは拒否）
+    async fn verify_mfa_pending_token(&self, mfa_token: &str) -> Result<JwtClaim> {
+        let claims = decode_mfa_pending_token(mfa_token, &self.jwt_keys)?;
+
+        if self.revocation_cache.is_jwt_revoked(claims.jti()).await? {
+            return Err(AppError::AuthenticationError(
+                "MFA token has already been used".to_string(),
+            ));
+        }
+
+        Ok(claims)
+    }
+
+    /// 二要素認証の完了後、二要素認証待ちトークンを使用済みにしてトークンを発行
+    async fn finish_mfa_login(
+        &self,
+        claims: &JwtClaim,
+        client: &SessionClient,
+    ) -> Result<(String, String, UserResponse)> {
+        // 二要素認証待ちトークンは一度だけ使用できる
+        if !self
+            .auth_repo
+            .consume_jwt(claims.jti(), claims.expires_at())
+            .await?
+        {
+            return Err(AppError::AuthenticationError(
+                "MFA token has already been used".to_string(),
+            ));
+        }
+
+        let user = self.get_current_user(claims.sub()).await?;
+        let (access_token, refresh_token) = self.start_session(&user.user_id, client).await?;
 
         Ok((access_token, refresh_token, user))
     }
 
     /// ログアウト処理
-    pub async fn logout(&self, jtis: Vec<String>) -> Result<()> {
+    /// 引数: (JTI, トークンの有効期限) のリスト
+    pub async fn logout(&self, tokens: Vec<(String, DateTime<Utc>)>) -> Result<()> {
+        // リフレッシュトークンのファミリー（セッション）ごと失効させる
+        for (jti, _) in &tokens {
+            let revoked = self
+                .auth_repo
+                .revoke_refresh_token_family_by_jti(jti, "logout")
+                .await?;
+            self.revocation_cache.revoke_sessions(&revoked);
+        }
+
         // トークンを無効化
-        if !jtis.is_empty() {
-            self.auth_repo.logout(jtis).await?;
+        if !tokens.is_empty() {
+            self.auth_repo.logout(tokens.clone()).await?;
+            for (jti, expires_at) in &tokens {
+                self.revocation_cache.revoke_jti(jti, *expires_at);
+            }
         }
 
         Ok(())
@@ -101,7 +660,7 @@ impl AuthService {
     async fn send_verification_code_internal(
         &self,
         email: &str,
-        purpose: VerificationPurpose,
+        purpose: EmailCodePurpose,
         client_ip: Option<&str>,
         check_user_exists: bool, // true: ユーザーが存在すべき, false: ユーザーが存在しないべき
     ) -> Result<()> {
@@ -137,17 +696,17 @@ impl AuthService {
         self.verification_store.store_verification_code(
             email.to_string(),
             verification_code.clone(),
-            purpose.clone(),
+            purpose.into(),
         );
 
         // メール送信（目的によって内容を変える）
         match purpose {
-            VerificationPurpose::Registration => {
+            EmailCodePurpose::Registration => {
                 self.email_service
                     .send_verification_code(email, &verification_code)
                     .await?
             }
-            VerificationPurpose::PasswordReset => {
+            EmailCodePurpose::PasswordReset => {
                 self.email_service
                     .send_password_reset_code(email, &verification_code)
                     .await?
@@ -164,7 +723,7 @@ impl AuthService {
 
         self.send_verification_code_internal(
             &email,
-            VerificationPurpose::Registration,
+            EmailCodePurpose::Registration,
             client_ip,
             false, // ユーザーが存在しないべき
         )
@@ -190,7 +749,7 @@ impl AuthService {
         }
 
         // 登録用JWTトークン発行（15分間有効）
-        let registration_token = issue_registration_token(&email, &self.jwt_secret)?;
+        let registration_token = issue_registration_token(&email, &self.jwt_keys)?;
 
         // インメモリストアに保存（使用済みチェック用）
         self.verification_store
@@ -204,6 +763,7 @@ impl AuthService {
         &self,
         registration_token: String,
         user: UserCreateRequest,
+        client: SessionClient,
     ) -> Result<(String, String, UserResponse)> {
         // 入力バリデーション
         validate_email_format(&user.email)?;
@@ -214,8 +774,7 @@ impl AuthService {
         }
 
         // JWTトークンを検証（有効期限、署名、メールアドレスを確認）
-        let key = create_decoding_key(&self.jwt_secret);
-        validate_registration_token(&registration_token, &user.email, &key)?;
+        validate_registration_token(&registration_token, &user.email, &self.jwt_keys)?;
 
         // インメモリストアで使用済みチェック
         self.verification_store
@@ -245,15 +804,8 @@ impl AuthService {
             .invalidate_registration_token(&registration_token);
 
         // 認証トークン発行
-        let roles = vec![
-            Role::EditMemo,
-            Role::ViewMemo,
-            Role::SummarizeMemo,
-            Role::EditTag,
-            Role::EditAccount,
-        ];
-        let access_token = issue_access_token(&user_response.user_id, roles, &self.jwt_secret)?;
-        let refresh_token = issue_refresh_token(&user_response.user_id, &self.jwt_secret)?;
+        let (access_token, refresh_token) =
+            self.start_session(&user_response.user_id, &client).await?;
 
         Ok((access_token, refresh_token, user_response))
     }
@@ -282,21 +834,39 @@ impl AuthService {
         let user_response = self.auth_repo.register(user).await?;
 
         // トークン発行
-        let roles = vec![
-            Role::EditMemo,
-            Role::ViewMemo,
-            Role::SummarizeMemo,
-            Role::EditTag,
-            Role::EditAccount,
-        ];
-        let access_token = issue_access_token(&user_response.user_id, roles, &self.jwt_secret)?;
-        let refresh_token = issue_refresh_token(&user_response.user_id, &self.jwt_secret)?;
+        let client = SessionClient {
+            user_agent: None,
+            ip_address: None,
+        };
+        let (access_token, refresh_token) =
+            self.start_session(&user_response.user_id, &client).await?;
 
         Ok((access_token, refresh_token, user_response))
     }
 
     /// トークンリフレッシュ
-    pub async fn refresh_access_token(&self, user_id: &str) -> Result<String> {
+    /// 提示されたリフレッシュトークンを失効させ、新しいアクセストークンとリフレッシュトークンを発行する
+    /// 戻り値: (アクセストークン, リフレッシュトークン)
+    pub async fn refresh_tokens(
+        &self,
+        refresh_token: &str,
+        client: SessionClient,
+    ) -> Result<(String, String)> {
+        let claims = decode_refresh_token(refresh_token, &self.jwt_keys)?;
+        let user_id = claims.sub();
+
+        // ログアウト済みのトークンか確認
+        if self.is_token_revoked(claims.jti()).await? {
+            return Err(AppError::AuthenticationError(
+                "Token has been revoked".to_string(),
+            ));
+        }
+
+        // ファミリーに属さないトークン（ローテーション導入前に発行されたもの）は再ログインが必要
+        let family_id = claims.sid().ok_or_else(|| {
+            AppError::AuthenticationError("Refresh token is not recognized".to_string())
+        })?;
+
         // ユーザー情報取得
         let user = self
             .auth_repo
@@ -311,22 +881,52 @@ impl AuthService {
             ));
         }
 
-        // 新しいアクセストークン発行
-        let roles = vec![
-            Role::EditMemo,
-            Role::ViewMemo,
-            Role::SummarizeMemo,
-            Role::EditTag,
-            Role::EditAccount,
-        ];
-        let access_token = issue_access_token(user_id, roles, &self.jwt_secret)?;
+        let new_refresh = issue_refresh_token(user_id, family_id, &self.jwt_keys)?;
+        match self
+            .auth_repo
+            .rotate_refresh_token(claims.jti(), family_id, user_id, &new_refresh)
+            .await?
+        {
+            RefreshRotation::Rotated => {
+                self.auth_repo
+                    .touch_session(family_id, &new_refresh.jti, &client)
+                    .await?;
+            }
+            RefreshRotation::ReuseDetected(revoked) => {
+                self.revocation_cache.revoke_sessions([&revoked]);
+                eprintln!(
+                    "Refresh token reuse detected (possible token theft): user={}, family={}, jti={}. Revoked the token family.",
+                    user_id,
+                    family_id,
+                    claims.jti()
+                );
+                return Err(AppError::AuthenticationError(
+                    "Refresh token has already been used. Please log in again.".to_string(),
+                ));
+            }
+            RefreshRotation::Rejected => {
+                return Err(AppError::AuthenticationError(
+                    "Refresh token has been revoked".to_string(),
+                ));
+            }
+        }
 
-        Ok(access_token)
+        let access_token =
+            issue_access_token(user_id, Some(family_id), SESSION_ROLES.to_vec(), &self.jwt_keys)?;
+
+        Ok((access_token, new_refresh.token))
+    }
+
+    /// アクセストークンが失効されているか確認（ログアウト・セッションの失効を含む）
+    pub async fn is_access_token_revoked(&self, claims: &JwtClaim) -> Result<bool> {
+        self.revocation_cache
+            .is_access_token_revoked(claims.jti(), claims.sid())
+            .await
     }
 
     /// JTI がrevoke されているか確認
     pub async fn is_token_revoked(&self, jti: &str) -> Result<bool> {
-        self.auth_repo.is_jwt_revoked(jti).await
+        self.revocation_cache.is_jwt_revoked(jti).await
     }
 
     /// 現在のユーザー情報取得
@@ -346,20 +946,9 @@ impl AuthService {
         Ok(user)
     }
 
-    /// ユーザー情報更新
+    /// ユーザー情報更新（メールアドレスは request_email_change で変更する）
     pub async fn update_user(&self, user_id: &str, req: UserUpdateRequest) -> Result<UserResponse> {
         // 入力バリデーション
-        if let Some(ref email) = req.email {
-            validate_email_format(email)?;
-            // メールアドレスの重複チェック
-            if let Some(existing_user) = self.auth_repo.find_user_by_email(email).await? {
-                if existing_user.user_id != user_id {
-                    return Err(AppError::ValidationError(
-                        "Email address is already in use".to_string(),
-                    ));
-                }
-            }
-        }
         if let Some(ref name) = req.display_name {
             validate_display_name_format(name)?;
         }
@@ -367,12 +956,141 @@ impl AuthService {
             validate_password_format(password)?;
         }
 
-        self.auth_repo.update_user(user_id, req).await
+        let password_changed = req.password.is_some();
+        let user = self.auth_repo.update_user(user_id, req).await?;
+
+        // パスワード変更時はすべてのセッションを失効させる
+        if password_changed {
+            self.revoke_all_sessions(user_id, "password_changed").await?;
+        }
+
+        Ok(user)
     }
 
-    /// ユーザー削除（論理削除）
-    pub async fn delete_user(&self, user_id: &str) -> Result<()> {
-        self.auth_repo.delete_user(user_id).await
+    /// ステップ1: メールアドレスの変更を開始
+    /// 変更後のアドレスに確認コードを送り、変更前のアドレスに変更の申請を通知する
+    pub async fn request_email_change(
+        &self,
+        user_id: &str,
+        new_email: &str,
+        client_ip: Option<&str>,
+    ) -> Result<()> {
+        validate_email_format(new_email)?;
+
+        let user = self.get_current_user(user_id).await?;
+        if user.email == new_email {
+            return Err(AppError::ValidationError(
+                "New email address is the same as the current one".to_string(),
+            ));
+        }
+
+        // レート制限チェック（確認コードの送信と同じ制限）
+        self.rate_limiter
+            .check_email_limit(new_email)
+            .map_err(AppError::ValidationError)?;
+        if let Some(ip) = client_ip {
+            self.rate_limiter
+                .check_ip_limit(ip)
+                .map_err(AppError::ValidationError)?;
+        }
+
+        if self.auth_repo.find_user_by_email(new_email).await?.is_some() {
+            return Err(AppError::ValidationError(
+                "Email address is already in use".to_string(),
+            ));
+        }
+
+        // 確認コード生成（6桁の数字）
+        let verification_code = format!("{:06}", rand::random::<u32>() % 1_000_000);
+        self.verification_store.store_email_change_code(
+            user_id.to_string(),
+            new_email.to_string(),
+            verification_code.clone(),
+        );
+
+        self.email_service
+            .send_email_change_code(new_email, &verification_code)
+            .await?;
+
+        // 乗っ取りに気付けるよう、変更前のアドレスにも通知する（送信に失敗しても変更は続けられる）
+        if let Err(e) = self
+            .email_service
+            .send_email_change_notice(&user.email, new_email)
+            .await
+        {
+            eprintln!(
+                "Failed to send email change notice to user {}: {}",
+                user_id, e
+            );
+        }
+
+        Ok(())
+    }
+
+    /// ステップ2: 確認コードを検証してメールアドレスを変更し、すべてのセッションを失効させる
+    pub async fn confirm_email_change(&self, user_id: &str, code: &str) -> Result<UserResponse> {
+        let new_email = self
+            .verification_store
+            .verify_email_change_code(user_id, code)
+            .map_err(AppError::ValidationError)?
+            .ok_or_else(|| AppError::ValidationError("Verification code is invalid".to_string()))?;
+
+        // 確認コードの送信後に同じアドレスで登録・変更された場合
+        if self.auth_repo.find_user_by_email(&new_email).await?.is_some() {
+            return Err(AppError::ValidationError(
+                "Email address is already in use".to_string(),
+            ));
+        }
+
+        let user = self.auth_repo.update_email(user_id, &new_email).await?;
+        self.revoke_all_sessions(user_id, "email_changed").await?;
+
+        Ok(user)
+    }
+
+    /// ユーザー削除（猶予期間の後に AccountDeletionService が完全に削除する）
+    /// すべてのセッションを失効させる。猶予期間中はログインして取り消せる
+    /// 戻り値: 削除予定日時
+    pub async fn delete_user(&self, user_id: &str) -> Result<DateTime<Utc>> {
+        let user = self.get_current_user(user_id).await?;
+
+        let scheduled_at = Utc::now() + chrono::Duration::days(self.account_deletion_grace_days);
+        self.auth_repo.schedule_deletion(user_id, scheduled_at).await?;
+        self.revoke_all_sessions(user_id, "account_deleted").await?;
+
+        // 送信に失敗しても削除の予約は取り消さない
+        if let Err(e) = self
+            .email_service
+            .send_account_deletion_scheduled(
+                &user.email,
+                &scheduled_at.format("%Y-%m-%d %H:%M UTC").to_string(),
+            )
+            .await
+        {
+            eprintln!(
+                "Failed to send account deletion notice to user {}: {}",
+                user_id, e
+            );
+        }
+
+        Ok(scheduled_at)
+    }
+
+    /// アカウントの削除予定日時（削除を申請していない場合は None）
+    pub async fn get_account_deletion(&self, user_id: &str) -> Result<Option<DateTime<Utc>>> {
+        self.get_current_user(user_id).await?;
+        self.auth_repo.find_deletion_scheduled_at(user_id).await
+    }
+
+    /// アカウントの削除を取り消す
+    pub async fn cancel_account_deletion(&self, user_id: &str) -> Result<()> {
+        self.get_current_user(user_id).await?;
+        if !self.auth_repo.cancel_deletion(user_id).await? {
+            return Err(AppError::NotFound(
+                "Account deletion is not scheduled".to_string(),
+            ));
+        }
+        Ok(())
     }
 
     /// パスワードリセット
@@ -400,7 +1118,34 @@ impl AuthService {
         self.auth_repo.validate_password(login_req).await?;
 
         // 新しいパスワードを設定
-        self.auth_repo.reset_password(user_id, new_password).await
+        self.auth_repo.reset_password(user_id, new_password).await?;
+
+        // すべてのセッションを失効させる
+        self.revoke_all_sessions(user_id, "password_changed").await
+    }
+
+    /// パスワードで再認証し、アカウント削除を許可する短時間のアクセストークンを発行
+    pub async fn reauthenticate(
+        &self,
+        user_id: &str,
+        session_id: Option<&str>,
+        password: &str,
+    ) -> Result<String> {
+        let user = self
+            .auth_repo
+            .find_user_by_id(user_id)
+            .await?
+            .ok_or_else(|| AppError::NotFound("User not found".to_string()))?;
+
+        let login_req = UserLoginRequest {
+            email: user.email,
+            password: password.to_string(),
+        };
+        self.auth_repo.validate_password(login_req).await?;
+
+        let mut roles = SESSION_ROLES.to_vec();
+        roles.push(Role::DeleteAccount);
+        issue_step_up_access_token(user_id, session_id, roles, &self.jwt_keys)
     }
 
     /// ステップ1: パスワードリセット開始（確認コード送信）
@@ -410,7 +1155,7 @@ impl AuthService {
 
         self.send_verification_code_internal(
             email,
-            VerificationPurpose::PasswordReset,
+            EmailCodePurpose::PasswordReset,
             client_ip,
             true, // ユーザーが存在すべき
         )
@@ -436,7 +1181,7 @@ impl AuthService {
         }
 
         // リセット用JWTトークン発行（30分間有効）
-        let reset_token = issue_password_reset_token(&email, &self.jwt_secret)?;
+        let reset_token = issue_password_reset_token(&email, &self.jwt_keys)?;
 
         // インメモリストアに保存（使用済みチェック用）
         self.verification_store
@@ -456,8 +1201,7 @@ impl AuthService {
         validate_password_format(new_password)?;
 
         // JWTトークンを検証（有効期限、署名、メールアドレスを確認）
-        let key = create_decoding_key(&self.jwt_secret);
-        validate_password_reset_token(reset_token, email, &key)?;
+        validate_password_reset_token(reset_token, email, &self.jwt_keys)?;
 
         // インメモリストアで使用済みチェック
         self.verification_store
@@ -476,6 +1220,12 @@ impl AuthService {
             .reset_password(&user.user_id, new_password)
             .await?;
 
+        // メールアドレスの確認が済んでいるため、ログインのロックも解除する
+        self.auth_repo.clear_failed_logins(&user.user_id).await?;
+
+        // すべてのセッションを失効させる
+        self.revoke_all_sessions(&user.user_id, "password_changed").await?;
+
         // リセットトークンを無効化
         self.verification_store
             .invalidate_registration_token(reset_token);
@@ -483,6 +1233,58 @@ impl AuthService {
         Ok(())
     }
 
+    /// ログイン中のセッション一覧
+    pub async fn list_sessions(
+        &self,
+        user_id: &str,
+        current_session_id: Option<&str>,
+    ) -> Result<Vec<Session>> {
+        let mut sessions = self.auth_repo.find_active_sessions(user_id).await?;
+        for session in &mut sessions {
+            session.current = current_session_id == Some(session.session_id.as_str());
+        }
+        Ok(sessions)
+    }
+
+    /// 指定したセッションをログアウトさせる
+    pub async fn revoke_session(&self, user_id: &str, session_id: &str) -> Result<()> {
+        let revoked = self
+            .auth_repo
+            .revoke_session(user_id, session_id)
+            .await?
+            .ok_or_else(|| AppError::NotFound("Session not found".to_string()))?;
+        self.revocation_cache.revoke_sessions([&revoked]);
+        Ok(())
+    }
+
+    /// 現在のセッション以外をすべてログアウトさせる
+    /// 戻り値: ログアウトさせたセッション数
+    pub async fn revoke_other_sessions(
+        &self,
+        user_id: &str,
+        current_session_id: Option<&str>,
+    ) -> Result<u64> {
+        let current_session_id = current_session_id.ok_or_else(|| {
+            AppError::ValidationError("Current session could not be determined".to_string())
+        })?;
+        let revoked = self
+            .auth_repo
+            .revoke_other_sessions(user_id, current_session_id)
+            .await?;
+        self.revocation_cache.revoke_sessions(&revoked);
+        Ok(revoked.len() as u64)
+    }
+
+    /// すべてのセッションを失効させる（パスワード・メールアドレスの変更時）
+    async fn revoke_all_sessions(&self, user_id: &str, reason: &str) -> Result<()> {
+        let revoked = self
+            .auth_repo
+            .revoke_all_refresh_token_families(user_id, reason)
+            .await?;
+        self.revocation_cache.revoke_sessions(&revoked);
+        Ok(())
+    }
+
     /// 統計情報取得（デバッグ用）
     pub fn get_verification_stats(&self) -> (usize, usize) {
         self.verification_store.stats()
@@ -518,30 +1320,127 @@ impl AuthService {
         Ok(())
     }
 
-    /// アクセストークンからユーザーIDを取得し、トークン失効をチェック
-    pub async fn extract_and_verify_user_from_access_token(
-        &self,
-        jar: &CookieJar,
-    ) -> std::result::Result<String, Response> {
-        let token = jar
-            .get("access_token")
-            .ok_or_else(|| {
-                map_error(AppError::AuthenticationError(
-                    "Authentication required".to_string(),
-                ))
-            })?;
-
-        let key = create_decoding_key(&self.jwt_secret);
-        let user_id = extract_user_id_from_token(token.value(), &key).map_err(map_error)?;
-        let jti = extract_jti_from_token(token.value(), &key).map_err(map_error)?;
-
-        // トークンが失効されていないか確認
-        if self.is_token_revoked(&jti).await.map_err(map_error)? {
-            return Err(map_error(AppError::AuthenticationError(
-                "Token has been revoked".to_string(),
-            )));
+    /// 新しいセッション（リフレッシュトークンのファミリー）を開始してトークンを発行
+    /// 戻り値: (アクセストークン, リフレッシュトークン)
+    async fn start_session(&self, user_id: &str, client: &SessionClient) -> Result<(String, String)> {
+        self.alert_if_new_client(user_id, client).await?;
+
+        let family_id = Uuid::new_v4().to_string();
+        let refresh_token = issue_refresh_token(user_id, &family_id, &self.jwt_keys)?;
+        self.auth_repo
+            .create_refresh_token_family(&family_id, user_id, &refresh_token)
+            .await?;
+        self.auth_repo
+            .create_session(&family_id, user_id, &refresh_token.jti, client)
+            .await?;
+
+        let access_token =
+            issue_access_token(user_id, Some(&family_id), SESSION_ROLES.to_vec(), &self.jwt_keys)?;
+        Ok((access_token, refresh_token.token))
+    }
+
+    /// 過去のセッションと異なる端末（IPアドレス・User-Agent）からのログインをメールで通知
+    async fn alert_if_new_client(&self, user_id: &str, client: &SessionClient) -> Result<()> {
+        let known = self.auth_repo.find_known_client(user_id, client).await?;
+        // 初回のログインは比較する端末がない。端末情報を取得できなかった項目は比較しない
+        let new_ip_address = client.ip_address.is_some() && !known.known_ip_address;
+        let new_user_agent = client.user_agent.is_some() && !known.known_user_agent;
+        if !known.has_sessions || !(new_ip_address || new_user_agent) {
+            return Ok(());
         }
 
-        Ok(user_id)
+        let Some(user) = self.auth_repo.find_user_by_id(user_id).await? else {
+            return Ok(());
+        };
+        println!(
+            "Login from a new device: user={}, new_ip={}, new_user_agent={}",
+            user_id, new_ip_address, new_user_agent
+        );
+
+        // ログインを遅らせないよう、バックグラウンドで送信
+        let email_service = self.email_service.clone();
+        let logged_in_at = Utc::now().format("%Y-%m-%d %H:%M UTC").to_string();
+        let ip_address = client.ip_address.clone().unwrap_or_else(|| "不明".to_string());
+        let user_agent = client.user_agent.clone().unwrap_or_else(|| "不明".to_string());
+        tokio::spawn(async move {
+            if let Err(e) = email_service
+                .send_new_login_alert(&user.email, &logged_in_at, &ip_address, &user_agent)
+                .await
+            {
+                eprintln!(
+                    "Failed to send new login alert to user {}: {}",
+                    user.user_id, e
+                );
+            }
+        });
+
+        Ok(())
+    }
+}
+
+/// 連続失敗回数に応じたロックの期間（ロックしない場合は None）
+/// LOCKOUT_THRESHOLD 回ごとにロックし、期間はロックのたびに倍にする
+fn lockout_duration(failed_login_count: i32) -> Option<chrono::Duration> {
+    if failed_login_count < LOCKOUT_THRESHOLD || failed_login_count % LOCKOUT_THRESHOLD != 0 {
+        return None;
+    }
+    let previous_lockouts = (failed_login_count / LOCKOUT_THRESHOLD - 1).min(30) as u32;
+    let minutes = LOCKOUT_BASE_MINUTES
+        .saturating_mul(1 << previous_lockouts)
+        .min(LOCKOUT_MAX_MINUTES);
+    Some(chrono::Duration::minutes(minutes))
+}
+
+/// ロック中のログインに返すエラー
+fn account_locked_error(locked_until: DateTime<Utc>) -> AppError {
+    AppError::Locked(format!(
+        "Account is locked due to repeated failed login attempts. Try again after {} or use the unlock link sent by email.",
+        locked_until.to_rfc3339()
+    ))
+}
+
+/// 外部IDから作成するユーザーの表示名（IDプロバイダーの名前、なければメールアドレスのローカル部）
+fn external_display_name(name: Option<&str>, email: &str) -> String {
+    let name = name
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .unwrap_or_else(|| email.split('@').next().unwrap_or(email));
+
+    let mut display_name = String::new();
+    for c in name.chars() {
+        if display_name.len() + c.len_utf8() > DISPLAY_NAME_MAX_BYTES {
+            break;
+        }
+        display_name.push(c);
+    }
+    if display_name.is_empty() {
+        "User".to_string()
+    } else {
+        display_name
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn identity(email: Option<&str>, email_verified: bool) -> VerifiedIdentity {
+        VerifiedIdentity {
+            provider: "stand-in".to_string(),
+            subject: "subject-1".to_string(),
+            email: email.map(str::to_string),
+            email_verified,
+            name: None,
+        }
+    }
+
+    #[test]
+    fn unverified_email_is_not_used_for_linking() {
+        assert_eq!(verified_email(&identity(Some("user@example.com"), false)), None);
+        assert_eq!(verified_email(&identity(None, true)), None);
+        assert_eq!(
+            verified_email(&identity(Some("user@example.com"), true)),
+            Some("user@example.com")
+        );
     }
 }
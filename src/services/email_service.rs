@@ -120,6 +120,188 @@ Mimo Server
         self.send_email(to_email, subject, &body).await
     }
 
+    /// メールアドレス変更の確認コードメールを送信（変更後のアドレスへ）
+    pub async fn send_email_change_code(&self, to_email: &str, code: &str) -> Result<()> {
+        let subject = "【Mimo】メールアドレス変更の確認コード";
+        let body = format!(
+            r#"
+メールアドレスの変更のリクエストを受け付けました。
+
+以下の確認コードを入力すると、このメールアドレスへの変更が完了します。
+
+確認コード: {}
+
+このコードは15分間有効です。
+もしこのメールに心当たりがない場合は、無視してください。
+
+---
+Mimo Server
+"#,
+            code
+        );
+
+        self.send_email(to_email, subject, &body).await
+    }
+
+    /// メールアドレス変更の申請を通知（変更前のアドレスへ）
+    pub async fn send_email_change_notice(&self, to_email: &str, new_email: &str) -> Result<()> {
+        let subject = "【Mimo】メールアドレス変更の申請";
+        let body = format!(
+            r#"
+アカウントのメールアドレスを次のアドレスに変更する申請がありました。
+
+変更後のメールアドレス: {}
+
+変更後のアドレスで確認コードが入力されると変更が完了し、すべての端末からログアウトされます。
+ご自身の操作であれば、このメールは無視してください。
+心当たりがない場合は、アカウント設定からセッションを失効させ、パスワードを変更してください。
+
+---
+Mimo Server
+"#,
+            new_email
+        );
+
+        self.send_email(to_email, subject, &body).await
+    }
+
+    /// ログインリンクメールを送信
+    pub async fn send_magic_link(&self, to_email: &str, link: &str) -> Result<()> {
+        let subject = "【Mimo】ログインリンク";
+        let body = format!(
+            r#"
+ログインリンクのリクエストを受け付けました。
+
+以下のリンクを開くと、パスワードを入力せずにログインできます。
+
+{}
+
+このリンクは15分間有効で、一度だけ使用できます。
+もしこのメールに心当たりがない場合は、無視してください。
+ログインリンクを使わない場合は、アカウント設定で無効にできます。
+
+---
+Mimo Server
+"#,
+            link
+        );
+
+        self.send_email(to_email, subject, &body).await
+    }
+
+    /// アカウントのロックとロック解除リンクのメールを送信
+    pub async fn send_account_locked(
+        &self,
+        to_email: &str,
+        locked_until: &str,
+        link: &str,
+    ) -> Result<()> {
+        let subject = "【Mimo】アカウントがロックされました";
+        let body = format!(
+            r#"
+パスワードによるログインに繰り返し失敗したため、アカウントをロックしました。
+{} まではパスワードでログインできません。
+
+ご自身の操作であれば、以下のリンクを開くとすぐにロックを解除できます。
+
+{}
+
+このリンクは1時間有効で、一度だけ使用できます。
+もしこのメールに心当たりがない場合は、第三者がログインを試みている可能性があります。
+ロックを解除せず、パスワードを変更することをおすすめします。
+
+---
+Mimo Server
+"#,
+            locked_until, link
+        );
+
+        self.send_email(to_email, subject, &body).await
+    }
+
+    /// 新しい端末からのログインを通知するメールを送信
+    pub async fn send_new_login_alert(
+        &self,
+        to_email: &str,
+        logged_in_at: &str,
+        ip_address: &str,
+        user_agent: &str,
+    ) -> Result<()> {
+        let subject = "【Mimo】新しい端末からのログイン";
+        let body = format!(
+            r#"
+これまでと異なる端末または場所から、アカウントへのログインがありました。
+
+日時: {}
+IPアドレス: {}
+端末: {}
+
+ご自身の操作であれば、このメールは無視してください。
+心当たりがない場合は、アカウント設定からセッションを失効させ、パスワードを変更してください。
+
+---
+Mimo Server
+"#,
+            logged_in_at, ip_address, user_agent
+        );
+
+        self.send_email(to_email, subject, &body).await
+    }
+
+    /// アカウント削除の予約を通知するメールを送信
+    pub async fn send_account_deletion_scheduled(
+        &self,
+        to_email: &str,
+        scheduled_at: &str,
+    ) -> Result<()> {
+        let subject = "【Mimo】アカウント削除のお知らせ";
+        let body = format!(
+            r#"
+アカウントの削除を受け付け、すべての端末からログアウトしました。
+
+{} に、メモ・要約・タグを含むすべてのデータを完全に削除します。
+削除後はデータを復元できません。
+
+それまでにログインしてアカウント設定から取り消すと、これまでどおり利用できます。
+もしこのメールに心当たりがない場合は、すぐにログインして削除を取り消し、パスワードを変更してください。
+
+---
+Mimo Server
+"#,
+            scheduled_at
+        );
+
+        self.send_email(to_email, subject, &body).await
+    }
+
+    /// 個人データのエクスポートの完了を通知するメールを送信
+    pub async fn send_data_export_ready(
+        &self,
+        to_email: &str,
+        expires_at: &str,
+        link: &str,
+    ) -> Result<()> {
+        let subject = "【Mimo】データのエクスポートが完了しました";
+        let body = format!(
+            r#"
+ご依頼いただいたデータのエクスポートが完了しました。
+
+以下のリンクを開き、ログインしてアーカイブをダウンロードしてください。
+
+{}
+
+ダウンロードできるのは {} までです。
+もしこのメールに心当たりがない場合は、パスワードを変更することをおすすめします。
+
+---
+Mimo Server
+"#,
+            link, expires_at
+        );
+
+        self.send_email(to_email, subject, &body).await
+    }
+
     /// メール送信（内部メソッド）
     async fn send_email(&self, to_email: &str, subject: &str, body: &str) -> Result<()> {
         let email = Message::builder()
@@ -0,0 +1,298 @@
+use crate::{
+    error::{AppError, Result},
+    repositories::{
+        AISummary, AuthRepository, DataExport, DataExportRepository, Memo, MemoHandler,
+        MemoRepository, SummaryRepository, Tag, TagRepository,
+        auth::UserResponse,
+        export::DataExportHandler,
+        summary::SummaryHandler,
+        tag::TagHandler,
+    },
+    services::EmailService,
+};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use serde_json::{Value, json};
+use std::collections::{BTreeSet, HashMap};
+use std::io::{Cursor, Write};
+use std::sync::Arc;
+use zip::{CompressionMethod, ZipWriter, write::SimpleFileOptions};
+
+/// 作成中のまま、この時間（分）を過ぎたエクスポートは中断されたとみなす
+const STALE_EXPORT_MINUTES: i64 = 60;
+
+/// 個人データのエクスポート（Takeout）
+///
+/// プロフィール・タグ・メモ・要約をJSONと、メモ・要約ごとのMarkdownにまとめたZIPを作成する。
+/// 作成はバックグラウンドで行い、完了したらメールで通知する。
+pub struct ExportService {
+    export_repo: Arc<DataExportRepository>,
+    auth_repo: Arc<AuthRepository>,
+    tag_repo: Arc<TagRepository>,
+    memo_repo: Arc<MemoRepository>,
+    summary_repo: Arc<SummaryRepository>,
+    email_service: Arc<EmailService>,
+    /// 完了メールのリンク先（フロントエンドの画面）
+    download_url: String,
+    /// アーカイブをダウンロードできる日数
+    retention_days: i64,
+}
+
+/// アーカイブに含めるデータ
+struct ExportData {
+    profile: UserResponse,
+    tags: Vec<Tag>,
+    memos: Vec<Memo>,
+    summaries: Vec<AISummary>,
+}
+
+impl ExportService {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        export_repo: Arc<DataExportRepository>,
+        auth_repo: Arc<AuthRepository>,
+        tag_repo: Arc<TagRepository>,
+        memo_repo: Arc<MemoRepository>,
+        summary_repo: Arc<SummaryRepository>,
+        email_service: Arc<EmailService>,
+        download_url: String,
+        retention_days: i64,
+    ) -> Self {
+        Self {
+            export_repo,
+            auth_repo,
+            tag_repo,
+            memo_repo,
+            summary_repo,
+            email_service,
+            download_url,
+            retention_days,
+        }
+    }
+
+    /// エクスポートを開始（アーカイブはバックグラウンドで作成する）
+    pub async fn start_export(self: Arc<Self>, user_id: &str) -> Result<DataExport> {
+        let export = self
+            .export_repo
+            .create(user_id)
+            .await?
+            .ok_or_else(|| {
+                AppError::ValidationError("An export is already in progress".to_string())
+            })?;
+
+        let service = self.clone();
+        let export_id = export.export_id.clone();
+        let user_id = user_id.to_string();
+        tokio::spawn(async move { service.run_export(&export_id, &user_id).await });
+
+        Ok(export)
+    }
+
+    /// エクスポートの状態
+    pub async fn get_export(&self, user_id: &str, export_id: &str) -> Result<DataExport> {
+        self.export_repo
+            .find_by_id(user_id, export_id)
+            .await?
+            .ok_or_else(|| AppError::NotFound("Export not found".to_string()))
+    }
+
+    /// 作成したアーカイブ（ZIP）
+    pub async fn download_archive(&self, user_id: &str, export_id: &str) -> Result<Vec<u8>> {
+        self.export_repo
+            .find_archive(user_id, export_id, Utc::now())
+            .await?
+            .ok_or_else(|| AppError::NotFound("Export archive is not available".to_string()))
+    }
+
+    /// 中断されたエクスポートを失敗にし、期限切れのアーカイブを削除
+    /// 戻り値: 削除した件数
+    pub async fn cleanup(&self) -> Result<u64> {
+        let now = Utc::now();
+        self.export_repo
+            .fail_stale(now - chrono::Duration::minutes(STALE_EXPORT_MINUTES))
+            .await?;
+        self.export_repo.delete_expired(now).await
+    }
+
+    async fn run_export(&self, export_id: &str, user_id: &str) {
+        let archive = match self.create_archive(user_id).await {
+            Ok(archive) => archive,
+            Err(e) => {
+                eprintln!("Failed to create export {} for user {}: {}", export_id, user_id, e);
+                if let Err(e) = self
+                    .export_repo
+                    .fail(export_id, "Failed to create the export archive")
+                    .await
+                {
+                    eprintln!("Failed to mark export {} as failed: {}", export_id, e);
+                }
+                return;
+            }
+        };
+
+        let expires_at = Utc::now() + chrono::Duration::days(self.retention_days);
+        if let Err(e) = self.export_repo.complete(export_id, archive, expires_at).await {
+            eprintln!("Failed to save export {}: {}", export_id, e);
+            return;
+        }
+
+        if let Err(e) = self.notify_ready(export_id, user_id, expires_at).await {
+            eprintln!(
+                "Failed to send export ready notice to user {}: {}",
+                user_id, e
+            );
+        }
+    }
+
+    async fn create_archive(&self, user_id: &str) -> Result<Vec<u8>> {
+        let profile = self
+            .auth_repo
+            .find_user_by_id(user_id)
+            .await?
+            .ok_or_else(|| AppError::NotFound("User not found".to_string()))?;
+        let tags = self.tag_repo.find_by_user_id(user_id).await?;
+        // ゴミ箱内のものも含める
+        let mut memos = self.memo_repo.find_by_user_id(user_id).await?;
+        memos.extend(self.memo_repo.find_trashed_by_user_id(user_id).await?);
+        let mut summaries = self.summary_repo.find_by_user_id(user_id).await?;
+        summaries.extend(self.summary_repo.find_trashed_by_user_id(user_id).await?);
+
+        let data = ExportData {
+            profile,
+            tags,
+            memos,
+            summaries,
+        };
+
+        // ZIPの圧縮はCPUを使うため別スレッドで実行
+        tokio::task::spawn_blocking(move || build_archive(&data))
+            .await
+            .map_err(|e| AppError::ExternalServiceError(format!("Export task failed: {}", e)))?
+            .map_err(|e| AppError::ExternalServiceError(format!("Failed to write archive: {}", e)))
+    }
+
+    async fn notify_ready(
+        &self,
+        export_id: &str,
+        user_id: &str,
+        expires_at: DateTime<Utc>,
+    ) -> Result<()> {
+        let user = self
+            .auth_repo
+            .find_user_by_id(user_id)
+            .await?
+            .ok_or_else(|| AppError::NotFound("User not found".to_string()))?;
+
+        let link = reqwest::Url::parse_with_params(&self.download_url, &[("export_id", export_id)])
+            .map_err(|e| AppError::ConfigError(format!("Invalid DATA_EXPORT_URL: {}", e)))?;
+
+        self.email_service
+            .send_data_export_ready(
+                &user.email,
+                &expires_at.format("%Y-%m-%d %H:%M UTC").to_string(),
+                link.as_str(),
+            )
+            .await
+    }
+}
+
+/// ZIPアーカイブを作成
+///
+/// - `profile.json` / `tags.json` / `memos.json` / `summaries.json`: 保存しているデータそのもの
+/// - `memos/<memo_id>.md` / `summaries/<summary_id>.md`: 本文と、タグなどのフロントマター
+fn build_archive(data: &ExportData) -> zip::result::ZipResult<Vec<u8>> {
+    let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+    let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+
+    write_json(&mut zip, options, "profile.json", &data.profile)?;
+    write_json(&mut zip, options, "tags.json", &data.tags)?;
+    write_json(&mut zip, options, "memos.json", &data.memos)?;
+    write_json(&mut zip, options, "summaries.json", &data.summaries)?;
+
+    let tag_names: HashMap<&str, &str> = data
+        .tags
+        .iter()
+        .map(|tag| (tag.tag_id.as_str(), tag.name.as_str()))
+        .collect();
+    let memo_tags: HashMap<&str, BTreeSet<&str>> = data
+        .memos
+        .iter()
+        .map(|memo| {
+            let names = memo
+                .auto_tag_id
+                .iter()
+                .chain(memo.manual_tag_id.iter())
+                .flatten()
+                .filter_map(|tag_id| tag_names.get(tag_id.as_str()).copied())
+                .collect();
+            (memo.memo_id.as_str(), names)
+        })
+        .collect();
+
+    for memo in &data.memos {
+        let mut fields = vec![
+            ("memo_id", json!(memo.memo_id)),
+            ("created_at", json!(memo.created_at)),
+            ("updated_at", json!(memo.updated_at)),
+            ("tags", json!(memo_tags[memo.memo_id.as_str()])),
+        ];
+        if let Some(deleted_at) = memo.deleted_at {
+            fields.push(("deleted_at", json!(deleted_at)));
+        }
+        zip.start_file(format!("memos/{}.md", memo.memo_id), options)?;
+        zip.write_all(markdown(&fields, &memo.content).as_bytes())?;
+    }
+
+    for summary in &data.summaries {
+        // 要約のタグは要約元メモのタグをまとめたもの
+        let tags: BTreeSet<&str> = summary
+            .memo_ids
+            .iter()
+            .filter_map(|memo_id| memo_tags.get(memo_id.as_str()))
+            .flatten()
+            .copied()
+            .collect();
+        let mut fields = vec![
+            ("summary_id", json!(summary.summary_id)),
+            ("created_at", json!(summary.created_at)),
+            ("updated_at", json!(summary.updated_at)),
+            ("is_auto_generated", json!(summary.is_auto_generated)),
+            ("memo_ids", json!(summary.memo_ids)),
+            ("tags", json!(tags)),
+        ];
+        if let Some(deleted_at) = summary.deleted_at {
+            fields.push(("deleted_at", json!(deleted_at)));
+        }
+        zip.start_file(format!("summaries/{}.md", summary.summary_id), options)?;
+        zip.write_all(markdown(&fields, &summary.content).as_bytes())?;
+    }
+
+    Ok(zip.finish()?.into_inner())
+}
+
+fn write_json<T: Serialize>(
+    zip: &mut ZipWriter<Cursor<Vec<u8>>>,
+    options: SimpleFileOptions,
+    name: &str,
+    value: &T,
+) -> zip::result::ZipResult<()> {
+    let json = serde_json::to_vec_pretty(value).map_err(std::io::Error::other)?;
+    zip.start_file(name, options)?;
+    zip.write_all(&json)?;
+    Ok(())
+}
+
+/// フロントマター付きのMarkdown
+/// 値はJSONで書き出す（JSONはYAMLとしても読めるため、エスケープを考えなくてよい）
+fn markdown(fields: &[(&str, Value)], content: &str) -> String {
+    let mut text = String::from("---\n");
+    for (key, value) in fields {
+        text.push_str(&format!("{}: {}\n", key, value));
+    }
+    text.push_str("---\n\n");
+    text.push_str(content);
+    if !content.ends_with('\n') {
+        text.push('\n');
+    }
+    text
+}
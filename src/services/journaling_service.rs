@@ -0,0 +1,248 @@
+use crate::{
+    error::{AppError, Result},
+    repositories::{
+        JournalingRepository, JournalingSchedule, MemoHandler, MemoRepository,
+        UpdateJournalingScheduleRequest,
+        journaling::{JournalingFrequency, JournalingHandler, JournalingRunStatus},
+    },
+    services::SummaryService,
+};
+use chrono::{DateTime, Duration, NaiveTime, Utc};
+use std::sync::Arc;
+
+/// 1回の実行で処理する設定の最大数（残りは次回の実行で処理する）
+const DUE_SCHEDULES_PER_RUN: i64 = 100;
+/// 1つの期間の要約を作成する最大回数
+const MAX_RUN_ATTEMPTS: i32 = 3;
+/// 失敗した期間を再試行するまでの時間（分）
+const RETRY_DELAY_MINUTES: i64 = 10;
+/// 作成中のまま、この時間（分）を過ぎた実行は中断されたとみなす
+const STALE_RUN_MINUTES: i64 = 30;
+
+/// 自動ジャーナリング（設定した頻度での自動要約）
+///
+/// 作成日時ごとに、前回の作成日時から今回の作成日時までに作成されたメモを要約する。
+/// 期間ごとの実行を PostgreSQL に記録し、同じ期間の要約を重複して作らない。
+pub struct JournalingService {
+    journaling_repo: Arc<JournalingRepository>,
+    memo_repo: Arc<MemoRepository>,
+    summary_service: Arc<SummaryService>,
+}
+
+impl JournalingService {
+    pub fn new(
+        journaling_repo: Arc<JournalingRepository>,
+        memo_repo: Arc<MemoRepository>,
+        summary_service: Arc<SummaryService>,
+    ) -> Self {
+        Self {
+            journaling_repo,
+            memo_repo,
+            summary_service,
+        }
+    }
+
+    /// 設定（設定がない場合は off）
+    pub async fn get_schedule(&self, user_id: &str) -> Result<JournalingSchedule> {
+        Ok(self
+            .journaling_repo
+            .find_schedule(user_id)
+            .await?
+            .unwrap_or_else(|| JournalingSchedule::off(user_id)))
+    }
+
+    /// 設定を変更（指定しなかった項目は現在の設定のまま）
+    pub async fn update_schedule(
+        &self,
+        user_id: &str,
+        req: UpdateJournalingScheduleRequest,
+    ) -> Result<JournalingSchedule> {
+        let mut schedule = self.get_schedule(user_id).await?;
+        if let Some(frequency) = req.frequency {
+            schedule.frequency = frequency;
+        }
+        if let Some(local_time) = req.local_time {
+            schedule.local_time = local_time.parse::<NaiveTime>().map_err(|_| {
+                AppError::ValidationError("local_time must be in HH:MM format".to_string())
+            })?;
+        }
+        if let Some(timezone) = req.timezone {
+            if timezone.parse::<chrono_tz::Tz>().is_err() {
+                return Err(AppError::ValidationError(format!(
+                    "Unknown timezone: {}",
+                    timezone
+                )));
+            }
+            schedule.timezone = timezone;
+        }
+        if let Some(weekday) = req.weekday {
+            if !(0..=6).contains(&weekday) {
+                return Err(AppError::ValidationError(
+                    "weekday must be between 0 (Sunday) and 6 (Saturday)".to_string(),
+                ));
+            }
+            schedule.weekday = weekday;
+        }
+        if let Some(day_of_month) = req.day_of_month {
+            if !(1..=28).contains(&day_of_month) {
+                return Err(AppError::ValidationError(
+                    "day_of_month must be between 1 and 28".to_string(),
+                ));
+            }
+            schedule.day_of_month = day_of_month;
+        }
+
+        if schedule.frequency == JournalingFrequency::Off {
+            self.journaling_repo.delete_schedule(user_id).await?;
+            schedule.next_run_at = None;
+            return Ok(schedule);
+        }
+
+        // 変更後の最初の作成日時からやり直す
+        schedule.next_run_at = Some(schedule.next_run_after(Utc::now())?);
+        self.journaling_repo.upsert_schedule(&schedule).await
+    }
+
+    /// 作成日時を過ぎた設定の要約を作成
+    /// 戻り値: 作成した要約の数（失敗した期間は次回以降の実行で再試行する）
+    pub async fn run_due(&self, now: DateTime<Utc>) -> Result<usize> {
+        let schedules = self
+            .journaling_repo
+            .find_due_schedules(now, DUE_SCHEDULES_PER_RUN)
+            .await?;
+
+        let mut created = 0;
+        for schedule in &schedules {
+            match self.run_schedule(schedule, now).await {
+                Ok(true) => created += 1,
+                Ok(false) => {}
+                Err(e) => eprintln!(
+                    "Failed to run journaling for user {}: {}",
+                    schedule.user_id, e
+                ),
+            }
+        }
+        Ok(created)
+    }
+
+    /// 1期間分の要約を作成
+    /// 戻り値: 要約を作成したかどうか
+    async fn run_schedule(&self, schedule: &JournalingSchedule, now: DateTime<Utc>) -> Result<bool> {
+        let user_id = schedule.user_id.as_str();
+        let Some(period_end) = schedule.next_run_at else {
+            return Ok(false);
+        };
+        let period_start = schedule.previous_run_before(period_end)?;
+
+        let claimed = self
+            .journaling_repo
+            .claim_run(
+                user_id,
+                period_start,
+                period_end,
+                MAX_RUN_ATTEMPTS,
+                now - Duration::minutes(RETRY_DELAY_MINUTES),
+            )
+            .await?;
+
+        let Some(attempts) = claimed else {
+            // 実行中・実行済み・再試行待ちの期間
+            let Some(run) = self.journaling_repo.find_run(user_id, period_start).await? else {
+                return Ok(false);
+            };
+            match run.status {
+                JournalingRunStatus::Running
+                    if run.started_at >= now - Duration::minutes(STALE_RUN_MINUTES) =>
+                {
+                    return Ok(false);
+                }
+                JournalingRunStatus::Running => {
+                    // 作成中にインスタンスが停止した場合、要約が保存済みの可能性があるため再試行しない
+                    self.journaling_repo
+                        .finish_run(
+                            user_id,
+                            period_start,
+                            JournalingRunStatus::Failed,
+                            None,
+                            Some("Journaling was interrupted"),
+                        )
+                        .await?;
+                }
+                JournalingRunStatus::Failed if run.attempts < MAX_RUN_ATTEMPTS => {
+                    return Ok(false);
+                }
+                _ => {}
+            }
+            self.advance(schedule, period_end).await?;
+            return Ok(false);
+        };
+
+        let result = self.summarize_period(user_id, period_start, period_end).await;
+        let created = match result {
+            Ok(Some(summary_id)) => {
+                self.journaling_repo
+                    .finish_run(user_id, period_start, JournalingRunStatus::Completed, Some(&summary_id), None)
+                    .await?;
+                true
+            }
+            Ok(None) => {
+                self.journaling_repo
+                    .finish_run(user_id, period_start, JournalingRunStatus::Skipped, None, None)
+                    .await?;
+                false
+            }
+            Err(e) => {
+                self.journaling_repo
+                    .finish_run(user_id, period_start, JournalingRunStatus::Failed, None, Some(&e.to_string()))
+                    .await?;
+                if attempts < MAX_RUN_ATTEMPTS {
+                    // 次の作成日時に進めず、次回以降の実行で再試行する
+                    return Err(e);
+                }
+                eprintln!(
+                    "Giving up journaling for user {} ({} - {}) after {} attempts: {}",
+                    user_id, period_start, period_end, attempts, e
+                );
+                false
+            }
+        };
+
+        self.advance(schedule, period_end).await?;
+        Ok(created)
+    }
+
+    /// 期間内のメモを要約
+    /// 戻り値: 作成した要約のID（期間内にメモがない場合は None）
+    async fn summarize_period(
+        &self,
+        user_id: &str,
+        period_start: DateTime<Utc>,
+        period_end: DateTime<Utc>,
+    ) -> Result<Option<String>> {
+        let memo_ids: Vec<String> = self
+            .memo_repo
+            .find_created_between(user_id, period_start, period_end)
+            .await?
+            .into_iter()
+            .map(|memo| memo.memo_id)
+            .collect();
+        if memo_ids.is_empty() {
+            return Ok(None);
+        }
+
+        let summary = self
+            .summary_service
+            .summarize_and_save(user_id.to_string(), memo_ids, true)
+            .await?;
+        Ok(Some(summary.summary_id))
+    }
+
+    /// 次の作成日時に進める（停止中に過ぎた期間は、次回以降の実行で1期間ずつ作成する）
+    async fn advance(&self, schedule: &JournalingSchedule, run_at: DateTime<Utc>) -> Result<()> {
+        let next_run_at = schedule.next_run_after(run_at)?;
+        self.journaling_repo
+            .advance_schedule(&schedule.user_id, run_at, next_run_at)
+            .await?;
+        Ok(())
+    }
+}
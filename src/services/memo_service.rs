@@ -1,33 +1,78 @@
 use crate::{
     error::{AppError, Result},
-    repositories::{Memo, MemoCreateRequest, MemoUpdateRequest, MemoHandler, MemoRepository},
-    services::TagService,
+    repositories::{
+        Memo, MemoCreateRequest, MemoDiff, MemoHandler, MemoList, MemoListQuery, MemoRepository,
+        MemoRevisionList, MemoRevisionRepository, MemoUpdateRequest,
+        memo::MAX_PAGE_LIMIT,
+        revision::{DiffOp, DiffSegment, MemoRevision, MemoRevisionHandler},
+        share::{ShareHandler, ShareRepository},
+        summary_chunk::SummaryChunkHandler,
+    },
+    services::{SearchService, TagService},
 };
-use chrono::Utc;
+use chrono::{DateTime, Utc};
+use std::collections::BTreeSet;
 use std::sync::Arc;
 use uuid::Uuid;
 
+/// 同時に更新された場合に、メモの保存をやり直す最大回数
+const MAX_REVISION_ATTEMPTS: usize = 5;
+
 pub struct MemoService {
     memo_repo: Arc<MemoRepository>,
+    revision_repo: Arc<MemoRevisionRepository>,
+    share_repo: Arc<ShareRepository>,
+    /// メモの本文から作った途中の要約のキャッシュ（メモを完全に削除したときに消す）
+    chunk_repo: Arc<dyn SummaryChunkHandler>,
     tag_service: Arc<TagService>,
+    search_service: Arc<SearchService>,
 }
 
 impl MemoService {
-    pub fn new(memo_repo: Arc<MemoRepository>, tag_service: Arc<TagService>) -> Self {
+    pub fn new(
+        memo_repo: Arc<MemoRepository>,
+        revision_repo: Arc<MemoRevisionRepository>,
+        share_repo: Arc<ShareRepository>,
+        chunk_repo: Arc<dyn SummaryChunkHandler>,
+        tag_service: Arc<TagService>,
+        search_service: Arc<SearchService>,
+    ) -> Self {
         Self {
             memo_repo,
+            revision_repo,
+            share_repo,
+            chunk_repo,
             tag_service,
+            search_service,
         }
     }
 
-    pub async fn find_by_user(&self, user_id: &str) -> Result<Vec<Memo>> {
-        self.memo_repo.find_by_user_id(user_id).await
+    /// ユーザーのメモをページ単位で取得
+    pub async fn find_page_by_user(&self, user_id: &str, query: MemoListQuery) -> Result<MemoList> {
+        if let Some(limit) = query.limit
+            && !(1..=MAX_PAGE_LIMIT).contains(&limit)
+        {
+            return Err(AppError::ValidationError(format!(
+                "limit must be between 1 and {}",
+                MAX_PAGE_LIMIT
+            )));
+        }
+        if let (Some(from), Some(to)) = (&query.from, &query.to)
+            && from >= to
+        {
+            return Err(AppError::ValidationError(
+                "from must be earlier than to".to_string(),
+            ));
+        }
+
+        self.memo_repo.find_page_by_user_id(user_id, &query).await
     }
 
     pub async fn find_by_id(&self, memo_id: &str) -> Result<Memo> {
         self.memo_repo
             .find_by_id(memo_id)
             .await?
+            .filter(|memo| memo.deleted_at.is_none()) // ゴミ箱のメモは存在しないものとして扱う
             .ok_or_else(|| AppError::NotFound(format!("Memo {} not found", memo_id)))
     }
 
@@ -55,32 +100,317 @@ impl MemoService {
             share_url_token: None,
             created_at: now,
             updated_at: now,
+            deleted_at: None,
+            revision: 1,
         };
 
-        self.memo_repo.create(memo).await
+        let memo = self.memo_repo.create(memo).await?;
+        self.record_revision(&memo, 1, None).await?;
+        self.sync_search_index(&memo).await;
+        Ok(memo)
     }
 
     // メモの更新機能
     pub async fn update_memo(&self, memo_id: &str, req: MemoUpdateRequest) -> Result<Memo> {
-        let mut memo = self.find_by_id(memo_id).await?;
-
         validate_memo_content(&req.content)?;
 
-        memo.content = req.content;
-        memo.updated_at = Utc::now();
-        
-        if req.manual_tag_id.is_some() {
-            memo.manual_tag_id = req.manual_tag_id;
+        self.save_with_revision(memo_id, None, |memo| {
+            memo.content = req.content.clone();
+            if req.manual_tag_id.is_some() {
+                memo.manual_tag_id = req.manual_tag_id.clone();
+            }
+        })
+        .await
+    }
+
+    /// メモの版の一覧（新しい順）
+    pub async fn list_revisions(&self, memo_id: &str) -> Result<MemoRevisionList> {
+        let revisions = self.revision_repo.find_by_memo_id(memo_id).await?;
+        Ok(MemoRevisionList { revisions })
+    }
+
+    /// 2つの版の差分
+    pub async fn diff_revisions(&self, memo_id: &str, from: u32, to: u32) -> Result<MemoDiff> {
+        let from_rev = self.find_revision(memo_id, from).await?;
+        let to_rev = self.find_revision(memo_id, to).await?;
+
+        let from_tags: BTreeSet<String> =
+            from_rev.manual_tag_id.unwrap_or_default().into_iter().collect();
+        let to_tags: BTreeSet<String> =
+            to_rev.manual_tag_id.unwrap_or_default().into_iter().collect();
+
+        Ok(MemoDiff {
+            memo_id: memo_id.to_string(),
+            from,
+            to,
+            segments: diff_text(&from_rev.content, &to_rev.content),
+            added_tag_ids: to_tags.difference(&from_tags).cloned().collect(),
+            removed_tag_ids: from_tags.difference(&to_tags).cloned().collect(),
+        })
+    }
+
+    /// 過去の版を復元（復元結果は新しい版として保存）
+    pub async fn restore_revision(&self, memo_id: &str, revision: u32) -> Result<Memo> {
+        let target = self.find_revision(memo_id, revision).await?;
+
+        self.save_with_revision(memo_id, Some(revision), |memo| {
+            memo.content = target.content.clone();
+            memo.manual_tag_id = target.manual_tag_id.clone();
+        })
+        .await
+    }
+
+    async fn find_revision(&self, memo_id: &str, revision: u32) -> Result<MemoRevision> {
+        self.revision_repo
+            .find_by_revision(memo_id, revision)
+            .await?
+            .ok_or_else(|| {
+                AppError::NotFound(format!("Revision {} of memo {} not found", revision, memo_id))
+            })
+    }
+
+    /// メモを変更して保存し、保存後の内容を新しい版として記録する
+    ///
+    /// 版番号はメモと同じ書き込みで進める（読み取った時点の版番号のままの場合のみ保存する）。
+    /// 他の更新と競合した場合は、最新のメモに変更を適用し直す。
+    async fn save_with_revision(
+        &self,
+        memo_id: &str,
+        restored_from: Option<u32>,
+        edit: impl Fn(&mut Memo),
+    ) -> Result<Memo> {
+        for _ in 0..MAX_REVISION_ATTEMPTS {
+            let current = self.find_by_id(memo_id).await?;
+            let base = self.current_revision(&current).await?;
+
+            let mut memo = current.clone();
+            edit(&mut memo);
+            memo.updated_at = Utc::now();
+            memo.revision = base + 1;
+
+            if !self
+                .memo_repo
+                .update_if_revision(&memo, current.revision)
+                .await?
+            {
+                continue;
+            }
+
+            // 版番号はメモの更新で確保済みのため、重複するのは記録済みの場合のみ
+            if !self
+                .record_revision(&memo, memo.revision, restored_from)
+                .await?
+            {
+                eprintln!(
+                    "Revision {} of memo {} was already recorded",
+                    memo.revision, memo_id
+                );
+            }
+            self.sync_search_index(&memo).await;
+            return Ok(memo);
         }
 
-        self.memo_repo.update(memo).await
+        Err(AppError::DatabaseError(format!(
+            "Memo {} was updated concurrently; please try again",
+            memo_id
+        )))
     }
 
+    /// メモの現在の版番号
+    async fn current_revision(&self, memo: &Memo) -> Result<u32> {
+        if memo.revision > 0 {
+            return Ok(memo.revision);
+        }
+        if let Some(latest) = self.revision_repo.find_latest(&memo.memo_id).await? {
+            return Ok(latest.revision);
+        }
+        // 版の記録導入前に作成されたメモは、更新前の内容を最初の版として残す
+        // （同時に記録された場合は記録済みの版を使う）
+        self.record_revision(memo, 1, None).await?;
+        Ok(1)
+    }
+
+    /// 戻り値: 記録したかどうか（同じ版番号が記録済みの場合は false）
+    async fn record_revision(
+        &self,
+        memo: &Memo,
+        revision: u32,
+        restored_from: Option<u32>,
+    ) -> Result<bool> {
+        self.revision_repo
+            .try_create(&MemoRevision {
+                memo_id: memo.memo_id.clone(),
+                user_id: memo.user_id.clone(),
+                revision,
+                content: memo.content.clone(),
+                manual_tag_id: memo.manual_tag_id.clone(),
+                restored_from,
+                created_at: memo.updated_at,
+            })
+            .await
+    }
+
+    /// メモをゴミ箱に移動（論理削除）
     pub async fn delete(&self, memo_id: &str) -> Result<()> {
         // 存在確認
         self.find_by_id(memo_id).await?;
-        self.memo_repo.delete(memo_id).await
+        self.memo_repo.set_deleted_at(memo_id, Some(Utc::now())).await?;
+
+        // 検索インデックスから削除（失敗してもメモの削除は成功させる）
+        if let Err(e) = self.search_service.remove_memo(memo_id).await {
+            eprintln!("Failed to remove memo {} from search index: {}", memo_id, e);
+        }
+        Ok(())
     }
+
+    /// ゴミ箱内のメモ一覧
+    pub async fn list_trash(&self, user_id: &str) -> Result<Vec<Memo>> {
+        self.memo_repo.find_trashed_by_user_id(user_id).await
+    }
+
+    /// ゴミ箱からメモを復元
+    pub async fn restore_from_trash(&self, user_id: &str, memo_id: &str) -> Result<Memo> {
+        let mut memo = self.find_trashed(user_id, memo_id).await?;
+        self.memo_repo.set_deleted_at(memo_id, None).await?;
+        memo.deleted_at = None;
+        self.sync_search_index(&memo).await;
+        Ok(memo)
+    }
+
+    /// ゴミ箱内のメモを完全に削除
+    pub async fn purge_from_trash(&self, user_id: &str, memo_id: &str) -> Result<()> {
+        self.find_trashed(user_id, memo_id).await?;
+        self.purge(memo_id).await?;
+        self.purge_summary_cache([user_id]).await
+    }
+
+    /// ユーザーのゴミ箱を空にする
+    /// 戻り値: 削除した件数
+    pub async fn empty_trash(&self, user_id: &str) -> Result<usize> {
+        let memos = self.memo_repo.find_trashed_by_user_id(user_id).await?;
+        for memo in &memos {
+            self.purge(&memo.memo_id).await?;
+        }
+        if !memos.is_empty() {
+            self.purge_summary_cache([user_id]).await?;
+        }
+        Ok(memos.len())
+    }
+
+    /// 保持期間を過ぎたゴミ箱内のメモを完全に削除
+    /// 戻り値: 削除した件数
+    pub async fn purge_trash_before(&self, cutoff: DateTime<Utc>) -> Result<usize> {
+        let memos = self.memo_repo.find_trashed_before(cutoff).await?;
+        for memo in &memos {
+            self.purge(&memo.memo_id).await?;
+        }
+        let user_ids: BTreeSet<&str> = memos.iter().map(|memo| memo.user_id.as_str()).collect();
+        self.purge_summary_cache(user_ids).await?;
+        Ok(memos.len())
+    }
+
+    /// ユーザーのメモをゴミ箱内のものも含めてすべて完全に削除（アカウント削除用）
+    /// 戻り値: 削除した件数
+    pub async fn purge_all_by_user(&self, user_id: &str) -> Result<usize> {
+        let mut memos = self.memo_repo.find_by_user_id(user_id).await?;
+        memos.extend(self.memo_repo.find_trashed_by_user_id(user_id).await?);
+        for memo in &memos {
+            self.purge(&memo.memo_id).await?;
+        }
+        self.purge_summary_cache([user_id]).await?;
+        Ok(memos.len())
+    }
+
+    async fn find_trashed(&self, user_id: &str, memo_id: &str) -> Result<Memo> {
+        let memo = self
+            .memo_repo
+            .find_by_id(memo_id)
+            .await?
+            .filter(|memo| memo.deleted_at.is_some())
+            .ok_or_else(|| AppError::NotFound(format!("Memo {} not found in trash", memo_id)))?;
+
+        if memo.user_id != user_id {
+            return Err(AppError::Forbidden("Access denied".to_string()));
+        }
+        Ok(memo)
+    }
+
+    /// メモと版履歴・共有リンクを物理削除
+    async fn purge(&self, memo_id: &str) -> Result<()> {
+        self.memo_repo.delete(memo_id).await?;
+        self.revision_repo.delete_by_memo_id(memo_id).await?;
+        self.share_repo.delete_by_memo_id(memo_id).await?;
+        Ok(())
+    }
+
+    /// 途中の要約のキャッシュを削除し、完全に削除したメモの本文が要約に残らないようにする
+    /// キャッシュのキーはプロンプトのハッシュでメモと対応付けられないため、ユーザーのキャッシュをすべて消す
+    async fn purge_summary_cache<'a>(&self, user_ids: impl IntoIterator<Item = &'a str>) -> Result<()> {
+        for user_id in user_ids {
+            self.chunk_repo.delete_by_user_id(user_id).await?;
+        }
+        Ok(())
+    }
+
+    /// 検索インデックスを更新（失敗してもメモの保存は成功させる）
+    async fn sync_search_index(&self, memo: &Memo) {
+        if let Err(e) = self.search_service.index_memo(memo).await {
+            eprintln!(
+                "Failed to update search index for memo {}: {}",
+                memo.memo_id, e
+            );
+        }
+    }
+}
+
+/// 文字単位の差分（最長共通部分列）を連続する同種の操作ごとにまとめる
+fn diff_text(from: &str, to: &str) -> Vec<DiffSegment> {
+    let a: Vec<char> = from.chars().collect();
+    let b: Vec<char> = to.chars().collect();
+
+    // lcs[i][j] = a[i..] と b[j..] の最長共通部分列の長さ
+    let mut lcs = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            lcs[i][j] = if a[i] == b[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut segments: Vec<DiffSegment> = Vec::new();
+    let mut push = |op: DiffOp, c: char| match segments.last_mut() {
+        Some(last) if last.op == op => last.text.push(c),
+        _ => segments.push(DiffSegment {
+            op,
+            text: c.to_string(),
+        }),
+    };
+
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() && j < b.len() {
+        if a[i] == b[j] {
+            push(DiffOp::Equal, a[i]);
+            i += 1;
+            j += 1;
+        } else if lcs[i + 1][j] >= lcs[i][j + 1] {
+            push(DiffOp::Delete, a[i]);
+            i += 1;
+        } else {
+            push(DiffOp::Insert, b[j]);
+            j += 1;
+        }
+    }
+    for &c in &a[i..] {
+        push(DiffOp::Delete, c);
+    }
+    for &c in &b[j..] {
+        push(DiffOp::Insert, c);
+    }
+
+    segments
 }
 
 fn validate_memo_content(content: &str) -> Result<()> {
@@ -0,0 +1,275 @@
+use crate::{
+    error::{AppError, Result},
+    repositories::mfa::{MfaRepository, MfaStatus, TotpCredential, TotpEnrollment},
+};
+use aes_gcm::{
+    Aes256Gcm, Nonce,
+    aead::{Aead, KeyInit, Payload},
+};
+use chrono::Utc;
+use rand::Rng;
+use sha2::{Digest, Sha256};
+use std::sync::Arc;
+use totp_rs::{Algorithm, Secret, TOTP};
+
+/// RFC 6238 のパラメータ（一般的な認証アプリの既定値）
+const TOTP_DIGITS: usize = 6;
+const TOTP_STEP: u64 = 30;
+/// 前後何ステップまでのずれを許容するか
+const TOTP_SKEW: u64 = 1;
+/// シークレットの長さ（160bit）
+const TOTP_SECRET_LEN: usize = 20;
+
+const RECOVERY_CODE_COUNT: usize = 10;
+/// 紛らわしい文字（0, 1, i, l, o）を除いた英数字
+const RECOVERY_CODE_ALPHABET: &[u8] = b"abcdefghjkmnpqrstuvwxyz23456789";
+const RECOVERY_CODE_HALF_LEN: usize = 5;
+
+pub struct MfaService {
+    mfa_repo: Arc<MfaRepository>,
+    cipher: Aes256Gcm,
+    issuer: String,
+}
+
+impl MfaService {
+    /// encryption_key: TOTPシークレットの暗号化に使う鍵の元になる文字列
+    pub fn new(mfa_repo: Arc<MfaRepository>, encryption_key: &str, issuer: String) -> Self {
+        let key = Sha256::digest(format!("mimo-totp-secret:{}", encryption_key).as_bytes());
+        Self {
+            mfa_repo,
+            cipher: Aes256Gcm::new(&key),
+            issuer,
+        }
+    }
+
+    /// 二要素認証が有効か
+    pub async fn is_enabled(&self, user_id: &str) -> Result<bool> {
+        Ok(self
+            .mfa_repo
+            .find_totp(user_id)
+            .await?
+            .is_some_and(|totp| totp.enabled_at.is_some()))
+    }
+
+    /// 二要素認証の状態
+    pub async fn status(&self, user_id: &str) -> Result<MfaStatus> {
+        let enabled_at = self
+            .mfa_repo
+            .find_totp(user_id)
+            .await?
+            .and_then(|totp| totp.enabled_at);
+        let recovery_codes_remaining = match enabled_at {
+            Some(_) => self.mfa_repo.count_unused_recovery_codes(user_id).await?,
+            None => 0,
+        };
+
+        Ok(MfaStatus {
+            totp_enabled: enabled_at.is_some(),
+            enabled_at,
+            recovery_codes_remaining,
+        })
+    }
+
+    /// TOTPの登録を開始（確認コードの検証までは有効にならない）
+    pub async fn start_totp_enrollment(&self, user_id: &str, account_name: &str) -> Result<TotpEnrollment> {
+        let secret: [u8; TOTP_SECRET_LEN] = rand::random();
+        let totp = TOTP::new(
+            Algorithm::SHA1,
+            TOTP_DIGITS,
+            TOTP_SKEW as u8,
+            TOTP_STEP,
+            secret.to_vec(),
+            Some(self.issuer.clone()),
+            account_name.to_string(),
+        )
+        .map_err(|e| AppError::ConfigError(e.to_string()))?;
+
+        let (ciphertext, nonce) = self.encrypt_secret(user_id, &secret)?;
+        if !self.mfa_repo.save_pending_totp(user_id, &ciphertext, &nonce).await? {
+            return Err(AppError::ValidationError(
+                "Two-factor authentication is already enabled".to_string(),
+            ));
+        }
+
+        Ok(TotpEnrollment {
+            secret: Secret::Raw(secret.to_vec()).to_encoded().to_string(),
+            provisioning_uri: totp.get_url(),
+        })
+    }
+
+    /// 確認コードを検証してTOTPを有効化し、リカバリーコードを発行
+    pub async fn confirm_totp_enrollment(&self, user_id: &str, code: &str) -> Result<Vec<String>> {
+        let credential = self
+            .mfa_repo
+            .find_totp(user_id)
+            .await?
+            .filter(|totp| totp.enabled_at.is_none())
+            .ok_or_else(|| {
+                AppError::ValidationError("Two-factor authentication enrollment has not been started".to_string())
+            })?;
+
+        let step = self
+            .matching_step(&credential, &normalize_code(code))?
+            .ok_or_else(invalid_code)?;
+
+        let (codes, hashes) = generate_recovery_codes();
+        if !self.mfa_repo.enable_totp(user_id, step, &hashes).await? {
+            return Err(AppError::ValidationError(
+                "Two-factor authentication is already enabled".to_string(),
+            ));
+        }
+
+        Ok(codes)
+    }
+
+    /// ログイン時などにTOTPコードまたはリカバリーコードを検証
+    /// 同じTOTPコード・リカバリーコードは再利用できない
+    pub async fn verify(&self, user_id: &str, code: &str) -> Result<()> {
+        let credential = self
+            .mfa_repo
+            .find_totp(user_id)
+            .await?
+            .filter(|totp| totp.enabled_at.is_some())
+            .ok_or_else(|| {
+                AppError::ValidationError("Two-factor authentication is not enabled".to_string())
+            })?;
+
+        let code = normalize_code(code);
+        let verified = if is_totp_code(&code) {
+            match self.matching_step(&credential, &code)? {
+                Some(step) => self.mfa_repo.consume_totp_step(user_id, step).await?,
+                None => false,
+            }
+        } else {
+            self.mfa_repo
+                .consume_recovery_code(user_id, &hash_recovery_code(&code))
+                .await?
+        };
+
+        if !verified {
+            return Err(invalid_code());
+        }
+        Ok(())
+    }
+
+    /// リカバリーコードを再発行（以前のコードは使えなくなる）
+    pub async fn regenerate_recovery_codes(&self, user_id: &str, code: &str) -> Result<Vec<String>> {
+        self.verify(user_id, code).await?;
+
+        let (codes, hashes) = generate_recovery_codes();
+        self.mfa_repo
+            .regenerate_recovery_codes(user_id, &hashes)
+            .await?;
+        Ok(codes)
+    }
+
+    /// 二要素認証を無効化
+    pub async fn disable(&self, user_id: &str, code: &str) -> Result<()> {
+        self.verify(user_id, code).await?;
+        self.mfa_repo.delete_totp(user_id).await?;
+        Ok(())
+    }
+
+    /// 現在時刻の前後のステップのうち、コードが一致するステップ
+    fn matching_step(&self, credential: &TotpCredential, code: &str) -> Result<Option<i64>> {
+        if !is_totp_code(code) {
+            return Ok(None);
+        }
+
+        let secret = self.decrypt_secret(credential)?;
+        let totp = TOTP::new_unchecked(
+            Algorithm::SHA1,
+            TOTP_DIGITS,
+            TOTP_SKEW as u8,
+            TOTP_STEP,
+            secret,
+            None,
+            String::new(),
+        );
+
+        let current = Utc::now().timestamp() as u64 / TOTP_STEP;
+        let step = (current.saturating_sub(TOTP_SKEW)..=current + TOTP_SKEW).find(|step| {
+            constant_time_eq(totp.generate(step * TOTP_STEP).as_bytes(), code.as_bytes())
+        });
+        Ok(step.map(|step| step as i64))
+    }
+
+    /// シークレットを暗号化（ユーザーIDを関連データとして他のユーザーへの流用を防ぐ）
+    fn encrypt_secret(&self, user_id: &str, secret: &[u8]) -> Result<(Vec<u8>, Vec<u8>)> {
+        let nonce: [u8; 12] = rand::random();
+        let ciphertext = self
+            .cipher
+            .encrypt(
+                &Nonce::from(nonce),
+                Payload {
+                    msg: secret,
+                    aad: user_id.as_bytes(),
+                },
+            )
+            .map_err(|_| AppError::EnvironmentError("Failed to encrypt TOTP secret".to_string()))?;
+        Ok((ciphertext, nonce.to_vec()))
+    }
+
+    fn decrypt_secret(&self, credential: &TotpCredential) -> Result<Vec<u8>> {
+        let nonce: [u8; 12] = credential
+            .secret_nonce
+            .as_slice()
+            .try_into()
+            .map_err(|_| AppError::EnvironmentError("Invalid TOTP secret nonce".to_string()))?;
+        self.cipher
+            .decrypt(
+                &Nonce::from(nonce),
+                Payload {
+                    msg: &credential.secret_ciphertext,
+                    aad: credential.user_id.as_bytes(),
+                },
+            )
+            .map_err(|_| AppError::EnvironmentError("Failed to decrypt TOTP secret".to_string()))
+    }
+}
+
+fn invalid_code() -> AppError {
+    AppError::AuthenticationError("Invalid two-factor authentication code".to_string())
+}
+
+/// 空白・ハイフンを除き小文字に揃える
+fn normalize_code(code: &str) -> String {
+    code.chars()
+        .filter(|c| !c.is_whitespace() && *c != '-')
+        .map(|c| c.to_ascii_lowercase())
+        .collect()
+}
+
+fn is_totp_code(code: &str) -> bool {
+    code.len() == TOTP_DIGITS && code.bytes().all(|b| b.is_ascii_digit())
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+/// リカバリーコード（xxxxx-xxxxx 形式）とそのハッシュを生成
+fn generate_recovery_codes() -> (Vec<String>, Vec<String>) {
+    let mut rng = rand::rng();
+    let codes: Vec<String> = (0..RECOVERY_CODE_COUNT)
+        .map(|_| {
+            let mut half = || -> String {
+                (0..RECOVERY_CODE_HALF_LEN)
+                    .map(|_| RECOVERY_CODE_ALPHABET[rng.random_range(0..RECOVERY_CODE_ALPHABET.len())] as char)
+                    .collect()
+            };
+            let first = half();
+            format!("{}-{}", first, half())
+        })
+        .collect();
+    let hashes = codes
+        .iter()
+        .map(|code| hash_recovery_code(&normalize_code(code)))
+        .collect();
+    (codes, hashes)
+}
+
+/// リカバリーコードは十分なエントロピーを持つため、検索可能なSHA-256で保存する
+fn hash_recovery_code(code: &str) -> String {
+    hex::encode(Sha256::digest(code.as_bytes()))
+}
@@ -1,15 +1,37 @@
+mod account_deletion_service;
+mod export_service;
+mod journaling_service;
 mod memo_service;
+mod mfa_service;
+pub mod oidc_service;
+mod personal_access_token_service;
+mod revocation_cache;
+mod search_service;
+mod share_service;
+mod summary_job_service;
 mod summary_service;
 mod tag_service;
+mod webauthn_service;
 mod auth_service;
 pub mod email_service;
 pub mod verification_store;
 pub mod rate_limiter;
 
+pub use account_deletion_service::AccountDeletionService;
+pub use export_service::ExportService;
+pub use journaling_service::JournalingService;
 pub use memo_service::MemoService;
+pub use mfa_service::MfaService;
+pub use oidc_service::OidcService;
+pub use personal_access_token_service::{PersonalAccessTokenService, is_personal_access_token};
+pub use revocation_cache::RevocationCache;
+pub use search_service::SearchService;
+pub use share_service::ShareService;
+pub use summary_job_service::SummaryJobService;
 pub use summary_service::SummaryService;
 pub use tag_service::TagService;
-pub use auth_service::AuthService;
+pub use webauthn_service::WebAuthnService;
+pub use auth_service::{AuthService, LoginOutcome};
 pub use email_service::EmailService;
 pub use verification_store::VerificationStore;
-pub use rate_limiter::{EmailRateLimiter, AuthRateLimiter};
\ No newline at end of file
+pub use rate_limiter::{EmailRateLimiter, AuthRateLimiter, ShareRateLimiter};
\ No newline at end of file
@@ -0,0 +1,825 @@
+use crate::{
+    config::OidcProviderConfig,
+    error::{AppError, Result},
+};
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use chrono::{DateTime, Duration, Utc};
+use dashmap::DashMap;
+use jsonwebtoken::{Algorithm, DecodingKey, Validation, decode, decode_header, jwk::Jwk};
+use reqwest::{Client, Url};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::sync::Arc;
+
+/// 認可リクエストの有効期間（分）
+const AUTHORIZATION_TTL_MINUTES: i64 = 10;
+/// ディスカバリー文書と公開鍵のキャッシュ期間（分）
+const DOCUMENTS_TTL_MINUTES: i64 = 60;
+/// 未知の鍵IDで公開鍵を取り直す最短間隔（秒）
+const JWKS_REFRESH_MIN_SECONDS: i64 = 60;
+/// IDトークンの有効期限などの検証で許容する時計のずれ（秒）
+const CLOCK_SKEW_SECONDS: u64 = 60;
+const HTTP_TIMEOUT_SECONDS: u64 = 10;
+
+/// IDトークンの署名に受け付けるアルゴリズム（共通鍵によるHS256などは受け付けない）
+const ALLOWED_ALGORITHMS: [Algorithm; 9] = [
+    Algorithm::RS256,
+    Algorithm::RS384,
+    Algorithm::RS512,
+    Algorithm::PS256,
+    Algorithm::PS384,
+    Algorithm::PS512,
+    Algorithm::ES256,
+    Algorithm::ES384,
+    Algorithm::EdDSA,
+];
+
+/// ログイン画面に表示するIDプロバイダー
+#[derive(Debug, Serialize)]
+pub struct OidcProviderInfo {
+    pub id: String,
+    pub name: String,
+}
+
+#[derive(Serialize)]
+pub struct OidcProviderList {
+    pub providers: Vec<OidcProviderInfo>,
+}
+
+/// IDプロバイダーへの認可リクエスト
+pub struct OidcAuthorization {
+    /// ブラウザをリダイレクトさせるURL
+    pub authorization_url: String,
+    /// コールバックで照合する state
+    pub state: String,
+}
+
+/// IDトークンで検証された外部ID
+pub struct VerifiedIdentity {
+    pub provider: String,
+    pub subject: String,
+    pub email: Option<String>,
+    pub email_verified: bool,
+    pub name: Option<String>,
+}
+
+/// 開始済みの認可リクエスト（state は一度だけ使用できる）
+struct PendingAuthorization {
+    provider: String,
+    nonce: String,
+    code_verifier: String,
+    expires_at: DateTime<Utc>,
+}
+
+/// ディスカバリー文書（必要な項目のみ）
+#[derive(Deserialize)]
+struct ProviderMetadata {
+    issuer: String,
+    authorization_endpoint: String,
+    token_endpoint: String,
+    jwks_uri: String,
+}
+
+/// プロバイダーごとにキャッシュするディスカバリー文書と公開鍵
+struct ProviderDocuments {
+    metadata: ProviderMetadata,
+    keys: Vec<Jwk>,
+    fetched_at: DateTime<Utc>,
+}
+
+#[derive(Deserialize)]
+struct JwksResponse {
+    keys: Vec<serde_json::Value>,
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    id_token: Option<String>,
+}
+
+/// IDトークンのクレーム（iss・aud・exp はライブラリで検証する）
+#[derive(Deserialize)]
+struct IdTokenClaims {
+    sub: String,
+    nonce: Option<String>,
+    azp: Option<String>,
+    email: Option<String>,
+    /// プロバイダーによっては文字列の "true" で返される
+    email_verified: Option<serde_json::Value>,
+    name: Option<String>,
+}
+
+/// OpenID Connect のクライアント
+/// ディスカバリー、PKCE、state・nonce の照合、IDトークンの検証を行う
+pub struct OidcService {
+    providers: Vec<OidcProviderConfig>,
+    http: Client,
+    // provider id -> ProviderDocuments
+    documents: DashMap<String, Arc<ProviderDocuments>>,
+    // state -> PendingAuthorization
+    pending: Arc<DashMap<String, PendingAuthorization>>,
+}
+
+impl OidcService {
+    pub fn new(providers: Vec<OidcProviderConfig>) -> Self {
+        let http = Client::builder()
+            .timeout(std::time::Duration::from_secs(HTTP_TIMEOUT_SECONDS))
+            .build()
+            .unwrap_or_default();
+
+        let service = Self {
+            providers,
+            http,
+            documents: DashMap::new(),
+            pending: Arc::new(DashMap::new()),
+        };
+
+        // バックグラウンドで定期的に期限切れの認可リクエストをクリーンアップ
+        let pending = service.pending.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(300)); // 5分ごと
+            loop {
+                interval.tick().await;
+                let now = Utc::now();
+                pending.retain(|_, authorization| authorization.expires_at > now);
+            }
+        });
+
+        service
+    }
+
+    /// 設定されたIDプロバイダーの一覧
+    pub fn providers(&self) -> Vec<OidcProviderInfo> {
+        self.providers
+            .iter()
+            .map(|provider| OidcProviderInfo {
+                id: provider.id.clone(),
+                name: if provider.name.is_empty() {
+                    provider.id.clone()
+                } else {
+                    provider.name.clone()
+                },
+            })
+            .collect()
+    }
+
+    /// 認可リクエストを開始し、IDプロバイダーの認可エンドポイントのURLを返す
+    pub async fn start_authorization(&self, provider_id: &str) -> Result<OidcAuthorization> {
+        let provider = self.provider(provider_id)?;
+        let documents = self.documents(provider, false).await?;
+
+        let state = random_token();
+        let nonce = random_token();
+        let code_verifier = random_token();
+        let code_challenge = URL_SAFE_NO_PAD.encode(Sha256::digest(code_verifier.as_bytes()));
+
+        let authorization_url = Url::parse_with_params(
+            &documents.metadata.authorization_endpoint,
+            &[
+                ("response_type", "code"),
+                ("client_id", provider.client_id.as_str()),
+                ("redirect_uri", provider.redirect_uri.as_str()),
+                ("scope", provider.scopes.join(" ").as_str()),
+                ("state", state.as_str()),
+                ("nonce", nonce.as_str()),
+                ("code_challenge", code_challenge.as_str()),
+                ("code_challenge_method", "S256"),
+            ],
+        )
+        .map_err(|e| AppError::ExternalServiceError(format!("Invalid authorization endpoint: {}", e)))?;
+
+        self.pending.insert(
+            state.clone(),
+            PendingAuthorization {
+                provider: provider.id.clone(),
+                nonce,
+                code_verifier,
+                expires_at: Utc::now() + Duration::minutes(AUTHORIZATION_TTL_MINUTES),
+            },
+        );
+
+        Ok(OidcAuthorization {
+            authorization_url: authorization_url.to_string(),
+            state,
+        })
+    }
+
+    /// 認可コードをIDトークンと交換し、IDトークンを検証して外部IDを返す
+    pub async fn complete_authorization(
+        &self,
+        provider_id: &str,
+        code: &str,
+        state: &str,
+    ) -> Result<VerifiedIdentity> {
+        let provider = self.provider(provider_id)?;
+
+        // state は一度だけ使用できる
+        let (_, pending) = self.pending.remove(state).ok_or_else(invalid_state)?;
+        if pending.expires_at <= Utc::now() || pending.provider != provider.id {
+            return Err(invalid_state());
+        }
+
+        let documents = self.documents(provider, false).await?;
+        let id_token = self
+            .exchange_code(provider, &documents.metadata, code, &pending.code_verifier)
+            .await?;
+        let claims = self.validate_id_token(provider, documents, &id_token).await?;
+
+        if claims.nonce.as_deref() != Some(pending.nonce.as_str()) {
+            return Err(AppError::AuthenticationError(
+                "ID token nonce does not match".to_string(),
+            ));
+        }
+
+        let email_verified = match claims.email_verified {
+            Some(serde_json::Value::Bool(verified)) => verified,
+            Some(serde_json::Value::String(verified)) => verified == "true",
+            _ => false,
+        };
+
+        Ok(VerifiedIdentity {
+            provider: provider.id.clone(),
+            subject: claims.sub,
+            email: claims.email,
+            email_verified,
+            name: claims.name,
+        })
+    }
+
+    fn provider(&self, provider_id: &str) -> Result<&OidcProviderConfig> {
+        self.providers
+            .iter()
+            .find(|provider| provider.id == provider_id)
+            .ok_or_else(|| AppError::NotFound(format!("Identity provider not found: {}", provider_id)))
+    }
+
+    /// ディスカバリー文書と公開鍵を取得（キャッシュが有効な場合はキャッシュを返す）
+    async fn documents(
+        &self,
+        provider: &OidcProviderConfig,
+        refresh_keys: bool,
+    ) -> Result<Arc<ProviderDocuments>> {
+        let now = Utc::now();
+        if let Some(cached) = self.documents.get(&provider.id).map(|entry| entry.clone()) {
+            let expired = cached.fetched_at + Duration::minutes(DOCUMENTS_TTL_MINUTES) <= now;
+            // 鍵のローテーションに追従するため、未知の鍵IDの場合は取り直す（短時間での連続取得はしない）
+            let refresh = refresh_keys
+                && cached.fetched_at + Duration::seconds(JWKS_REFRESH_MIN_SECONDS) <= now;
+            if !expired && !refresh {
+                return Ok(cached);
+            }
+        }
+
+        let discovery_url = format!(
+            "{}/.well-known/openid-configuration",
+            provider.issuer.trim_end_matches('/')
+        );
+        let metadata: ProviderMetadata = self.get_json(&discovery_url).await?;
+
+        // 発行者がなりすまされていないか確認
+        if metadata.issuer.trim_end_matches('/') != provider.issuer.trim_end_matches('/') {
+            return Err(AppError::ExternalServiceError(format!(
+                "Issuer mismatch in discovery document: {}",
+                metadata.issuer
+            )));
+        }
+
+        let jwks: JwksResponse = self.get_json(&metadata.jwks_uri).await?;
+        // 未対応の鍵は無視する
+        let keys = jwks
+            .keys
+            .into_iter()
+            .filter_map(|key| serde_json::from_value::<Jwk>(key).ok())
+            .filter(|key| {
+                !matches!(key.common.public_key_use, Some(jsonwebtoken::jwk::PublicKeyUse::Encryption))
+            })
+            .collect();
+
+        let documents = Arc::new(ProviderDocuments {
+            metadata,
+            keys,
+            fetched_at: now,
+        });
+        self.documents.insert(provider.id.clone(), documents.clone());
+        Ok(documents)
+    }
+
+    async fn get_json<T: serde::de::DeserializeOwned>(&self, url: &str) -> Result<T> {
+        let response = self
+            .http
+            .get(url)
+            .send()
+            .await
+            .map_err(|e| AppError::ExternalServiceError(format!("Failed to fetch {}: {}", url, e)))?;
+
+        if !response.status().is_success() {
+            return Err(AppError::ExternalServiceError(format!(
+                "Failed to fetch {}: {}",
+                url,
+                response.status()
+            )));
+        }
+
+        response
+            .json()
+            .await
+            .map_err(|e| AppError::ExternalServiceError(format!("Invalid response from {}: {}", url, e)))
+    }
+
+    /// 認可コードをトークンエンドポイントでIDトークンと交換（PKCE の code_verifier を送る）
+    async fn exchange_code(
+        &self,
+        provider: &OidcProviderConfig,
+        metadata: &ProviderMetadata,
+        code: &str,
+        code_verifier: &str,
+    ) -> Result<String> {
+        let mut form = vec![
+            ("grant_type", "authorization_code"),
+            ("code", code),
+            ("redirect_uri", provider.redirect_uri.as_str()),
+            ("client_id", provider.client_id.as_str()),
+            ("code_verifier", code_verifier),
+        ];
+        if !provider.client_secret.is_empty() {
+            form.push(("client_secret", provider.client_secret.as_str()));
+        }
+
+        let response = self
+            .http
+            .post(&metadata.token_endpoint)
+            .form(&form)
+            .send()
+            .await
+            .map_err(|e| AppError::ExternalServiceError(format!("Failed to request token: {}", e)))?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            eprintln!("OIDC token request failed ({}): {} {}", provider.id, status, body);
+            return Err(AppError::AuthenticationError(
+                "Authorization code was rejected by the identity provider".to_string(),
+            ));
+        }
+
+        let token: TokenResponse = response
+            .json()
+            .await
+            .map_err(|e| AppError::ExternalServiceError(format!("Invalid token response: {}", e)))?;
+
+        token.id_token.ok_or_else(|| {
+            AppError::ExternalServiceError("Token response does not contain an ID token".to_string())
+        })
+    }
+
+    /// IDトークンの署名・発行者・対象者・有効期限を検証
+    async fn validate_id_token(
+        &self,
+        provider: &OidcProviderConfig,
+        documents: Arc<ProviderDocuments>,
+        id_token: &str,
+    ) -> Result<IdTokenClaims> {
+        let header = decode_header(id_token).map_err(|_| invalid_id_token())?;
+        if !ALLOWED_ALGORITHMS.contains(&header.alg) {
+            return Err(invalid_id_token());
+        }
+
+        let documents = if find_key(&documents.keys, header.kid.as_deref()).is_some() {
+            documents
+        } else {
+            self.documents(provider, true).await?
+        };
+        let jwk = find_key(&documents.keys, header.kid.as_deref()).ok_or_else(invalid_id_token)?;
+        let key = DecodingKey::from_jwk(jwk).map_err(|_| invalid_id_token())?;
+
+        let mut validation = Validation::new(header.alg);
+        validation.leeway = CLOCK_SKEW_SECONDS;
+        validation.set_issuer(&[documents.metadata.issuer.as_str()]);
+        validation.set_audience(&[provider.client_id.as_str()]);
+        validation.set_required_spec_claims(&["exp", "iat", "iss", "aud", "sub"]);
+
+        let claims = decode::<IdTokenClaims>(id_token, &key, &validation)
+            .map_err(|e| {
+                eprintln!("OIDC ID token validation failed ({}): {}", provider.id, e);
+                invalid_id_token()
+            })?
+            .claims;
+
+        // 認可された当事者が指定されている場合は自分であること
+        if let Some(azp) = &claims.azp
+            && azp != &provider.client_id
+        {
+            return Err(invalid_id_token());
+        }
+
+        Ok(claims)
+    }
+}
+
+/// 鍵IDで公開鍵を探す（鍵IDがない場合は鍵が1つだけのときに限りそれを使う）
+fn find_key<'a>(keys: &'a [Jwk], kid: Option<&str>) -> Option<&'a Jwk> {
+    match kid {
+        Some(kid) => keys.iter().find(|key| key.common.key_id.as_deref() == Some(kid)),
+        None if keys.len() == 1 => keys.first(),
+        None => None,
+    }
+}
+
+fn random_token() -> String {
+    URL_SAFE_NO_PAD.encode(rand::random::<[u8; 32]>())
+}
+
+fn invalid_state() -> AppError {
+    AppError::AuthenticationError("Authorization request is invalid or expired".to_string())
+}
+
+fn invalid_id_token() -> AppError {
+    AppError::AuthenticationError("ID token is invalid".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{Json, Router, extract::State, routing::get};
+    use ed25519_dalek::pkcs8::{EncodePrivateKey, spki::der::pem::LineEnding};
+    use jsonwebtoken::{EncodingKey, Header, encode};
+    use serde_json::{Value, json};
+    use std::sync::Mutex;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    const PROVIDER_ID: &str = "stand-in";
+    const CLIENT_ID: &str = "mimo-client";
+
+    /// テスト用の署名鍵
+    struct SigningKey {
+        kid: String,
+        encoding_key: EncodingKey,
+        jwk: Value,
+    }
+
+    impl SigningKey {
+        fn ed25519(kid: &str, seed: u8) -> Self {
+            let key = ed25519_dalek::SigningKey::from_bytes(&[seed; 32]);
+            let pem = key.to_pkcs8_pem(LineEnding::LF).unwrap();
+            Self {
+                kid: kid.to_string(),
+                encoding_key: EncodingKey::from_ed_pem(pem.as_bytes()).unwrap(),
+                jwk: json!({
+                    "kty": "OKP",
+                    "crv": "Ed25519",
+                    "x": URL_SAFE_NO_PAD.encode(key.verifying_key().to_bytes()),
+                    "kid": kid,
+                    "alg": "EdDSA",
+                    "use": "sig",
+                }),
+            }
+        }
+
+        fn sign(&self, claims: &Value) -> String {
+            let mut header = Header::new(Algorithm::EdDSA);
+            header.kid = Some(self.kid.clone());
+            encode(&header, claims, &self.encoding_key).unwrap()
+        }
+    }
+
+    /// ローカルで動かすIDプロバイダー（ディスカバリー・JWKS・トークンエンドポイント）
+    struct StandInProvider {
+        issuer: String,
+        /// JWKSで公開する鍵
+        jwks: Mutex<Vec<Value>>,
+        /// トークンエンドポイントが返すIDトークン
+        id_token: Mutex<String>,
+        jwks_fetches: AtomicUsize,
+    }
+
+    async fn discovery(State(provider): State<Arc<StandInProvider>>) -> Json<Value> {
+        Json(json!({
+            "issuer": provider.issuer,
+            "authorization_endpoint": format!("{}/authorize", provider.issuer),
+            "token_endpoint": format!("{}/token", provider.issuer),
+            "jwks_uri": format!("{}/jwks", provider.issuer),
+        }))
+    }
+
+    async fn jwks(State(provider): State<Arc<StandInProvider>>) -> Json<Value> {
+        provider.jwks_fetches.fetch_add(1, Ordering::SeqCst);
+        Json(json!({ "keys": *provider.jwks.lock().unwrap() }))
+    }
+
+    async fn token(State(provider): State<Arc<StandInProvider>>) -> Json<Value> {
+        Json(json!({
+            "access_token": "stand-in-access-token",
+            "token_type": "Bearer",
+            "id_token": *provider.id_token.lock().unwrap(),
+        }))
+    }
+
+    /// IDプロバイダーを空いているポートで起動し、そのプロバイダーを使うサービスを返す
+    async fn start(keys: &[&SigningKey]) -> (Arc<StandInProvider>, OidcService) {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let issuer = format!("http://{}", listener.local_addr().unwrap());
+        let provider = Arc::new(StandInProvider {
+            issuer: issuer.clone(),
+            jwks: Mutex::new(keys.iter().map(|key| key.jwk.clone()).collect()),
+            id_token: Mutex::new(String::new()),
+            jwks_fetches: AtomicUsize::new(0),
+        });
+
+        let app = Router::new()
+            .route("/.well-known/openid-configuration", get(discovery))
+            .route("/jwks", get(jwks))
+            .route("/token", axum::routing::post(token))
+            .with_state(provider.clone());
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let service = OidcService::new(vec![OidcProviderConfig {
+            id: PROVIDER_ID.to_string(),
+            name: String::new(),
+            issuer,
+            client_id: CLIENT_ID.to_string(),
+            client_secret: String::new(),
+            redirect_uri: "https://mimo.example/callback".to_string(),
+            scopes: vec!["openid".to_string(), "email".to_string()],
+        }]);
+        (provider, service)
+    }
+
+    /// 有効なIDトークンのクレーム
+    fn claims(provider: &StandInProvider, nonce: &str) -> Value {
+        let now = Utc::now().timestamp();
+        json!({
+            "iss": provider.issuer,
+            "aud": CLIENT_ID,
+            "sub": "subject-1",
+            "iat": now,
+            "exp": now + 300,
+            "nonce": nonce,
+            "email": "user@example.com",
+            "email_verified": true,
+            "name": "Stand-in User",
+        })
+    }
+
+    /// 認可リクエストを開始し、(state, nonce) を返す
+    async fn authorize(service: &OidcService) -> (String, String) {
+        let authorization = service.start_authorization(PROVIDER_ID).await.unwrap();
+        let url = Url::parse(&authorization.authorization_url).unwrap();
+        let nonce = url
+            .query_pairs()
+            .find(|(name, _)| name == "nonce")
+            .map(|(_, value)| value.into_owned())
+            .unwrap();
+        assert!(url.query_pairs().any(|(name, value)| name == "state" && value == authorization.state));
+        (authorization.state, nonce)
+    }
+
+    /// 認可リクエストを開始し、トークンエンドポイントが id_token を返すようにしてコールバックを処理する
+    async fn login(
+        provider: &StandInProvider,
+        service: &OidcService,
+        id_token: impl FnOnce(&str) -> String,
+    ) -> Result<VerifiedIdentity> {
+        let (state, nonce) = authorize(service).await;
+        *provider.id_token.lock().unwrap() = id_token(&nonce);
+        service.complete_authorization(PROVIDER_ID, "code", &state).await
+    }
+
+    fn assert_rejected<T>(result: Result<T>, message: &str) {
+        match result {
+            Ok(_) => panic!("expected rejection: {}", message),
+            Err(e) => assert!(e.to_string().contains(message), "{} (expected: {})", e, message),
+        }
+    }
+
+    /// claims を書き換えたIDトークン
+    fn with(mut claims: Value, changes: Value) -> Value {
+        for (name, value) in changes.as_object().unwrap() {
+            if value.is_null() {
+                claims.as_object_mut().unwrap().remove(name);
+            } else {
+                claims[name] = value.clone();
+            }
+        }
+        claims
+    }
+
+    #[tokio::test]
+    async fn valid_id_token_is_accepted() {
+        let key = SigningKey::ed25519("key-1", 1);
+        let (provider, service) = start(&[&key]).await;
+
+        let identity = login(&provider, &service, |nonce| key.sign(&claims(&provider, nonce)))
+            .await
+            .unwrap();
+        assert_eq!(identity.provider, PROVIDER_ID);
+        assert_eq!(identity.subject, "subject-1");
+        assert_eq!(identity.email.as_deref(), Some("user@example.com"));
+        assert!(identity.email_verified);
+        assert_eq!(identity.name.as_deref(), Some("Stand-in User"));
+    }
+
+    #[tokio::test]
+    async fn unknown_or_replayed_state_is_rejected() {
+        let key = SigningKey::ed25519("key-1", 1);
+        let (provider, service) = start(&[&key]).await;
+
+        let (state, nonce) = authorize(&service).await;
+        *provider.id_token.lock().unwrap() = key.sign(&claims(&provider, &nonce));
+
+        assert_rejected(
+            service.complete_authorization(PROVIDER_ID, "code", "unknown-state").await,
+            "Authorization request is invalid or expired",
+        );
+        service
+            .complete_authorization(PROVIDER_ID, "code", &state)
+            .await
+            .unwrap();
+        // state は一度だけ使用できる
+        assert_rejected(
+            service.complete_authorization(PROVIDER_ID, "code", &state).await,
+            "Authorization request is invalid or expired",
+        );
+    }
+
+    #[tokio::test]
+    async fn nonce_mismatch_is_rejected() {
+        let key = SigningKey::ed25519("key-1", 1);
+        let (provider, service) = start(&[&key]).await;
+
+        assert_rejected(
+            login(&provider, &service, |_| key.sign(&claims(&provider, "another-nonce"))).await,
+            "ID token nonce does not match",
+        );
+        assert_rejected(
+            login(&provider, &service, |nonce| {
+                key.sign(&with(claims(&provider, nonce), json!({ "nonce": null })))
+            })
+            .await,
+            "ID token nonce does not match",
+        );
+    }
+
+    #[tokio::test]
+    async fn wrong_issuer_or_audience_is_rejected() {
+        let key = SigningKey::ed25519("key-1", 1);
+        let (provider, service) = start(&[&key]).await;
+
+        assert_rejected(
+            login(&provider, &service, |nonce| {
+                key.sign(&with(claims(&provider, nonce), json!({ "iss": "https://evil.example" })))
+            })
+            .await,
+            "ID token is invalid",
+        );
+        assert_rejected(
+            login(&provider, &service, |nonce| {
+                key.sign(&with(claims(&provider, nonce), json!({ "aud": "another-client" })))
+            })
+            .await,
+            "ID token is invalid",
+        );
+    }
+
+    #[tokio::test]
+    async fn expired_id_token_is_rejected() {
+        let key = SigningKey::ed25519("key-1", 1);
+        let (provider, service) = start(&[&key]).await;
+
+        let now = Utc::now().timestamp();
+        assert_rejected(
+            login(&provider, &service, |nonce| {
+                key.sign(&with(
+                    claims(&provider, nonce),
+                    json!({ "iat": now - 3600, "exp": now - 600 }),
+                ))
+            })
+            .await,
+            "ID token is invalid",
+        );
+    }
+
+    #[tokio::test]
+    async fn symmetric_and_unsigned_id_tokens_are_rejected() {
+        let key = SigningKey::ed25519("key-1", 1);
+        let (provider, service) = start(&[&key]).await;
+
+        // 公開されている値（client_id）を共通鍵にしたHS256
+        assert_rejected(
+            login(&provider, &service, |nonce| {
+                let mut header = Header::new(Algorithm::HS256);
+                header.kid = Some("key-1".to_string());
+                encode(
+                    &header,
+                    &claims(&provider, nonce),
+                    &EncodingKey::from_secret(CLIENT_ID.as_bytes()),
+                )
+                .unwrap()
+            })
+            .await,
+            "ID token is invalid",
+        );
+
+        // 署名なし（alg: none）
+        assert_rejected(
+            login(&provider, &service, |nonce| {
+                format!(
+                    "{}.{}.",
+                    URL_SAFE_NO_PAD.encode(r#"{"alg":"none","kid":"key-1"}"#),
+                    URL_SAFE_NO_PAD.encode(claims(&provider, nonce).to_string())
+                )
+            })
+            .await,
+            "ID token is invalid",
+        );
+    }
+
+    #[tokio::test]
+    async fn foreign_authorized_party_is_rejected() {
+        let key = SigningKey::ed25519("key-1", 1);
+        let (provider, service) = start(&[&key]).await;
+
+        assert_rejected(
+            login(&provider, &service, |nonce| {
+                key.sign(&with(claims(&provider, nonce), json!({ "azp": "another-client" })))
+            })
+            .await,
+            "ID token is invalid",
+        );
+        login(&provider, &service, |nonce| {
+            key.sign(&with(claims(&provider, nonce), json!({ "azp": CLIENT_ID })))
+        })
+        .await
+        .unwrap();
+    }
+
+    #[tokio::test]
+    async fn unknown_key_id_refreshes_jwks() {
+        let old_key = SigningKey::ed25519("key-1", 1);
+        let new_key = SigningKey::ed25519("key-2", 2);
+        let (provider, service) = start(&[&old_key]).await;
+
+        login(&provider, &service, |nonce| old_key.sign(&claims(&provider, nonce)))
+            .await
+            .unwrap();
+        assert_eq!(provider.jwks_fetches.load(Ordering::SeqCst), 1);
+
+        // 鍵のローテーション
+        provider.jwks.lock().unwrap().push(new_key.jwk.clone());
+
+        // 直前に取得したばかりの場合は取り直さない
+        assert_rejected(
+            login(&provider, &service, |nonce| new_key.sign(&claims(&provider, nonce))).await,
+            "ID token is invalid",
+        );
+        assert_eq!(provider.jwks_fetches.load(Ordering::SeqCst), 1);
+
+        // 取り直しの最短間隔を過ぎたものとする
+        let mut cached = service.documents.get_mut(PROVIDER_ID).unwrap();
+        let documents = Arc::get_mut(&mut cached).unwrap();
+        documents.fetched_at -= Duration::seconds(JWKS_REFRESH_MIN_SECONDS + 1);
+        drop(cached);
+
+        login(&provider, &service, |nonce| new_key.sign(&claims(&provider, nonce)))
+            .await
+            .unwrap();
+        assert_eq!(provider.jwks_fetches.load(Ordering::SeqCst), 2);
+
+        // JWKSにない鍵は取り直しても使えない
+        let unknown_key = SigningKey::ed25519("key-3", 3);
+        let mut cached = service.documents.get_mut(PROVIDER_ID).unwrap();
+        Arc::get_mut(&mut cached).unwrap().fetched_at -=
+            Duration::seconds(JWKS_REFRESH_MIN_SECONDS + 1);
+        drop(cached);
+        assert_rejected(
+            login(&provider, &service, |nonce| unknown_key.sign(&claims(&provider, nonce))).await,
+            "ID token is invalid",
+        );
+        assert_eq!(provider.jwks_fetches.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn unverified_email_is_reported_as_unverified() {
+        let key = SigningKey::ed25519("key-1", 1);
+        let (provider, service) = start(&[&key]).await;
+
+        for email_verified in [json!(false), json!("false"), Value::Null] {
+            let identity = login(&provider, &service, |nonce| {
+                key.sign(&with(
+                    claims(&provider, nonce),
+                    json!({ "email_verified": email_verified }),
+                ))
+            })
+            .await
+            .unwrap();
+            assert_eq!(identity.email.as_deref(), Some("user@example.com"));
+            assert!(!identity.email_verified, "email_verified: {}", email_verified);
+        }
+
+        let identity = login(&provider, &service, |nonce| {
+            key.sign(&with(claims(&provider, nonce), json!({ "email_verified": "true" })))
+        })
+        .await
+        .unwrap();
+        assert!(identity.email_verified);
+    }
+}
@@ -0,0 +1,167 @@
+use crate::{
+    auth::Role,
+    error::{AppError, Result},
+    repositories::personal_access_token::{
+        CreatePersonalAccessTokenRequest, CreatedPersonalAccessToken, NewPersonalAccessToken,
+        PersonalAccessToken, PersonalAccessTokenHandler, PersonalAccessTokenRepository,
+    },
+};
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use chrono::Utc;
+use sha2::{Digest, Sha256};
+use std::sync::Arc;
+
+/// パーソナルアクセストークンの接頭辞（JWTと区別するため）
+const TOKEN_PREFIX: &str = "mimo_pat_";
+/// 一覧表示用に保存する先頭部分の長さ（接頭辞を除く）
+const DISPLAY_PREFIX_LEN: usize = 6;
+/// ユーザーあたりのトークン数の上限
+const MAX_TOKENS_PER_USER: i64 = 20;
+const NAME_MAX_LEN: usize = 100;
+
+/// パーソナルアクセストークンに付与できる権限
+/// アカウント操作（EditAccount 以上）は付与できないため、トークンから新たなトークンは作れない
+const GRANTABLE_ROLES: [Role; 4] = [
+    Role::EditMemo,
+    Role::ViewMemo,
+    Role::SummarizeMemo,
+    Role::EditTag,
+];
+
+pub struct PersonalAccessTokenService {
+    token_repo: Arc<PersonalAccessTokenRepository>,
+}
+
+impl PersonalAccessTokenService {
+    pub fn new(token_repo: Arc<PersonalAccessTokenRepository>) -> Self {
+        Self { token_repo }
+    }
+
+    /// トークンを作成（平文のトークンはこのレスポンスでのみ返す）
+    pub async fn create(
+        &self,
+        user_id: &str,
+        req: CreatePersonalAccessTokenRequest,
+    ) -> Result<CreatedPersonalAccessToken> {
+        let name = validate_token_name(&req.name)?;
+
+        if req.roles.is_empty() {
+            return Err(AppError::ValidationError(
+                "At least one role is required".to_string(),
+            ));
+        }
+        if let Some(role) = req.roles.iter().find(|role| !GRANTABLE_ROLES.contains(role)) {
+            return Err(AppError::ValidationError(format!(
+                "Role {} cannot be granted to a personal access token",
+                role.as_str()
+            )));
+        }
+        if let Some(expires_at) = req.expires_at
+            && expires_at <= Utc::now()
+        {
+            return Err(AppError::ValidationError(
+                "expires_at must be in the future".to_string(),
+            ));
+        }
+
+        if self.token_repo.count_by_user_id(user_id).await? >= MAX_TOKENS_PER_USER {
+            return Err(AppError::ValidationError(format!(
+                "A user can have at most {} personal access tokens",
+                MAX_TOKENS_PER_USER
+            )));
+        }
+
+        let mut roles = req.roles;
+        roles.sort_by_key(|role| role.as_str());
+        roles.dedup();
+
+        let token = generate_token();
+        let info = self
+            .token_repo
+            .create(NewPersonalAccessToken {
+                user_id: user_id.to_string(),
+                name,
+                token_prefix: token[..TOKEN_PREFIX.len() + DISPLAY_PREFIX_LEN].to_string(),
+                token_hash: hash_token(&token),
+                roles,
+                expires_at: req.expires_at,
+            })
+            .await?;
+
+        Ok(CreatedPersonalAccessToken { token, info })
+    }
+
+    /// ユーザーのトークン一覧
+    pub async fn list(&self, user_id: &str) -> Result<Vec<PersonalAccessToken>> {
+        self.token_repo.find_by_user_id(user_id).await
+    }
+
+    /// トークンの名前を変更
+    pub async fn rename(&self, user_id: &str, token_id: &str, name: &str) -> Result<PersonalAccessToken> {
+        let name = validate_token_name(name)?;
+        self.token_repo
+            .rename(user_id, token_id, &name)
+            .await?
+            .ok_or_else(|| AppError::NotFound("Personal access token not found".to_string()))
+    }
+
+    /// トークンを失効（削除）
+    pub async fn revoke(&self, user_id: &str, token_id: &str) -> Result<()> {
+        if !self.token_repo.delete(user_id, token_id).await? {
+            return Err(AppError::NotFound(
+                "Personal access token not found".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// トークンを検証し、最終使用日時を更新
+    pub async fn authenticate(&self, token: &str) -> Result<PersonalAccessToken> {
+        let invalid = || AppError::AuthenticationError("Invalid personal access token".to_string());
+
+        let info = self
+            .token_repo
+            .find_active_by_hash(&hash_token(token))
+            .await?
+            .ok_or_else(invalid)?;
+
+        let now = Utc::now();
+        if info.expires_at.is_some_and(|expires_at| expires_at <= now) {
+            return Err(AppError::AuthenticationError(
+                "Personal access token has expired".to_string(),
+            ));
+        }
+
+        self.token_repo.touch(&info.token_id, now).await?;
+        Ok(info)
+    }
+}
+
+/// JWTではなくパーソナルアクセストークンか
+pub fn is_personal_access_token(token: &str) -> bool {
+    token.starts_with(TOKEN_PREFIX)
+}
+
+fn generate_token() -> String {
+    format!(
+        "{}{}",
+        TOKEN_PREFIX,
+        URL_SAFE_NO_PAD.encode(rand::random::<[u8; 32]>())
+    )
+}
+
+/// トークンは十分なエントロピーを持つため、検索可能なSHA-256で保存する
+fn hash_token(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
+}
+
+fn validate_token_name(name: &str) -> Result<String> {
+    let name = name.trim();
+    if name.is_empty() || name.chars().count() > NAME_MAX_LEN {
+        return Err(AppError::ValidationError(format!(
+            "Token name must be between 1 and {} characters",
+            NAME_MAX_LEN
+        )));
+    }
+    Ok(name.to_string())
+}
@@ -1,3 +1,4 @@
+use dashmap::DashMap;
 use governor::{
     clock::{Clock, DefaultClock},
     state::keyed::DefaultKeyedStateStore,
@@ -5,7 +6,7 @@ use governor::{
 };
 use std::num::NonZeroU32;
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 /// メール送信用のレートリミッター設定
 pub struct EmailRateLimiter {
@@ -23,6 +24,18 @@ pub struct AuthRateLimiter {
     per_user: Arc<RateLimiter<String, DefaultKeyedStateStore<String>, DefaultClock>>,
 }
 
+/// 公開共有リンク用のレートリミッター設定
+pub struct ShareRateLimiter {
+    // IPアドレスごとのレート制限（1分間に60回まで、トークンの確認前に適用）
+    per_ip: Arc<RateLimiter<String, DefaultKeyedStateStore<String>, DefaultClock>>,
+    // IPアドレス・共有トークンの組ごとのレート制限（1分間に30回まで）
+    per_ip_token: Arc<RateLimiter<String, DefaultKeyedStateStore<String>, DefaultClock>>,
+    // 共有トークンごとのパスワード失敗回数の制限（IPアドレスに関係なく10回まで、以降2分ごとに1回）
+    password_failures_per_token: Arc<RateLimiter<String, DefaultKeyedStateStore<String>, DefaultClock>>,
+    // パスワード失敗の制限に達した共有トークンと、再び試行できるようになる時刻
+    locked_tokens: Arc<DashMap<String, Instant>>,
+}
+
 impl AuthRateLimiter {
     pub fn new() -> Self {
         // 1時間に20回の制限
@@ -147,3 +160,122 @@ impl Default for EmailRateLimiter {
         Self::new()
     }
 }
+
+impl ShareRateLimiter {
+    pub fn new() -> Self {
+        // 1分間に60回の制限（存在しないトークンの総当たり防止）
+        let ip_quota = Quota::per_minute(NonZeroU32::new(60).unwrap());
+        let per_ip = Arc::new(RateLimiter::dashmap(ip_quota));
+
+        // 1分間に30回の制限（パスワードの総当たり・過剰なアクセス防止）
+        // 他のIPアドレスからのアクセスで正規の閲覧者が制限されないよう、IPアドレスと組にする
+        let token_quota = Quota::per_minute(NonZeroU32::new(30).unwrap());
+        let per_ip_token = Arc::new(RateLimiter::dashmap(token_quota));
+
+        // パスワードの失敗は複数のIPアドレスに分散されても合算する
+        // （パスワードの検証は重いため、制限中は検証せずに拒否する）
+        let failure_quota = Quota::with_period(Duration::from_secs(2 * 60))
+            .unwrap()
+            .allow_burst(NonZeroU32::new(10).unwrap());
+        let password_failures_per_token = Arc::new(RateLimiter::dashmap(failure_quota));
+
+        Self {
+            per_ip,
+            per_ip_token,
+            password_failures_per_token,
+            locked_tokens: Arc::new(DashMap::new()),
+        }
+    }
+
+    /// Check rate limit for shared memo access (per IP, then per IP and token)
+    pub fn check_limit(&self, ip: &str, token: &str) -> Result<(), String> {
+        let result = self
+            .per_ip
+            .check_key(&ip.to_string())
+            .and_then(|_| self.per_ip_token.check_key(&format!("{}:{}", ip, token)));
+        match result {
+            Ok(_) => Ok(()),
+            Err(negative) => {
+                let wait_time = negative.wait_time_from(DefaultClock::default().now());
+                let seconds = wait_time.as_secs();
+
+                Err(format!(
+                    "Too many requests for this share link. Please try again in {}s.",
+                    seconds
+                ))
+            }
+        }
+    }
+
+    /// Check whether password attempts for the share link are allowed
+    pub fn check_password_attempt(&self, token: &str) -> Result<(), String> {
+        let Some(unlock_at) = self.locked_tokens.get(token).map(|entry| *entry) else {
+            return Ok(());
+        };
+        let now = Instant::now();
+        if unlock_at <= now {
+            self.locked_tokens.remove(token);
+            return Ok(());
+        }
+
+        Err(format!(
+            "Too many failed password attempts for this share link. Please try again in {}s.",
+            (unlock_at - now).as_secs().max(1)
+        ))
+    }
+
+    /// Record a failed password attempt and lock the share link when the limit is reached
+    pub fn record_password_failure(&self, token: &str) {
+        if let Err(negative) = self.password_failures_per_token.check_key(&token.to_string()) {
+            let wait_time = negative.wait_time_from(DefaultClock::default().now());
+            self.locked_tokens
+                .insert(token.to_string(), Instant::now() + wait_time);
+        }
+    }
+
+    /// 制限に達していないキーを削除してメモリを解放（定期的に呼び出す）
+    pub fn retain_recent(&self) {
+        self.per_ip.retain_recent();
+        self.per_ip_token.retain_recent();
+        self.password_failures_per_token.retain_recent();
+        let now = Instant::now();
+        self.locked_tokens.retain(|_, unlock_at| *unlock_at > now);
+    }
+}
+
+impl Default for ShareRateLimiter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn share_password_failures_lock_the_token_across_ips() {
+        let limiter = ShareRateLimiter::new();
+        for _ in 0..10 {
+            assert!(limiter.check_password_attempt("token").is_ok());
+            limiter.record_password_failure("token");
+        }
+        assert!(limiter.check_password_attempt("token").is_ok());
+        limiter.record_password_failure("token");
+
+        assert!(limiter.check_password_attempt("token").is_err());
+        // 他の共有トークンには影響しない
+        assert!(limiter.check_password_attempt("other").is_ok());
+    }
+
+    #[test]
+    fn share_password_lock_expires() {
+        let limiter = ShareRateLimiter::new();
+        limiter
+            .locked_tokens
+            .insert("token".to_string(), Instant::now() - Duration::from_secs(1));
+
+        assert!(limiter.check_password_attempt("token").is_ok());
+        assert!(limiter.locked_tokens.is_empty());
+    }
+}
@@ -0,0 +1,152 @@
+use crate::error::Result;
+use crate::repositories::auth::{
+    AuthRepository, RevocationKind, RevocationNotification, RevokedToken,
+};
+use chrono::{DateTime, Utc};
+use dashmap::DashMap;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// 通知の接続が切れたときに再接続するまでの間隔（秒）
+const RECONNECT_DELAY_SECONDS: u64 = 5;
+/// 有効期限を過ぎた記録を削除する間隔（秒）
+const PRUNE_INTERVAL_SECONDS: u64 = 300;
+
+/// 失効済みのトークン・セッションのインメモリキャッシュ
+///
+/// 起動時にPostgreSQLから読み込み、このインスタンスでの失効は直接、
+/// 他のインスタンスでの失効は LISTEN/NOTIFY で反映する。
+/// 通知の接続が切れている間は通知を取りこぼしている可能性があるため、PostgreSQLに問い合わせる。
+pub struct RevocationCache {
+    auth_repo: Arc<AuthRepository>,
+    // JTI -> 有効期限
+    jtis: Arc<DashMap<String, DateTime<Utc>>>,
+    // セッション（ファミリー）ID -> 有効期限
+    sessions: Arc<DashMap<String, DateTime<Utc>>>,
+    /// 読み込みが完了し、通知を受け取れている間だけ true
+    synced: AtomicBool,
+}
+
+impl RevocationCache {
+    pub fn new(auth_repo: Arc<AuthRepository>) -> Self {
+        let cache = Self {
+            auth_repo,
+            jtis: Arc::new(DashMap::new()),
+            sessions: Arc::new(DashMap::new()),
+            synced: AtomicBool::new(false),
+        };
+
+        // バックグラウンドで定期的に期限切れの記録を削除
+        // （有効期限を過ぎたトークンは署名の検証で拒否されるため、失効の記録は不要）
+        let jtis = cache.jtis.clone();
+        let sessions = cache.sessions.clone();
+        tokio::spawn(async move {
+            let mut interval =
+                tokio::time::interval(tokio::time::Duration::from_secs(PRUNE_INTERVAL_SECONDS));
+            loop {
+                interval.tick().await;
+                let now = Utc::now();
+                jtis.retain(|_, expires_at| *expires_at > now);
+                sessions.retain(|_, expires_at| *expires_at > now);
+            }
+        });
+
+        cache
+    }
+
+    /// PostgreSQLとの同期を続ける（戻らない）
+    /// 先に LISTEN してから読み込むため、読み込み中の失効も取りこぼさない
+    pub async fn run_sync(&self) {
+        loop {
+            match self.auth_repo.listen_revocations().await {
+                Ok(mut listener) => {
+                    match self.reload().await {
+                        Ok(()) => {
+                            self.synced.store(true, Ordering::Release);
+                            println!(
+                                "Revocation cache synced ({} tokens, {} sessions)",
+                                self.jtis.len(),
+                                self.sessions.len()
+                            );
+                            // 接続が切れるまで通知を反映（切断時は Ok(None)）
+                            while let Ok(Some(notification)) = listener.try_recv().await {
+                                self.apply_notification(notification.payload());
+                            }
+                            self.synced.store(false, Ordering::Release);
+                            eprintln!(
+                                "Revocation notification connection lost; falling back to PostgreSQL until resynced"
+                            );
+                        }
+                        Err(e) => eprintln!("Failed to load revocations: {}", e),
+                    }
+                }
+                Err(e) => eprintln!("Failed to listen for revocations: {}", e),
+            }
+
+            tokio::time::sleep(tokio::time::Duration::from_secs(RECONNECT_DELAY_SECONDS)).await;
+        }
+    }
+
+    /// 有効期限内の失効をすべて読み込む
+    async fn reload(&self) -> Result<()> {
+        for token in self.auth_repo.find_revoked_jtis().await? {
+            Self::insert(&self.jtis, token.id, token.expires_at);
+        }
+        for session in self.auth_repo.find_revoked_sessions().await? {
+            Self::insert(&self.sessions, session.id, session.expires_at);
+        }
+        Ok(())
+    }
+
+    fn apply_notification(&self, payload: &str) {
+        let notification = match serde_json::from_str::<RevocationNotification>(payload) {
+            Ok(notification) => notification,
+            Err(e) => {
+                eprintln!("Ignoring malformed revocation notification: {}", e);
+                return;
+            }
+        };
+        let expires_at =
+            DateTime::from_timestamp(notification.expires_at, 0).unwrap_or(DateTime::<Utc>::MAX_UTC);
+        match notification.kind {
+            RevocationKind::Jti => Self::insert(&self.jtis, notification.id, expires_at),
+            RevocationKind::Session => Self::insert(&self.sessions, notification.id, expires_at),
+        }
+    }
+
+    /// 失効を記録（既に記録されている場合は遅い方の有効期限を残す）
+    fn insert(map: &DashMap<String, DateTime<Utc>>, id: String, expires_at: DateTime<Utc>) {
+        map.entry(id)
+            .and_modify(|current| *current = (*current).max(expires_at))
+            .or_insert(expires_at);
+    }
+
+    /// このインスタンスで失効させたトークンを記録（通知を待たずに反映する）
+    pub fn revoke_jti(&self, jti: &str, expires_at: DateTime<Utc>) {
+        Self::insert(&self.jtis, jti.to_string(), expires_at);
+    }
+
+    /// このインスタンスで失効させたセッションを記録（通知を待たずに反映する）
+    pub fn revoke_sessions<'a>(&self, sessions: impl IntoIterator<Item = &'a RevokedToken>) {
+        for session in sessions {
+            Self::insert(&self.sessions, session.id.clone(), session.expires_at);
+        }
+    }
+
+    /// JTIが失効されているか確認
+    pub async fn is_jwt_revoked(&self, jti: &str) -> Result<bool> {
+        if !self.synced.load(Ordering::Acquire) {
+            return self.auth_repo.is_jwt_revoked(jti).await;
+        }
+        Ok(self.jtis.contains_key(jti))
+    }
+
+    /// アクセストークンが失効されているか確認（JTI単位・セッション単位）
+    pub async fn is_access_token_revoked(&self, jti: &str, session_id: Option<&str>) -> Result<bool> {
+        if !self.synced.load(Ordering::Acquire) {
+            return self.auth_repo.is_access_token_revoked(jti, session_id).await;
+        }
+        Ok(self.jtis.contains_key(jti)
+            || session_id.is_some_and(|session_id| self.sessions.contains_key(session_id)))
+    }
+}
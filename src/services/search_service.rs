@@ -0,0 +1,518 @@
+use crate::{
+    error::{AppError, Result},
+    repositories::{
+        AISummary, Memo, MemoHandler, MemoRepository, SearchIndexRepository, SearchRequest,
+        SearchResults, SummaryRepository,
+        search::{
+            SearchDocType, SearchHit, SearchIndexEntry, SearchIndexHandler, SearchIndexQuery,
+            SearchTarget,
+        },
+        summary::SummaryHandler,
+    },
+};
+use chrono::{DateTime, Utc};
+use std::collections::{BTreeSet, HashMap};
+use std::sync::Arc;
+
+const DEFAULT_SEARCH_LIMIT: usize = 20;
+const MAX_SEARCH_LIMIT: usize = 100;
+const MAX_QUERY_LENGTH: usize = 100;
+// インデックスから取得する候補の上限（この中でスコアリングする）
+const MAX_CANDIDATES: i64 = 500;
+// 抜粋の文字数
+const SNIPPET_LENGTH: usize = 80;
+
+/// スコアリング前の検索候補
+struct CandidateDocument {
+    doc_type: SearchDocType,
+    id: String,
+    content: String,
+    tag_ids: Vec<String>,
+    created_at: DateTime<Utc>,
+}
+
+/// 日本語を含む本文の全文検索
+///
+/// MongoDBのテキストインデックスは日本語の分かち書きに対応していないため、
+/// 本文を正規化してbi-gramに分割し、専用コレクションに保存して検索する。
+pub struct SearchService {
+    index_repo: Arc<SearchIndexRepository>,
+    memo_repo: Arc<MemoRepository>,
+    summary_repo: Arc<SummaryRepository>,
+}
+
+impl SearchService {
+    pub fn new(
+        index_repo: Arc<SearchIndexRepository>,
+        memo_repo: Arc<MemoRepository>,
+        summary_repo: Arc<SummaryRepository>,
+    ) -> Self {
+        Self {
+            index_repo,
+            memo_repo,
+            summary_repo,
+        }
+    }
+
+    /// メモをインデックスに登録（更新時も同じ）
+    pub async fn index_memo(&self, memo: &Memo) -> Result<()> {
+        let tag_ids = memo
+            .auto_tag_id
+            .iter()
+            .chain(memo.manual_tag_id.iter())
+            .flatten()
+            .cloned()
+            .collect::<BTreeSet<String>>()
+            .into_iter()
+            .collect();
+
+        self.index_repo
+            .upsert(SearchIndexEntry {
+                doc_type: SearchDocType::Memo,
+                doc_id: memo.memo_id.clone(),
+                user_id: memo.user_id.clone(),
+                tokens: tokenize_for_index(&memo.content),
+                tag_ids,
+                created_at: memo.created_at,
+            })
+            .await
+    }
+
+    /// 要約をインデックスに登録
+    pub async fn index_summary(&self, summary: &AISummary) -> Result<()> {
+        self.index_repo
+            .upsert(SearchIndexEntry {
+                doc_type: SearchDocType::Summary,
+                doc_id: summary.summary_id.clone(),
+                user_id: summary.user_id.clone(),
+                tokens: tokenize_for_index(&summary.content),
+                tag_ids: Vec::new(),
+                created_at: summary.created_at,
+            })
+            .await
+    }
+
+    pub async fn remove_memo(&self, memo_id: &str) -> Result<()> {
+        self.index_repo.delete(SearchDocType::Memo, memo_id).await
+    }
+
+    pub async fn remove_summary(&self, summary_id: &str) -> Result<()> {
+        self.index_repo
+            .delete(SearchDocType::Summary, summary_id)
+            .await
+    }
+
+    /// ユーザーの全メモ・要約をインデックスから削除（アカウント削除用）
+    pub async fn remove_user(&self, user_id: &str) -> Result<()> {
+        self.index_repo.delete_by_user_id(user_id).await
+    }
+
+    /// ユーザーの全メモ・要約のインデックスを作り直す
+    /// 戻り値: 登録した件数
+    pub async fn reindex_user(&self, user_id: &str) -> Result<usize> {
+        let memos = self.memo_repo.find_by_user_id(user_id).await?;
+        let summaries = self.summary_repo.find_by_user_id(user_id).await?;
+
+        for memo in &memos {
+            self.index_memo(memo).await?;
+        }
+        for summary in &summaries {
+            self.index_summary(summary).await?;
+        }
+
+        Ok(memos.len() + summaries.len())
+    }
+
+    /// 全文検索
+    pub async fn search(&self, user_id: &str, req: SearchRequest) -> Result<SearchResults> {
+        let limit = req.limit.unwrap_or(DEFAULT_SEARCH_LIMIT);
+        if !(1..=MAX_SEARCH_LIMIT).contains(&limit) {
+            return Err(AppError::ValidationError(format!(
+                "limit must be between 1 and {}",
+                MAX_SEARCH_LIMIT
+            )));
+        }
+        if req.q.chars().count() > MAX_QUERY_LENGTH {
+            return Err(AppError::ValidationError(format!(
+                "Query cannot exceed {} characters",
+                MAX_QUERY_LENGTH
+            )));
+        }
+
+        let terms = query_terms(&req.q);
+        if terms.is_empty() {
+            return Err(AppError::ValidationError(
+                "Query cannot be empty".to_string(),
+            ));
+        }
+        let tokens: Vec<String> = terms
+            .iter()
+            .flat_map(|term| tokenize_term(term))
+            .collect::<BTreeSet<String>>()
+            .into_iter()
+            .collect();
+
+        let doc_types: &[SearchDocType] = match req.target {
+            SearchTarget::All => &[SearchDocType::Memo, SearchDocType::Summary],
+            SearchTarget::Memo => &[SearchDocType::Memo],
+            SearchTarget::Summary => &[SearchDocType::Summary],
+        };
+
+        let candidates = self
+            .index_repo
+            .find_candidates(SearchIndexQuery {
+                user_id,
+                tokens: &tokens,
+                doc_types,
+                tag_id: req.tag_id.as_deref(),
+                from: req.from,
+                to: req.to,
+                limit: MAX_CANDIDATES,
+            })
+            .await?;
+
+        // 候補の本文を取得
+        let memo_ids: Vec<String> = candidates
+            .iter()
+            .filter(|c| c.doc_type == SearchDocType::Memo)
+            .map(|c| c.doc_id.clone())
+            .collect();
+        let summary_ids: Vec<String> = candidates
+            .iter()
+            .filter(|c| c.doc_type == SearchDocType::Summary)
+            .map(|c| c.doc_id.clone())
+            .collect();
+
+        let mut documents: Vec<CandidateDocument> = Vec::new();
+        if !memo_ids.is_empty() {
+            let tag_ids: HashMap<&str, &Vec<String>> = candidates
+                .iter()
+                .map(|c| (c.doc_id.as_str(), &c.tag_ids))
+                .collect();
+            for memo in self.memo_repo.find_by_ids(&memo_ids).await? {
+                if memo.user_id != user_id || memo.deleted_at.is_some() {
+                    continue;
+                }
+                let tags = tag_ids
+                    .get(memo.memo_id.as_str())
+                    .map(|t| t.to_vec())
+                    .unwrap_or_default();
+                documents.push(CandidateDocument {
+                    doc_type: SearchDocType::Memo,
+                    id: memo.memo_id,
+                    content: memo.content,
+                    tag_ids: tags,
+                    created_at: memo.created_at,
+                });
+            }
+        }
+        if !summary_ids.is_empty() {
+            for summary in self.summary_repo.find_by_ids(&summary_ids).await? {
+                if summary.user_id != user_id || summary.deleted_at.is_some() {
+                    continue;
+                }
+                documents.push(CandidateDocument {
+                    doc_type: SearchDocType::Summary,
+                    id: summary.summary_id,
+                    content: summary.content,
+                    tag_ids: Vec::new(),
+                    created_at: summary.created_at,
+                });
+            }
+        }
+
+        // bi-gramの一致は語の一致を保証しないため、本文で再確認してスコアを付ける
+        let mut hits: Vec<SearchHit> = documents
+            .into_iter()
+            .filter_map(|doc| {
+                let (score, matches) = score_document(&doc.content, &terms)?;
+                let (snippet, highlights) = build_snippet(&doc.content, &matches);
+                Some(SearchHit {
+                    doc_type: doc.doc_type,
+                    id: doc.id,
+                    score,
+                    snippet,
+                    highlights,
+                    tag_ids: doc.tag_ids,
+                    created_at: doc.created_at,
+                })
+            })
+            .collect();
+
+        hits.sort_by(|a, b| {
+            b.score
+                .total_cmp(&a.score)
+                .then_with(|| b.created_at.cmp(&a.created_at))
+        });
+
+        let total = hits.len();
+        let results = hits
+            .into_iter()
+            .skip(req.offset.unwrap_or(0))
+            .take(limit)
+            .collect();
+
+        Ok(SearchResults { results, total })
+    }
+}
+
+// ------------------------------------------------------------------
+// トークナイザ
+// ------------------------------------------------------------------
+
+/// 1文字単位の正規化（全角英数記号→半角、英字→小文字）
+/// 文字数を変えないため、正規化後の位置をそのまま元の本文に適用できる
+fn normalize_char(c: char) -> char {
+    let c = match c {
+        '\u{FF01}'..='\u{FF5E}' => char::from_u32(c as u32 - 0xFEE0).unwrap_or(c),
+        '\u{3000}' => ' ',
+        _ => c,
+    };
+    let mut lower = c.to_lowercase();
+    match (lower.next(), lower.next()) {
+        (Some(l), None) => l,
+        _ => c,
+    }
+}
+
+fn normalize(text: &str) -> Vec<char> {
+    text.chars().map(normalize_char).collect()
+}
+
+/// 区切り文字（空白・句読点・括弧など）
+fn is_separator(c: char) -> bool {
+    c.is_whitespace()
+        || c.is_ascii_punctuation()
+        || matches!(c, '\u{3001}'..='\u{303F}' | '\u{30FB}' | '\u{FF5F}'..='\u{FF65}')
+}
+
+/// 区切り文字で分割した連続部分
+fn runs(chars: &[char]) -> impl Iterator<Item = &[char]> {
+    chars
+        .split(|c| is_separator(*c))
+        .filter(|run| !run.is_empty())
+}
+
+/// インデックス用トークン: 各連続部分のuni-gramとbi-gram
+fn tokenize_for_index(text: &str) -> Vec<String> {
+    let chars = normalize(text);
+    let mut tokens = BTreeSet::new();
+    for run in runs(&chars) {
+        for c in run {
+            tokens.insert(c.to_string());
+        }
+        for pair in run.windows(2) {
+            tokens.insert(pair.iter().collect());
+        }
+    }
+    tokens.into_iter().collect()
+}
+
+/// 検索語のトークン: 1文字ならuni-gram、それ以外はbi-gram
+fn tokenize_term(term: &[char]) -> Vec<String> {
+    let mut tokens = Vec::new();
+    for run in runs(term) {
+        if run.len() == 1 {
+            tokens.push(run[0].to_string());
+        } else {
+            tokens.extend(run.windows(2).map(|pair| pair.iter().collect()));
+        }
+    }
+    tokens
+}
+
+/// 検索クエリを空白で分割し、正規化した語のリストにする
+fn query_terms(query: &str) -> Vec<Vec<char>> {
+    let chars = normalize(query);
+    chars
+        .split(|c| c.is_whitespace())
+        .filter(|term| term.iter().any(|c| !is_separator(*c)))
+        .map(|term| term.to_vec())
+        .collect()
+}
+
+/// 本文中の各語の出現位置を求めてスコアを計算する
+/// いずれかの語が含まれない場合はNone
+fn score_document(content: &str, terms: &[Vec<char>]) -> Option<(f64, Vec<(usize, usize)>)> {
+    let chars = normalize(content);
+    let mut matches = Vec::new();
+    let mut weighted = 0.0;
+
+    for term in terms {
+        let mut count = 0;
+        let mut i = 0;
+        while i + term.len() <= chars.len() {
+            if chars[i..i + term.len()] == term[..] {
+                matches.push((i, i + term.len()));
+                count += 1;
+                i += term.len();
+            } else {
+                i += 1;
+            }
+        }
+        if count == 0 {
+            return None;
+        }
+        weighted += (count * term.len()) as f64;
+    }
+
+    // 長い本文ほど一致の重みを下げる
+    let score = weighted / (chars.len().max(1) as f64).sqrt();
+    matches.sort_unstable();
+    Some((score, matches))
+}
+
+/// 最初の一致箇所を中心に抜粋を作り、抜粋内の一致位置を返す
+fn build_snippet(content: &str, matches: &[(usize, usize)]) -> (String, Vec<[usize; 2]>) {
+    let chars: Vec<char> = content.chars().collect();
+    let first = matches.first().map(|m| m.0).unwrap_or(0);
+    let start = first
+        .saturating_sub(SNIPPET_LENGTH / 4)
+        .min(chars.len().saturating_sub(SNIPPET_LENGTH));
+    let end = (start + SNIPPET_LENGTH).min(chars.len());
+
+    let snippet = chars[start..end].iter().collect();
+    let mut highlights: Vec<[usize; 2]> = Vec::new();
+    for &(s, e) in matches {
+        if s < start || e > end {
+            continue;
+        }
+        let (s, e) = (s - start, e - start);
+        // 重なる一致箇所は結合する
+        match highlights.last_mut() {
+            Some(last) if s <= last[1] => last[1] = last[1].max(e),
+            _ => highlights.push([s, e]),
+        }
+    }
+
+    (snippet, highlights)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tokens(text: &str) -> Vec<String> {
+        tokenize_for_index(text)
+    }
+
+    fn chars(text: &str) -> Vec<char> {
+        text.chars().collect()
+    }
+
+    #[test]
+    fn normalizes_full_width_and_case() {
+        assert_eq!(normalize("ＡＢＣ１２３！"), chars("abc123!"));
+        assert_eq!(normalize("Rust\u{3000}メモ"), chars("rust メモ"));
+        // かな・漢字はそのまま
+        assert_eq!(normalize("ひらがなカタカナ漢字"), chars("ひらがなカタカナ漢字"));
+    }
+
+    #[test]
+    fn indexes_unigrams_and_bigrams_of_mixed_text() {
+        let tokens = tokens("今日はRust、ｍｅｍｏ");
+        for expected in [
+            "今", "日", "は", "r", "今日", "日は", "はr", "ru", "st", "me", "mo",
+        ] {
+            assert!(tokens.contains(&expected.to_string()), "missing {:?}", expected);
+        }
+        // 区切り文字をまたぐbi-gramは作らない
+        assert!(!tokens.contains(&"t、".to_string()));
+        assert!(!tokens.contains(&"tm".to_string()));
+        assert!(!tokens.iter().any(|token| token.contains('、')));
+    }
+
+    #[test]
+    fn tokenizes_query_terms_as_bigrams() {
+        let terms = query_terms("東京タワー  ＡＩ 　、");
+        assert_eq!(terms, vec![chars("東京タワー"), chars("ai")]);
+        assert_eq!(
+            tokenize_term(&terms[0]),
+            vec!["東京", "京タ", "タワ", "ワー"]
+        );
+        assert_eq!(tokenize_term(&chars("猫")), vec!["猫"]);
+        // 語の中の区切り文字で分割する
+        assert_eq!(tokenize_term(&chars("a・b")), vec!["a", "b"]);
+    }
+
+    #[test]
+    fn every_query_bigram_is_in_the_index() {
+        let text = "新しいカフェでコーヒーを飲んだ";
+        let index = tokens(text);
+        for term in query_terms("カフェ コーヒー 飲") {
+            for token in tokenize_term(&term) {
+                assert!(index.contains(&token), "missing {:?}", token);
+            }
+        }
+    }
+
+    #[test]
+    fn requires_every_term() {
+        let terms = query_terms("カフェ 紅茶");
+        assert!(score_document("カフェでコーヒー", &terms).is_none());
+        assert!(score_document("カフェで紅茶", &terms).is_some());
+    }
+
+    #[test]
+    fn scores_more_and_denser_matches_higher() {
+        let terms = query_terms("カフェ");
+        let (once, _) = score_document("カフェに行った", &terms).unwrap();
+        let (twice, _) = score_document("カフェに行った。別のカフェ", &terms).unwrap();
+        let (long, _) = score_document(
+            &format!("カフェに行った。{}", "長い日記の本文".repeat(20)),
+            &terms,
+        )
+        .unwrap();
+        assert!(twice > once);
+        assert!(once > long);
+    }
+
+    #[test]
+    fn match_positions_are_char_offsets() {
+        let terms = query_terms("ｒｕｓｔ");
+        let (_, matches) = score_document("今日はRustとrust", &terms).unwrap();
+        assert_eq!(matches, vec![(3, 7), (8, 12)]);
+    }
+
+    #[test]
+    fn snippet_window_keeps_multibyte_boundaries() {
+        let content = format!("{}目印{}", "あ".repeat(100), "い".repeat(100));
+        let terms = query_terms("目印");
+        let (_, matches) = score_document(&content, &terms).unwrap();
+        let (snippet, highlights) = build_snippet(&content, &matches);
+
+        assert_eq!(snippet.chars().count(), SNIPPET_LENGTH);
+        assert_eq!(highlights.len(), 1);
+        let [s, e] = highlights[0];
+        let highlighted: String = snippet.chars().skip(s).take(e - s).collect();
+        assert_eq!(highlighted, "目印");
+        // 一致箇所の前に抜粋の1/4を残す
+        assert_eq!(s, SNIPPET_LENGTH / 4);
+    }
+
+    #[test]
+    fn snippet_is_shifted_to_fit_near_the_end() {
+        let content = format!("{}末尾😀", "本文".repeat(60));
+        let terms = query_terms("末尾");
+        let (_, matches) = score_document(&content, &terms).unwrap();
+        let (snippet, highlights) = build_snippet(&content, &matches);
+
+        assert!(snippet.ends_with("末尾😀"));
+        assert_eq!(snippet.chars().count(), SNIPPET_LENGTH);
+        let [s, e] = highlights[0];
+        assert_eq!(e, SNIPPET_LENGTH - 1);
+        assert_eq!(e - s, 2);
+    }
+
+    #[test]
+    fn overlapping_highlights_are_merged_and_outside_ones_dropped() {
+        let content = "ああいいああ";
+        let (snippet, highlights) = build_snippet(content, &[(0, 2), (1, 4), (4, 6)]);
+        assert_eq!(snippet, content);
+        assert_eq!(highlights, vec![[0, 6]]);
+
+        let content = "あ".repeat(200);
+        let (_, highlights) = build_snippet(&content, &[(10, 12), (150, 152)]);
+        assert_eq!(highlights, vec![[10, 12]]);
+    }
+}
@@ -0,0 +1,144 @@
+use crate::{
+    error::{AppError, Result},
+    services::ShareRateLimiter,
+    repositories::{
+        AuthRepository, Memo, MemoHandler, MemoRepository,
+        share::{CreateShareRequest, MemoShare, ShareHandler, ShareInfo, ShareRepository, SharedMemo},
+    },
+};
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use chrono::Utc;
+use std::sync::Arc;
+
+/// メモの公開共有
+pub struct ShareService {
+    share_repo: Arc<ShareRepository>,
+    memo_repo: Arc<MemoRepository>,
+    rate_limiter: Arc<ShareRateLimiter>,
+}
+
+impl ShareService {
+    pub fn new(
+        share_repo: Arc<ShareRepository>,
+        memo_repo: Arc<MemoRepository>,
+        rate_limiter: Arc<ShareRateLimiter>,
+    ) -> Self {
+        Self {
+            share_repo,
+            memo_repo,
+            rate_limiter,
+        }
+    }
+
+    /// 共有リンクを作成（既存のリンクがあれば無効化して作り直す）
+    pub async fn create_share(&self, memo: &Memo, req: CreateShareRequest) -> Result<ShareInfo> {
+        let now = Utc::now();
+        if let Some(expires_at) = req.expires_at
+            && expires_at <= now
+        {
+            return Err(AppError::ValidationError(
+                "expires_at must be in the future".to_string(),
+            ));
+        }
+        let password_hash = match req.password.as_deref() {
+            Some(password) if !password.is_empty() => {
+                if password.len() > 256 {
+                    return Err(AppError::ValidationError(
+                        "Password cannot exceed 256 characters".to_string(),
+                    ));
+                }
+                Some(AuthRepository::hash_password(password)?)
+            }
+            _ => None,
+        };
+
+        self.share_repo.delete_by_memo_id(&memo.memo_id).await?;
+
+        let share = self
+            .share_repo
+            .create(MemoShare {
+                token: generate_share_token(),
+                memo_id: memo.memo_id.clone(),
+                user_id: memo.user_id.clone(),
+                password_hash,
+                expires_at: req.expires_at,
+                view_count: 0,
+                last_viewed_at: None,
+                created_at: now,
+            })
+            .await?;
+
+        self.memo_repo
+            .set_share_url_token(&memo.memo_id, Some(share.token.clone()))
+            .await?;
+
+        Ok(share.into())
+    }
+
+    /// 共有リンクの情報を取得
+    pub async fn get_share(&self, memo: &Memo) -> Result<ShareInfo> {
+        self.share_repo
+            .find_by_memo_id(&memo.memo_id)
+            .await?
+            .map(ShareInfo::from)
+            .ok_or_else(|| AppError::NotFound("Memo is not shared".to_string()))
+    }
+
+    /// 共有リンクを無効化
+    pub async fn revoke_share(&self, memo: &Memo) -> Result<()> {
+        self.share_repo.delete_by_memo_id(&memo.memo_id).await?;
+        self.memo_repo
+            .set_share_url_token(&memo.memo_id, None)
+            .await
+    }
+
+    /// 共有リンクからメモを閲覧（認証不要）
+    pub async fn view_shared_memo(&self, token: &str, password: Option<&str>) -> Result<SharedMemo> {
+        let share = self
+            .share_repo
+            .find_by_token(token)
+            .await?
+            .ok_or_else(|| AppError::NotFound("Shared memo not found".to_string()))?;
+
+        let now = Utc::now();
+        if share.expires_at.is_some_and(|expires_at| expires_at <= now) {
+            return Err(AppError::NotFound("Share link has expired".to_string()));
+        }
+
+        if let Some(hash) = &share.password_hash {
+            let password = password.ok_or_else(|| {
+                AppError::AuthenticationError("Password required".to_string())
+            })?;
+            // 複数のIPアドレスからの総当たりに備え、失敗回数は共有トークンごとに数える
+            self.rate_limiter
+                .check_password_attempt(token)
+                .map_err(AppError::Locked)?;
+            if AuthRepository::verify_password(password, hash).is_err() {
+                self.rate_limiter.record_password_failure(token);
+                return Err(AppError::AuthenticationError("Invalid password".to_string()));
+            }
+        }
+
+        // ゴミ箱に移動したメモは公開しない
+        let memo = self
+            .memo_repo
+            .find_by_id(&share.memo_id)
+            .await?
+            .filter(|memo| memo.deleted_at.is_none())
+            .ok_or_else(|| AppError::NotFound("Shared memo not found".to_string()))?;
+
+        self.share_repo.record_view(token, now).await?;
+
+        Ok(SharedMemo {
+            content: memo.content,
+            created_at: memo.created_at,
+            updated_at: memo.updated_at,
+            view_count: share.view_count + 1,
+        })
+    }
+}
+
+/// URLに含める推測困難なトークンを生成
+fn generate_share_token() -> String {
+    URL_SAFE_NO_PAD.encode(rand::random::<[u8; 24]>())
+}
@@ -0,0 +1,247 @@
+use crate::{
+    error::{AppError, Result},
+    repositories::{
+        SummaryJob, SummaryJobRepository,
+        summary_job::{SummaryJobHandler, SummaryJobStage},
+    },
+    services::SummaryService,
+};
+use chrono::{Duration, Utc};
+use std::sync::Arc;
+use tokio::sync::Notify;
+
+/// 実行待ちのジョブがない場合に、次に確認するまでの時間（秒）
+const IDLE_POLL_SECONDS: u64 = 5;
+/// 再試行までの待ち時間の初期値（秒）。失敗するたびに2倍にする
+const RETRY_BASE_SECONDS: i64 = 30;
+/// 再試行までの待ち時間の上限（秒）
+const RETRY_MAX_SECONDS: i64 = 10 * 60;
+/// 実行中のジョブが生きていることを記録する間隔（秒）
+const HEARTBEAT_SECONDS: u64 = 60;
+/// 実行中のまま、この時間（分）記録がないジョブは中断されたとみなす
+const STALE_JOB_MINUTES: i64 = 15;
+/// 終了したジョブを残しておく日数
+const FINISHED_JOB_RETENTION_DAYS: i64 = 7;
+
+/// 要約ジョブ（要約の作成をリクエストから切り離して実行する）
+///
+/// ジョブは PostgreSQL に保存し、各インスタンスのワーカーが取り出して実行する。
+/// LLMの呼び出しなどで失敗したジョブは、待ち時間を延ばしながら再試行する。
+/// 状態の変更は取り出したときの試行回数で確認し、中断されたとみなされて
+/// 別のワーカーが実行し直しているジョブは、元のワーカーでは要約を保存しない。
+pub struct SummaryJobService {
+    job_repo: Arc<SummaryJobRepository>,
+    summary_service: Arc<SummaryService>,
+    /// 新しいジョブが登録されたことをワーカーに知らせる
+    queued: Notify,
+    workers: usize,
+    max_concurrent_per_user: i64,
+    max_attempts: i32,
+}
+
+impl SummaryJobService {
+    pub fn new(
+        job_repo: Arc<SummaryJobRepository>,
+        summary_service: Arc<SummaryService>,
+        workers: usize,
+        max_concurrent_per_user: i64,
+        max_attempts: i32,
+    ) -> Self {
+        Self {
+            job_repo,
+            summary_service,
+            queued: Notify::new(),
+            workers,
+            max_concurrent_per_user,
+            max_attempts,
+        }
+    }
+
+    /// 要約ジョブを登録
+    pub async fn enqueue(&self, user_id: &str, memo_ids: Vec<String>) -> Result<SummaryJob> {
+        if memo_ids.is_empty() {
+            return Err(AppError::ValidationError(
+                "No memos to summarize".to_string(),
+            ));
+        }
+
+        let job = self.job_repo.create(user_id, &memo_ids).await?;
+        self.queued.notify_one();
+        Ok(job)
+    }
+
+    /// ジョブの状態
+    pub async fn get_job(&self, user_id: &str, job_id: &str) -> Result<SummaryJob> {
+        self.job_repo
+            .find_by_id(user_id, job_id)
+            .await?
+            .ok_or_else(|| AppError::NotFound("Summary job not found".to_string()))
+    }
+
+    /// ワーカーを起動
+    pub fn start_workers(self: Arc<Self>) {
+        for _ in 0..self.workers {
+            let service = self.clone();
+            tokio::spawn(async move { service.run_worker().await });
+        }
+    }
+
+    /// 中断されたジョブを実行待ちに戻し、古いジョブを削除
+    /// 戻り値: 削除した件数
+    pub async fn cleanup(&self) -> Result<u64> {
+        let now = Utc::now();
+        let requeued = self
+            .job_repo
+            .requeue_stale(now - Duration::minutes(STALE_JOB_MINUTES), self.max_attempts)
+            .await?;
+        if requeued > 0 {
+            self.queued.notify_waiters();
+        }
+        self.job_repo
+            .delete_finished_before(now - Duration::days(FINISHED_JOB_RETENTION_DAYS))
+            .await
+    }
+
+    async fn run_worker(&self) {
+        loop {
+            match self
+                .job_repo
+                .claim_next(Utc::now(), self.max_concurrent_per_user)
+                .await
+            {
+                Ok(Some(job)) => self.run_job(&job).await,
+                Ok(None) => {
+                    // 新しいジョブの登録か、再試行の待ち時間の経過を待つ
+                    tokio::select! {
+                        _ = self.queued.notified() => {}
+                        _ = tokio::time::sleep(std::time::Duration::from_secs(IDLE_POLL_SECONDS)) => {}
+                    }
+                }
+                Err(e) => {
+                    eprintln!("Failed to claim summary job: {}", e);
+                    tokio::time::sleep(std::time::Duration::from_secs(IDLE_POLL_SECONDS)).await;
+                }
+            }
+        }
+    }
+
+    async fn run_job(&self, job: &SummaryJob) {
+        let result = match self.summarize_with_heartbeat(job).await {
+            Ok(None) => Ok(false),
+            Ok(Some(summary_id)) => {
+                self.job_repo
+                    .succeed(&job.job_id, job.attempts, &summary_id)
+                    .await
+            }
+            Err(e) if is_retryable(&e) && job.attempts < self.max_attempts => {
+                let delay = (RETRY_BASE_SECONDS << (job.attempts - 1).clamp(0, 16))
+                    .min(RETRY_MAX_SECONDS);
+                eprintln!(
+                    "Summary job {} failed (attempt {}), retrying in {}s: {}",
+                    job.job_id, job.attempts, delay, e
+                );
+                self.job_repo
+                    .retry(
+                        &job.job_id,
+                        job.attempts,
+                        &e.to_string(),
+                        Utc::now() + Duration::seconds(delay),
+                    )
+                    .await
+            }
+            Err(e) => {
+                eprintln!(
+                    "Summary job {} failed after {} attempts: {}",
+                    job.job_id, job.attempts, e
+                );
+                self.job_repo
+                    .fail(&job.job_id, job.attempts, &e.to_string())
+                    .await
+            }
+        };
+
+        match result {
+            Ok(true) => {}
+            Ok(false) => eprintln!(
+                "Summary job {} (attempt {}) is no longer owned by this worker",
+                job.job_id, job.attempts
+            ),
+            Err(e) => eprintln!("Failed to update summary job {}: {}", job.job_id, e),
+        }
+    }
+
+    /// 実行中であることを定期的に記録しながら要約を作成
+    /// 戻り値: 作成した要約のID（ジョブが別のワーカーに移った場合は None）
+    async fn summarize_with_heartbeat(&self, job: &SummaryJob) -> Result<Option<String>> {
+        let summarize = self.summarize(job);
+        tokio::pin!(summarize);
+
+        let mut heartbeat =
+            tokio::time::interval(std::time::Duration::from_secs(HEARTBEAT_SECONDS));
+        // 最初のtickはすぐに完了するため読み捨てる
+        heartbeat.tick().await;
+
+        loop {
+            tokio::select! {
+                result = &mut summarize => return result,
+                _ = heartbeat.tick() => {
+                    match self.job_repo.heartbeat(&job.job_id, job.attempts).await {
+                        Ok(true) => {}
+                        // 実行し直しているワーカーと競合しないよう、生成を中止する
+                        Ok(false) => return Ok(None),
+                        Err(e) => eprintln!(
+                            "Failed to record heartbeat for summary job {}: {}",
+                            job.job_id, e
+                        ),
+                    }
+                }
+            }
+        }
+    }
+
+    /// 戻り値: 作成した要約のID（ジョブが別のワーカーに移った場合は None）
+    async fn summarize(&self, job: &SummaryJob) -> Result<Option<String>> {
+        if !self
+            .job_repo
+            .set_stage(&job.job_id, job.attempts, SummaryJobStage::LoadingMemos)
+            .await?
+        {
+            return Ok(None);
+        }
+        let memos = self
+            .summary_service
+            .load_memos(&job.user_id, &job.memo_ids)
+            .await?;
+
+        if !self
+            .job_repo
+            .set_stage(&job.job_id, job.attempts, SummaryJobStage::Generating)
+            .await?
+        {
+            return Ok(None);
+        }
+        let content = self.summary_service.generate_summary(&memos).await?;
+
+        // 保存の直前にも確認し、実行し直しているジョブと要約が重複しないようにする
+        if !self
+            .job_repo
+            .set_stage(&job.job_id, job.attempts, SummaryJobStage::Saving)
+            .await?
+        {
+            return Ok(None);
+        }
+        let summary = self
+            .summary_service
+            .save_summary(job.user_id.clone(), job.memo_ids.clone(), content, false)
+            .await?;
+        Ok(Some(summary.summary_id))
+    }
+}
+
+/// 時間をおけば成功する可能性がある失敗かどうか（LLM・DBの一時的な障害など）
+fn is_retryable(error: &AppError) -> bool {
+    matches!(
+        error,
+        AppError::ExternalServiceError(_) | AppError::DatabaseError(_)
+    )
+}
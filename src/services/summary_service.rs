@@ -1,31 +1,72 @@
 use crate::{
     error::{AppError, Result},
+    llm::{
+        LlmProvider, TextStream,
+        tokens::{estimate_tokens, truncate_to_tokens},
+    },
     repositories::{
-        AISummary, Memo, MemoHandler, MemoRepository, SummaryRepository, summary::SummaryHandler,
+        AISummary, Memo, MemoHandler, MemoRepository, SummaryRepository, SummaryView,
+        summary::SummaryHandler, summary_chunk::SummaryChunkHandler,
     },
+    services::SearchService,
 };
-use chrono::Utc;
-use reqwest::Client;
-use serde_json::json;
-use std::{env, sync::Arc};
+use chrono::{DateTime, Utc};
+use futures::stream::{self, StreamExt, TryStreamExt};
+use sha2::{Digest, Sha256};
+use std::{collections::HashSet, sync::Arc};
 use uuid::Uuid;
 
+/// 分割した部分を同時に要約する数
+const CHUNK_CONCURRENCY: usize = 4;
+/// 部分の要約を統合する段数の上限（それでも収まらない場合はエラーにする）
+const MAX_REDUCE_LEVELS: usize = 4;
+/// 箇条書きの1項目あたりの、本文以外のトークン数（"- " と改行）
+const ITEM_OVERHEAD_TOKENS: usize = 2;
+
 pub struct SummaryService {
     summary_repo: Arc<SummaryRepository>,
     memo_repo: Arc<MemoRepository>,
+    search_service: Arc<SearchService>,
+    /// 分割して要約したときの途中の要約のキャッシュ
+    chunk_repo: Arc<dyn SummaryChunkHandler>,
+    /// 要約に使うLLM
+    llm: Arc<dyn LlmProvider>,
+    /// 1回のリクエストで送るテキストのトークン数の上限
+    max_input_tokens: usize,
 }
 
 impl SummaryService {
-    pub fn new(summary_repo: Arc<SummaryRepository>, memo_repo: Arc<MemoRepository>) -> Self {
+    pub fn new(
+        summary_repo: Arc<SummaryRepository>,
+        memo_repo: Arc<MemoRepository>,
+        search_service: Arc<SearchService>,
+        chunk_repo: Arc<dyn SummaryChunkHandler>,
+        llm: Arc<dyn LlmProvider>,
+        max_input_tokens: usize,
+    ) -> Self {
         Self {
             summary_repo,
             memo_repo,
+            search_service,
+            chunk_repo,
+            llm,
+            max_input_tokens,
         }
     }
 
     // ユーザーのジャーナル（要約）履歴を取得
-    pub async fn get_user_journals(&self, user_id: &str) -> Result<Vec<AISummary>> {
-        self.summary_repo.find_by_user_id(user_id).await
+    pub async fn get_user_journals(&self, user_id: &str) -> Result<Vec<SummaryView>> {
+        let summaries = self.summary_repo.find_by_user_id(user_id).await?;
+        self.to_views(summaries).await
+    }
+
+    /// 要約を取得（要約元メモの状態を付加）
+    pub async fn get_summary(&self, user_id: &str, summary_id: &str) -> Result<SummaryView> {
+        let summary = self.get_summary_by_id(user_id, summary_id).await?;
+        let mut views = self.to_views(vec![summary]).await?;
+        views
+            .pop()
+            .ok_or_else(|| AppError::NotFound("Summary not found".to_string()))
     }
 
     pub async fn get_summary_by_id(&self, user_id: &str, summary_id: &str) -> Result<AISummary> {
@@ -33,6 +74,7 @@ impl SummaryService {
             .summary_repo
             .find_by_id(summary_id)
             .await?
+            .filter(|summary| summary.deleted_at.is_none()) // ゴミ箱の要約は存在しないものとして扱う
             .ok_or_else(|| AppError::DatabaseError("Summary not found".to_string()))?;
 
         if summary.user_id != user_id {
@@ -50,12 +92,24 @@ impl SummaryService {
         is_auto_generated: bool,
     ) -> Result<AISummary> {
         // 0. MemoIDからMemo本体を取得し、user_idでフィルタリング
+        let memos = self.load_memos(&user_id, &memo_ids).await?;
+
+        // 1. 要約ロジックの実行
+        let summary_content = self.generate_summary(&memos).await?; // 外部API呼び出し部分
+
+        // 2. DBへ保存
+        self.save_summary(user_id, memo_ids, summary_content, is_auto_generated)
+            .await
+    }
+
+    /// 要約するメモ（他のユーザーのメモ・ゴミ箱内のメモは除く）
+    pub async fn load_memos(&self, user_id: &str, memo_ids: &[String]) -> Result<Vec<Memo>> {
         let memos = self
             .memo_repo
-            .find_by_ids(&memo_ids)
+            .find_by_ids(memo_ids)
             .await?
             .into_iter()
-            .filter(|memo| memo.user_id == user_id)
+            .filter(|memo| memo.user_id == user_id && memo.deleted_at.is_none())
             .collect::<Vec<Memo>>();
 
         // メモが空ならAPIを呼ばずにエラーを返す
@@ -64,11 +118,18 @@ impl SummaryService {
                 "No memos to summarize".to_string(),
             ));
         }
+        Ok(memos)
+    }
 
-        // 1. 要約ロジックの実行
-        let summary_content = self.call_gemini_api(&memos).await?; // 外部API呼び出し部分
-
-        // 2. DBへの保存データの構築
+    /// 生成した要約を保存し、検索インデックスに登録
+    pub async fn save_summary(
+        &self,
+        user_id: String,
+        memo_ids: Vec<String>,
+        summary_content: String,
+        is_auto_generated: bool,
+    ) -> Result<AISummary> {
+        // DBへの保存データの構築
         let now = Utc::now();
         let summary = AISummary {
             summary_id: Uuid::new_v4().to_string(),
@@ -78,87 +139,493 @@ impl SummaryService {
             created_at: now,
             updated_at: now,
             is_auto_generated: is_auto_generated,
+            deleted_at: None,
         };
 
-        // 3. DBへ保存
-        self.summary_repo.create(summary).await
+        let summary = self.summary_repo.create(summary).await?;
+
+        // 検索インデックスに登録（失敗しても要約の保存は成功させる）
+        if let Err(e) = self.search_service.index_summary(&summary).await {
+            eprintln!(
+                "Failed to update search index for summary {}: {}",
+                summary.summary_id, e
+            );
+        }
+        Ok(summary)
     }
 
+    /// 要約をゴミ箱に移動（論理削除）
     pub async fn delete_summary(&self, user_id: &str, summary_id: &str) -> Result<()> {
         // 削除前に要約の所有者を確認
         self.get_summary_by_id(user_id, summary_id).await?;
+        self.summary_repo
+            .set_deleted_at(summary_id, Some(Utc::now()))
+            .await?;
+
+        if let Err(e) = self.search_service.remove_summary(summary_id).await {
+            eprintln!(
+                "Failed to remove summary {} from search index: {}",
+                summary_id, e
+            );
+        }
+        Ok(())
+    }
+
+    /// ゴミ箱内の要約一覧
+    pub async fn list_trash(&self, user_id: &str) -> Result<Vec<AISummary>> {
+        self.summary_repo.find_trashed_by_user_id(user_id).await
+    }
+
+    /// ゴミ箱から要約を復元
+    pub async fn restore_from_trash(&self, user_id: &str, summary_id: &str) -> Result<SummaryView> {
+        let mut summary = self.find_trashed(user_id, summary_id).await?;
+        self.summary_repo.set_deleted_at(summary_id, None).await?;
+        summary.deleted_at = None;
+
+        if let Err(e) = self.search_service.index_summary(&summary).await {
+            eprintln!(
+                "Failed to update search index for summary {}: {}",
+                summary_id, e
+            );
+        }
+
+        let mut views = self.to_views(vec![summary]).await?;
+        views
+            .pop()
+            .ok_or_else(|| AppError::NotFound("Summary not found".to_string()))
+    }
+
+    /// ゴミ箱内の要約を完全に削除
+    pub async fn purge_from_trash(&self, user_id: &str, summary_id: &str) -> Result<()> {
+        self.find_trashed(user_id, summary_id).await?;
         self.summary_repo.delete(summary_id).await
     }
 
-    // Gemini APIを呼び出す関数
-    async fn call_gemini_api(&self, memos: &[Memo]) -> Result<String> {
-        let input_text = memos
+    /// ユーザーのゴミ箱を空にする
+    /// 戻り値: 削除した件数
+    pub async fn empty_trash(&self, user_id: &str) -> Result<usize> {
+        let summaries = self.summary_repo.find_trashed_by_user_id(user_id).await?;
+        for summary in &summaries {
+            self.summary_repo.delete(&summary.summary_id).await?;
+        }
+        Ok(summaries.len())
+    }
+
+    /// 保持期間を過ぎたゴミ箱内の要約を完全に削除
+    /// 戻り値: 削除した件数
+    pub async fn purge_trash_before(&self, cutoff: DateTime<Utc>) -> Result<usize> {
+        let summaries = self.summary_repo.find_trashed_before(cutoff).await?;
+        for summary in &summaries {
+            self.summary_repo.delete(&summary.summary_id).await?;
+        }
+        Ok(summaries.len())
+    }
+
+    /// ユーザーの要約をゴミ箱内のものも含めてすべて完全に削除（アカウント削除用）
+    /// 戻り値: 削除した件数
+    pub async fn purge_all_by_user(&self, user_id: &str) -> Result<usize> {
+        let mut summaries = self.summary_repo.find_by_user_id(user_id).await?;
+        summaries.extend(self.summary_repo.find_trashed_by_user_id(user_id).await?);
+        for summary in &summaries {
+            self.summary_repo.delete(&summary.summary_id).await?;
+        }
+        self.chunk_repo.delete_by_user_id(user_id).await?;
+        Ok(summaries.len())
+    }
+
+    async fn find_trashed(&self, user_id: &str, summary_id: &str) -> Result<AISummary> {
+        let summary = self
+            .summary_repo
+            .find_by_id(summary_id)
+            .await?
+            .filter(|summary| summary.deleted_at.is_some())
+            .ok_or_else(|| AppError::NotFound("Summary not found in trash".to_string()))?;
+
+        if summary.user_id != user_id {
+            return Err(AppError::Forbidden("Access denied".to_string()));
+        }
+        Ok(summary)
+    }
+
+    /// 要約元メモのうちゴミ箱にあるものを調べてレスポンスを構築
+    async fn to_views(&self, summaries: Vec<AISummary>) -> Result<Vec<SummaryView>> {
+        let memo_ids: Vec<String> = summaries
             .iter()
-            .map(|memo| format!("- {}", memo.content)) // 各メモをハイフン付きの箇条書き形式に変換
-            .collect::<Vec<String>>() // ベクタに収集
-            .join("\n"); // 改行で結合して一つの文字列にする
+            .flat_map(|s| s.memo_ids.iter().cloned())
+            .collect::<HashSet<String>>()
+            .into_iter()
+            .collect();
 
-        // debug用出力
-        #[cfg(debug_assertions)]
-        {
-            println!("AIに送るテキスト:\n{}", input_text);
+        let trashed: HashSet<String> = if memo_ids.is_empty() {
+            HashSet::new()
+        } else {
+            self.memo_repo
+                .find_by_ids(&memo_ids)
+                .await?
+                .into_iter()
+                .filter(|memo| memo.deleted_at.is_some())
+                .map(|memo| memo.memo_id)
+                .collect()
+        };
+
+        Ok(summaries
+            .into_iter()
+            .map(|summary| {
+                let trashed_memo_ids = summary
+                    .memo_ids
+                    .iter()
+                    .filter(|id| trashed.contains(*id))
+                    .cloned()
+                    .collect();
+                SummaryView {
+                    summary,
+                    trashed_memo_ids,
+                }
+            })
+            .collect())
+    }
+
+    // LLMで要約を生成する関数
+    pub async fn generate_summary(&self, memos: &[Memo]) -> Result<String> {
+        let prompt = self.final_prompt(memos).await?;
+        self.llm.generate(&prompt).await
+    }
+
+    /// LLMで要約を生成し、生成されたところから順に返す
+    /// （ストリームを破棄するとLLMへのリクエストも中断される）
+    pub async fn generate_summary_stream(&self, memos: &[Memo]) -> Result<TextStream> {
+        let prompt = self.final_prompt(memos).await?;
+        self.llm.generate_stream(&prompt).await
+    }
+
+    /// 最終的な要約のプロンプト
+    ///
+    /// メモがトークン数の上限に収まらない場合は、上限に収まる部分ごとに要約し（map）、
+    /// 部分の要約が上限に収まるまで、さらにまとめて要約する（reduce）。
+    async fn final_prompt(&self, memos: &[Memo]) -> Result<String> {
+        let budget = self.item_budget(PromptKind::Final);
+        // 1件で上限を超えるメモは切り詰める
+        let mut items: Vec<String> = memos
+            .iter()
+            .map(|memo| truncate_to_tokens(&memo.content, budget))
+            .collect();
+        if fits(&items, budget) {
+            return Ok(summary_prompt(PromptKind::Final, &items));
+        }
+
+        let user_id = memos.first().map(|memo| memo.user_id.as_str()).unwrap_or_default();
+        for level in 0..MAX_REDUCE_LEVELS {
+            let kind = if level == 0 {
+                PromptKind::MemoChunk
+            } else {
+                PromptKind::SummaryChunk
+            };
+            let chunks = chunk_items(&items, self.item_budget(kind));
+            items = stream::iter(chunks)
+                .map(|chunk| self.summarize_chunk(user_id, kind, chunk))
+                .buffered(CHUNK_CONCURRENCY)
+                .try_collect()
+                .await?;
+
+            if fits(&items, self.item_budget(PromptKind::Reduce)) {
+                return Ok(summary_prompt(PromptKind::Reduce, &items));
+            }
+        }
+
+        Err(AppError::ValidationError(
+            "Too many memos to summarize at once".to_string(),
+        ))
+    }
+
+    /// 部分の要約（同じモデル・同じ内容の部分はキャッシュを使う）
+    async fn summarize_chunk(
+        &self,
+        user_id: &str,
+        kind: PromptKind,
+        items: Vec<String>,
+    ) -> Result<String> {
+        let prompt = summary_prompt(kind, &items);
+        let cache_key = hex::encode(Sha256::digest(
+            format!("{}\n{}", self.llm.model_id(), prompt).as_bytes(),
+        ));
+        if let Some(content) = self.chunk_repo.find(user_id, &cache_key).await? {
+            return Ok(content);
         }
 
+        let content = self.llm.generate(&prompt).await?;
+        // キャッシュに保存できなくても要約は続ける
+        if let Err(e) = self.chunk_repo.save(user_id, &cache_key, &content).await {
+            eprintln!("Failed to cache summary chunk: {}", e);
+        }
+        Ok(content)
+    }
+
+    /// プロンプトのうち、箇条書きに使えるトークン数
+    fn item_budget(&self, kind: PromptKind) -> usize {
+        // 指示文が上限を超える設定でも、少なくとも1件は送れるようにする
+        self.max_input_tokens
+            .saturating_sub(estimate_tokens(kind.instruction()))
+            .max(ITEM_OVERHEAD_TOKENS * 16)
+    }
+}
+
+/// 要約のプロンプトの種類
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum PromptKind {
+    /// メモをそのまま要約する
+    Final,
+    /// 分割したメモの一部を要約する
+    MemoChunk,
+    /// 部分の要約をさらにまとめる
+    SummaryChunk,
+    /// 部分の要約を統合して最終的な要約を作る
+    Reduce,
+}
+
+impl PromptKind {
+    fn instruction(&self) -> &'static str {
+        match self {
+            PromptKind::Final => "以下の箇条書きのメモは、あるユーザーの一日の記録です。これらを統合して、一日の振り返り日記のような自然な文章に要約してください。尚、タイトルを先頭に付けることとし、 # タイトル名 の形式で作成した上で改行してください。\n\n[メモ内容]\n",
+            PromptKind::MemoChunk => "以下の箇条書きのメモは、あるユーザーの記録の一部です。後で他の部分と統合するため、出来事・感情・気づきを漏らさず、簡潔な箇条書きにまとめてください。タイトルは付けないでください。\n\n[メモ内容]\n",
+            PromptKind::SummaryChunk => "以下は、あるユーザーの記録を部分ごとに要約したものの一部です。後で他の部分と統合するため、重要な出来事・感情・気づきを漏らさず、簡潔な箇条書きにまとめてください。タイトルは付けないでください。\n\n[要約内容]\n",
+            PromptKind::Reduce => "以下は、あるユーザーの記録を部分ごとに要約したものです。これらを統合して、期間全体の振り返り日記のような自然な文章に要約してください。尚、タイトルを先頭に付けることとし、 # タイトル名 の形式で作成した上で改行してください。\n\n[要約内容]\n",
+        }
+    }
+}
+
+/// 箇条書き全体のトークン数が budget 以下かどうか
+fn fits(items: &[String], budget: usize) -> bool {
+    items
+        .iter()
+        .map(|item| estimate_tokens(item) + ITEM_OVERHEAD_TOKENS)
+        .sum::<usize>()
+        <= budget
+}
+
+/// 順番を保ったまま、トークン数が budget 以下になるように分割
+fn chunk_items(items: &[String], budget: usize) -> Vec<Vec<String>> {
+    let mut chunks = Vec::new();
+    let mut chunk = Vec::new();
+    let mut tokens = 0;
+    for item in items {
+        let item = truncate_to_tokens(item, budget.saturating_sub(ITEM_OVERHEAD_TOKENS));
+        let cost = estimate_tokens(&item) + ITEM_OVERHEAD_TOKENS;
+        if !chunk.is_empty() && tokens + cost > budget {
+            chunks.push(std::mem::take(&mut chunk));
+            tokens = 0;
+        }
+        tokens += cost;
+        chunk.push(item);
+    }
+    if !chunk.is_empty() {
+        chunks.push(chunk);
+    }
+    chunks
+}
+
+/// 要約のプロンプト
+fn summary_prompt(kind: PromptKind, items: &[String]) -> String {
+    let input_text = items
+        .iter()
+        .map(|item| format!("- {}", item)) // 各項目をハイフン付きの箇条書き形式に変換
+        .collect::<Vec<String>>() // ベクタに収集
+        .join("\n"); // 改行で結合して一つの文字列にする
+
+    // debug用出力
+    #[cfg(debug_assertions)]
+    {
+        println!("AIに送るテキスト:\n{}", input_text);
+    }
+
     // AIに送るプロンプトを作成
-    let prompt = format!(
-        "以下の箇条書きのメモは、あるユーザーの一日の記録です。これらを統合して、一日の振り返り日記のような自然な文章に要約してください。尚、タイトルを先頭に付けることとし、 # タイトル名 の形式で作成した上で改行してください。\n\n[メモ内容]\n{}",
-        input_text
-    );
-
-        // APIキーの取得
-        let api_key = env::var("GEMINI_API_KEY")
-            .map_err(|_| AppError::ConfigError("GEMINI_API_KEY is not set".to_string()))?;
-
-        let url = format!(
-            "https://generativelanguage.googleapis.com/v1beta/models/gemini-2.5-flash:generateContent?key={}",
-            api_key
-        );
+    format!("{}{}", kind.instruction(), input_text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm::MockProvider;
+    use crate::repositories::SearchIndexRepository;
+    use std::collections::HashMap;
+    use std::sync::Mutex;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// 呼び出し回数を数えるプロバイダー
+    struct CountingProvider {
+        inner: MockProvider,
+        calls: AtomicUsize,
+    }
+
+    #[async_trait::async_trait]
+    impl LlmProvider for CountingProvider {
+        fn model_id(&self) -> String {
+            self.inner.model_id()
+        }
+
+        async fn generate(&self, prompt: &str) -> Result<String> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            self.inner.generate(prompt).await
+        }
+    }
+
+    /// メモリ上の部分の要約のキャッシュ
+    #[derive(Default)]
+    struct InMemoryChunkRepository {
+        chunks: Mutex<HashMap<(String, String), String>>,
+    }
 
-        let client = Client::new(); // reqwestのHTTPクライアントを作成
-        let response = client
-            .post(&url)
-            .json(&json!({
-                "contents": [{
-                    "parts": [{
-                        "text": prompt
-                    }]
-                }]
-            }))
-            .send()
+    #[async_trait::async_trait]
+    impl SummaryChunkHandler for InMemoryChunkRepository {
+        async fn find(&self, user_id: &str, cache_key: &str) -> Result<Option<String>> {
+            let chunks = self.chunks.lock().unwrap();
+            Ok(chunks
+                .get(&(user_id.to_string(), cache_key.to_string()))
+                .cloned())
+        }
+
+        async fn save(&self, user_id: &str, cache_key: &str, content: &str) -> Result<()> {
+            let mut chunks = self.chunks.lock().unwrap();
+            chunks.insert(
+                (user_id.to_string(), cache_key.to_string()),
+                content.to_string(),
+            );
+            Ok(())
+        }
+
+        async fn delete_by_user_id(&self, user_id: &str) -> Result<u64> {
+            let mut chunks = self.chunks.lock().unwrap();
+            let before = chunks.len();
+            chunks.retain(|(id, _), _| id != user_id);
+            Ok((before - chunks.len()) as u64)
+        }
+    }
+
+    /// 部分の要約だけを確かめるため、MongoDBには接続しない（クライアントは接続を遅延する）
+    async fn service(
+        llm: Arc<CountingProvider>,
+        chunk_repo: Arc<InMemoryChunkRepository>,
+        max_input_tokens: usize,
+    ) -> SummaryService {
+        let db = mongodb::Client::with_uri_str("mongodb://127.0.0.1:27017")
             .await
-            .map_err(|e| {
-                AppError::ExternalServiceError(format!("Failed to send request: {}", e))
-            })?;
-
-        // ステータスコード確認
-        if !response.status().is_success() {
-            let status = response.status();
-            let error_text = response.text().await.unwrap_or_default();
-            return Err(AppError::ExternalServiceError(format!(
-                "Gemini API error: status={}, body={}",
-                status, error_text
-            )));
-        }
-
-        // レスポンスのパース
-        let response_json: serde_json::Value = response.json().await.map_err(|e| {
-            AppError::ExternalServiceError(format!("Failed to parse response: {}", e))
-        })?;
-
-        // 要約テキストの抽出
-        let content = response_json["candidates"]
-            .get(0)
-            .and_then(|c| c["content"]["parts"].get(0)) // 最初の候補の content の parts 配列の最初の要素を取得
-            .and_then(|p| p["text"].as_str()) // テキスト部分を文字列として取得
-            .ok_or_else(|| {
-                AppError::ExternalServiceError("Invalid response format from Gemini".to_string())
-            })?
-            .to_string();
-        Ok(content)
+            .unwrap()
+            .database("mimo_test");
+        let memo_repo = Arc::new(MemoRepository::new(db.clone()));
+        let summary_repo = Arc::new(SummaryRepository::new(db.clone()));
+        let search_service = Arc::new(SearchService::new(
+            Arc::new(SearchIndexRepository::new(db)),
+            memo_repo.clone(),
+            summary_repo.clone(),
+        ));
+        SummaryService::new(
+            summary_repo,
+            memo_repo,
+            search_service,
+            chunk_repo,
+            llm,
+            max_input_tokens,
+        )
+    }
+
+    fn memo(index: usize, content: String) -> Memo {
+        Memo {
+            memo_id: format!("memo_{}", index),
+            content,
+            user_id: "user_1".to_string(),
+            auto_tag_id: None,
+            manual_tag_id: None,
+            share_url_token: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            deleted_at: None,
+            revision: 1,
+        }
+    }
+
+    fn items(contents: &[&str]) -> Vec<String> {
+        contents.iter().map(|c| c.to_string()).collect()
+    }
+
+    #[test]
+    fn chunk_items_keeps_order_within_budget() {
+        let items: Vec<String> = (0..50)
+            .map(|i| format!("{} 今日の出来事 {}", i, "あ".repeat(i % 7)))
+            .collect();
+        let budget = 40;
+        let chunks = chunk_items(&items, budget);
+
+        assert!(chunks.len() > 1);
+        for chunk in &chunks {
+            assert!(!chunk.is_empty());
+            assert!(fits(chunk, budget));
+        }
+        assert_eq!(chunks.concat(), items);
+    }
+
+    #[test]
+    fn chunk_items_truncates_oversized_item() {
+        let long = "漢".repeat(100);
+        let chunks = chunk_items(&items(&["a", &long, "b"]), 20);
+
+        assert_eq!(chunks.len(), 3);
+        assert_eq!(chunks[0], items(&["a"]));
+        assert!(chunks[1][0].ends_with('…'));
+        assert!(fits(&chunks[1], 20));
+        assert_eq!(chunks[2], items(&["b"]));
+    }
+
+    #[test]
+    fn fits_counts_item_overhead() {
+        assert!(fits(&[], 0));
+        assert!(fits(&items(&["abc"]), 1 + ITEM_OVERHEAD_TOKENS));
+        assert!(!fits(&items(&["abc"]), ITEM_OVERHEAD_TOKENS));
+    }
+
+    #[tokio::test]
+    async fn small_memo_set_is_summarized_in_one_call() {
+        let llm = Arc::new(CountingProvider {
+            inner: MockProvider::new("test".to_string()),
+            calls: AtomicUsize::new(0),
+        });
+        let chunk_repo = Arc::new(InMemoryChunkRepository::default());
+        let service = service(llm.clone(), chunk_repo.clone(), 1_000).await;
+
+        let memos = vec![memo(0, "短いメモ".to_string())];
+        service.generate_summary(&memos).await.unwrap();
+
+        assert_eq!(llm.calls.load(Ordering::SeqCst), 1);
+        assert!(chunk_repo.chunks.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn regenerating_summary_reuses_cached_chunks() {
+        let llm = Arc::new(CountingProvider {
+            inner: MockProvider::new("test".to_string()),
+            calls: AtomicUsize::new(0),
+        });
+        let chunk_repo = Arc::new(InMemoryChunkRepository::default());
+        let service = service(llm.clone(), chunk_repo.clone(), 1_000).await;
+
+        let memos: Vec<Memo> = (0..40)
+            .map(|i| memo(i, format!("memo {:02} {}", i, "x".repeat(100))))
+            .collect();
+
+        let first = service.generate_summary(&memos).await.unwrap();
+        let first_calls = llm.calls.load(Ordering::SeqCst);
+        let cached = chunk_repo.chunks.lock().unwrap().len();
+        // 部分ごとの要約と、それをまとめた最終的な要約
+        assert!(cached >= 2);
+        assert_eq!(first_calls, cached + 1);
+
+        let second = service.generate_summary(&memos).await.unwrap();
+        // 部分の要約はキャッシュから取得し、最終的な要約だけを呼び出す
+        assert_eq!(llm.calls.load(Ordering::SeqCst), first_calls + 1);
+        assert_eq!(second, first);
+
+        // 削除するとキャッシュも消える
+        assert_eq!(
+            chunk_repo.delete_by_user_id("user_1").await.unwrap(),
+            cached as u64
+        );
     }
 }
@@ -1,23 +1,19 @@
 use crate::{
-    error::{AppError, Result},
+    error::Result,
+    llm::LlmProvider,
     repositories::{CreateTagRequest, Tag, TagRepository, UpdateTagRequest, tag::TagHandler}, // TagHandlerトレイトをインポート
 };
-use reqwest::Client; // HTTPクライアント
-use serde_json::json;
 use std::sync::Arc;
- // JSONマクロ
 
 pub struct TagService {
     tag_repo: Arc<TagRepository>, // MongoTagRepository → TagRepositoryに変更
-    gemini_api_key: String,
+    /// タグ推薦に使うLLM
+    llm: Arc<dyn LlmProvider>,
 }
 
 impl TagService {
-    pub fn new(tag_repo: Arc<TagRepository>, gemini_api_key: String) -> Self {
-        Self {
-            tag_repo,
-            gemini_api_key,
-        }
+    pub fn new(tag_repo: Arc<TagRepository>, llm: Arc<dyn LlmProvider>) -> Self {
+        Self { tag_repo, llm }
     }
 
     pub async fn get_tags_by_user(&self, user_id: &str) -> Result<Vec<Tag>> {
@@ -59,53 +55,8 @@ impl TagService {
             memo_content, tags_str
         );
 
-        // APIキーのチェック
-        if self.gemini_api_key.is_empty() {
-            return Err(AppError::ConfigError(
-                "GEMINI_API_KEY is not configured".to_string(),
-            ));
-        }
-
-        let url = format!(
-            "https://generativelanguage.googleapis.com/v1beta/models/gemini-2.5-flash:generateContent?key={}",
-            self.gemini_api_key
-        );
-
-        let client = Client::new();
-        let response = client // reqwestのHTTPクライアントを作成
-            .post(&url) // POSTリクエスト
-            .json(&json!({
-                "contents": [{
-                    "parts": [{ "text": prompt }] // プロンプトをJSON形式で送信
-                }]
-            }))
-            .send()
-            .await
-            .map_err(|e| {
-                AppError::ExternalServiceError(format!("Failed to send request: {}", e))
-            })?;
-
-        // ステータスコードチェック
-        if !response.status().is_success() {
-            let status = response.status();
-            let error_text = response.text().await.unwrap_or_default();
-            return Err(AppError::ExternalServiceError(format!(
-                "Gemini API error in auto-tagging: status={}, body={}",
-                status, error_text
-            )));
-        }
-
-        // レスポンス解析
-        let response_json: serde_json::Value = response.json().await.map_err(|e| {
-            AppError::ExternalServiceError(format!("Failed to parse response: {}", e))
-        })?;
-
-        let suggested_name = response_json["candidates"] // AIの返答からタグ名を抽出
-            .get(0)
-            .and_then(|c| c["content"]["parts"].get(0)) // 最初の候補の content の parts 配列の最初の要素を取得
-            .and_then(|p| p["text"].as_str()) // テキスト部分を文字列として取得
-            .map(|s| s.trim()) // 前後の改行や空白を削除
-            .unwrap_or("None");
+        let response = self.llm.generate(&prompt).await?;
+        let suggested_name = response.trim(); // 前後の改行や空白を削除
 
         // タグ名からIDに変換
         if let Some(tag) = tags.into_iter().find(|t| t.name.trim() == suggested_name) {
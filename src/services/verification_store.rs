@@ -7,6 +7,7 @@ use std::sync::Arc;
 pub enum VerificationPurpose {
     Registration,  // ユーザー登録
     PasswordReset, // パスワードリセット
+    EmailChange,   // メールアドレスの変更（ユーザーIDごとに保存し、変更後のアドレスに送る）
 }
 
 /// 認証コードの情報
@@ -83,8 +84,36 @@ impl VerificationStore {
         code: &str,
         purpose: &VerificationPurpose,
     ) -> Result<bool, String> {
-        let key = (email.to_string(), purpose.clone());
-        
+        self.verify_entry((email.to_string(), purpose.clone()), code)
+            .map(|verified| verified.is_some())
+    }
+
+    /// メールアドレス変更の確認コードを保存（ユーザーごとに1つ、有効期限: 15分）
+    pub fn store_email_change_code(&self, user_id: String, new_email: String, code: String) {
+        let verification = VerificationCode {
+            email: new_email,
+            code,
+            purpose: VerificationPurpose::EmailChange,
+            expires_at: Utc::now() + Duration::minutes(15),
+            attempts: 0,
+        };
+        self.verification_codes
+            .insert((user_id, VerificationPurpose::EmailChange), verification);
+    }
+
+    /// メールアドレス変更の確認コードを検証
+    /// 戻り値: 確認できた場合は変更後のメールアドレス
+    pub fn verify_email_change_code(&self, user_id: &str, code: &str) -> Result<Option<String>, String> {
+        self.verify_entry((user_id.to_string(), VerificationPurpose::EmailChange), code)
+            .map(|verified| verified.map(|verification| verification.email))
+    }
+
+    /// 認証コードを検証し、一致した場合は削除して返す
+    fn verify_entry(
+        &self,
+        key: (String, VerificationPurpose),
+        code: &str,
+    ) -> Result<Option<VerificationCode>, String> {
         // get_mutを使って原子的にチェックと更新を行う
         let mut entry = self
             .verification_codes
@@ -114,10 +143,9 @@ impl VerificationStore {
         // コード照合
         if stored_code == code {
             drop(entry); // ロックを解放してから削除
-            self.verification_codes.remove(&key);
-            Ok(true)
+            Ok(self.verification_codes.remove(&key).map(|(_, verification)| verification))
         } else {
-            Ok(false)
+            Ok(None)
         }
     }
 
@@ -0,0 +1,315 @@
+use crate::{
+    error::{AppError, Result},
+    repositories::webauthn::{
+        NewWebAuthnCredential, WebAuthnAuthenticationChallenge, WebAuthnCredential,
+        WebAuthnRegistrationChallenge, WebAuthnRepository,
+    },
+    webauthn::{
+        AuthenticationCredential, CredentialDescriptor, RegistrationCredential, RelyingParty,
+        UserVerification, decode_base64url,
+    },
+};
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use chrono::{DateTime, Duration, Utc};
+use dashmap::DashMap;
+use sha2::{Digest, Sha256};
+use std::sync::Arc;
+
+/// ユーザーあたりの資格情報数の上限
+const MAX_CREDENTIALS_PER_USER: i64 = 20;
+const NAME_MAX_LEN: usize = 100;
+const DEFAULT_CREDENTIAL_NAME: &str = "Passkey";
+
+/// 開始済みのセレモニー（チャレンジは一度だけ使用できる）
+struct Ceremony {
+    challenge: [u8; 32],
+    kind: CeremonyKind,
+    expires_at: DateTime<Utc>,
+}
+
+enum CeremonyKind {
+    Registration { user_id: String },
+    /// user_id が None の場合はパスキーによるパスワードレスログイン
+    Authentication {
+        user_id: Option<String>,
+        user_verification: UserVerification,
+    },
+}
+
+pub struct WebAuthnService {
+    credential_repo: Arc<WebAuthnRepository>,
+    relying_party: RelyingParty,
+    // ceremony_id -> Ceremony
+    ceremonies: Arc<DashMap<String, Ceremony>>,
+}
+
+impl WebAuthnService {
+    pub fn new(credential_repo: Arc<WebAuthnRepository>, relying_party: RelyingParty) -> Self {
+        let service = Self {
+            credential_repo,
+            relying_party,
+            ceremonies: Arc::new(DashMap::new()),
+        };
+
+        // バックグラウンドで定期的に期限切れのセレモニーをクリーンアップ
+        let ceremonies = service.ceremonies.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(300)); // 5分ごと
+            loop {
+                interval.tick().await;
+                let now = Utc::now();
+                ceremonies.retain(|_, ceremony| ceremony.expires_at > now);
+            }
+        });
+
+        service
+    }
+
+    /// 登録セレモニーを開始
+    pub async fn start_registration(
+        &self,
+        user_id: &str,
+        user_name: &str,
+        display_name: &str,
+    ) -> Result<WebAuthnRegistrationChallenge> {
+        let existing = self.credential_repo.find_by_user_id(user_id).await?;
+        if existing.len() as i64 >= MAX_CREDENTIALS_PER_USER {
+            return Err(AppError::ValidationError(format!(
+                "A user can have at most {} passkeys",
+                MAX_CREDENTIALS_PER_USER
+            )));
+        }
+
+        // 登録済みの認証器で重複して登録しないよう除外する
+        let exclude_credentials = existing
+            .into_iter()
+            .map(|credential| CredentialDescriptor::new(credential.credential_id, credential.transports))
+            .collect();
+
+        let (ceremony_id, challenge) = self.begin_ceremony(CeremonyKind::Registration {
+            user_id: user_id.to_string(),
+        });
+
+        Ok(WebAuthnRegistrationChallenge {
+            ceremony_id,
+            public_key: self.relying_party.creation_options(
+                &challenge,
+                &user_handle(user_id),
+                user_name,
+                display_name,
+                exclude_credentials,
+            ),
+        })
+    }
+
+    /// 登録セレモニーを完了し、資格情報を保存
+    pub async fn finish_registration(
+        &self,
+        user_id: &str,
+        ceremony_id: &str,
+        name: Option<&str>,
+        credential: &RegistrationCredential,
+    ) -> Result<WebAuthnCredential> {
+        let name = validate_credential_name(name.unwrap_or(DEFAULT_CREDENTIAL_NAME))?;
+
+        let ceremony = self.take_ceremony(ceremony_id)?;
+        let CeremonyKind::Registration { user_id: ceremony_user } = &ceremony.kind else {
+            return Err(invalid_ceremony());
+        };
+        if ceremony_user != user_id {
+            return Err(invalid_ceremony());
+        }
+
+        let verified = self
+            .relying_party
+            .verify_registration(credential, &ceremony.challenge)?;
+
+        self.credential_repo
+            .create(NewWebAuthnCredential {
+                credential_id: verified.credential_id,
+                user_id: user_id.to_string(),
+                name,
+                public_key: verified.public_key,
+                algorithm: verified.algorithm as i32,
+                sign_count: verified.sign_count as i64,
+                transports: credential.response.transports.clone(),
+            })
+            .await?
+            .ok_or_else(|| {
+                AppError::ValidationError("This passkey is already registered".to_string())
+            })
+    }
+
+    /// 認証セレモニーを開始
+    /// user_id を指定した場合はそのユーザーの資格情報に限定する（二要素認証）
+    pub async fn start_authentication(
+        &self,
+        user_id: Option<&str>,
+        user_verification: UserVerification,
+    ) -> Result<WebAuthnAuthenticationChallenge> {
+        let allow_credentials = match user_id {
+            Some(user_id) => {
+                let credentials = self.credential_repo.find_by_user_id(user_id).await?;
+                if credentials.is_empty() {
+                    return Err(AppError::ValidationError(
+                        "No passkeys are registered".to_string(),
+                    ));
+                }
+                credentials
+                    .into_iter()
+                    .map(|credential| {
+                        CredentialDescriptor::new(credential.credential_id, credential.transports)
+                    })
+                    .collect()
+            }
+            None => Vec::new(),
+        };
+
+        let (ceremony_id, challenge) = self.begin_ceremony(CeremonyKind::Authentication {
+            user_id: user_id.map(str::to_string),
+            user_verification,
+        });
+
+        Ok(WebAuthnAuthenticationChallenge {
+            ceremony_id,
+            public_key: self.relying_party.request_options(
+                &challenge,
+                allow_credentials,
+                user_verification,
+            ),
+        })
+    }
+
+    /// 認証セレモニーを完了し、認証されたユーザーIDを返す
+    /// expected_user_id: 二要素認証の場合はパスワードを検証したユーザー
+    pub async fn finish_authentication(
+        &self,
+        ceremony_id: &str,
+        credential: &AuthenticationCredential,
+        expected_user_id: Option<&str>,
+    ) -> Result<String> {
+        let ceremony = self.take_ceremony(ceremony_id)?;
+        let CeremonyKind::Authentication {
+            user_id: ceremony_user,
+            user_verification,
+        } = &ceremony.kind
+        else {
+            return Err(invalid_ceremony());
+        };
+        if ceremony_user.as_deref() != expected_user_id {
+            return Err(invalid_ceremony());
+        }
+
+        let invalid = || AppError::AuthenticationError("Invalid passkey".to_string());
+
+        // rawId を正規化した base64url で検索する
+        let credential_id =
+            URL_SAFE_NO_PAD.encode(decode_base64url(&credential.raw_id).map_err(|_| invalid())?);
+        let stored = self
+            .credential_repo
+            .find_active_by_id(&credential_id)
+            .await?
+            .ok_or_else(invalid)?;
+
+        if expected_user_id.is_some_and(|user_id| user_id != stored.user_id) {
+            return Err(invalid());
+        }
+        if let Some(handle) = &credential.response.user_handle
+            && decode_base64url(handle).map_err(|_| invalid())? != user_handle(&stored.user_id)
+        {
+            return Err(invalid());
+        }
+
+        let sign_count = self.relying_party.verify_assertion(
+            credential,
+            &ceremony.challenge,
+            &stored.public_key,
+            stored.sign_count as u32,
+            *user_verification,
+        )?;
+
+        // 同じカウンタでの同時使用を防ぐ
+        if !self
+            .credential_repo
+            .record_use(&stored.credential_id, stored.sign_count, sign_count as i64)
+            .await?
+        {
+            return Err(invalid());
+        }
+
+        Ok(stored.user_id)
+    }
+
+    /// パスキーが登録されているか
+    pub async fn has_credentials(&self, user_id: &str) -> Result<bool> {
+        Ok(self.credential_repo.count_by_user_id(user_id).await? > 0)
+    }
+
+    /// ユーザーのパスキー一覧
+    pub async fn list(&self, user_id: &str) -> Result<Vec<WebAuthnCredential>> {
+        self.credential_repo.find_by_user_id(user_id).await
+    }
+
+    /// パスキーの名前を変更
+    pub async fn rename(&self, user_id: &str, credential_id: &str, name: &str) -> Result<WebAuthnCredential> {
+        let name = validate_credential_name(name)?;
+        self.credential_repo
+            .rename(user_id, credential_id, &name)
+            .await?
+            .ok_or_else(|| AppError::NotFound("Passkey not found".to_string()))
+    }
+
+    /// パスキーを削除
+    pub async fn delete(&self, user_id: &str, credential_id: &str) -> Result<()> {
+        if !self.credential_repo.delete(user_id, credential_id).await? {
+            return Err(AppError::NotFound("Passkey not found".to_string()));
+        }
+        Ok(())
+    }
+
+    fn begin_ceremony(&self, kind: CeremonyKind) -> (String, [u8; 32]) {
+        let ceremony_id = uuid::Uuid::new_v4().to_string();
+        let challenge: [u8; 32] = rand::random();
+        self.ceremonies.insert(
+            ceremony_id.clone(),
+            Ceremony {
+                challenge,
+                kind,
+                expires_at: Utc::now() + Duration::minutes(5),
+            },
+        );
+        (ceremony_id, challenge)
+    }
+
+    /// セレモニーを取り出す（一度取り出したセレモニーは再利用できない）
+    fn take_ceremony(&self, ceremony_id: &str) -> Result<Ceremony> {
+        let (_, ceremony) = self
+            .ceremonies
+            .remove(ceremony_id)
+            .ok_or_else(invalid_ceremony)?;
+        if ceremony.expires_at <= Utc::now() {
+            return Err(invalid_ceremony());
+        }
+        Ok(ceremony)
+    }
+}
+
+fn invalid_ceremony() -> AppError {
+    AppError::ValidationError("WebAuthn ceremony not found or expired".to_string())
+}
+
+/// WebAuthn の user.id（ユーザーIDを直接含めないようハッシュ化する）
+fn user_handle(user_id: &str) -> Vec<u8> {
+    Sha256::digest(format!("mimo-webauthn-user:{}", user_id).as_bytes()).to_vec()
+}
+
+fn validate_credential_name(name: &str) -> Result<String> {
+    let name = name.trim();
+    if name.is_empty() || name.chars().count() > NAME_MAX_LEN {
+        return Err(AppError::ValidationError(format!(
+            "Passkey name must be between 1 and {} characters",
+            NAME_MAX_LEN
+        )));
+    }
+    Ok(name.to_string())
+}
@@ -0,0 +1,925 @@
+use crate::error::{AppError, Result};
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use ciborium::value::Value;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+//////
+// WebAuthn（Relying Party 側）の実装
+// アテステーションは要求せず（"none"）、公開鍵は ES256 / EdDSA / RS256 に対応する
+
+/// COSEアルゴリズム識別子
+const COSE_ALG_ES256: i64 = -7;
+const COSE_ALG_EDDSA: i64 = -8;
+const COSE_ALG_RS256: i64 = -257;
+
+/// authenticatorData のフラグ
+const FLAG_USER_PRESENT: u8 = 0x01;
+const FLAG_USER_VERIFIED: u8 = 0x04;
+const FLAG_ATTESTED_CREDENTIAL_DATA: u8 = 0x40;
+
+/// セレモニーのタイムアウト（ミリ秒）
+const CEREMONY_TIMEOUT_MS: u64 = 5 * 60 * 1000;
+
+/// Relying Party の設定
+#[derive(Debug, Clone)]
+pub struct RelyingParty {
+    /// RP ID（通常はフロントエンドのドメイン）
+    pub id: String,
+    pub name: String,
+    /// 許可するオリジン（clientDataJSON の origin と照合）
+    pub origins: Vec<String>,
+}
+
+/// 認証器でのユーザー検証（PIN・生体認証）の要求
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum UserVerification {
+    Required,
+    Preferred,
+    Discouraged,
+}
+
+impl UserVerification {
+    fn as_str(&self) -> &'static str {
+        match self {
+            UserVerification::Required => "required",
+            UserVerification::Preferred => "preferred",
+            UserVerification::Discouraged => "discouraged",
+        }
+    }
+}
+
+// ------------------------------------------------------------------
+// クライアントに渡すオプション
+// ------------------------------------------------------------------
+
+/// navigator.credentials.create() に渡す publicKey オプション
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreationOptions {
+    pub challenge: String,
+    pub rp: RpEntity,
+    pub user: UserEntity,
+    pub pub_key_cred_params: Vec<CredentialParameter>,
+    pub timeout: u64,
+    pub attestation: &'static str,
+    pub exclude_credentials: Vec<CredentialDescriptor>,
+    pub authenticator_selection: AuthenticatorSelection,
+}
+
+/// navigator.credentials.get() に渡す publicKey オプション
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RequestOptions {
+    pub challenge: String,
+    pub timeout: u64,
+    pub rp_id: String,
+    pub allow_credentials: Vec<CredentialDescriptor>,
+    pub user_verification: &'static str,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RpEntity {
+    pub id: String,
+    pub name: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UserEntity {
+    pub id: String,
+    pub name: String,
+    pub display_name: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CredentialParameter {
+    #[serde(rename = "type")]
+    pub type_: &'static str,
+    pub alg: i64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CredentialDescriptor {
+    #[serde(rename = "type")]
+    pub type_: &'static str,
+    pub id: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub transports: Vec<String>,
+}
+
+impl CredentialDescriptor {
+    pub fn new(credential_id: String, transports: Vec<String>) -> Self {
+        Self {
+            type_: "public-key",
+            id: credential_id,
+            transports,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuthenticatorSelection {
+    pub resident_key: &'static str,
+    pub user_verification: &'static str,
+}
+
+// ------------------------------------------------------------------
+// クライアントから受け取る資格情報（PublicKeyCredential の JSON 表現）
+// ------------------------------------------------------------------
+
+/// navigator.credentials.create() の結果
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RegistrationCredential {
+    /// 資格情報ID（base64url。id と同じ値）
+    pub raw_id: String,
+    pub response: AttestationResponse,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AttestationResponse {
+    #[serde(rename = "clientDataJSON")]
+    pub client_data_json: String,
+    pub attestation_object: String,
+    #[serde(default)]
+    pub transports: Vec<String>,
+}
+
+/// navigator.credentials.get() の結果
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuthenticationCredential {
+    /// 資格情報ID（base64url。id と同じ値）
+    pub raw_id: String,
+    pub response: AssertionResponse,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AssertionResponse {
+    #[serde(rename = "clientDataJSON")]
+    pub client_data_json: String,
+    pub authenticator_data: String,
+    pub signature: String,
+    pub user_handle: Option<String>,
+}
+
+/// 登録を検証した資格情報
+pub struct VerifiedCredential {
+    /// 資格情報ID（base64url）
+    pub credential_id: String,
+    /// COSE形式の公開鍵
+    pub public_key: Vec<u8>,
+    pub algorithm: i64,
+    pub sign_count: u32,
+}
+
+#[derive(Deserialize)]
+struct CollectedClientData {
+    #[serde(rename = "type")]
+    type_: String,
+    challenge: String,
+    origin: String,
+    #[serde(default, rename = "crossOrigin")]
+    cross_origin: bool,
+}
+
+struct AuthenticatorData<'a> {
+    rp_id_hash: &'a [u8],
+    flags: u8,
+    sign_count: u32,
+    /// 登録時のみ含まれる（資格情報ID, COSE公開鍵）
+    attested_credential: Option<(Vec<u8>, Vec<u8>)>,
+}
+
+impl RelyingParty {
+    /// 登録セレモニーのオプション
+    pub fn creation_options(
+        &self,
+        challenge: &[u8],
+        user_handle: &[u8],
+        user_name: &str,
+        display_name: &str,
+        exclude_credentials: Vec<CredentialDescriptor>,
+    ) -> CreationOptions {
+        CreationOptions {
+            challenge: URL_SAFE_NO_PAD.encode(challenge),
+            rp: RpEntity {
+                id: self.id.clone(),
+                name: self.name.clone(),
+            },
+            user: UserEntity {
+                id: URL_SAFE_NO_PAD.encode(user_handle),
+                name: user_name.to_string(),
+                display_name: display_name.to_string(),
+            },
+            pub_key_cred_params: [COSE_ALG_ES256, COSE_ALG_EDDSA, COSE_ALG_RS256]
+                .into_iter()
+                .map(|alg| CredentialParameter {
+                    type_: "public-key",
+                    alg,
+                })
+                .collect(),
+            timeout: CEREMONY_TIMEOUT_MS,
+            attestation: "none",
+            exclude_credentials,
+            // パスキー（discoverable credential）として保存されるよう要求
+            authenticator_selection: AuthenticatorSelection {
+                resident_key: "preferred",
+                user_verification: UserVerification::Preferred.as_str(),
+            },
+        }
+    }
+
+    /// 認証セレモニーのオプション
+    /// allow_credentials が空の場合、認証器に保存されたパスキーから選択させる
+    pub fn request_options(
+        &self,
+        challenge: &[u8],
+        allow_credentials: Vec<CredentialDescriptor>,
+        user_verification: UserVerification,
+    ) -> RequestOptions {
+        RequestOptions {
+            challenge: URL_SAFE_NO_PAD.encode(challenge),
+            timeout: CEREMONY_TIMEOUT_MS,
+            rp_id: self.id.clone(),
+            allow_credentials,
+            user_verification: user_verification.as_str(),
+        }
+    }
+
+    /// 登録セレモニーの応答を検証
+    /// アテステーションは要求していないため、attStmt は検証しない
+    pub fn verify_registration(
+        &self,
+        credential: &RegistrationCredential,
+        challenge: &[u8],
+    ) -> Result<VerifiedCredential> {
+        let invalid = |msg: &str| AppError::ValidationError(format!("Invalid WebAuthn registration: {}", msg));
+
+        let client_data = decode_base64url(&credential.response.client_data_json).map_err(invalid)?;
+        self.verify_client_data(&client_data, "webauthn.create", challenge)
+            .map_err(invalid)?;
+
+        let attestation_object =
+            decode_base64url(&credential.response.attestation_object).map_err(invalid)?;
+        let attestation: Value = ciborium::de::from_reader(attestation_object.as_slice())
+            .map_err(|_| invalid("malformed attestation object"))?;
+        let auth_data = map_get_text(&attestation, "authData")
+            .and_then(Value::as_bytes)
+            .ok_or_else(|| invalid("missing authData"))?;
+
+        let auth_data = parse_authenticator_data(auth_data).map_err(invalid)?;
+        self.verify_rp_id_hash(auth_data.rp_id_hash).map_err(invalid)?;
+        if auth_data.flags & FLAG_USER_PRESENT == 0 {
+            return Err(invalid("user presence flag is not set"));
+        }
+
+        let (credential_id, public_key) = auth_data
+            .attested_credential
+            .ok_or_else(|| invalid("missing attested credential data"))?;
+        if decode_base64url(&credential.raw_id).map_err(invalid)? != credential_id {
+            return Err(invalid("credential id does not match"));
+        }
+
+        let (algorithm, _) = parse_cose_key(&public_key).map_err(invalid)?;
+
+        Ok(VerifiedCredential {
+            credential_id: URL_SAFE_NO_PAD.encode(&credential_id),
+            public_key,
+            algorithm,
+            sign_count: auth_data.sign_count,
+        })
+    }
+
+    /// 認証セレモニーの応答を検証し、新しい署名カウンタを返す
+    pub fn verify_assertion(
+        &self,
+        credential: &AuthenticationCredential,
+        challenge: &[u8],
+        public_key: &[u8],
+        stored_sign_count: u32,
+        user_verification: UserVerification,
+    ) -> Result<u32> {
+        let invalid = |msg: &str| AppError::AuthenticationError(format!("Invalid WebAuthn assertion: {}", msg));
+
+        let client_data = decode_base64url(&credential.response.client_data_json).map_err(invalid)?;
+        self.verify_client_data(&client_data, "webauthn.get", challenge)
+            .map_err(invalid)?;
+
+        let raw_auth_data =
+            decode_base64url(&credential.response.authenticator_data).map_err(invalid)?;
+        let auth_data = parse_authenticator_data(&raw_auth_data).map_err(invalid)?;
+        self.verify_rp_id_hash(auth_data.rp_id_hash).map_err(invalid)?;
+        if auth_data.flags & FLAG_USER_PRESENT == 0 {
+            return Err(invalid("user presence flag is not set"));
+        }
+        if user_verification == UserVerification::Required
+            && auth_data.flags & FLAG_USER_VERIFIED == 0
+        {
+            return Err(invalid("user verification is required"));
+        }
+
+        // 署名カウンタが増えていない場合は認証器の複製を疑う（カウンタ非対応の認証器は常に0）
+        if (auth_data.sign_count != 0 || stored_sign_count != 0)
+            && auth_data.sign_count <= stored_sign_count
+        {
+            return Err(invalid("signature counter did not increase"));
+        }
+
+        // 署名対象: authenticatorData || SHA-256(clientDataJSON)
+        let mut signed = raw_auth_data.clone();
+        signed.extend_from_slice(&Sha256::digest(&client_data));
+
+        let signature = decode_base64url(&credential.response.signature).map_err(invalid)?;
+        let (_, key) = parse_cose_key(public_key).map_err(invalid)?;
+        if !key.verify(&signed, &signature) {
+            return Err(invalid("signature verification failed"));
+        }
+
+        Ok(auth_data.sign_count)
+    }
+
+    fn verify_client_data(
+        &self,
+        client_data: &[u8],
+        expected_type: &str,
+        challenge: &[u8],
+    ) -> std::result::Result<(), &'static str> {
+        let client_data: CollectedClientData =
+            serde_json::from_slice(client_data).map_err(|_| "malformed clientDataJSON")?;
+
+        if client_data.type_ != expected_type {
+            return Err("unexpected ceremony type");
+        }
+        if decode_base64url(&client_data.challenge)? != challenge {
+            return Err("challenge does not match");
+        }
+        if !self.origins.contains(&client_data.origin) {
+            return Err("origin is not allowed");
+        }
+        if client_data.cross_origin {
+            return Err("cross-origin ceremonies are not allowed");
+        }
+        Ok(())
+    }
+
+    fn verify_rp_id_hash(&self, rp_id_hash: &[u8]) -> std::result::Result<(), &'static str> {
+        if Sha256::digest(self.id.as_bytes())[..] != *rp_id_hash {
+            return Err("RP ID hash does not match");
+        }
+        Ok(())
+    }
+}
+
+/// base64url（パディングの有無を問わない）をデコード
+pub fn decode_base64url(value: &str) -> std::result::Result<Vec<u8>, &'static str> {
+    URL_SAFE_NO_PAD
+        .decode(value.trim_end_matches('='))
+        .map_err(|_| "malformed base64url")
+}
+
+fn parse_authenticator_data(data: &[u8]) -> std::result::Result<AuthenticatorData<'_>, &'static str> {
+    if data.len() < 37 {
+        return Err("authenticator data is too short");
+    }
+    let flags = data[32];
+    let sign_count = u32::from_be_bytes([data[33], data[34], data[35], data[36]]);
+
+    let attested_credential = if flags & FLAG_ATTESTED_CREDENTIAL_DATA != 0 {
+        // aaguid (16) || credentialIdLength (2) || credentialId || credentialPublicKey
+        let rest = &data[37..];
+        if rest.len() < 18 {
+            return Err("attested credential data is too short");
+        }
+        let id_len = u16::from_be_bytes([rest[16], rest[17]]) as usize;
+        let rest = &rest[18..];
+        if rest.len() < id_len {
+            return Err("credential id is truncated");
+        }
+        let (credential_id, key_and_extensions) = rest.split_at(id_len);
+
+        // 公開鍵の後に拡張データが続く場合があるため、CBORとして読み取った長さで切り出す
+        let mut reader = key_and_extensions;
+        let _: Value =
+            ciborium::de::from_reader(&mut reader).map_err(|_| "malformed credential public key")?;
+        let key_len = key_and_extensions.len() - reader.len();
+
+        Some((credential_id.to_vec(), key_and_extensions[..key_len].to_vec()))
+    } else {
+        None
+    };
+
+    Ok(AuthenticatorData {
+        rp_id_hash: &data[..32],
+        flags,
+        sign_count,
+        attested_credential,
+    })
+}
+
+/// COSE形式の公開鍵
+enum CosePublicKey {
+    Es256(p256::ecdsa::VerifyingKey),
+    EdDsa(ed25519_dalek::VerifyingKey),
+    Rs256(rsa::RsaPublicKey),
+}
+
+impl CosePublicKey {
+    fn verify(&self, message: &[u8], signature: &[u8]) -> bool {
+        use rsa::signature::Verifier;
+
+        match self {
+            CosePublicKey::Es256(key) => p256::ecdsa::Signature::from_der(signature)
+                .is_ok_and(|signature| key.verify(message, &signature).is_ok()),
+            CosePublicKey::EdDsa(key) => ed25519_dalek::Signature::from_slice(signature)
+                .is_ok_and(|signature| key.verify_strict(message, &signature).is_ok()),
+            CosePublicKey::Rs256(key) => {
+                let key = rsa::pkcs1v15::VerifyingKey::<Sha256>::new(key.clone());
+                rsa::pkcs1v15::Signature::try_from(signature)
+                    .is_ok_and(|signature| key.verify(message, &signature).is_ok())
+            }
+        }
+    }
+}
+
+/// COSE_Key を読み取り、(アルゴリズム, 公開鍵) を返す
+fn parse_cose_key(bytes: &[u8]) -> std::result::Result<(i64, CosePublicKey), &'static str> {
+    let key: Value = ciborium::de::from_reader(bytes).map_err(|_| "malformed COSE key")?;
+    let int = |label: i64| map_get_int(&key, label).and_then(value_as_i64);
+    let bytes = |label: i64| map_get_int(&key, label).and_then(Value::as_bytes);
+
+    // kty(1), alg(3), crv(-1), x/n(-1, -2), y/e(-2, -3)
+    match (int(1), int(3)) {
+        (Some(2), Some(COSE_ALG_ES256)) => {
+            let (Some(1), Some(x), Some(y)) = (int(-1), bytes(-2), bytes(-3)) else {
+                return Err("unsupported EC2 key");
+            };
+            let mut sec1 = vec![0x04];
+            sec1.extend_from_slice(x);
+            sec1.extend_from_slice(y);
+            let key = p256::ecdsa::VerifyingKey::from_sec1_bytes(&sec1)
+                .map_err(|_| "invalid P-256 public key")?;
+            Ok((COSE_ALG_ES256, CosePublicKey::Es256(key)))
+        }
+        (Some(1), Some(COSE_ALG_EDDSA)) => {
+            let (Some(6), Some(x)) = (int(-1), bytes(-2)) else {
+                return Err("unsupported OKP key");
+            };
+            let x: &[u8; 32] = x.as_slice().try_into().map_err(|_| "invalid Ed25519 public key")?;
+            let key = ed25519_dalek::VerifyingKey::from_bytes(x)
+                .map_err(|_| "invalid Ed25519 public key")?;
+            Ok((COSE_ALG_EDDSA, CosePublicKey::EdDsa(key)))
+        }
+        (Some(3), Some(COSE_ALG_RS256)) => {
+            let (Some(n), Some(e)) = (bytes(-1), bytes(-2)) else {
+                return Err("unsupported RSA key");
+            };
+            let n = rsa::BigUint::from_bytes_be(n);
+            if n.bits() < 2048 {
+                return Err("RSA key must be at least 2048 bits");
+            }
+            let key = rsa::RsaPublicKey::new(n, rsa::BigUint::from_bytes_be(e))
+                .map_err(|_| "invalid RSA public key")?;
+            Ok((COSE_ALG_RS256, CosePublicKey::Rs256(key)))
+        }
+        _ => Err("unsupported public key algorithm"),
+    }
+}
+
+fn map_get_text<'a>(map: &'a Value, key: &str) -> Option<&'a Value> {
+    map.as_map()?
+        .iter()
+        .find(|(k, _)| k.as_text() == Some(key))
+        .map(|(_, v)| v)
+}
+
+fn map_get_int(map: &Value, key: i64) -> Option<&Value> {
+    map.as_map()?
+        .iter()
+        .find(|(k, _)| value_as_i64(k) == Some(key))
+        .map(|(_, v)| v)
+}
+
+fn value_as_i64(value: &Value) -> Option<i64> {
+    value.as_integer().and_then(|i| i64::try_from(i).ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use p256::ecdsa::signature::Signer;
+
+    const RP_ID: &str = "example.com";
+    const ORIGIN: &str = "https://example.com";
+    const CHALLENGE: &[u8] = b"0123456789abcdef0123456789abcdef";
+    const CREDENTIAL_ID: &[u8] = b"software-authenticator-credential";
+
+    fn rp() -> RelyingParty {
+        RelyingParty {
+            id: RP_ID.to_string(),
+            name: "Mimo".to_string(),
+            origins: vec![ORIGIN.to_string()],
+        }
+    }
+
+    fn int(value: i64) -> Value {
+        Value::Integer(value.into())
+    }
+
+    fn cbor(value: &Value) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        ciborium::ser::into_writer(value, &mut bytes).unwrap();
+        bytes
+    }
+
+    /// ソフトウェアの認証器
+    enum SoftwareKey {
+        Es256(p256::ecdsa::SigningKey),
+        EdDsa(ed25519_dalek::SigningKey),
+    }
+
+    impl SoftwareKey {
+        fn es256(seed: u8) -> Self {
+            SoftwareKey::Es256(p256::ecdsa::SigningKey::from_slice(&[seed; 32]).unwrap())
+        }
+
+        fn ed25519(seed: u8) -> Self {
+            SoftwareKey::EdDsa(ed25519_dalek::SigningKey::from_bytes(&[seed; 32]))
+        }
+
+        fn cose_key(&self) -> Vec<u8> {
+            let map = match self {
+                SoftwareKey::Es256(key) => {
+                    let point = key.verifying_key().to_encoded_point(false);
+                    vec![
+                        (int(1), int(2)),
+                        (int(3), int(COSE_ALG_ES256)),
+                        (int(-1), int(1)),
+                        (int(-2), Value::Bytes(point.x().unwrap().to_vec())),
+                        (int(-3), Value::Bytes(point.y().unwrap().to_vec())),
+                    ]
+                }
+                SoftwareKey::EdDsa(key) => vec![
+                    (int(1), int(1)),
+                    (int(3), int(COSE_ALG_EDDSA)),
+                    (int(-1), int(6)),
+                    (int(-2), Value::Bytes(key.verifying_key().to_bytes().to_vec())),
+                ],
+            };
+            cbor(&Value::Map(map))
+        }
+
+        fn sign(&self, message: &[u8]) -> Vec<u8> {
+            match self {
+                SoftwareKey::Es256(key) => {
+                    let signature: p256::ecdsa::Signature = key.sign(message);
+                    signature.to_der().as_bytes().to_vec()
+                }
+                SoftwareKey::EdDsa(key) => key.sign(message).to_bytes().to_vec(),
+            }
+        }
+    }
+
+    fn client_data(type_: &str, challenge: &[u8], origin: &str) -> Vec<u8> {
+        serde_json::json!({
+            "type": type_,
+            "challenge": URL_SAFE_NO_PAD.encode(challenge),
+            "origin": origin,
+            "crossOrigin": false,
+        })
+        .to_string()
+        .into_bytes()
+    }
+
+    fn authenticator_data(rp_id: &str, flags: u8, sign_count: u32, cose_key: Option<&[u8]>) -> Vec<u8> {
+        let mut data = Sha256::digest(rp_id.as_bytes()).to_vec();
+        data.push(flags);
+        data.extend_from_slice(&sign_count.to_be_bytes());
+        if let Some(cose_key) = cose_key {
+            data.extend_from_slice(&[0; 16]);
+            data.extend_from_slice(&(CREDENTIAL_ID.len() as u16).to_be_bytes());
+            data.extend_from_slice(CREDENTIAL_ID);
+            data.extend_from_slice(cose_key);
+        }
+        data
+    }
+
+    /// 登録セレモニーの応答
+    struct Registration<'a> {
+        challenge: &'a [u8],
+        origin: &'a str,
+        rp_id: &'a str,
+        flags: u8,
+        cose_key: Vec<u8>,
+    }
+
+    impl Registration<'_> {
+        fn new(key: &SoftwareKey) -> Self {
+            Registration {
+                challenge: CHALLENGE,
+                origin: ORIGIN,
+                rp_id: RP_ID,
+                flags: FLAG_USER_PRESENT | FLAG_USER_VERIFIED | FLAG_ATTESTED_CREDENTIAL_DATA,
+                cose_key: key.cose_key(),
+            }
+        }
+
+        fn credential(&self) -> RegistrationCredential {
+            let auth_data = authenticator_data(self.rp_id, self.flags, 0, Some(&self.cose_key));
+            let attestation = Value::Map(vec![
+                (Value::Text("fmt".to_string()), Value::Text("none".to_string())),
+                (Value::Text("attStmt".to_string()), Value::Map(vec![])),
+                (Value::Text("authData".to_string()), Value::Bytes(auth_data)),
+            ]);
+            RegistrationCredential {
+                raw_id: URL_SAFE_NO_PAD.encode(CREDENTIAL_ID),
+                response: AttestationResponse {
+                    client_data_json: URL_SAFE_NO_PAD
+                        .encode(client_data("webauthn.create", self.challenge, self.origin)),
+                    attestation_object: URL_SAFE_NO_PAD.encode(cbor(&attestation)),
+                    transports: vec![],
+                },
+            }
+        }
+
+        fn verify(&self) -> Result<VerifiedCredential> {
+            rp().verify_registration(&self.credential(), CHALLENGE)
+        }
+    }
+
+    /// 認証セレモニーの応答
+    struct Assertion<'a> {
+        key: &'a SoftwareKey,
+        type_: &'a str,
+        challenge: &'a [u8],
+        origin: &'a str,
+        rp_id: &'a str,
+        flags: u8,
+        sign_count: u32,
+    }
+
+    impl<'a> Assertion<'a> {
+        fn new(key: &'a SoftwareKey) -> Self {
+            Assertion {
+                key,
+                type_: "webauthn.get",
+                challenge: CHALLENGE,
+                origin: ORIGIN,
+                rp_id: RP_ID,
+                flags: FLAG_USER_PRESENT | FLAG_USER_VERIFIED,
+                sign_count: 1,
+            }
+        }
+
+        fn credential(&self) -> AuthenticationCredential {
+            let client_data = client_data(self.type_, self.challenge, self.origin);
+            let auth_data = authenticator_data(self.rp_id, self.flags, self.sign_count, None);
+            let mut signed = auth_data.clone();
+            signed.extend_from_slice(&Sha256::digest(&client_data));
+
+            AuthenticationCredential {
+                raw_id: URL_SAFE_NO_PAD.encode(CREDENTIAL_ID),
+                response: AssertionResponse {
+                    client_data_json: URL_SAFE_NO_PAD.encode(&client_data),
+                    authenticator_data: URL_SAFE_NO_PAD.encode(&auth_data),
+                    signature: URL_SAFE_NO_PAD.encode(self.key.sign(&signed)),
+                    user_handle: None,
+                },
+            }
+        }
+
+        /// 登録済みの公開鍵・署名カウンタで検証
+        fn verify(&self, public_key: &[u8], stored_sign_count: u32, uv: UserVerification) -> Result<u32> {
+            rp().verify_assertion(&self.credential(), CHALLENGE, public_key, stored_sign_count, uv)
+        }
+    }
+
+    fn assert_rejected<T>(result: Result<T>, reason: &str) {
+        match result {
+            Ok(_) => panic!("expected rejection: {}", reason),
+            Err(e) => assert!(e.to_string().contains(reason), "{} (expected: {})", e, reason),
+        }
+    }
+
+    #[test]
+    fn registers_and_authenticates_with_es256() {
+        let key = SoftwareKey::es256(1);
+        let credential = Registration::new(&key).verify().unwrap();
+        assert_eq!(credential.credential_id, URL_SAFE_NO_PAD.encode(CREDENTIAL_ID));
+        assert_eq!(credential.algorithm, COSE_ALG_ES256);
+        assert_eq!(credential.sign_count, 0);
+
+        let sign_count = Assertion::new(&key)
+            .verify(&credential.public_key, credential.sign_count, UserVerification::Required)
+            .unwrap();
+        assert_eq!(sign_count, 1);
+    }
+
+    #[test]
+    fn registers_and_authenticates_with_ed25519() {
+        let key = SoftwareKey::ed25519(2);
+        let credential = Registration::new(&key).verify().unwrap();
+        assert_eq!(credential.algorithm, COSE_ALG_EDDSA);
+
+        let sign_count = Assertion {
+            sign_count: 7,
+            ..Assertion::new(&key)
+        }
+        .verify(&credential.public_key, 6, UserVerification::Required)
+        .unwrap();
+        assert_eq!(sign_count, 7);
+    }
+
+    #[test]
+    fn registration_rejects_wrong_challenge() {
+        let key = SoftwareKey::es256(1);
+        let registration = Registration {
+            challenge: b"another challenge",
+            ..Registration::new(&key)
+        };
+        assert_rejected(registration.verify(), "challenge does not match");
+    }
+
+    #[test]
+    fn registration_rejects_wrong_origin() {
+        let key = SoftwareKey::es256(1);
+        let registration = Registration {
+            origin: "https://evil.example",
+            ..Registration::new(&key)
+        };
+        assert_rejected(registration.verify(), "origin is not allowed");
+    }
+
+    #[test]
+    fn registration_rejects_wrong_rp_id_hash() {
+        let key = SoftwareKey::es256(1);
+        let registration = Registration {
+            rp_id: "evil.example",
+            ..Registration::new(&key)
+        };
+        assert_rejected(registration.verify(), "RP ID hash does not match");
+    }
+
+    #[test]
+    fn registration_rejects_missing_user_presence() {
+        let key = SoftwareKey::es256(1);
+        let registration = Registration {
+            flags: FLAG_USER_VERIFIED | FLAG_ATTESTED_CREDENTIAL_DATA,
+            ..Registration::new(&key)
+        };
+        assert_rejected(registration.verify(), "user presence flag is not set");
+    }
+
+    #[test]
+    fn registration_rejects_rsa_key_under_2048_bits() {
+        let key = SoftwareKey::es256(1);
+        let rsa_key = |n: Vec<u8>| {
+            cbor(&Value::Map(vec![
+                (int(1), int(3)),
+                (int(3), int(COSE_ALG_RS256)),
+                (int(-1), Value::Bytes(n)),
+                (int(-2), Value::Bytes(vec![1, 0, 1])),
+            ]))
+        };
+
+        // 1024ビット
+        let registration = Registration {
+            cose_key: rsa_key(vec![0xff; 128]),
+            ..Registration::new(&key)
+        };
+        assert_rejected(registration.verify(), "RSA key must be at least 2048 bits");
+
+        // 256バイトでも先頭が0の場合は2048ビット未満
+        let mut n = vec![0xff; 256];
+        n[0] = 0;
+        let registration = Registration {
+            cose_key: rsa_key(n),
+            ..Registration::new(&key)
+        };
+        assert_rejected(registration.verify(), "RSA key must be at least 2048 bits");
+    }
+
+    #[test]
+    fn assertion_rejects_wrong_challenge() {
+        let key = SoftwareKey::es256(1);
+        let assertion = Assertion {
+            challenge: b"another challenge",
+            ..Assertion::new(&key)
+        };
+        assert_rejected(
+            assertion.verify(&key.cose_key(), 0, UserVerification::Required),
+            "challenge does not match",
+        );
+    }
+
+    #[test]
+    fn assertion_rejects_wrong_ceremony_type() {
+        let key = SoftwareKey::es256(1);
+        let assertion = Assertion {
+            type_: "webauthn.create",
+            ..Assertion::new(&key)
+        };
+        assert_rejected(
+            assertion.verify(&key.cose_key(), 0, UserVerification::Required),
+            "unexpected ceremony type",
+        );
+    }
+
+    #[test]
+    fn assertion_rejects_wrong_origin() {
+        let key = SoftwareKey::es256(1);
+        let assertion = Assertion {
+            origin: "https://evil.example",
+            ..Assertion::new(&key)
+        };
+        assert_rejected(
+            assertion.verify(&key.cose_key(), 0, UserVerification::Required),
+            "origin is not allowed",
+        );
+    }
+
+    #[test]
+    fn assertion_rejects_wrong_rp_id_hash() {
+        let key = SoftwareKey::es256(1);
+        let assertion = Assertion {
+            rp_id: "evil.example",
+            ..Assertion::new(&key)
+        };
+        assert_rejected(
+            assertion.verify(&key.cose_key(), 0, UserVerification::Required),
+            "RP ID hash does not match",
+        );
+    }
+
+    #[test]
+    fn assertion_rejects_missing_user_presence() {
+        let key = SoftwareKey::es256(1);
+        let assertion = Assertion {
+            flags: FLAG_USER_VERIFIED,
+            ..Assertion::new(&key)
+        };
+        assert_rejected(
+            assertion.verify(&key.cose_key(), 0, UserVerification::Discouraged),
+            "user presence flag is not set",
+        );
+    }
+
+    #[test]
+    fn assertion_requires_user_verification_only_when_required() {
+        let key = SoftwareKey::es256(1);
+        let assertion = Assertion {
+            flags: FLAG_USER_PRESENT,
+            ..Assertion::new(&key)
+        };
+        assert_rejected(
+            assertion.verify(&key.cose_key(), 0, UserVerification::Required),
+            "user verification is required",
+        );
+        assert!(
+            assertion
+                .verify(&key.cose_key(), 0, UserVerification::Preferred)
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn assertion_rejects_sign_count_that_does_not_increase() {
+        let key = SoftwareKey::es256(1);
+        for sign_count in [5, 4, 0] {
+            let assertion = Assertion {
+                sign_count,
+                ..Assertion::new(&key)
+            };
+            assert_rejected(
+                assertion.verify(&key.cose_key(), 5, UserVerification::Required),
+                "signature counter did not increase",
+            );
+        }
+
+        // カウンタ非対応の認証器（常に0）は許可する
+        let assertion = Assertion {
+            sign_count: 0,
+            ..Assertion::new(&key)
+        };
+        assert_eq!(
+            assertion
+                .verify(&key.cose_key(), 0, UserVerification::Required)
+                .unwrap(),
+            0
+        );
+    }
+
+    #[test]
+    fn assertion_rejects_signature_from_another_key() {
+        let key = SoftwareKey::ed25519(2);
+        let other = SoftwareKey::ed25519(3);
+        assert_rejected(
+            Assertion::new(&other).verify(&key.cose_key(), 0, UserVerification::Required),
+            "signature verification failed",
+        );
+    }
+}